@@ -21,6 +21,7 @@ import { useTheme } from '@/components/theme-provider';
 import { getStorageUsage } from '@/lib/storage';
 import { X, Shield, Eye, EyeOff } from 'lucide-react';
 import { logError } from '@/lib/error-sanitizer';
+import { isMissingSecureValue } from '@/lib/ai';
 import { useTranslation } from '@/components/language-provider';
 import { useRouter, usePathname } from 'next/navigation';
 import { i18n } from '@/i18n-config';
@@ -104,7 +105,9 @@ export function SettingsDialog({ open, onOpenChange, onDataCleared }: SettingsDi
           setApiKey(savedKey);
         }
       } catch (error) {
-        logError('Failed to load API key', error);
+        if (!isMissingSecureValue(error)) {
+          logError('Failed to load API key', error);
+        }
       }
     };
     loadApiKey();
@@ -8,6 +8,18 @@ import { convertToWav } from '@/lib/audio-converter';
 
 const STORAGE_KEY = 'openai_api_key';
 
+/**
+ * Whether a `get_secure_value` error only means that no value is stored,
+ * as opposed to a legacy or corrupted one
+ */
+export function isMissingSecureValue(error: unknown): boolean {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    (error as { kind?: unknown }).kind === 'missing'
+  );
+}
+
 /**
  * Sanitizes an error object to remove sensitive information like API keys
  * before logging to console
@@ -95,7 +107,9 @@ export async function getApiKey(): Promise<string> {
         return storedKey;
       }
     } catch (error) {
-      logSanitizedError('Failed to retrieve API key from secure storage:', error);
+      if (!isMissingSecureValue(error)) {
+        logSanitizedError('Failed to retrieve API key from secure storage:', error);
+      }
       // Fall through to environment variable
     }
   }
@@ -193,3 +193,37 @@ export function clearEncryptionKey(): void {
     console.error('Failed to clear encryption key:', err);
   }
 }
+
+/**
+ * Key domain of data encrypted by the Tauri backend
+ */
+export type BackendDataContext = 'history' | 'sync';
+
+/**
+ * Encrypts data in the Tauri backend with the app's machine-bound key
+ * infrastructure instead of the key in localStorage
+ * @param data - Plain text to encrypt
+ * @param context - Key domain; data only decrypts with the same one
+ * @returns Base64-encoded nonce and ciphertext
+ */
+export async function encryptWithBackend(
+  data: string,
+  context: BackendDataContext = 'history',
+): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<string>('encrypt_data', { data, context });
+}
+
+/**
+ * Decrypts data encrypted by encryptWithBackend
+ * @param encryptedData - Base64-encoded nonce and ciphertext
+ * @param context - Key domain the data was encrypted with
+ * @returns Decrypted plain text
+ */
+export async function decryptWithBackend(
+  encryptedData: string,
+  context: BackendDataContext = 'history',
+): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  return invoke<string>('decrypt_data', { data: encryptedData, context });
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::transcription::TranscriptSegment;
+
+/// Speaking pace of one transcript, stored with its entry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaceMetrics {
+    pub word_count: u32,
+    /// Length of the recording
+    pub duration_ms: u64,
+    /// Time covered by speech, excluding pauses between segments
+    pub speaking_ms: u64,
+    /// Words per minute of speaking time
+    pub words_per_minute: f32,
+}
+
+impl PaceMetrics {
+    pub fn from_segments(segments: &[TranscriptSegment], duration_ms: u64) -> Self {
+        let mut tracker = PaceTracker::default();
+        for segment in segments {
+            tracker.push(segment);
+        }
+
+        Self {
+            word_count: tracker.word_count,
+            duration_ms,
+            speaking_ms: tracker.speaking_ms,
+            words_per_minute: words_per_minute(tracker.word_count, tracker.speaking_ms),
+        }
+    }
+}
+
+/// Running pace, emitted as `transcription-pace` while segments come in
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaceUpdate {
+    pub word_count: u32,
+    /// Position in the recording the transcript has reached
+    pub elapsed_ms: u64,
+    /// Pace so far
+    pub words_per_minute: f32,
+    /// Pace of the latest segment alone
+    pub current_words_per_minute: f32,
+}
+
+/// Accumulates pace over transcript segments as they are produced
+#[derive(Debug, Default)]
+pub struct PaceTracker {
+    word_count: u32,
+    speaking_ms: u64,
+}
+
+impl PaceTracker {
+    pub fn push(&mut self, segment: &TranscriptSegment) -> PaceUpdate {
+        let words = segment.text.split_whitespace().count() as u32;
+        let length_ms = segment.end_ms.saturating_sub(segment.start_ms);
+
+        self.word_count += words;
+        self.speaking_ms += length_ms;
+
+        PaceUpdate {
+            word_count: self.word_count,
+            elapsed_ms: segment.end_ms,
+            words_per_minute: words_per_minute(self.word_count, self.speaking_ms),
+            current_words_per_minute: words_per_minute(words, length_ms),
+        }
+    }
+}
+
+/// Words per minute of `ms` speaking time
+pub fn words_per_minute(words: u32, ms: u64) -> f32 {
+    if ms == 0 {
+        0.0
+    } else {
+        words as f32 * 60_000.0 / ms as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, end_ms: u64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            speaker: None,
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_pace_excludes_pauses() {
+        let segments = [
+            segment(0, 3000, "one two three four five six"),
+            // Five seconds of silence before this one
+            segment(8000, 11000, "seven eight nine ten eleven twelve"),
+        ];
+
+        let metrics = PaceMetrics::from_segments(&segments, 12000);
+        assert_eq!(metrics.word_count, 12);
+        assert_eq!(metrics.speaking_ms, 6000);
+        assert_eq!(metrics.words_per_minute, 120.0);
+
+        let mut tracker = PaceTracker::default();
+        let update = tracker.push(&segments[0]);
+        assert_eq!((update.word_count, update.elapsed_ms), (6, 3000));
+        assert_eq!(update.current_words_per_minute, 120.0);
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+pub mod chunk_stream;
+pub mod devices;
+pub mod pipeline;
+pub mod wav_info;
+
+pub use chunk_stream::encode_pcm16;
+use pipeline::Pipeline;
+pub use pipeline::{AudioFormat, RecordingConfig, VadConfig};
+
+/// Block length `remove_silence` judges speech by, about what a capture
+/// callback delivers
+const VAD_BLOCK_MS: usize = 20;
+
+/// Sample rate speech recognition models expect
+pub const SPEECH_SAMPLE_RATE: u32 = 16000;
+
+/// Processed audio of a recording in progress, delivered as it is captured
+pub struct LiveAudio {
+    pub receiver: UnboundedReceiver<Vec<f32>>,
+    pub format: AudioFormat,
+}
+
+impl LiveAudio {
+    /// Convert the audio to 16 kHz mono on its way, off the capture thread.
+    /// Needs a Tokio runtime.
+    pub fn into_speech(self) -> LiveAudio {
+        let LiveAudio {
+            mut receiver,
+            format,
+        } = self;
+        let (sender, speech) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut converter = SpeechConverter::new(format);
+            while let Some(samples) = receiver.recv().await {
+                if sender.send(converter.process(samples)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        LiveAudio {
+            receiver: speech,
+            format: AudioFormat {
+                sample_rate: SPEECH_SAMPLE_RATE,
+                channels: 1,
+            },
+        }
+    }
+}
+
+/// Converts audio to 16 kHz mono for speech recognition, block by block
+pub struct SpeechConverter {
+    pipeline: Pipeline,
+}
+
+impl SpeechConverter {
+    pub fn new(format: AudioFormat) -> Self {
+        let config = RecordingConfig {
+            target_sample_rate: Some(SPEECH_SAMPLE_RATE),
+            ..Default::default()
+        };
+        Self {
+            pipeline: Pipeline::new(&config, format),
+        }
+    }
+
+    pub fn process(&mut self, samples: Vec<f32>) -> Vec<f32> {
+        self.pipeline.process(samples)
+    }
+}
+
+/// Convert samples to 16 kHz mono for speech recognition
+pub fn to_speech_samples(samples: Vec<f32>, format: AudioFormat) -> Vec<f32> {
+    SpeechConverter::new(format).process(samples)
+}
+
+/// Encode 16 kHz mono samples as a 16-bit WAV file
+pub fn encode_speech_wav(samples: &[f32]) -> Result<Vec<u8>, String> {
+    let format = AudioFormat {
+        sample_rate: SPEECH_SAMPLE_RATE,
+        channels: 1,
+    };
+
+    pipeline::encode_wav(samples, format, pipeline::WavEncoding::Pcm16)
+        .map_err(|e| format!("Failed to encode recording: {}", e))
+}
+
+/// Drop the pauses from 16 kHz mono samples with the gate `vad` configures,
+/// as it would during capture
+pub fn remove_silence(samples: &[f32], vad: &VadConfig) -> Vec<f32> {
+    let format = AudioFormat {
+        sample_rate: SPEECH_SAMPLE_RATE,
+        channels: 1,
+    };
+    let config = RecordingConfig {
+        vad: Some(VadConfig {
+            skip_silence: true,
+            ..vad.clone()
+        }),
+        ..Default::default()
+    };
+    let mut pipeline = Pipeline::new(&config, format);
+    let block = SPEECH_SAMPLE_RATE as usize * VAD_BLOCK_MS / 1000;
+
+    samples
+        .chunks(block)
+        .flat_map(|chunk| pipeline.process(chunk.to_vec()))
+        .collect()
+}
+
+/// Read a WAV file as 16 kHz mono
+pub fn load_speech_samples(path: &Path) -> Result<Vec<f32>, String> {
+    let reader = hound::WavReader::open(path)
+        .map_err(|e| format!("Failed to open audio file (only WAV is supported): {}", e))?;
+    let (samples, format) =
+        pipeline::decode_wav(reader).map_err(|e| format!("Failed to decode audio file: {}", e))?;
+
+    Ok(to_speech_samples(samples, format))
+}
+
+/// Decode WAV data in memory as 16 kHz mono
+pub fn decode_speech_samples(wav: &[u8]) -> Result<Vec<f32>, String> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(wav))
+        .map_err(|e| format!("Failed to read audio (only WAV is supported): {}", e))?;
+    let (samples, format) =
+        pipeline::decode_wav(reader).map_err(|e| format!("Failed to decode audio: {}", e))?;
+
+    Ok(to_speech_samples(samples, format))
+}
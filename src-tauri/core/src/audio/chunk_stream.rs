@@ -0,0 +1,166 @@
+use base64::Engine;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+
+use super::pipeline::AudioFormat;
+use crate::Events;
+
+/// How each streamed chunk is compressed before it crosses the IPC boundary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChunkCompression {
+    None,
+    /// Raw DEFLATE (RFC 1951), decodable with `DecompressionStream("deflate-raw")`
+    #[default]
+    Deflate,
+}
+
+/// Options for emitting processed audio while recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChunkStreamConfig {
+    /// Duration of audio per emitted chunk
+    pub chunk_ms: u32,
+    pub compression: ChunkCompression,
+}
+
+impl Default for ChunkStreamConfig {
+    fn default() -> Self {
+        Self {
+            chunk_ms: 1000,
+            compression: ChunkCompression::Deflate,
+        }
+    }
+}
+
+/// Payload of the `recording-chunk` event. `data` is base64 of (optionally
+/// compressed) little-endian 16-bit PCM in the pipeline's output format.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioChunkEvent {
+    sequence: u64,
+    sample_rate: u32,
+    channels: u16,
+    compression: ChunkCompression,
+    uncompressed_bytes: usize,
+    data: String,
+}
+
+/// Hands processed samples from the audio callback to a worker thread that
+/// batches, encodes, compresses and emits them, keeping that work off the
+/// realtime thread. The worker flushes and exits once the streamer is dropped.
+pub struct ChunkStreamer {
+    sender: mpsc::Sender<Vec<f32>>,
+}
+
+impl ChunkStreamer {
+    pub fn spawn(
+        events: Events,
+        config: ChunkStreamConfig,
+        format: AudioFormat,
+        sequence: Arc<AtomicU64>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel::<Vec<f32>>();
+        let chunk_samples = (format.sample_rate as usize * format.channels as usize)
+            * config.chunk_ms.max(10) as usize
+            / 1000;
+
+        std::thread::spawn(move || {
+            let mut pending: Vec<f32> = Vec::with_capacity(chunk_samples * 2);
+
+            for block in receiver {
+                pending.extend(block);
+                while pending.len() >= chunk_samples {
+                    let chunk: Vec<f32> = pending.drain(..chunk_samples).collect();
+                    emit_chunk(&events, &config, format, &sequence, &chunk);
+                }
+            }
+
+            if !pending.is_empty() {
+                emit_chunk(&events, &config, format, &sequence, &pending);
+            }
+        });
+
+        Self { sender }
+    }
+
+    pub fn push(&self, samples: &[f32]) {
+        let _ = self.sender.send(samples.to_vec());
+    }
+}
+
+/// Encode samples as 16-bit little-endian PCM
+pub fn encode_pcm16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|&s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// Compress a chunk with the configured method
+fn compress(data: Vec<u8>, compression: ChunkCompression) -> std::io::Result<Vec<u8>> {
+    match compression {
+        ChunkCompression::None => Ok(data),
+        ChunkCompression::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+            encoder.write_all(&data)?;
+            encoder.finish()
+        }
+    }
+}
+
+fn emit_chunk(
+    events: &Events,
+    config: &ChunkStreamConfig,
+    format: AudioFormat,
+    sequence: &AtomicU64,
+    samples: &[f32],
+) {
+    let pcm = encode_pcm16(samples);
+    let uncompressed_bytes = pcm.len();
+
+    let data = match compress(pcm, config.compression) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Failed to compress audio chunk: {}", e);
+            return;
+        }
+    };
+
+    events.emit(
+        "recording-chunk",
+        AudioChunkEvent {
+            sequence: sequence.fetch_add(1, Ordering::SeqCst),
+            sample_rate: format.sample_rate,
+            channels: format.channels,
+            compression: config.compression,
+            uncompressed_bytes,
+            data: base64::engine::general_purpose::STANDARD.encode(data),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_deflate_roundtrip_shrinks_silence() {
+        let pcm = encode_pcm16(&[0.0; 16000]);
+        let compressed = compress(pcm.clone(), ChunkCompression::Deflate).unwrap();
+        assert!(compressed.len() < pcm.len() / 10);
+
+        let mut decompressed = Vec::new();
+        DeflateDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, pcm);
+    }
+}
@@ -0,0 +1,151 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// An input device with the id preferences refer to it by
+pub struct InputDevice {
+    /// Stable across launches and re-enumeration; see `stable_id`
+    pub id: String,
+    pub name: String,
+    pub channels: u16,
+    pub device: cpal::Device,
+}
+
+/// Strip the numbering Windows adds to tell identical devices apart, which
+/// changes with the order they were plugged in: "Microphone (2- USB Audio)"
+/// becomes "Microphone (USB Audio)" and "Headset (2)" becomes "Headset"
+fn base_name(name: &str) -> String {
+    let name = name.trim();
+
+    if let Some(stripped) = name.strip_suffix(')') {
+        if let Some((before, inside)) = stripped.rsplit_once(" (") {
+            if !inside.is_empty() && inside.chars().all(|c| c.is_ascii_digit()) {
+                return before.trim_end().to_string();
+            }
+            if let Some((number, rest)) = inside.split_once("- ") {
+                if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                    return format!("{} ({})", before, rest);
+                }
+            }
+        }
+    }
+
+    name.to_string()
+}
+
+/// Id derived from what identifies a device rather than its position: the
+/// audio host, the name without Windows' numbering and the channel count
+fn stable_id(host: &str, name: &str, channels: u16) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(host.as_bytes());
+    hasher.update([0]);
+    hasher.update(base_name(name).as_bytes());
+    hasher.update([0]);
+    hasher.update(channels.to_le_bytes());
+    let digest = hasher.finalize();
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Give identical devices distinct ids, numbered in enumeration order
+fn disambiguate(ids: &mut [String]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for id in ids.iter_mut() {
+        let copies = seen.entry(id.clone()).or_insert(0);
+        *copies += 1;
+        if *copies > 1 {
+            *id = format!("{}-{}", id, copies);
+        }
+    }
+}
+
+/// Input devices with their stable ids
+pub fn input_devices(host: &cpal::Host) -> Result<Vec<InputDevice>, String> {
+    let host_name = host.id().name();
+    let devices: Vec<(String, u16, cpal::Device)> = host
+        .input_devices()
+        .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let channels = device
+                .default_input_config()
+                .map(|config| config.channels())
+                .unwrap_or(0);
+            Some((name, channels, device))
+        })
+        .collect();
+
+    let mut ids: Vec<String> = devices
+        .iter()
+        .map(|(name, channels, _)| stable_id(host_name, name, *channels))
+        .collect();
+    disambiguate(&mut ids);
+
+    Ok(ids
+        .into_iter()
+        .zip(devices)
+        .map(|(id, (name, channels, device))| InputDevice {
+            id,
+            name,
+            channels,
+            device,
+        })
+        .collect())
+}
+
+/// Find an input device by its stable id. Preferences saved before stable
+/// ids existed hold the device name, which is matched as well.
+pub fn find(host: &cpal::Host, id: &str) -> Option<InputDevice> {
+    let devices = input_devices(host).ok()?;
+    let position = devices
+        .iter()
+        .position(|device| device.id == id)
+        .or_else(|| devices.iter().position(|device| device.name == id))
+        .or_else(|| {
+            devices
+                .iter()
+                .position(|device| base_name(&device.name) == base_name(id))
+        })?;
+    devices.into_iter().nth(position)
+}
+
+/// Stable id of the default input device
+pub fn default_id(host: &cpal::Host, devices: &[InputDevice]) -> Option<String> {
+    let name = host.default_input_device()?.name().ok()?;
+    devices
+        .iter()
+        .find(|device| device.name == name)
+        .map(|device| device.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_survive_renumbering() {
+        assert_eq!(
+            base_name("Microphone (2- USB Audio Device)"),
+            "Microphone (USB Audio Device)"
+        );
+        assert_eq!(base_name("Headset (2)"), "Headset");
+        assert_eq!(base_name("Line In (Realtek)"), "Line In (Realtek)");
+
+        let first = stable_id("WASAPI", "Microphone (USB Audio Device)", 1);
+        assert_eq!(
+            first,
+            stable_id("WASAPI", "Microphone (3- USB Audio Device)", 1)
+        );
+        assert_ne!(
+            first,
+            stable_id("WASAPI", "Microphone (USB Audio Device)", 2)
+        );
+        assert_ne!(first, stable_id("ASIO", "Microphone (USB Audio Device)", 1));
+
+        let mut ids = vec![first.clone(), "other".to_string(), first.clone()];
+        disambiguate(&mut ids);
+        assert_eq!(
+            ids,
+            [first.clone(), "other".to_string(), format!("{}-2", first)]
+        );
+    }
+}
@@ -0,0 +1,583 @@
+use serde::{Deserialize, Serialize};
+
+use super::chunk_stream::ChunkStreamConfig;
+
+/// Sample layout of an interleaved f32 buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Sample encoding of the produced WAV file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WavEncoding {
+    /// 16-bit integer PCM, understood by every transcription API
+    #[default]
+    Pcm16,
+    /// 32-bit float, lossless with respect to the captured samples
+    Float32,
+}
+
+/// Voice activity detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VadConfig {
+    /// RMS level (0.0-1.0) above which a block counts as speech
+    pub threshold: f32,
+    /// How long the gate stays open after speech ends
+    pub hangover_ms: u32,
+    /// Drop silent stretches instead of zeroing them, shrinking long recordings
+    pub skip_silence: bool,
+    /// With `skip_silence`, how much of each pause is kept so speech does not run together
+    pub max_gap_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.01,
+            hangover_ms: 300,
+            skip_silence: false,
+            max_gap_ms: 500,
+        }
+    }
+}
+
+/// Capture processing options passed from the frontend to `start_recording`.
+/// Stages run in a fixed order: downmix, resample, filter, VAD; the encoding
+/// is applied when the recording is stopped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordingConfig {
+    /// Average all input channels into a single mono channel
+    pub downmix: bool,
+    /// Resample to this rate (e.g. 16000 for speech models); `None` keeps the device rate
+    pub target_sample_rate: Option<u32>,
+    /// Cutoff of a high-pass filter removing rumble and DC offset
+    pub high_pass_hz: Option<f32>,
+    /// Silence blocks without speech; `None` disables the gate
+    pub vad: Option<VadConfig>,
+    pub encoding: WavEncoding,
+    /// Emit processed audio as `recording-chunk` events while recording
+    pub stream_chunks: Option<ChunkStreamConfig>,
+    /// Note the frontmost app and window title at start, for prompt context.
+    /// Only set once the user has opted in.
+    pub capture_window_context: bool,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            downmix: true,
+            target_sample_rate: None,
+            high_pass_hz: None,
+            vad: None,
+            encoding: WavEncoding::Pcm16,
+            stream_chunks: None,
+            capture_window_context: false,
+        }
+    }
+}
+
+/// A processing step applied to blocks of interleaved samples as they are captured
+pub trait Stage: Send {
+    /// Process one block, returning the samples to hand to the next stage
+    fn process(&mut self, input: Vec<f32>) -> Vec<f32>;
+}
+
+/// Averages all channels of each frame into one
+struct Downmix {
+    channels: usize,
+}
+
+impl Stage for Downmix {
+    fn process(&mut self, input: Vec<f32>) -> Vec<f32> {
+        input
+            .chunks_exact(self.channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect()
+    }
+}
+
+/// Streaming linear-interpolation resampler
+struct Resample {
+    channels: usize,
+    /// Input frames advanced per output frame
+    step: f64,
+    /// Position of the next output frame, relative to the previous block's last frame
+    position: f64,
+    previous: Option<Vec<f32>>,
+}
+
+impl Resample {
+    fn new(channels: usize, from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            channels,
+            step: from_rate as f64 / to_rate as f64,
+            position: 0.0,
+            previous: None,
+        }
+    }
+}
+
+impl Stage for Resample {
+    fn process(&mut self, input: Vec<f32>) -> Vec<f32> {
+        let frames = input.len() / self.channels;
+        if frames == 0 {
+            return Vec::new();
+        }
+
+        // Index 0 is the last frame of the previous block, so interpolation
+        // continues seamlessly across block boundaries
+        let previous = self
+            .previous
+            .take()
+            .unwrap_or_else(|| input[..self.channels].to_vec());
+        let frame_at = |index: usize, channel: usize| match index {
+            0 => previous[channel],
+            _ => input[(index - 1) * self.channels + channel],
+        };
+
+        let mut output = Vec::with_capacity((frames as f64 / self.step) as usize * self.channels);
+        while self.position < frames as f64 {
+            let index = self.position.floor() as usize;
+            let fraction = (self.position - index as f64) as f32;
+            for channel in 0..self.channels {
+                let current = frame_at(index, channel);
+                let next = frame_at(index + 1, channel);
+                output.push(current + (next - current) * fraction);
+            }
+            self.position += self.step;
+        }
+
+        self.position -= frames as f64;
+        self.previous = Some(input[(frames - 1) * self.channels..].to_vec());
+        output
+    }
+}
+
+/// One-pole high-pass filter per channel
+struct HighPass {
+    channels: usize,
+    alpha: f32,
+    previous_input: Vec<f32>,
+    previous_output: Vec<f32>,
+}
+
+impl HighPass {
+    fn new(channels: usize, sample_rate: u32, cutoff_hz: f32) -> Self {
+        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_hz.max(1.0));
+        let dt = 1.0 / sample_rate as f32;
+        Self {
+            channels,
+            alpha: rc / (rc + dt),
+            previous_input: vec![0.0; channels],
+            previous_output: vec![0.0; channels],
+        }
+    }
+}
+
+impl Stage for HighPass {
+    fn process(&mut self, mut input: Vec<f32>) -> Vec<f32> {
+        for frame in input.chunks_mut(self.channels) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let output = self.alpha
+                    * (self.previous_output[channel] + *sample - self.previous_input[channel]);
+                self.previous_input[channel] = *sample;
+                self.previous_output[channel] = output;
+                *sample = output;
+            }
+        }
+        input
+    }
+}
+
+/// Energy-based voice activity gate: blocks without speech are silenced, or
+/// dropped beyond a short gap when skipping silence
+struct VoiceActivityGate {
+    threshold: f32,
+    hangover_samples: usize,
+    /// Samples left before the gate closes again
+    open_for: usize,
+    /// Silent samples to keep per pause; `None` keeps all of them (zeroed)
+    max_gap_samples: Option<usize>,
+    /// Silent samples kept since the gate last closed
+    gap_kept: usize,
+    channels: usize,
+}
+
+impl VoiceActivityGate {
+    fn new(config: &VadConfig, format: AudioFormat) -> Self {
+        let samples_per_ms =
+            |ms: u32| format.sample_rate as usize * format.channels as usize * ms as usize / 1000;
+
+        Self {
+            threshold: config.threshold,
+            hangover_samples: samples_per_ms(config.hangover_ms),
+            open_for: 0,
+            max_gap_samples: config
+                .skip_silence
+                .then(|| samples_per_ms(config.max_gap_ms)),
+            gap_kept: 0,
+            channels: format.channels.max(1) as usize,
+        }
+    }
+}
+
+impl Stage for VoiceActivityGate {
+    fn process(&mut self, mut input: Vec<f32>) -> Vec<f32> {
+        if input.is_empty() {
+            return input;
+        }
+
+        let rms = (input.iter().map(|s| s * s).sum::<f32>() / input.len() as f32).sqrt();
+
+        if rms >= self.threshold {
+            self.open_for = self.hangover_samples;
+            self.gap_kept = 0;
+        } else if self.open_for > 0 {
+            self.open_for = self.open_for.saturating_sub(input.len());
+        } else {
+            input.iter_mut().for_each(|sample| *sample = 0.0);
+
+            if let Some(max_gap) = self.max_gap_samples {
+                // Keep whole frames only, so channels stay aligned
+                let keep = max_gap.saturating_sub(self.gap_kept).min(input.len());
+                let keep = keep - keep % self.channels;
+                input.truncate(keep);
+                self.gap_kept += keep;
+            }
+        }
+
+        input
+    }
+}
+
+/// Ordered chain of stages built from a `RecordingConfig`
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+    output_format: AudioFormat,
+}
+
+impl Pipeline {
+    pub fn new(config: &RecordingConfig, input_format: AudioFormat) -> Self {
+        let mut stages: Vec<Box<dyn Stage>> = Vec::new();
+        let mut format = input_format;
+
+        if config.downmix && format.channels > 1 {
+            stages.push(Box::new(Downmix {
+                channels: format.channels as usize,
+            }));
+            format.channels = 1;
+        }
+
+        if let Some(target_rate) = config.target_sample_rate {
+            if target_rate > 0 && target_rate != format.sample_rate {
+                stages.push(Box::new(Resample::new(
+                    format.channels as usize,
+                    format.sample_rate,
+                    target_rate,
+                )));
+                format.sample_rate = target_rate;
+            }
+        }
+
+        if let Some(cutoff_hz) = config.high_pass_hz {
+            stages.push(Box::new(HighPass::new(
+                format.channels as usize,
+                format.sample_rate,
+                cutoff_hz,
+            )));
+        }
+
+        if let Some(vad) = &config.vad {
+            stages.push(Box::new(VoiceActivityGate::new(vad, format)));
+        }
+
+        Self {
+            stages,
+            output_format: format,
+        }
+    }
+
+    /// Run a block of captured samples through every stage
+    pub fn process(&mut self, block: Vec<f32>) -> Vec<f32> {
+        self.stages
+            .iter_mut()
+            .fold(block, |samples, stage| stage.process(samples))
+    }
+
+    /// Format of the samples produced by `process`
+    pub fn output_format(&self) -> AudioFormat {
+        self.output_format
+    }
+}
+
+/// Peak and RMS level of a block of samples (linear, 0.0-1.0 for full scale)
+pub fn peak_and_rms(samples: &[f32]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    (peak, rms)
+}
+
+/// Convert a linear level to dBFS, flooring silence at -120 dB
+pub fn to_dbfs(level: f32) -> f32 {
+    (20.0 * level.max(1e-6).log10()).max(-120.0)
+}
+
+/// Blend the last `frames` frames before `boundary` into the first frames after
+/// it, so two takes join without a click. The overlap is removed, shortening
+/// the buffer; the fade is clamped to the length of either side.
+pub fn crossfade(samples: &mut Vec<f32>, boundary: usize, frames: usize, channels: u16) {
+    let channels = channels.max(1) as usize;
+    let frames = frames
+        .min(boundary / channels)
+        .min(samples.len().saturating_sub(boundary) / channels);
+    if frames == 0 {
+        return;
+    }
+
+    let overlap = frames * channels;
+    let start = boundary - overlap;
+    for frame in 0..frames {
+        let fade_in = (frame + 1) as f32 / (frames + 1) as f32;
+        for channel in 0..channels {
+            let offset = frame * channels + channel;
+            let outgoing = samples[start + offset];
+            let incoming = samples[boundary + offset];
+            samples[start + offset] = outgoing * (1.0 - fade_in) + incoming * fade_in;
+        }
+    }
+
+    samples.drain(boundary..boundary + overlap);
+}
+
+/// Encode processed samples as a WAV file. `format` must describe the
+/// interleaving of `samples`, otherwise the audio plays at the wrong speed;
+/// a trailing partial frame is dropped.
+pub fn encode_wav(
+    samples: &[f32],
+    format: AudioFormat,
+    encoding: WavEncoding,
+) -> Result<Vec<u8>, hound::Error> {
+    let channels = format.channels.max(1) as usize;
+    let samples = &samples[..samples.len() - samples.len() % channels];
+    let mut cursor = std::io::Cursor::new(Vec::new());
+
+    {
+        let spec = match encoding {
+            WavEncoding::Pcm16 => hound::WavSpec {
+                channels: format.channels,
+                sample_rate: format.sample_rate,
+                bits_per_sample: 16,
+                sample_format: hound::SampleFormat::Int,
+            },
+            WavEncoding::Float32 => hound::WavSpec {
+                channels: format.channels,
+                sample_rate: format.sample_rate,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            },
+        };
+
+        let mut writer = hound::WavWriter::new(&mut cursor, spec)?;
+
+        for &sample in samples {
+            match encoding {
+                WavEncoding::Pcm16 => {
+                    let amplitude = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                    writer.write_sample(amplitude)?;
+                }
+                WavEncoding::Float32 => writer.write_sample(sample)?,
+            }
+        }
+
+        writer.finalize()?;
+    }
+
+    Ok(cursor.into_inner())
+}
+
+/// Decode a WAV file into interleaved f32 samples and their format
+pub fn decode_wav<R: std::io::Read>(
+    reader: hound::WavReader<R>,
+) -> Result<(Vec<f32>, AudioFormat), hound::Error> {
+    let spec = reader.spec();
+    let format = AudioFormat {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+    };
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    Ok((samples, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEREO_48K: AudioFormat = AudioFormat {
+        sample_rate: 48000,
+        channels: 2,
+    };
+
+    #[test]
+    fn test_downmix_averages_channels() {
+        let mut pipeline = Pipeline::new(&RecordingConfig::default(), STEREO_48K);
+        let output = pipeline.process(vec![1.0, 0.0, 0.5, 0.5]);
+
+        assert_eq!(output, vec![0.5, 0.5]);
+        assert_eq!(pipeline.output_format().channels, 1);
+    }
+
+    #[test]
+    fn test_resample_produces_expected_length() {
+        let config = RecordingConfig {
+            target_sample_rate: Some(16000),
+            ..Default::default()
+        };
+        let mut pipeline = Pipeline::new(&config, STEREO_48K);
+
+        // 480 stereo frames at 48 kHz, split over blocks, become ~160 mono frames at 16 kHz
+        let total: usize = (0..4)
+            .map(|_| pipeline.process(vec![0.25; 240]).len())
+            .sum();
+
+        assert_eq!(total, 160);
+        assert_eq!(pipeline.output_format().sample_rate, 16000);
+    }
+
+    #[test]
+    fn test_vad_silences_quiet_blocks() {
+        let config = RecordingConfig {
+            vad: Some(VadConfig {
+                threshold: 0.1,
+                hangover_ms: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mono = AudioFormat {
+            sample_rate: 16000,
+            channels: 1,
+        };
+        let mut pipeline = Pipeline::new(&config, mono);
+
+        assert_eq!(pipeline.process(vec![0.01; 4]), vec![0.0; 4]);
+        assert_eq!(pipeline.process(vec![0.5; 4]), vec![0.5; 4]);
+    }
+
+    #[test]
+    fn test_skip_silence_keeps_short_gap_only() {
+        let config = RecordingConfig {
+            vad: Some(VadConfig {
+                threshold: 0.1,
+                hangover_ms: 0,
+                skip_silence: true,
+                max_gap_ms: 1,
+            }),
+            ..Default::default()
+        };
+        let mono = AudioFormat {
+            sample_rate: 16000,
+            channels: 1,
+        };
+        let mut pipeline = Pipeline::new(&config, mono);
+
+        // 1 ms at 16 kHz: 16 samples of each pause survive
+        assert_eq!(pipeline.process(vec![0.5; 10]).len(), 10);
+        assert_eq!(pipeline.process(vec![0.01; 10]), vec![0.0; 10]);
+        assert_eq!(pipeline.process(vec![0.01; 10]), vec![0.0; 6]);
+        assert!(pipeline.process(vec![0.01; 1000]).is_empty());
+
+        // Speech resets the allowance for the next pause
+        assert_eq!(pipeline.process(vec![0.5; 10]).len(), 10);
+        assert_eq!(pipeline.process(vec![0.01; 10]).len(), 10);
+    }
+
+    #[test]
+    fn test_levels() {
+        let (peak, rms) = peak_and_rms(&[0.5, -1.0, 0.5, -0.5]);
+        assert_eq!(peak, 1.0);
+        assert!((rms - 0.661).abs() < 0.001);
+
+        assert_eq!(to_dbfs(1.0), 0.0);
+        assert!((to_dbfs(0.5) + 6.02).abs() < 0.01);
+        assert_eq!(to_dbfs(0.0), -120.0);
+    }
+
+    #[test]
+    fn test_crossfade_blends_and_removes_overlap() {
+        let mut samples = vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+        crossfade(&mut samples, 4, 3, 1);
+
+        assert_eq!(samples.len(), 5);
+        assert_eq!(samples[0], 1.0);
+        assert_eq!(samples[1], 0.75);
+        assert_eq!(samples[2], 0.5);
+        assert_eq!(samples[3], 0.25);
+        assert_eq!(samples[4], 0.0);
+
+        // Nothing recorded after the boundary yet: leave the buffer alone
+        let mut samples = vec![1.0; 4];
+        crossfade(&mut samples, 4, 3, 1);
+        assert_eq!(samples, vec![1.0; 4]);
+    }
+
+    #[test]
+    fn test_encode_wav_uses_output_format() {
+        let wav = encode_wav(&[0.0; 8], STEREO_48K, WavEncoding::Float32).unwrap();
+        let reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.spec().sample_rate, 48000);
+        assert_eq!(reader.spec().bits_per_sample, 32);
+    }
+
+    #[test]
+    fn test_decode_wav_roundtrip() {
+        let wav = encode_wav(&[0.5, -0.5, 0.25, 0.0], STEREO_48K, WavEncoding::Pcm16).unwrap();
+        let reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+        let (samples, format) = decode_wav(reader).unwrap();
+
+        assert_eq!(format, STEREO_48K);
+        assert_eq!(samples.len(), 4);
+        assert!((samples[0] - 0.5).abs() < 0.001);
+        assert!((samples[1] + 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_multichannel_recording_keeps_duration() {
+        let config = RecordingConfig {
+            downmix: false,
+            ..Default::default()
+        };
+
+        for config in [config, RecordingConfig::default()] {
+            let mut pipeline = Pipeline::new(&config, STEREO_48K);
+            // Half a second of stereo audio plus a stray sample
+            let samples = pipeline.process(vec![0.1; 48001]);
+            let wav = encode_wav(&samples, pipeline.output_format(), WavEncoding::Pcm16).unwrap();
+            let reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+
+            assert_eq!(reader.duration(), 24000);
+            assert_eq!(reader.spec().channels, pipeline.output_format().channels);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+/// Descriptive metadata written into the RIFF `LIST/INFO` chunk of a WAV file
+#[derive(Debug, Clone, Default)]
+pub struct WavMetadata {
+    /// Creation timestamp (ICRD), ISO 8601
+    pub created_at: String,
+    /// Name and version of the producing application (ISFT)
+    pub software: String,
+    /// Input device the audio was captured from (ISRF, "source form")
+    pub device_name: Option<String>,
+    /// Title of the recording (INAM)
+    pub title: Option<String>,
+    /// Free-form comment (ICMT)
+    pub comment: Option<String>,
+}
+
+impl WavMetadata {
+    /// Metadata for a recording captured now by this app
+    pub fn for_recording(
+        device_name: Option<String>,
+        title: Option<String>,
+        comment: Option<String>,
+    ) -> Self {
+        Self {
+            created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            software: format!("Voice Assistant {}", env!("CARGO_PKG_VERSION")),
+            device_name,
+            title,
+            comment,
+        }
+    }
+
+    /// INFO subchunks in writing order, skipping empty values
+    fn entries(&self) -> Vec<(&'static [u8; 4], &str)> {
+        [
+            (b"ICRD", Some(self.created_at.as_str())),
+            (b"ISFT", Some(self.software.as_str())),
+            (b"ISRF", self.device_name.as_deref()),
+            (b"INAM", self.title.as_deref()),
+            (b"ICMT", self.comment.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| value.filter(|v| !v.is_empty()).map(|v| (id, v)))
+        .collect()
+    }
+}
+
+/// Append a `LIST/INFO` chunk to a complete WAV file and fix up the RIFF size
+pub fn append_info_chunk(mut wav: Vec<u8>, metadata: &WavMetadata) -> Vec<u8> {
+    let mut info = b"INFO".to_vec();
+
+    for (id, value) in metadata.entries() {
+        // Values are NUL-terminated and padded to an even length
+        let mut data = value.as_bytes().to_vec();
+        data.push(0);
+
+        info.extend_from_slice(id);
+        info.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        info.extend_from_slice(&data);
+        if data.len() % 2 == 1 {
+            info.push(0);
+        }
+    }
+
+    wav.extend_from_slice(b"LIST");
+    wav.extend_from_slice(&(info.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&info);
+
+    let riff_size = (wav.len() - 8) as u32;
+    wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_wav() -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        writer.write_sample(0i16).unwrap();
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn test_info_chunk_keeps_wav_readable() {
+        let metadata = WavMetadata {
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            software: "Voice Assistant 0.1.0".to_string(),
+            device_name: Some("USB Mic".to_string()),
+            title: Some("Standup".to_string()),
+            comment: None,
+        };
+        let wav = append_info_chunk(empty_wav(), &metadata);
+
+        let riff_size = u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_size, wav.len() - 8);
+
+        let reader = hound::WavReader::new(std::io::Cursor::new(&wav)).unwrap();
+        assert_eq!(reader.len(), 1);
+
+        let needle = b"INAM\x08\x00\x00\x00Standup\x00";
+        assert!(wav.windows(needle.len()).any(|w| w == needle));
+        assert!(!wav.windows(4).any(|w| w == b"ICMT"));
+    }
+}
@@ -0,0 +1,900 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::RwLock;
+pub use zeroize::{Zeroize, Zeroizing};
+
+/// A 256-bit key, wiped from memory when dropped
+pub type Key = Zeroizing<[u8; 32]>;
+
+/// Plaintext used to verify that a key can encrypt and decrypt
+const ROUNDTRIP_PROBE: &[u8] = b"voice-assistant-key-health-probe";
+
+/// Highest Argon2id cost accepted from a file, so crafted parameters in a
+/// backup cannot tie up a thread for hours or exhaust memory
+const MAX_ARGON2_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_ARGON2_ITERATIONS: u32 = 10;
+const MAX_ARGON2_PARALLELISM: u32 = 4;
+
+/// Key derivation of data sealed with a passphrase
+const PASSPHRASE_KDF: &str = "argon2id";
+
+/// Returned by secure storage operations while its passphrase is not entered
+pub const STORE_LOCKED_ERROR: &str = "Secure storage is locked. Unlock it with your passphrase.";
+
+/// Where the master key of secure storage comes from
+enum StoreKey {
+    /// Derived from the machine ID
+    Machine,
+    /// Protected by a passphrase that was not entered yet
+    Locked,
+    /// Derived from the entered passphrase; only ever kept in memory
+    Passphrase(Key),
+}
+
+static STORE_KEY: RwLock<StoreKey> = RwLock::new(StoreKey::Machine);
+
+/// Supplies the machine key; see `set_machine_key_source`
+type MachineKeySource = Box<dyn Fn() -> Result<Key, String> + Send + Sync>;
+
+static MACHINE_KEY_SOURCE: RwLock<Option<MachineKeySource>> = RwLock::new(None);
+
+/// Random key used in place of the machine key where no machine ID can be
+/// read; see `use_fallback_key`
+static FALLBACK_KEY: RwLock<Option<Key>> = RwLock::new(None);
+
+/// Service name mixed into the key of secure values; see `set_service_name`
+static SERVICE_NAME: RwLock<Option<String>> = RwLock::new(None);
+
+/// What the key of secure storage is derived from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyBackend {
+    /// The machine ID
+    MachineId,
+    /// A random key in a file next to the data, because no machine ID is
+    /// available. Anyone who can read the file can decrypt the data.
+    KeyFile,
+    /// The user's passphrase
+    Passphrase,
+}
+
+/// Independent key domains derived from the master key, so compromise or
+/// rotation of one context's key leaves the others untouched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    /// Values in the secure storage directory (API keys, tokens)
+    SecureValues,
+    /// Encrypted history entries and exports
+    History,
+    /// Payloads exchanged with sync backends
+    Sync,
+    /// Recordings waiting in the offline transcription queue
+    PendingAudio,
+    /// Entries of the secure storage audit log
+    AuditLog,
+}
+
+impl KeyContext {
+    /// HKDF info label. Bumping the version rotates the key of a single context.
+    fn label(self) -> &'static [u8] {
+        match self {
+            KeyContext::SecureValues => b"voice-assistant/secure-values/v1",
+            KeyContext::History => b"voice-assistant/history/v1",
+            KeyContext::Sync => b"voice-assistant/sync/v1",
+            KeyContext::PendingAudio => b"voice-assistant/pending-audio/v1",
+            KeyContext::AuditLog => b"voice-assistant/audit-log/v1",
+        }
+    }
+}
+
+/// Outcome of a single diagnostic check on the key infrastructure
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl DiagnosticCheck {
+    pub fn new(name: &str, result: Result<String, String>) -> Self {
+        let ok = result.is_ok();
+        Self {
+            name: name.to_string(),
+            ok,
+            detail: result.unwrap_or_else(|e| e),
+        }
+    }
+}
+
+/// Generate a consistent 32-byte key based on the machine's unique ID
+/// This replaces the OS Keyring to prevent UI blocking/hanging
+fn get_machine_key() -> Result<Key, String> {
+    if let Some(key) = &*FALLBACK_KEY.read().unwrap_or_else(|e| e.into_inner()) {
+        return Ok(key.clone());
+    }
+    if let Some(source) = &*MACHINE_KEY_SOURCE.read().unwrap_or_else(|e| e.into_inner()) {
+        return source();
+    }
+    read_machine_key()
+}
+
+/// Derive the machine key from the machine ID. Reading it can mean running a
+/// system tool, which is too slow to repeat for every value, so the app
+/// keeps the key and hands it out through `set_machine_key_source`.
+pub fn read_machine_key() -> Result<Key, String> {
+    let machine_id = machine_uid::get().map_err(|e| format!("Could not get machine ID: {}", e))?;
+    Ok(derive_machine_key(&machine_id))
+}
+
+/// Take the machine key from `source` instead of reading the machine ID for
+/// every operation
+pub fn set_machine_key_source(source: impl Fn() -> Result<Key, String> + Send + Sync + 'static) {
+    *MACHINE_KEY_SOURCE.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(source));
+}
+
+/// Whether a machine ID can be read to derive the machine key from
+pub fn machine_id_available() -> bool {
+    machine_uid::get().is_ok_and(|machine_id| !machine_id.trim().is_empty())
+}
+
+/// A new random key, e.g. for `use_fallback_key`
+pub fn generate_key() -> Key {
+    let mut key = Key::default();
+    OsRng.fill_bytes(key.as_mut_slice());
+    key
+}
+
+/// Use `key` in place of the machine key, for machines without a machine ID.
+/// The caller keeps it somewhere it survives restarts.
+pub fn use_fallback_key(key: Key) {
+    *FALLBACK_KEY.write().unwrap_or_else(|e| e.into_inner()) = Some(key);
+}
+
+/// Give the secure values of `service` (e.g. an app identifier and storage
+/// profile) a key of their own, so builds sharing a machine can neither read
+/// nor overwrite each other's values. `None` keeps the key secure values had
+/// before service names existed.
+pub fn set_service_name(service: Option<String>) {
+    *SERVICE_NAME.write().unwrap_or_else(|e| e.into_inner()) = service;
+}
+
+/// What the key of secure values currently comes from
+pub fn key_backend() -> KeyBackend {
+    if !matches!(
+        *STORE_KEY.read().unwrap_or_else(|e| e.into_inner()),
+        StoreKey::Machine
+    ) {
+        KeyBackend::Passphrase
+    } else if FALLBACK_KEY.read().unwrap_or_else(|e| e.into_inner()).is_some() {
+        KeyBackend::KeyFile
+    } else {
+        KeyBackend::MachineId
+    }
+}
+
+/// Derive the 32-byte key from a machine ID
+fn derive_machine_key(machine_id: &str) -> Key {
+    // Hash the machine ID to get a fixed-length 32-byte key
+    let mut hasher = Sha256::new();
+    hasher.update(machine_id.as_bytes());
+    // Optional: Add a hardcoded "salt" specific to your app to ensure key uniqueness
+    hasher.update(b"voice-assistant-v1-salt");
+
+    let mut result = hasher.finalize();
+    let mut key = Key::default();
+    key.copy_from_slice(&result);
+    result.zeroize();
+    key
+}
+
+/// Internal encryption function that accepts a key directly
+/// Used for testing and by the public encrypt function
+fn encrypt_with_key(data: &[u8], key: &[u8; 32]) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+
+    // Generate random nonce (96-bits / 12 bytes for AES-GCM)
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    // Encrypt
+    let ciphertext = cipher
+        .encrypt(&nonce, data)
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    // Combine nonce and ciphertext: [Nonce (12 bytes)] + [Ciphertext]
+    let mut combined = nonce.to_vec();
+    combined.extend(ciphertext);
+
+    // Encode as base64
+    Ok(general_purpose::STANDARD.encode(combined))
+}
+
+/// Internal decryption function that accepts a key directly
+/// Used for testing and by the public decrypt functions
+fn decrypt_with_key(encrypted_str: &str, key: &[u8; 32]) -> Result<Zeroizing<Vec<u8>>, String> {
+    // Decode base64
+    let encrypted_data = general_purpose::STANDARD
+        .decode(encrypted_str)
+        .map_err(|e| format!("Invalid Base64: {}", e))?;
+
+    if encrypted_data.len() < 12 {
+        return Err("Data too short to contain nonce".to_string());
+    }
+
+    // Split nonce and ciphertext
+    // AES-GCM Standard Nonce is 12 bytes
+    let nonce = Nonce::from_slice(&encrypted_data[0..12]);
+    let ciphertext = &encrypted_data[12..];
+
+    let cipher = Aes256Gcm::new(key.into());
+
+    // Decrypt
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|e| format!("Decryption failed: {}", e))
+}
+
+/// Whether `data` has the shape of `encrypt` output: base64 of a nonce and
+/// at least an authentication tag. Anything else was never encrypted, such
+/// as a value written in plain text by an old version.
+pub fn looks_encrypted(data: &str) -> bool {
+    general_purpose::STANDARD
+        .decode(data.trim())
+        .is_ok_and(|bytes| bytes.len() >= 12 + 16)
+}
+
+/// Derive the key for one context from the master key using HKDF-SHA256
+fn derive_context_key(master_key: &[u8; 32], context: KeyContext) -> Key {
+    let service = SERVICE_NAME.read().unwrap_or_else(|e| e.into_inner()).clone();
+    derive_service_context_key(master_key, context, service.as_deref())
+}
+
+/// `derive_context_key` for secure values of `service`; other contexts are
+/// shared by every service
+fn derive_service_context_key(
+    master_key: &[u8; 32],
+    context: KeyContext,
+    service: Option<&str>,
+) -> Key {
+    let mut info = context.label().to_vec();
+    if let (KeyContext::SecureValues, Some(service)) = (context, service) {
+        info.push(b'/');
+        info.extend_from_slice(service.as_bytes());
+    }
+
+    let hkdf = Hkdf::<Sha256>::new(None, master_key);
+    let mut key = Key::default();
+    hkdf.expand(&info, key.as_mut_slice())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// The master key of secure storage, which may come from a passphrase
+fn store_master_key() -> Result<Key, String> {
+    match &*STORE_KEY.read().unwrap_or_else(|e| e.into_inner()) {
+        StoreKey::Machine => get_machine_key(),
+        StoreKey::Locked => Err(STORE_LOCKED_ERROR.to_string()),
+        StoreKey::Passphrase(key) => Ok(key.clone()),
+    }
+}
+
+fn set_store_key(store_key: StoreKey) {
+    *STORE_KEY.write().unwrap_or_else(|e| e.into_inner()) = store_key;
+}
+
+/// Forget the passphrase key; secure storage fails with `STORE_LOCKED_ERROR`
+/// until `unlock_store`
+pub fn lock_store() {
+    set_store_key(StoreKey::Locked);
+}
+
+/// Use a key from `derive_store_key` for secure storage
+pub fn unlock_store(key: Key) {
+    set_store_key(StoreKey::Passphrase(key));
+}
+
+/// Go back to the machine key for secure storage
+pub fn use_machine_key() {
+    set_store_key(StoreKey::Machine);
+}
+
+pub fn is_store_locked() -> bool {
+    matches!(
+        *STORE_KEY.read().unwrap_or_else(|e| e.into_inner()),
+        StoreKey::Locked
+    )
+}
+
+/// The machine ID based key, which secure storage uses without a passphrase
+pub fn machine_key() -> Result<Key, String> {
+    get_machine_key()
+}
+
+/// Argon2id settings and salt of a passphrase-protected store, kept next to
+/// the store in plain text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassphraseKeyParams {
+    /// Base64
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Mix in the machine key, so the passphrase alone cannot open a copy
+    /// of the store on another machine
+    pub bind_to_machine: bool,
+}
+
+impl PassphraseKeyParams {
+    /// Fresh salt with the OWASP recommended Argon2id cost (19 MiB, 2 passes)
+    pub fn new(bind_to_machine: bool) -> Self {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            salt: general_purpose::STANDARD.encode(salt),
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+            bind_to_machine,
+        }
+    }
+}
+
+/// Derive the master key of secure storage from a passphrase with Argon2id.
+/// Deliberately slow; call it off the async runtime.
+pub fn derive_store_key(
+    passphrase: &str,
+    params: &PassphraseKeyParams,
+) -> Result<Key, String> {
+    let in_range = params.memory_kib <= MAX_ARGON2_MEMORY_KIB
+        && (1..=MAX_ARGON2_ITERATIONS).contains(&params.iterations)
+        && (1..=MAX_ARGON2_PARALLELISM).contains(&params.parallelism);
+    if !in_range {
+        return Err("Passphrase key parameters are out of range".to_string());
+    }
+
+    let salt = general_purpose::STANDARD
+        .decode(&params.salt)
+        .map_err(|e| format!("Invalid salt: {}", e))?;
+    let argon2_params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(32),
+    )
+    .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+
+    let mut key = Key::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params)
+        .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut_slice())
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+
+    if !params.bind_to_machine {
+        return Ok(key);
+    }
+
+    let hkdf = Hkdf::<Sha256>::new(Some(key.as_slice()), get_machine_key()?.as_slice());
+    let mut bound = Key::default();
+    hkdf.expand(b"voice-assistant/passphrase-bound/v1", bound.as_mut_slice())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Ok(bound)
+}
+
+/// The key of one context, looked up once for a batch of operations. Both
+/// keys are wiped when it is dropped.
+pub struct ContextKey {
+    master: Key,
+    key: Key,
+}
+
+impl ContextKey {
+    /// The key of `context` on this machine. Secure values use the
+    /// passphrase key instead when the store has one.
+    pub fn current(context: KeyContext) -> Result<Self, String> {
+        let master_key = match context {
+            KeyContext::SecureValues => store_master_key()?,
+            _ => get_machine_key()?,
+        };
+        Ok(Self::from_master(&master_key, context))
+    }
+
+    /// The key of `context` given an explicit master key (e.g. a recovery key)
+    pub fn from_master(master_key: &[u8; 32], context: KeyContext) -> Self {
+        Self {
+            master: Zeroizing::new(*master_key),
+            key: derive_context_key(master_key, context),
+        }
+    }
+
+    /// Returns base64-encoded encrypted data with nonce prepended
+    pub fn encrypt(&self, data: &[u8]) -> Result<String, String> {
+        encrypt_with_key(data, &self.key)
+    }
+
+    pub fn decrypt(&self, encrypted_data: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+        decrypt_with_key(encrypted_data, &self.key)
+    }
+
+    /// Data written before per-context keys existed was encrypted with the
+    /// master key itself, so that is tried as a fallback. Only for migrating
+    /// such data; anything written since must use `decrypt`.
+    pub fn decrypt_legacy(&self, encrypted_data: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+        self.decrypt(encrypted_data)
+            .or_else(|e| decrypt_with_key(encrypted_data, &self.master).map_err(|_| e))
+    }
+
+    /// Encrypt `data_key` for storing next to the data it encrypts
+    pub fn wrap_key(&self, data_key: &DataKey) -> Result<String, String> {
+        self.encrypt(data_key.0.as_slice())
+    }
+
+    /// The data key `wrapped` by `wrap_key`
+    pub fn unwrap_key(&self, wrapped: &str) -> Result<DataKey, String> {
+        let bytes = self.decrypt(wrapped)?;
+        let mut key = Key::default();
+        if bytes.len() != key.len() {
+            return Err("Invalid data key length".to_string());
+        }
+        key.copy_from_slice(&bytes);
+        Ok(DataKey(key))
+    }
+}
+
+/// A random key for a single piece of data, stored wrapped by a context key.
+/// Changing the context key then only means wrapping the data keys again,
+/// and no two pieces of data share a key.
+#[derive(Clone)]
+pub struct DataKey(Key);
+
+impl DataKey {
+    pub fn generate() -> Self {
+        Self(generate_key())
+    }
+
+    /// Returns base64-encoded encrypted data with nonce prepended
+    pub fn encrypt(&self, data: &[u8]) -> Result<String, String> {
+        encrypt_with_key(data, &self.0)
+    }
+
+    pub fn decrypt(&self, encrypted_data: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+        decrypt_with_key(encrypted_data, &self.0)
+    }
+}
+
+/// Encrypt data using AES-256-GCM with the key of `context` on this machine
+/// Returns base64-encoded encrypted data with nonce prepended
+pub fn encrypt(context: KeyContext, data: &[u8]) -> Result<String, String> {
+    ContextKey::current(context)?.encrypt(data)
+}
+
+/// Decrypt data using AES-256-GCM with the key of `context` on this machine
+/// Takes base64-encoded encrypted data with nonce prepended
+pub fn decrypt(context: KeyContext, encrypted_data: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    ContextKey::current(context)?.decrypt(encrypted_data)
+}
+
+/// Decrypt data for `context` given an explicit master key (e.g. a recovery key)
+pub fn decrypt_with_master_key(
+    context: KeyContext,
+    encrypted_data: &str,
+    master_key: &[u8; 32],
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    ContextKey::from_master(master_key, context).decrypt(encrypted_data)
+}
+
+/// Short identifier of a key, safe to store next to the data it encrypts.
+/// Used to detect that the machine key changed (e.g. after an OS reinstall).
+pub fn key_fingerprint(key: &[u8; 32]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"voice-assistant-key-fingerprint");
+    hasher.update(key);
+
+    hasher.finalize()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Fingerprint of the master key currently used for secure values
+pub fn current_key_fingerprint() -> Result<String, String> {
+    Ok(key_fingerprint(&*store_master_key()?))
+}
+
+/// Export the master key of secure values as a base64 recovery key that can
+/// later be passed to `decode_recovery_key` to read them on another machine
+pub fn export_recovery_key() -> Result<String, String> {
+    Ok(general_purpose::STANDARD.encode(store_master_key()?.as_slice()))
+}
+
+/// Decode a recovery key produced by `export_recovery_key`
+pub fn decode_recovery_key(recovery_key: &str) -> Result<Key, String> {
+    let bytes = Zeroizing::new(
+        general_purpose::STANDARD
+            .decode(recovery_key.trim())
+            .map_err(|e| format!("Invalid recovery key: {}", e))?,
+    );
+
+    let mut key = Key::default();
+    if bytes.len() != key.len() {
+        return Err("Invalid recovery key: expected 32 bytes".to_string());
+    }
+    key.copy_from_slice(&bytes);
+    Ok(key)
+}
+
+/// Data encrypted with a key derived from a passphrase rather than the
+/// machine key, so it can be opened on another machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassphraseSealed {
+    /// Key derivation function, currently always "argon2id"
+    pub kdf: String,
+    /// Base64
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Base64 nonce and ciphertext, as produced by `encrypt`
+    pub data: String,
+}
+
+impl PassphraseSealed {
+    /// The key derivation settings, never bound to a machine
+    fn key_params(&self) -> PassphraseKeyParams {
+        PassphraseKeyParams {
+            salt: self.salt.clone(),
+            memory_kib: self.memory_kib,
+            iterations: self.iterations,
+            parallelism: self.parallelism,
+            bind_to_machine: false,
+        }
+    }
+}
+
+fn seal_with_params(
+    passphrase: &str,
+    data: &[u8],
+    params: &PassphraseKeyParams,
+) -> Result<PassphraseSealed, String> {
+    let key = derive_store_key(passphrase, params)?;
+
+    Ok(PassphraseSealed {
+        kdf: PASSPHRASE_KDF.to_string(),
+        salt: params.salt.clone(),
+        memory_kib: params.memory_kib,
+        iterations: params.iterations,
+        parallelism: params.parallelism,
+        data: encrypt_with_key(data, &key)?,
+    })
+}
+
+/// Encrypt `data` with a key derived from `passphrase` with Argon2id.
+/// Deliberately slow; call it off the async runtime.
+pub fn seal_with_passphrase(passphrase: &str, data: &[u8]) -> Result<PassphraseSealed, String> {
+    seal_with_params(passphrase, data, &PassphraseKeyParams::new(false))
+}
+
+/// Decrypt data sealed by `seal_with_passphrase`. The key derivation cost
+/// comes from the file, so it is rejected outside a fixed range.
+pub fn open_with_passphrase(
+    sealed: &PassphraseSealed,
+    passphrase: &str,
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    if sealed.kdf != PASSPHRASE_KDF {
+        return Err(format!("Unsupported key derivation: {}", sealed.kdf));
+    }
+    let key = derive_store_key(passphrase, &sealed.key_params())?;
+
+    decrypt_with_key(&sealed.data, &key)
+        .map_err(|_| "Wrong passphrase or damaged data".to_string())
+}
+
+/// Encrypt and decrypt a probe value to verify the key works end to end
+fn roundtrip_check(key: &[u8; 32]) -> Result<String, String> {
+    let encrypted = encrypt_with_key(ROUNDTRIP_PROBE, key)?;
+    let decrypted = decrypt_with_key(&encrypted, key)?;
+
+    if decrypted.as_slice() != ROUNDTRIP_PROBE {
+        return Err("Decrypted probe does not match the original".to_string());
+    }
+
+    Ok("Encrypt/decrypt roundtrip succeeded".to_string())
+}
+
+/// Check that the key source is reachable, yields valid key material and
+/// that the derived key can roundtrip data.
+pub fn run_key_diagnostics() -> Vec<DiagnosticCheck> {
+    if let Some(key) = &*FALLBACK_KEY.read().unwrap_or_else(|e| e.into_inner()) {
+        return vec![
+            DiagnosticCheck::new(
+                "keySource",
+                Ok("No machine ID, using the fallback key file (reduced security)".to_string()),
+            ),
+            DiagnosticCheck::new("roundtrip", roundtrip_check(key)),
+        ];
+    }
+
+    let machine_id = machine_uid::get().map_err(|e| format!("Could not get machine ID: {}", e));
+
+    let mut checks = vec![DiagnosticCheck::new(
+        "keySource",
+        machine_id
+            .as_ref()
+            .map(|_| "Machine ID is readable".to_string())
+            .map_err(Clone::clone),
+    )];
+
+    let machine_id = match machine_id {
+        Ok(id) => id,
+        Err(_) => return checks,
+    };
+
+    let trimmed = machine_id.trim();
+    let key = derive_machine_key(&machine_id);
+    checks.push(DiagnosticCheck::new(
+        "keyMaterial",
+        if trimmed.is_empty() {
+            Err("Machine ID is empty, the derived key is not machine-specific".to_string())
+        } else {
+            Ok(format!("Derived a {}-byte key", key.len()))
+        },
+    ));
+
+    checks.push(DiagnosticCheck::new("roundtrip", roundtrip_check(&key)));
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test helper: generate a test key
+    fn test_key() -> [u8; 32] {
+        [42u8; 32] // Fixed key for testing
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let original_data = b"Hello, this is sensitive data!";
+        let key = test_key();
+
+        // Encrypt
+        let encrypted = encrypt_with_key(original_data, &key).expect("Encryption should succeed");
+
+        // Verify encrypted data is different from original
+        assert_ne!(encrypted, String::from_utf8_lossy(original_data));
+
+        // Decrypt
+        let decrypted = decrypt_with_key(&encrypted, &key).expect("Decryption should succeed");
+
+        // Verify decrypted matches original
+        assert_eq!(*decrypted, original_data);
+    }
+
+    #[test]
+    fn test_encrypt_produces_different_ciphertext() {
+        let data = b"Same data";
+        let key = test_key();
+
+        let encrypted1 = encrypt_with_key(data, &key).expect("Encryption should succeed");
+        let encrypted2 = encrypt_with_key(data, &key).expect("Encryption should succeed");
+
+        // Due to random nonce, same plaintext should produce different ciphertext
+        assert_ne!(encrypted1, encrypted2);
+
+        // But both should decrypt to same plaintext
+        let decrypted1 = decrypt_with_key(&encrypted1, &key).expect("Decryption should succeed");
+        let decrypted2 = decrypt_with_key(&encrypted2, &key).expect("Decryption should succeed");
+        assert_eq!(decrypted1, decrypted2);
+        assert_eq!(*decrypted1, data);
+    }
+
+    #[test]
+    fn test_decrypt_invalid_base64() {
+        let key = test_key();
+        let result = decrypt_with_key("not-valid-base64!!!", &key);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decrypt_too_short() {
+        let key = test_key();
+        let result = decrypt_with_key("YWJj", &key); // "abc" (3 bytes)
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("too short"));
+    }
+
+    #[test]
+    fn test_decrypt_corrupted_data() {
+        let key = test_key();
+        let original = b"test data";
+        let mut encrypted = encrypt_with_key(original, &key).expect("Encryption should succeed");
+
+        // Corrupt the encrypted data
+        if let Some(last_char) = encrypted.pop() {
+            encrypted.push(if last_char == 'A' { 'B' } else { 'A' });
+        }
+
+        let result = decrypt_with_key(&encrypted, &key);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_check() {
+        let key = test_key();
+        assert!(roundtrip_check(&key).is_ok());
+    }
+
+    #[test]
+    fn test_derive_machine_key_is_stable() {
+        assert_eq!(
+            derive_machine_key("machine-a"),
+            derive_machine_key("machine-a")
+        );
+        assert_ne!(
+            derive_machine_key("machine-a"),
+            derive_machine_key("machine-b")
+        );
+    }
+
+    #[test]
+    fn test_recovery_key_roundtrip() {
+        let key = test_key();
+        let recovery_key = general_purpose::STANDARD.encode(key);
+
+        assert_eq!(*decode_recovery_key(&recovery_key).unwrap(), key);
+        assert!(decode_recovery_key("YWJj").is_err());
+    }
+
+    #[test]
+    fn test_service_keys_are_independent() {
+        let master = test_key();
+        let derive = |context, service| derive_service_context_key(&master, context, service);
+        let default_key = derive(KeyContext::SecureValues, None);
+
+        assert_eq!(
+            default_key,
+            derive_context_key(&master, KeyContext::SecureValues)
+        );
+        assert_ne!(default_key, derive(KeyContext::SecureValues, Some("dev")));
+        assert_ne!(
+            derive(KeyContext::SecureValues, Some("dev")),
+            derive(KeyContext::SecureValues, Some("work"))
+        );
+        assert_eq!(
+            derive(KeyContext::History, None),
+            derive(KeyContext::History, Some("dev"))
+        );
+    }
+
+    #[test]
+    fn test_data_key_wrapping() {
+        let data_key = DataKey::generate();
+        let encrypted = data_key.encrypt(b"secret").unwrap();
+
+        let context_key = ContextKey::from_master(&test_key(), KeyContext::SecureValues);
+        let wrapped = context_key.wrap_key(&data_key).unwrap();
+        let unwrapped = context_key.unwrap_key(&wrapped).unwrap();
+        assert_eq!(*unwrapped.decrypt(&encrypted).unwrap(), b"secret");
+
+        let other_key = ContextKey::from_master(&[7u8; 32], KeyContext::SecureValues);
+        assert!(other_key.unwrap_key(&wrapped).is_err());
+        assert!(DataKey::generate().decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_key_fingerprint_differs_per_key() {
+        assert_eq!(key_fingerprint(&test_key()).len(), 32);
+        assert_ne!(key_fingerprint(&test_key()), key_fingerprint(&[7u8; 32]));
+    }
+
+    #[test]
+    fn test_context_keys_are_independent() {
+        let master = test_key();
+        let history_key = derive_context_key(&master, KeyContext::History);
+        let encrypted =
+            encrypt_with_key(b"secret", &history_key).expect("Encryption should succeed");
+
+        assert!(decrypt_with_master_key(KeyContext::History, &encrypted, &master).is_ok());
+        assert!(decrypt_with_master_key(KeyContext::SecureValues, &encrypted, &master).is_err());
+        assert!(decrypt_with_master_key(KeyContext::Sync, &encrypted, &master).is_err());
+    }
+
+    #[test]
+    fn test_context_key_roundtrip() {
+        let key = ContextKey::from_master(&test_key(), KeyContext::SecureValues);
+        let encrypted = key.encrypt(b"secret").expect("Encryption should succeed");
+
+        assert_eq!(*key.decrypt(&encrypted).unwrap(), b"secret");
+        assert_eq!(
+            *decrypt_with_master_key(KeyContext::SecureValues, &encrypted, &test_key()).unwrap(),
+            b"secret"
+        );
+    }
+
+    #[test]
+    fn test_looks_encrypted() {
+        let encrypted = encrypt_with_key(b"", &test_key()).expect("Encryption should succeed");
+        assert!(looks_encrypted(&encrypted));
+        assert!(!looks_encrypted("sk-proj-abc123"));
+        assert!(!looks_encrypted("c2hvcnQ="));
+    }
+
+    #[test]
+    fn test_legacy_master_key_data_still_decrypts() {
+        let master = test_key();
+        let legacy = encrypt_with_key(b"old secret", &master).expect("Encryption should succeed");
+
+        let key = ContextKey::from_master(&master, KeyContext::SecureValues);
+        let decrypted = key.decrypt_legacy(&legacy).expect("Legacy data should decrypt");
+        assert_eq!(*decrypted, b"old secret");
+
+        assert!(key.decrypt(&legacy).is_err());
+        assert!(key.unwrap_key(&legacy).is_err());
+        assert!(decrypt_with_master_key(KeyContext::History, &legacy, &master).is_err());
+    }
+
+    #[test]
+    fn test_passphrase_sealing() {
+        let params = PassphraseKeyParams {
+            salt: general_purpose::STANDARD.encode(b"0123456789abcdef"),
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+            bind_to_machine: false,
+        };
+        let sealed = seal_with_params("correct horse", b"secret", &params).unwrap();
+        assert_eq!(sealed.kdf, "argon2id");
+        assert_eq!(*open_with_passphrase(&sealed, "correct horse").unwrap(), b"secret");
+        assert!(open_with_passphrase(&sealed, "wrong horse").is_err());
+
+        // Costs a crafted backup could use to stall the import
+        for costly in [
+            PassphraseSealed {
+                iterations: u32::MAX,
+                ..sealed.clone()
+            },
+            PassphraseSealed {
+                memory_kib: u32::MAX,
+                ..sealed.clone()
+            },
+            PassphraseSealed {
+                parallelism: 0,
+                ..sealed.clone()
+            },
+        ] {
+            let error = open_with_passphrase(&costly, "correct horse").unwrap_err();
+            assert!(error.contains("out of range"));
+        }
+    }
+
+    #[test]
+    fn test_derive_store_key() {
+        let params = PassphraseKeyParams {
+            salt: general_purpose::STANDARD.encode(b"0123456789abcdef"),
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+            bind_to_machine: false,
+        };
+        let key = derive_store_key("passphrase", &params).unwrap();
+        assert_eq!(key, derive_store_key("passphrase", &params).unwrap());
+        assert_ne!(key, derive_store_key("passphrase!", &params).unwrap());
+
+        let other_salt = PassphraseKeyParams {
+            salt: general_purpose::STANDARD.encode(b"fedcba9876543210"),
+            ..params
+        };
+        assert_ne!(key, derive_store_key("passphrase", &other_salt).unwrap());
+    }
+}
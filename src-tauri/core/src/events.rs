@@ -0,0 +1,62 @@
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Receives the events the engine reports, e.g. to forward them to a UI
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: serde_json::Value);
+}
+
+impl<F: Fn(&str, serde_json::Value) + Send + Sync> EventSink for F {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        self(event, payload)
+    }
+}
+
+/// Where audio chunks, retries and live transcripts are reported. Event names
+/// and payloads are the ones the app's frontend listens for.
+#[derive(Clone, Default)]
+pub struct Events(Option<Arc<dyn EventSink>>);
+
+impl Events {
+    pub fn new(sink: impl EventSink + 'static) -> Self {
+        Self(Some(Arc::new(sink)))
+    }
+
+    /// Drop every event, for headless use
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    pub fn emit(&self, event: &str, payload: impl Serialize) {
+        let Some(sink) = &self.0 else {
+            return;
+        };
+        match serde_json::to_value(payload) {
+            Ok(payload) => sink.emit(event, payload),
+            Err(e) => eprintln!("Failed to serialize {} event: {}", event, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_closure_sink() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        let events = Events::new(move |event: &str, payload: serde_json::Value| {
+            sink.lock().unwrap().push((event.to_string(), payload));
+        });
+
+        events.emit("transcription-final", serde_json::json!({ "text": "Hi" }));
+        Events::none().emit("transcription-final", "dropped");
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].0, "transcription-final");
+        assert_eq!(received[0].1["text"], "Hi");
+    }
+}
@@ -0,0 +1,317 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Transaction};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::timestamps::{self, Formatter};
+use crate::transcript::{self, SegmentKind};
+
+/// Bumped whenever `SCHEMA` changes; stored in the `metadata` table
+const SCHEMA_VERSION: u32 = 2;
+
+/// Schema of the exported database. Timestamps are ISO 8601 strings in UTC, so
+/// `date(created_at)` and friends work directly.
+const SCHEMA: &str = "
+-- One row per history entry
+CREATE TABLE entries (
+    id              TEXT PRIMARY KEY,
+    created_at      TEXT NOT NULL,
+    -- created_at in the exporting user's time zone, with its offset
+    created_at_local TEXT NOT NULL,
+    title           TEXT NOT NULL,
+    summary         TEXT NOT NULL,
+    -- TODO, RESEARCH, DRAFT or NOTE
+    intent          TEXT NOT NULL,
+    -- ISO 639-1 code, when detected
+    language        TEXT,
+    pinned          INTEGER NOT NULL,
+    transcript      TEXT NOT NULL,
+    research_answer TEXT,
+    draft_content   TEXT,
+    word_count      INTEGER NOT NULL
+);
+
+CREATE TABLE tags (
+    entry_id TEXT NOT NULL REFERENCES entries(id),
+    tag      TEXT NOT NULL
+);
+
+CREATE TABLE key_facts (
+    entry_id TEXT NOT NULL REFERENCES entries(id),
+    position INTEGER NOT NULL,
+    fact     TEXT NOT NULL
+);
+
+CREATE TABLE todos (
+    entry_id TEXT NOT NULL REFERENCES entries(id),
+    position INTEGER NOT NULL,
+    task     TEXT NOT NULL,
+    done     INTEGER NOT NULL,
+    due      TEXT
+);
+
+-- The transcript split into prose and dictated code (kind 'prose' or 'code')
+CREATE TABLE segments (
+    entry_id TEXT NOT NULL REFERENCES entries(id),
+    position INTEGER NOT NULL,
+    kind     TEXT NOT NULL,
+    text     TEXT NOT NULL
+);
+
+-- schema_version, exported_at, time_zone and entry_count
+CREATE TABLE metadata (
+    key   TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE INDEX entries_created_at ON entries(created_at);
+CREATE INDEX tags_tag ON tags(tag);
+";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Todo {
+    task: String,
+    done: bool,
+    due: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct EntryData {
+    todos: Option<Vec<Todo>>,
+    research_answer: Option<String>,
+    draft_content: Option<String>,
+}
+
+/// The fields of a frontend history entry that are exported
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryEntry {
+    id: String,
+    created_at: String,
+    original_transcript: String,
+    language: Option<String>,
+    pinned: bool,
+    title: String,
+    tags: Vec<String>,
+    summary: String,
+    key_facts: Vec<String>,
+    intent: String,
+    data: EntryData,
+}
+
+fn insert_entry(
+    tx: &Transaction,
+    entry: &HistoryEntry,
+    formatter: &Formatter,
+) -> rusqlite::Result<()> {
+    // Unparseable timestamps are kept as they are rather than failing the export
+    let (created_at, created_at_local) = match formatter.format(&entry.created_at) {
+        Ok(formatted) => (formatted.utc, formatted.local),
+        Err(_) => (entry.created_at.clone(), entry.created_at.clone()),
+    };
+    tx.execute(
+        "INSERT INTO entries (id, created_at, created_at_local, title, summary, intent,
+             language, pinned, transcript, research_answer, draft_content, word_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            entry.id,
+            created_at,
+            created_at_local,
+            entry.title,
+            entry.summary,
+            entry.intent,
+            entry.language,
+            entry.pinned,
+            entry.original_transcript,
+            entry.data.research_answer,
+            entry.data.draft_content,
+            entry.original_transcript.split_whitespace().count() as i64,
+        ],
+    )?;
+
+    for tag in &entry.tags {
+        tx.execute(
+            "INSERT INTO tags (entry_id, tag) VALUES (?1, ?2)",
+            params![entry.id, tag],
+        )?;
+    }
+
+    for (position, fact) in entry.key_facts.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO key_facts (entry_id, position, fact) VALUES (?1, ?2, ?3)",
+            params![entry.id, position as i64, fact],
+        )?;
+    }
+
+    for (position, todo) in entry.data.todos.iter().flatten().enumerate() {
+        tx.execute(
+            "INSERT INTO todos (entry_id, position, task, done, due) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![entry.id, position as i64, todo.task, todo.done, todo.due],
+        )?;
+    }
+
+    let segments = transcript::segments(&entry.original_transcript);
+    for (position, segment) in segments.iter().enumerate() {
+        let kind = match segment.kind {
+            SegmentKind::Prose => "prose",
+            SegmentKind::Code => "code",
+        };
+        tx.execute(
+            "INSERT INTO segments (entry_id, position, kind, text) VALUES (?1, ?2, ?3, ?4)",
+            params![entry.id, position as i64, kind, segment.text],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_database(
+    path: &Path,
+    entries: &[HistoryEntry],
+    formatter: &Formatter,
+) -> rusqlite::Result<()> {
+    let mut conn = Connection::open(path)?;
+    let tx = conn.transaction()?;
+
+    tx.execute_batch(SCHEMA)?;
+    for entry in entries {
+        insert_entry(&tx, entry, formatter)?;
+    }
+
+    let metadata = [
+        ("schema_version", SCHEMA_VERSION.to_string()),
+        ("exported_at", chrono::Utc::now().to_rfc3339()),
+        ("time_zone", formatter.time_zone()),
+        ("entry_count", entries.len().to_string()),
+    ];
+    for (key, value) in metadata {
+        tx.execute(
+            "INSERT INTO metadata (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+    }
+
+    tx.commit()
+}
+
+/// Whether something created at `created_at` is older than `max_days` at
+/// `now`. An unreadable timestamp counts as expired, so it cannot be used to
+/// keep history past a retention limit.
+pub fn is_expired(created_at: &str, max_days: u32, now: DateTime<Utc>) -> bool {
+    timestamps::parse(created_at)
+        .map(|created| now - created.with_timezone(&Utc) > chrono::Duration::days(max_days.into()))
+        .unwrap_or(true)
+}
+
+/// Drop the entries a retention limit of `max_days` no longer allows
+pub fn retain_recent(entries: &mut Vec<HistoryEntry>, max_days: u32, now: DateTime<Utc>) {
+    entries.retain(|entry| !is_expired(&entry.created_at, max_days, now));
+}
+
+/// Write `entries` to a new SQLite database at `path`, replacing any file there
+pub fn export(path: &Path, entries: &[HistoryEntry], formatter: &Formatter) -> Result<(), String> {
+    // Built next to the target and moved into place, so a failed export
+    // leaves neither a half-written database nor a clobbered old one
+    let partial = path.with_extension("sqlite.part");
+    let _ = fs::remove_file(&partial);
+
+    let result = write_database(&partial, entries, formatter)
+        .map_err(|e| format!("Failed to write database: {}", e))
+        .and_then(|_| {
+            fs::rename(&partial, path).map_err(|e| format!("Failed to save database: {}", e))
+        });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_is_queryable() {
+        let dir =
+            std::env::temp_dir().join(format!("export-sqlite-test-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.sqlite");
+        fs::write(&path, b"stale").unwrap();
+
+        let entries: Vec<HistoryEntry> = serde_json::from_value(serde_json::json!([{
+            "id": "a",
+            "createdAt": "2024-05-01T11:00:00+02:00",
+            "originalTranscript": "Call Bob. Begin code. git push. End code.",
+            "title": "Deploy",
+            "tags": ["work", "ops"],
+            "summary": "Deploy today",
+            "keyFacts": ["Bob owns it"],
+            "intent": "TODO",
+            "data": { "todos": [{ "task": "Push", "done": true, "due": null }] }
+        }]))
+        .unwrap();
+
+        let formatter = Formatter::new(Some("America/New_York"), Some("en-US")).unwrap();
+        export(&path, &entries, &formatter).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let (title, words): (String, i64) = conn
+            .query_row("SELECT title, word_count FROM entries", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((title.as_str(), words), ("Deploy", 8));
+
+        let (created_at, local): (String, String) = conn
+            .query_row(
+                "SELECT created_at, created_at_local FROM entries",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(created_at, "2024-05-01T09:00:00.000Z");
+        assert_eq!(local, "2024-05-01T05:00:00-04:00");
+
+        let tags: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM tags WHERE entry_id = 'a'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tags, 2);
+
+        let code: String = conn
+            .query_row("SELECT text FROM segments WHERE kind = 'code'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(code, "git push");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retain_recent() {
+        let mut entries: Vec<HistoryEntry> = serde_json::from_value(serde_json::json!([
+            { "id": "new", "createdAt": "2024-05-09T12:00:00Z" },
+            { "id": "edge", "createdAt": "2024-05-03T14:00:00+02:00" },
+            { "id": "old", "createdAt": "2024-05-01T11:00:00Z" },
+            { "id": "unreadable", "createdAt": "yesterday" }
+        ]))
+        .unwrap();
+        let now = DateTime::parse_from_rfc3339("2024-05-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        retain_recent(&mut entries, 7, now);
+
+        let ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, vec!["new", "edge"]);
+        assert!(is_expired("2024-05-03T11:59:59Z", 7, now));
+    }
+}
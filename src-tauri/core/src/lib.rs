@@ -0,0 +1,16 @@
+//! The voice assistant's engine without the app around it: audio processing
+//! and WAV encoding, input devices, the transcription providers, transcript
+//! formatting, at-rest encryption and the history's SQLite export. Nothing
+//! here depends on Tauri; what the engine reports while it works goes to an
+//! [`Events`] sink.
+
+pub mod analytics;
+pub mod audio;
+pub mod crypto;
+mod events;
+pub mod history;
+pub mod timestamps;
+pub mod transcript;
+pub mod transcription;
+
+pub use events::{EventSink, Events};
@@ -0,0 +1,306 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+/// Used when the system locale cannot be determined
+const FALLBACK_LOCALE: &str = "en-US";
+
+/// strftime patterns for a locale's date and time
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LocaleStyle {
+    date: &'static str,
+    time: &'static str,
+}
+
+/// Date order and clock of the common locales; others get ISO dates and a
+/// 24-hour clock
+fn locale_style(locale: &str) -> LocaleStyle {
+    let locale = locale.replace('_', "-").to_lowercase();
+    let language = locale.split('-').next().unwrap_or_default();
+    let region = locale.split('-').nth(1).unwrap_or_default();
+
+    let (date, time) = match (language, region) {
+        ("en", "us" | "ph") => ("%m/%d/%Y", "%-I:%M %p"),
+        ("en", "ca") => ("%Y-%m-%d", "%-I:%M %p"),
+        ("en", "au" | "in" | "nz") => ("%d/%m/%Y", "%-I:%M %p"),
+        ("en", _) => ("%d/%m/%Y", "%H:%M"),
+        ("fr", "ca") => ("%Y-%m-%d", "%H:%M"),
+        ("de" | "ru" | "pl" | "cs" | "fi" | "nb" | "no" | "da" | "tr" | "uk", _) => {
+            ("%d.%m.%Y", "%H:%M")
+        }
+        ("fr" | "es" | "it" | "pt" | "el" | "ca", _) => ("%d/%m/%Y", "%H:%M"),
+        ("nl", _) => ("%d-%m-%Y", "%H:%M"),
+        ("ja" | "zh", _) => ("%Y/%m/%d", "%H:%M"),
+        ("ko", _) => ("%Y. %m. %d.", "%H:%M"),
+        ("hu", _) => ("%Y. %m. %d.", "%H:%M"),
+        _ => ("%Y-%m-%d", "%H:%M"),
+    };
+    LocaleStyle { date, time }
+}
+
+/// Locale from the environment, e.g. `de_DE.UTF-8` becomes `de-DE`
+fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| {
+            value
+                .split(['.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        .map(|value| value.replace('_', "-"))
+}
+
+#[cfg(target_os = "macos")]
+fn system_locale() -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct GlobalPreferences {
+        #[serde(rename = "AppleLocale")]
+        apple_locale: Option<String>,
+    }
+
+    // Apps started from the Finder get no LANG
+    let home = std::env::var("HOME").ok()?;
+    let path = format!("{}/Library/Preferences/.GlobalPreferences.plist", home);
+    plist::from_file::<_, GlobalPreferences>(path)
+        .ok()
+        .and_then(|preferences| preferences.apple_locale)
+        .map(|locale| {
+            locale
+                .split('@')
+                .next()
+                .unwrap_or_default()
+                .replace('_', "-")
+        })
+        .or_else(locale_from_env)
+}
+
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetUserDefaultLocaleName(name: *mut u16, length: i32) -> i32;
+    }
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut name = [0u16; 85];
+    // SAFETY: the buffer holds `name.len()` UTF-16 units
+    let length = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    if length <= 1 {
+        return locale_from_env();
+    }
+    // The length includes the terminating NUL
+    Some(String::from_utf16_lossy(&name[..length as usize - 1]))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn system_locale() -> Option<String> {
+    locale_from_env()
+}
+
+/// Parse a stored timestamp. RFC 3339 timestamps keep the offset they were
+/// recorded with; ones without an offset are taken as UTC.
+pub(crate) fn parse(timestamp: &str) -> Result<DateTime<FixedOffset>, String> {
+    let timestamp = timestamp.trim();
+    DateTime::parse_from_rfc3339(timestamp)
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f")
+                .or_else(|_| NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f"))
+                .map(|naive| naive.and_utc().fixed_offset())
+        })
+        .map_err(|_| format!("Invalid timestamp: {}", timestamp))
+}
+
+/// Display time zone
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Named(Tz),
+    /// The system's zone when its IANA name is unknown
+    Local,
+}
+
+/// A timestamp prepared for display
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedTimestamp {
+    /// Normalized to UTC, as stored
+    pub utc: String,
+    /// RFC 3339 in the display time zone
+    pub local: String,
+    /// Calendar day in the display time zone, for grouping
+    pub date: String,
+    /// Date and time in the display locale
+    pub display: String,
+    /// Time zone abbreviation or offset, e.g. "CEST" or "+05:30"
+    pub zone: String,
+    /// Local time where the entry was recorded, when that place's offset
+    /// differs from the display time zone's, e.g. after traveling
+    pub recorded_local: Option<String>,
+}
+
+/// Formats timestamps for the user's time zone and locale
+#[derive(Debug, Clone)]
+pub struct Formatter {
+    zone: Zone,
+    locale: String,
+    style: LocaleStyle,
+}
+
+impl Formatter {
+    /// `None` follows the system's time zone or locale
+    pub fn new(time_zone: Option<&str>, locale: Option<&str>) -> Result<Self, String> {
+        let zone = match time_zone {
+            Some(name) => Zone::Named(parse_time_zone(name)?),
+            None => system_zone(),
+        };
+        Ok(Self::with_zone(zone, locale))
+    }
+
+    /// Like `new`, but an unknown time zone falls back to the system's
+    pub fn with_fallback(time_zone: Option<&str>, locale: Option<&str>) -> Self {
+        let zone = time_zone
+            .and_then(|name| parse_time_zone(name).ok())
+            .map_or_else(system_zone, Zone::Named);
+        Self::with_zone(zone, locale)
+    }
+
+    fn with_zone(zone: Zone, locale: Option<&str>) -> Self {
+        let locale = locale
+            .map(str::to_string)
+            .or_else(system_locale)
+            .unwrap_or_else(|| FALLBACK_LOCALE.to_string());
+
+        Self {
+            zone,
+            style: locale_style(&locale),
+            locale,
+        }
+    }
+
+    pub fn time_zone(&self) -> String {
+        match self.zone {
+            Zone::Named(tz) => tz.name().to_string(),
+            Zone::Local => Local::now().format("%:z").to_string(),
+        }
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    fn pattern(&self) -> String {
+        format!("{} {}", self.style.date, self.style.time)
+    }
+
+    pub fn format(&self, timestamp: &str) -> Result<FormattedTimestamp, String> {
+        let recorded = parse(timestamp)?;
+        let utc = recorded.with_timezone(&Utc);
+        let (local, zone) = match self.zone {
+            Zone::Named(tz) => {
+                let local = utc.with_timezone(&tz);
+                (local.fixed_offset(), local.format("%Z").to_string())
+            }
+            Zone::Local => {
+                let local = utc.with_timezone(&Local).fixed_offset();
+                (local, local.format("%:z").to_string())
+            }
+        };
+
+        // Timestamps stored as UTC say nothing about where they were recorded
+        let recorded_local = (recorded.offset().local_minus_utc() != 0
+            && recorded.offset() != local.offset())
+        .then(|| {
+            format!(
+                "{} (UTC{})",
+                recorded.format(&self.pattern()),
+                recorded.format("%:z")
+            )
+        });
+
+        Ok(FormattedTimestamp {
+            utc: utc.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            local: local.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            date: local.format("%Y-%m-%d").to_string(),
+            display: local.format(&self.pattern()).to_string(),
+            zone,
+            recorded_local,
+        })
+    }
+
+    /// Calendar day a timestamp falls on in the display time zone, or the
+    /// timestamp's own date part if it cannot be parsed
+    pub fn local_date(&self, timestamp: &str) -> String {
+        match self.format(timestamp) {
+            Ok(formatted) => formatted.date,
+            Err(_) => timestamp.get(..10).unwrap_or(timestamp).to_string(),
+        }
+    }
+
+    /// Current time in the display time zone, RFC 3339
+    pub fn now(&self) -> String {
+        let now = Utc::now();
+        match self.zone {
+            Zone::Named(tz) => now.with_timezone(&tz).to_rfc3339(),
+            Zone::Local => now.with_timezone(&Local).to_rfc3339(),
+        }
+    }
+}
+
+fn parse_time_zone(name: &str) -> Result<Tz, String> {
+    name.parse()
+        .map_err(|_| format!("Unknown time zone: {}", name))
+}
+
+fn system_zone() -> Zone {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .map_or(Zone::Local, Zone::Named)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_normalizes_to_utc() {
+        let utc = |timestamp| parse(timestamp).map(|parsed| parsed.with_timezone(&Utc));
+        assert_eq!(
+            utc("2024-05-01T09:00:00+09:00").unwrap().to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+        // No offset means UTC
+        assert_eq!(
+            utc("2024-05-01 09:00:00.250").unwrap().to_rfc3339(),
+            "2024-05-01T09:00:00.250+00:00"
+        );
+        assert!(parse("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_format_in_zone_and_locale() {
+        let berlin = Formatter::new(Some("Europe/Berlin"), Some("de-DE")).unwrap();
+        let formatted = berlin.format("2024-05-01T22:30:00.000Z").unwrap();
+        assert_eq!(formatted.date, "2024-05-02");
+        assert_eq!(formatted.display, "02.05.2024 00:30");
+        assert_eq!(formatted.zone, "CEST");
+        assert_eq!(formatted.local, "2024-05-02T00:30:00+02:00");
+        assert_eq!(formatted.recorded_local, None);
+
+        // Recorded in Tokyo, shown in New York
+        let new_york = Formatter::new(Some("America/New_York"), Some("en_US")).unwrap();
+        let formatted = new_york.format("2024-01-15T09:05:00+09:00").unwrap();
+        assert_eq!(formatted.display, "01/14/2024 7:05 PM");
+        assert_eq!(formatted.zone, "EST");
+        assert_eq!(
+            formatted.recorded_local.as_deref(),
+            Some("01/15/2024 9:05 AM (UTC+09:00)")
+        );
+
+        assert!(Formatter::new(Some("Mars/Olympus"), None).is_err());
+        assert_eq!(locale_style("xx"), locale_style("sv-SE"));
+    }
+}
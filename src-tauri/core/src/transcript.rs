@@ -0,0 +1,54 @@
+use serde::Serialize;
+
+pub mod chunking;
+pub mod corrections;
+mod dictation;
+pub mod document;
+pub mod markdown;
+mod profanity;
+pub mod post_processing;
+pub mod punctuation;
+pub mod rich_text;
+pub mod subtitles;
+
+pub use chunking::{ChunkOptions, TokenCount, TranscriptChunk};
+pub use corrections::Correction;
+pub use dictation::{Segment, SegmentKind};
+pub use post_processing::{PostProcessing, PostProcessingStep, PostProcessor};
+pub use profanity::{ProfanityFilter, ProfanityMode};
+
+/// A transcript with its dictated formatting applied
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedTranscript {
+    /// Final text with addresses written out and code blocks fenced
+    pub text: String,
+    /// Prose and code parts; code segments must be passed through verbatim by
+    /// later formatting and LLM steps
+    pub segments: Vec<Segment>,
+    pub urls: Vec<String>,
+    pub emails: Vec<String>,
+}
+
+/// Write out spoken URLs and emails and turn "begin code" ... "end code"
+/// sections into code blocks with their symbols intact
+pub fn format(text: &str) -> FormattedTranscript {
+    let dictation = dictation::parse(text);
+
+    FormattedTranscript {
+        text: dictation::render(&dictation.segments),
+        segments: dictation.segments,
+        urls: dictation.urls,
+        emails: dictation.emails,
+    }
+}
+
+/// Text of `format`
+pub fn format_text(text: &str) -> String {
+    dictation::render(&dictation::parse(text).segments)
+}
+
+/// Prose and code segments of a transcript
+pub fn segments(text: &str) -> Vec<Segment> {
+    dictation::parse(text).segments
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use tiktoken_rs::CoreBPE;
+
+/// How `chunk_transcript` splits a transcript
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChunkOptions {
+    /// Token budget per chunk
+    pub max_tokens: usize,
+    /// Tokens of context repeated from the end of the previous chunk
+    pub overlap_tokens: usize,
+    /// Encoding ("o200k_base", "cl100k_base", ...) or an OpenAI model name
+    pub tokenizer: String,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: 2000,
+            overlap_tokens: 200,
+            tokenizer: "o200k_base".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptChunk {
+    pub index: usize,
+    pub text: String,
+    pub token_count: usize,
+}
+
+/// Look up a tokenizer by encoding or model name
+pub fn tokenizer(name: &str) -> Result<&'static CoreBPE, String> {
+    use tiktoken_rs::tokenizer::Tokenizer;
+
+    let encoding = match name {
+        "o200k_base" => Some(Tokenizer::O200kBase),
+        "cl100k_base" => Some(Tokenizer::Cl100kBase),
+        "p50k_base" => Some(Tokenizer::P50kBase),
+        "r50k_base" => Some(Tokenizer::R50kBase),
+        _ => None,
+    };
+
+    match encoding {
+        Some(encoding) => tiktoken_rs::bpe_for_tokenizer(encoding),
+        None => tiktoken_rs::bpe_for_model(name),
+    }
+    .map_err(|_| format!("Unknown tokenizer or model: {}", name))
+}
+
+/// Result of `count_tokens`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCount {
+    pub tokens: usize,
+    /// The model's context window, when known
+    pub context_window: Option<usize>,
+    /// Tokens left in the context window
+    pub remaining: Option<usize>,
+}
+
+/// Count the tokens `text` takes for an encoding or model
+pub fn count_tokens(text: &str, model: &str) -> Result<TokenCount, String> {
+    let tokens = tokenizer(model)?.encode_ordinary(text).len();
+    let context_window = tiktoken_rs::model::get_context_size(model);
+
+    Ok(TokenCount {
+        tokens,
+        context_window,
+        remaining: context_window.map(|window| window.saturating_sub(tokens)),
+    })
+}
+
+/// Split `text` between two characters wherever `is_boundary` says so. Pieces
+/// keep their whitespace, so joining them gives back the text.
+fn split_where(text: &str, is_boundary: impl Fn(char, char) -> bool) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((_, c)) = chars.next() {
+        if let Some(&(next_index, next)) = chars.peek() {
+            if is_boundary(c, next) {
+                pieces.push(&text[start..next_index]);
+                start = next_index;
+            }
+        }
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+
+    pieces
+}
+
+/// Sentences with their token counts; sentences over the budget are split
+/// into words. A single word over the budget still becomes its own unit.
+fn units<'a>(text: &'a str, bpe: &CoreBPE, max_tokens: usize) -> Vec<(&'a str, usize)> {
+    let count = |piece: &str| bpe.encode_ordinary(piece).len();
+
+    split_where(text, |c, next| {
+        (matches!(c, '.' | '!' | '?') && next.is_whitespace()) || c == '\n'
+    })
+    .into_iter()
+    .flat_map(|sentence| {
+        let tokens = count(sentence);
+        if tokens <= max_tokens {
+            vec![(sentence, tokens)]
+        } else {
+            split_where(sentence, |c, next| {
+                !c.is_whitespace() && next.is_whitespace()
+            })
+            .into_iter()
+            .map(|word| (word, count(word)))
+            .collect()
+        }
+    })
+    .collect()
+}
+
+/// Split a transcript into chunks of at most `max_tokens`, breaking between
+/// sentences where possible and starting each chunk with the last sentences
+/// of the previous one, up to `overlap_tokens`
+pub fn chunk(text: &str, options: &ChunkOptions) -> Result<Vec<TranscriptChunk>, String> {
+    if options.max_tokens == 0 {
+        return Err("maxTokens must be greater than zero".to_string());
+    }
+    if options.overlap_tokens >= options.max_tokens {
+        return Err("overlapTokens must be smaller than maxTokens".to_string());
+    }
+
+    let bpe = tokenizer(&options.tokenizer)?;
+    let mut texts: Vec<String> = Vec::new();
+    let mut current: Vec<(&str, usize)> = Vec::new();
+    let mut current_tokens = 0;
+
+    for unit in units(text, bpe, options.max_tokens) {
+        if current_tokens + unit.1 > options.max_tokens && !current.is_empty() {
+            texts.push(current.iter().map(|(piece, _)| *piece).collect());
+
+            let mut carried: Vec<(&str, usize)> = Vec::new();
+            let mut carried_tokens = 0;
+            for &(piece, tokens) in current.iter().rev() {
+                if carried_tokens + tokens > options.overlap_tokens {
+                    break;
+                }
+                carried.insert(0, (piece, tokens));
+                carried_tokens += tokens;
+            }
+            // The overlap gives way when the next unit needs the room
+            while !carried.is_empty() && carried_tokens + unit.1 > options.max_tokens {
+                carried_tokens -= carried.remove(0).1;
+            }
+
+            current = carried;
+            current_tokens = carried_tokens;
+        }
+
+        current.push(unit);
+        current_tokens += unit.1;
+    }
+    if !current.is_empty() {
+        texts.push(current.iter().map(|(piece, _)| *piece).collect());
+    }
+
+    Ok(texts
+        .into_iter()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .enumerate()
+        .map(|(index, text)| TranscriptChunk {
+            index,
+            token_count: bpe.encode_ordinary(&text).len(),
+            text,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(max_tokens: usize, overlap_tokens: usize) -> ChunkOptions {
+        ChunkOptions {
+            max_tokens,
+            overlap_tokens,
+            ..ChunkOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_chunks_respect_budget_and_overlap() {
+        let text = (1..=40)
+            .map(|i| format!("This is sentence number {} of the meeting.", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let chunks = chunk(&text, &options(50, 12)).unwrap();
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.token_count <= 50, "{}", chunk.token_count);
+        }
+
+        // Each chunk starts with the sentence the previous one ended with
+        for pair in chunks.windows(2) {
+            let last_sentence = pair[0].text.rsplit(". ").next().unwrap();
+            assert!(pair[1].text.starts_with(last_sentence));
+        }
+        assert!(chunks
+            .last()
+            .unwrap()
+            .text
+            .ends_with("number 40 of the meeting."));
+    }
+
+    #[test]
+    fn test_long_sentences_are_split_by_words() {
+        let text = "word ".repeat(100);
+        let chunks = chunk(&text, &options(30, 0)).unwrap();
+
+        assert!(chunks.len() >= 4);
+        assert!(chunks.iter().all(|chunk| chunk.token_count <= 30));
+        let words: usize = chunks
+            .iter()
+            .map(|chunk| chunk.text.split_whitespace().count())
+            .sum();
+        assert_eq!(words, 100);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(chunk("text", &options(0, 0)).is_err());
+        assert!(chunk("text", &options(10, 10)).is_err());
+        assert!(tokenizer("gpt-4o").is_ok());
+        assert!(tokenizer("not-a-model").is_err());
+        assert!(count_tokens("text", "not-a-model").is_err());
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        let count = count_tokens("hello world", "gpt-4o").unwrap();
+        assert_eq!(count.tokens, 2);
+        assert_eq!(count.context_window, Some(128_000));
+        assert_eq!(count.remaining, Some(127_998));
+
+        let count = count_tokens("hello world", "cl100k_base").unwrap();
+        assert_eq!((count.tokens, count.context_window), (2, None));
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Longest vocabulary prompt; Whisper only reads the last 224 tokens of one
+const MAX_PROMPT_CHARS: usize = 800;
+
+/// A word or phrase engines keep getting wrong, and what it should be
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Correction {
+    /// Matched as whole words, ignoring case
+    pub wrong: String,
+    pub right: String,
+}
+
+/// Length in bytes of the start of `text` that is `wrong` as whole words
+fn match_len(text: &str, wrong: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for expected in wrong.chars() {
+        let (_, actual) = chars.next()?;
+        if !actual.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+
+    let end = chars.next().map_or(text.len(), |(index, _)| index);
+    let ends_word = text[end..]
+        .chars()
+        .next()
+        .is_none_or(|next| !next.is_alphanumeric());
+    ends_word.then_some(end)
+}
+
+/// Replace every correction's wrong words in `text` with the right ones. The
+/// longest match wins where corrections overlap.
+pub fn apply(text: &str, corrections: &[Correction]) -> String {
+    let mut corrections: Vec<&Correction> = corrections
+        .iter()
+        .filter(|correction| !correction.wrong.is_empty())
+        .collect();
+    corrections.sort_by_key(|correction| Reverse(correction.wrong.chars().count()));
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut starts_word = true;
+    while let Some(next) = rest.chars().next() {
+        if starts_word {
+            let found = corrections.iter().find_map(|correction| {
+                match_len(rest, &correction.wrong).map(|len| (correction, len))
+            });
+            if let Some((correction, len)) = found {
+                output.push_str(&correction.right);
+                rest = &rest[len..];
+                starts_word = false;
+                continue;
+            }
+        }
+
+        output.push(next);
+        rest = &rest[next.len_utf8()..];
+        starts_word = !next.is_alphanumeric();
+    }
+    output
+}
+
+/// `prompt` with the corrected spellings appended, so engines that take a
+/// vocabulary prompt get them right in the first place. Later terms are
+/// dropped once the prompt gets too long.
+pub fn vocabulary_prompt(corrections: &[Correction], prompt: Option<&str>) -> Option<String> {
+    let prompt = prompt.map(str::trim).filter(|prompt| !prompt.is_empty());
+
+    let mut terms: Vec<&str> = Vec::new();
+    let mut length = prompt.map_or(0, str::len);
+    for correction in corrections {
+        let term = correction.right.trim();
+        if term.is_empty() || terms.contains(&term) {
+            continue;
+        }
+        if length + term.len() + 2 > MAX_PROMPT_CHARS {
+            break;
+        }
+        length += term.len() + 2;
+        terms.push(term);
+    }
+
+    let glossary = (!terms.is_empty()).then(|| format!("Glossary: {}.", terms.join(", ")));
+    match (prompt, glossary) {
+        (Some(prompt), Some(glossary)) => Some(format!("{} {}", prompt, glossary)),
+        (prompt, glossary) => glossary.or(prompt.map(str::to_string)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn correction(wrong: &str, right: &str) -> Correction {
+        Correction {
+            wrong: wrong.to_string(),
+            right: right.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_corrections() {
+        let corrections = [
+            correction("shivon", "Siobhan"),
+            correction("cube control", "kubectl"),
+            correction("cube", "Kube"),
+        ];
+
+        assert_eq!(
+            apply(
+                "Shivon ran cube control, not cube. Cubes and shivons stay.",
+                &corrections
+            ),
+            "Siobhan ran kubectl, not Kube. Cubes and shivons stay."
+        );
+
+        assert_eq!(
+            vocabulary_prompt(&corrections, Some("A talk about clusters.")).as_deref(),
+            Some("A talk about clusters. Glossary: Siobhan, kubectl, Kube.")
+        );
+        assert_eq!(vocabulary_prompt(&[], Some(" ")), None);
+    }
+}
@@ -0,0 +1,399 @@
+use serde::Serialize;
+
+/// Top-level domains recognised in spoken addresses
+const TLDS: &[&str] = &[
+    "com", "org", "net", "io", "dev", "app", "ai", "co", "de", "uk", "eu", "edu", "gov", "info",
+    "me", "us",
+];
+
+/// Words that precede "at" or "dot" in ordinary speech ("look at", "the dot com era")
+/// and therefore never start an address
+const NON_ADDRESS_WORDS: &[&str] = &[
+    "a",
+    "an",
+    "the",
+    "this",
+    "that",
+    "it",
+    "me",
+    "us",
+    "them",
+    "him",
+    "her",
+    "you",
+    "is",
+    "are",
+    "was",
+    "were",
+    "be",
+    "look",
+    "looking",
+    "meet",
+    "stay",
+    "work",
+    "works",
+    "live",
+    "lives",
+    "arrive",
+    "here",
+    "there",
+    "available",
+];
+
+/// Spoken symbols inside code blocks, two-word phrases first
+const SPOKEN_SYMBOLS: &[(&str, &str)] = &[
+    ("open paren", "("),
+    ("close paren", ")"),
+    ("open bracket", "["),
+    ("close bracket", "]"),
+    ("open brace", "{"),
+    ("close brace", "}"),
+    ("new line", "\n"),
+    ("double quote", "\""),
+    ("single quote", "'"),
+    ("less than", "<"),
+    ("greater than", ">"),
+    ("equals", "="),
+    ("semicolon", ";"),
+    ("colon", ":"),
+    ("comma", ","),
+    ("dot", "."),
+    ("underscore", "_"),
+    ("plus", "+"),
+    ("minus", "-"),
+    ("star", "*"),
+    ("slash", "/"),
+    ("hash", "#"),
+    ("arrow", "->"),
+];
+
+/// Tokens written without a space before them
+const GLUE_LEFT: &[&str] = &["(", ")", "]", ";", ",", ".", ":", "_", "\n"];
+/// Tokens written without a space after them
+const GLUE_RIGHT: &[&str] = &["(", "[", ".", "_", "\n"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SegmentKind {
+    Prose,
+    /// Dictated between "begin code" and "end code"; must not be reformatted
+    Code,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub text: String,
+}
+
+/// Transcript split into prose and code, with spoken addresses written out
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Dictation {
+    pub segments: Vec<Segment>,
+    pub urls: Vec<String>,
+    pub emails: Vec<String>,
+}
+
+/// Lowercased word without surrounding punctuation added by the recognizer
+pub(super) fn normalized(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+fn is_word(words: &[&str], index: usize, expected: &[&str]) -> bool {
+    words
+        .get(index)
+        .is_some_and(|word| expected.contains(&normalized(word).as_str()))
+}
+
+/// "begin code" / "start code" or "end code" / "stop code" at `index`
+pub(super) fn is_code_marker(words: &[&str], index: usize, verbs: &[&str]) -> bool {
+    is_word(words, index, verbs) && is_word(words, index + 1, &["code"])
+}
+
+/// A domain or user label: the word itself, without any punctuation inside
+fn label(word: &str) -> Option<String> {
+    let trimmed = word.trim_end_matches(['.', ',', '!', '?', ';', ':']);
+    let valid = !trimmed.is_empty()
+        && trimmed
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| trimmed.to_string())
+}
+
+/// Labels joined by spoken "dot", returning them and the index after the last one
+fn dotted_name(words: &[&str], start: usize) -> (Vec<String>, usize) {
+    let mut labels = Vec::new();
+    let mut index = start;
+
+    while let Some(next) = words.get(index).and_then(|word| label(word)) {
+        if next.eq_ignore_ascii_case("dot") {
+            break;
+        }
+
+        // Punctuation after a label ends the name ("example dot com, and ...")
+        let clean = next.len() == words[index].len();
+        labels.push(next);
+        index += 1;
+
+        let continues = is_word(words, index, &["dot"])
+            && words.get(index + 1).and_then(|word| label(word)).is_some();
+        if !clean || !continues {
+            break;
+        }
+        index += 1;
+    }
+
+    (labels, index)
+}
+
+fn is_domain(labels: &[String]) -> bool {
+    labels.len() >= 2
+        && TLDS.contains(&labels[labels.len() - 1].to_lowercase().as_str())
+        && !NON_ADDRESS_WORDS.contains(&labels[0].to_lowercase().as_str())
+}
+
+/// Spoken URL or email at the start of `words`: the written address, trailing
+/// punctuation from the last word, whether it is an email, and words consumed
+fn parse_address(words: &[&str]) -> Option<(String, String, bool, usize)> {
+    let mut address = String::new();
+    let mut index = 0;
+
+    if is_word(words, 0, &["http", "https"])
+        && is_word(words, 1, &["colon"])
+        && is_word(words, 2, &["slash"])
+        && is_word(words, 3, &["slash"])
+    {
+        address = format!("{}://", normalized(words[0]));
+        index = 4;
+    }
+
+    let (first, after_first) = dotted_name(words, index);
+    if first.is_empty() {
+        return None;
+    }
+
+    let is_email = address.is_empty() && is_word(words, after_first, &["at"]);
+    let (domain, mut end) = if is_email {
+        if NON_ADDRESS_WORDS.contains(&first[0].to_lowercase().as_str()) {
+            return None;
+        }
+        let (domain, end) = dotted_name(words, after_first + 1);
+        address.push_str(&first.join("."));
+        address.push('@');
+        (domain, end)
+    } else {
+        (first, after_first)
+    };
+
+    if !is_domain(&domain) {
+        return None;
+    }
+    address.push_str(&domain.join(".").to_lowercase());
+
+    // Path segments: "slash docs slash setup"
+    while !is_email && is_word(words, end, &["slash"]) {
+        match words.get(end + 1).and_then(|word| label(word)) {
+            Some(segment) => {
+                address.push('/');
+                address.push_str(&segment);
+                end += 2;
+            }
+            None => break,
+        }
+    }
+
+    let last = words[end - 1];
+    let trailing = last[label(last).map_or(last.len(), |l| l.len())..].to_string();
+
+    Some((address, trailing, is_email, end))
+}
+
+/// Render dictated code: spoken symbols become characters and punctuation the
+/// recognizer added to the words is dropped
+fn render_code(words: &[&str]) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut index = 0;
+
+    while index < words.len() {
+        let two = words
+            .get(index + 1)
+            .map(|next| format!("{} {}", normalized(words[index]), normalized(next)));
+        let one = normalized(words[index]);
+
+        if let Some((_, symbol)) = SPOKEN_SYMBOLS
+            .iter()
+            .find(|(phrase, _)| two.as_deref() == Some(*phrase))
+        {
+            tokens.push(symbol.to_string());
+            index += 2;
+        } else if let Some((_, symbol)) = SPOKEN_SYMBOLS.iter().find(|(phrase, _)| *phrase == one) {
+            tokens.push(symbol.to_string());
+            index += 1;
+        } else {
+            let word = words[index].trim_end_matches(['.', ',', '!', '?']);
+            if !word.is_empty() {
+                tokens.push(word.to_string());
+            }
+            index += 1;
+        }
+    }
+
+    let mut code = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let glued = i == 0
+            || GLUE_LEFT.contains(&token.as_str())
+            || GLUE_RIGHT.contains(&tokens[i - 1].as_str());
+        if !glued {
+            code.push(' ');
+        }
+        code.push_str(token);
+    }
+    code
+}
+
+/// Detect dictated code blocks and spoken URLs and emails
+pub fn parse(text: &str) -> Dictation {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut dictation = Dictation::default();
+    let mut prose: Vec<String> = Vec::new();
+
+    let flush = |prose: &mut Vec<String>, segments: &mut Vec<Segment>| {
+        if !prose.is_empty() {
+            segments.push(Segment {
+                kind: SegmentKind::Prose,
+                text: prose.join(" "),
+            });
+            prose.clear();
+        }
+    };
+
+    let mut index = 0;
+    while index < words.len() {
+        if is_code_marker(&words, index, &["begin", "start"]) {
+            // An unterminated block runs to the end of the transcript
+            let end =
+                (index + 2..words.len()).find(|&i| is_code_marker(&words, i, &["end", "stop"]));
+            let body_end = end.unwrap_or(words.len());
+
+            flush(&mut prose, &mut dictation.segments);
+            dictation.segments.push(Segment {
+                kind: SegmentKind::Code,
+                text: render_code(&words[index + 2..body_end]),
+            });
+            index = end.map_or(words.len(), |i| i + 2);
+            continue;
+        }
+
+        if let Some((address, trailing, is_email, consumed)) = parse_address(&words[index..]) {
+            prose.push(format!("{}{}", address, trailing));
+            if is_email {
+                dictation.emails.push(address);
+            } else {
+                dictation.urls.push(address);
+            }
+            index += consumed;
+            continue;
+        }
+
+        prose.push(words[index].to_string());
+        index += 1;
+    }
+
+    flush(&mut prose, &mut dictation.segments);
+    dictation
+}
+
+/// Join segments into the final text, fencing code blocks
+pub fn render(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment.kind {
+            SegmentKind::Prose => segment.text.clone(),
+            SegmentKind::Code => format!("```\n{}\n```", segment.text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(text: &str) -> String {
+        render(&parse(text).segments)
+    }
+
+    #[test]
+    fn test_spoken_urls_and_emails() {
+        let dictation =
+            parse("Docs are at example dot com slash docs. Mail jane dot doe at acme dot io");
+
+        assert_eq!(dictation.urls, vec!["example.com/docs"]);
+        assert_eq!(dictation.emails, vec!["jane.doe@acme.io"]);
+        assert_eq!(
+            render(&dictation.segments),
+            "Docs are at example.com/docs. Mail jane.doe@acme.io"
+        );
+
+        assert_eq!(
+            formatted("open https colon slash slash github dot com now"),
+            "open https://github.com now"
+        );
+    }
+
+    #[test]
+    fn test_ordinary_speech_is_left_alone() {
+        for text in [
+            "look at google dot com",
+            "the dot com bubble",
+            "meet me at noon",
+            "dot the i",
+        ] {
+            let dictation = parse(text);
+            assert!(dictation.emails.is_empty(), "{}", text);
+        }
+
+        assert_eq!(formatted("the dot com bubble"), "the dot com bubble");
+        assert_eq!(formatted("look at google dot com"), "look at google.com");
+    }
+
+    #[test]
+    fn test_code_block_keeps_symbols() {
+        let dictation = parse(concat!(
+            "Here it is. Begin code. Let x equals foo open paren bar close paren ",
+            "semicolon. End code. Done."
+        ));
+
+        assert_eq!(
+            dictation.segments,
+            vec![
+                Segment {
+                    kind: SegmentKind::Prose,
+                    text: "Here it is.".to_string()
+                },
+                Segment {
+                    kind: SegmentKind::Code,
+                    text: "Let x = foo(bar);".to_string()
+                },
+                Segment {
+                    kind: SegmentKind::Prose,
+                    text: "Done.".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            render(&dictation.segments),
+            "Here it is.\n```\nLet x = foo(bar);\n```\nDone."
+        );
+    }
+
+    #[test]
+    fn test_unterminated_code_block_runs_to_end() {
+        let dictation = parse("start code my underscore var dot len");
+
+        assert_eq!(dictation.segments.len(), 1);
+        assert_eq!(dictation.segments[0].text, "my_var.len");
+    }
+}
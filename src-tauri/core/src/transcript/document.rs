@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::transcription::TranscriptSegment;
+
+/// Identifies a file as a transcript document
+pub const DOCUMENT_FORMAT: &str = "transcriber-transcript";
+
+/// Bumped whenever the document changes shape; readers reject newer versions
+pub const DOCUMENT_VERSION: u32 = 1;
+
+/// The recording a transcript was made from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u16>,
+    /// Container or codec, e.g. "wav" or "opus"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// Engine and model that produced a transcript
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    pub engine: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// A transcript as the frontend hands it over for export
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptExport {
+    pub title: Option<String>,
+    /// RFC 3339
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub text: String,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub segments: Vec<TranscriptSegment>,
+    /// Names the user gave to diarization labels, e.g. "A" -> "Alice"
+    #[serde(default)]
+    pub speaker_names: BTreeMap<String, String>,
+    pub audio: Option<AudioMetadata>,
+    pub provider: Option<ProviderInfo>,
+}
+
+/// A diarization label and the name it was given, if any
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Speaker {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Versioned JSON document holding everything known about a transcript, so
+/// other tools (or a later import) get it without loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptDocument {
+    pub format: String,
+    pub version: u32,
+    /// RFC 3339
+    pub exported_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// With their word timestamps, where the engine reported them
+    pub segments: Vec<TranscriptSegment>,
+    /// Every label used in `segments`, in order of first appearance
+    pub speakers: Vec<Speaker>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<ProviderInfo>,
+}
+
+impl TranscriptDocument {
+    /// The document for `export`, exported at `exported_at`
+    pub fn new(export: TranscriptExport, exported_at: String) -> Self {
+        let mut speakers: Vec<Speaker> = Vec::new();
+        for label in export.segments.iter().filter_map(|s| s.speaker.as_deref()) {
+            if !speakers.iter().any(|speaker| speaker.label == label) {
+                speakers.push(Speaker {
+                    label: label.to_string(),
+                    name: export.speaker_names.get(label).cloned(),
+                });
+            }
+        }
+
+        Self {
+            format: DOCUMENT_FORMAT.to_string(),
+            version: DOCUMENT_VERSION,
+            exported_at,
+            title: export.title,
+            created_at: export.created_at,
+            text: export.text,
+            language: export.language,
+            segments: export.segments,
+            speakers,
+            audio: export.audio,
+            provider: export.provider,
+        }
+    }
+
+    /// Read a document written by any version up to the current one
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let document: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid transcript document: {}", e))?;
+        if document.format != DOCUMENT_FORMAT {
+            return Err(format!("Not a transcript document: {}", document.format));
+        }
+        if document.version > DOCUMENT_VERSION {
+            return Err(format!(
+                "Transcript document version {} is newer than this app supports",
+                document.version
+            ));
+        }
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::TranscriptWord;
+
+    #[test]
+    fn test_document_roundtrip() {
+        let segment = |speaker: &str, text: &str| TranscriptSegment {
+            start_ms: 0,
+            end_ms: 1000,
+            text: text.to_string(),
+            speaker: Some(speaker.to_string()),
+            confidence: Some(0.9),
+            words: vec![TranscriptWord {
+                start_ms: 0,
+                end_ms: 400,
+                text: text.to_string(),
+                confidence: 0.9,
+            }],
+        };
+        let export = TranscriptExport {
+            title: Some("Interview".to_string()),
+            text: "Hi Hello Bye".to_string(),
+            segments: vec![
+                segment("B", "Hi"),
+                segment("A", "Hello"),
+                segment("B", "Bye"),
+            ],
+            speaker_names: BTreeMap::from([("A".to_string(), "Alice".to_string())]),
+            audio: Some(AudioMetadata {
+                duration_ms: Some(3000),
+                format: Some("wav".to_string()),
+                ..Default::default()
+            }),
+            provider: Some(ProviderInfo {
+                engine: "whisper".to_string(),
+                model: Some("base".to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let document = TranscriptDocument::new(export, "2026-10-15T09:00:00Z".to_string());
+        assert_eq!(
+            document.speakers,
+            vec![
+                Speaker {
+                    label: "B".to_string(),
+                    name: None
+                },
+                Speaker {
+                    label: "A".to_string(),
+                    name: Some("Alice".to_string())
+                },
+            ]
+        );
+
+        let json = serde_json::to_string(&document).unwrap();
+        let parsed = TranscriptDocument::parse(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        assert_eq!(parsed.segments[1].words[0].end_ms, 400);
+        assert_eq!(parsed.audio.unwrap().duration_ms, Some(3000));
+
+        let newer = json.replace("\"version\":1", "\"version\":2");
+        assert!(TranscriptDocument::parse(&newer).is_err());
+        assert!(TranscriptDocument::parse(r#"{"format":"other"}"#).is_err());
+    }
+}
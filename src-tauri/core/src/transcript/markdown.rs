@@ -0,0 +1,182 @@
+use serde::Deserialize;
+
+use crate::transcription::TranscriptSegment;
+
+/// What goes into a Markdown export
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MarkdownOptions {
+    /// Level of the title heading; sections are one level below
+    pub heading_level: u8,
+    /// Show the date under the title
+    pub include_date: bool,
+    /// Start each paragraph with its speaker in bold
+    pub speaker_prefixes: bool,
+    /// Start each paragraph with its start time as inline code
+    pub timestamps: bool,
+    /// Add the summary as its own section before the transcript
+    pub include_summary: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            heading_level: 1,
+            include_date: true,
+            speaker_prefixes: true,
+            timestamps: true,
+            include_summary: true,
+        }
+    }
+}
+
+/// A transcript to export as Markdown
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownTranscript {
+    pub title: Option<String>,
+    /// Shown as given, e.g. already formatted for the user's locale
+    pub date: Option<String>,
+    pub summary: Option<String>,
+    /// One paragraph each
+    #[serde(default)]
+    pub segments: Vec<TranscriptSegment>,
+    /// Used when there are no segments
+    #[serde(default)]
+    pub text: String,
+}
+
+/// `MM:SS`, or `H:MM:SS` from an hour on
+fn timestamp(ms: u64) -> String {
+    let seconds = ms / 1000;
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn heading(level: u8, text: &str) -> String {
+    format!(
+        "{} {}\n\n",
+        "#".repeat(level.clamp(1, 6) as usize),
+        text.trim()
+    )
+}
+
+/// Render a transcript as Markdown: a heading with the title and date, an
+/// optional summary section, then one paragraph per segment
+pub fn render(transcript: &MarkdownTranscript, options: &MarkdownOptions) -> String {
+    let level = options.heading_level.clamp(1, 5);
+    let mut output = String::new();
+
+    let title = transcript
+        .title
+        .as_deref()
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or("Transcript");
+    output.push_str(&heading(level, title));
+    if let Some(date) = transcript.date.as_deref().filter(|_| options.include_date) {
+        output.push_str(&format!("_{}_\n\n", date.trim()));
+    }
+
+    let summary = transcript
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|summary| options.include_summary && !summary.is_empty());
+    if let Some(summary) = summary {
+        output.push_str(&heading(level + 1, "Summary"));
+        output.push_str(summary);
+        output.push_str("\n\n");
+        output.push_str(&heading(level + 1, "Transcript"));
+    }
+
+    if transcript.segments.is_empty() {
+        let text = transcript.text.trim();
+        if !text.is_empty() {
+            output.push_str(text);
+            output.push_str("\n\n");
+        }
+    }
+    for segment in &transcript.segments {
+        let mut prefix = String::new();
+        if options.timestamps {
+            prefix.push_str(&format!("`{}` ", timestamp(segment.start_ms)));
+        }
+        if let Some(speaker) = segment
+            .speaker
+            .as_deref()
+            .filter(|_| options.speaker_prefixes)
+        {
+            prefix.push_str(&format!("**{}:** ", speaker));
+        }
+        output.push_str(&format!("{}{}\n\n", prefix, segment.text.trim()));
+    }
+
+    output.truncate(output.trim_end().len());
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, speaker: Option<&str>, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_ms,
+            end_ms: start_ms + 1000,
+            text: text.to_string(),
+            speaker: speaker.map(str::to_string),
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let transcript = MarkdownTranscript {
+            title: Some("Standup".to_string()),
+            date: Some("Oct 15, 2026".to_string()),
+            summary: Some("Release is on track.".to_string()),
+            segments: vec![
+                segment(5_000, Some("A"), "Morning all."),
+                segment(3_725_000, None, " Done. "),
+            ],
+            text: String::new(),
+        };
+
+        assert_eq!(
+            render(&transcript, &MarkdownOptions::default()),
+            "# Standup\n\n_Oct 15, 2026_\n\n## Summary\n\nRelease is on track.\n\n\
+             ## Transcript\n\n`00:05` **A:** Morning all.\n\n`1:02:05` Done.\n"
+        );
+
+        let plain = MarkdownOptions {
+            heading_level: 2,
+            include_date: false,
+            speaker_prefixes: false,
+            timestamps: false,
+            include_summary: false,
+        };
+        assert_eq!(
+            render(&transcript, &plain),
+            "## Standup\n\nMorning all.\n\nDone.\n"
+        );
+
+        let untimed = MarkdownTranscript {
+            text: "Just text.".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&untimed, &MarkdownOptions::default()),
+            "# Transcript\n\nJust text.\n"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::corrections::{self, Correction};
+use super::dictation::normalized;
+
+/// Hesitations dropped by `TrimFillers`
+const FILLERS: &[&str] = &[
+    "um", "umm", "uh", "uhh", "uhm", "er", "erm", "ah", "hmm", "mm", "mhm",
+];
+
+/// "I" and its contractions, written in lowercase by some engines
+const FIRST_PERSON: &[&str] = &["i", "i'm", "i've", "i'll", "i'd"];
+
+/// A step transcripts pass through before they reach the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PostProcessor {
+    /// Drop hesitations such as "um" and "uh"
+    TrimFillers,
+    /// Capitalize the start of each sentence and "I"
+    FixCasing,
+    /// Apply the user's corrections
+    Corrections,
+    /// Replace every match of a regular expression; `$1` in the replacement
+    /// inserts the first group
+    Regex {
+        pattern: String,
+        replacement: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostProcessingStep {
+    #[serde(flatten)]
+    pub processor: PostProcessor,
+    pub enabled: bool,
+}
+
+/// The post-processors finished transcripts run through, in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PostProcessing {
+    pub steps: Vec<PostProcessingStep>,
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        let step = |processor, enabled| PostProcessingStep { processor, enabled };
+        Self {
+            steps: vec![
+                step(PostProcessor::TrimFillers, false),
+                step(PostProcessor::Corrections, true),
+                step(PostProcessor::FixCasing, false),
+            ],
+        }
+    }
+}
+
+enum Step {
+    TrimFillers,
+    FixCasing,
+    Corrections,
+    Regex(Regex, String),
+}
+
+/// Enabled post-processors, ready to run
+pub struct Pipeline {
+    steps: Vec<Step>,
+    corrections: Vec<Correction>,
+}
+
+impl PostProcessing {
+    /// Compile the enabled steps, failing on an invalid regular expression
+    pub fn compile(&self, corrections: &[Correction]) -> Result<Pipeline, String> {
+        let mut steps = Vec::new();
+        for step in self.steps.iter().filter(|step| step.enabled) {
+            steps.push(match &step.processor {
+                PostProcessor::TrimFillers => Step::TrimFillers,
+                PostProcessor::FixCasing => Step::FixCasing,
+                PostProcessor::Corrections => Step::Corrections,
+                PostProcessor::Regex {
+                    pattern,
+                    replacement,
+                } => Step::Regex(
+                    Regex::new(pattern)
+                        .map_err(|e| format!("Invalid rule \"{}\": {}", pattern, e))?,
+                    replacement.clone(),
+                ),
+            });
+        }
+
+        Ok(Pipeline {
+            steps,
+            corrections: corrections.to_vec(),
+        })
+    }
+}
+
+impl Pipeline {
+    pub fn apply(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_string(), |text, step| match step {
+                Step::TrimFillers => trim_fillers(&text),
+                Step::FixCasing => fix_casing(&text),
+                Step::Corrections => corrections::apply(&text, &self.corrections),
+                Step::Regex(regex, replacement) => {
+                    regex.replace_all(&text, replacement.as_str()).into_owned()
+                }
+            })
+    }
+}
+
+fn capitalize_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Drop filler words. Commas around a filler go with it, other punctuation
+/// moves to the word before, and a capital moves to the word after.
+fn trim_fillers(text: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut capitalize_next = false;
+
+    for word in text.split_whitespace() {
+        if !FILLERS.contains(&normalized(word).as_str()) {
+            words.push(if capitalize_next {
+                capitalize_first(word)
+            } else {
+                word.to_string()
+            });
+            capitalize_next = false;
+            continue;
+        }
+
+        capitalize_next |= word.starts_with(char::is_uppercase);
+        let punctuation = word.trim_start_matches(char::is_alphanumeric);
+        if let Some(previous) = words.last_mut() {
+            if punctuation.starts_with(',') {
+                if previous.ends_with(',') {
+                    previous.pop();
+                }
+            } else if !punctuation.is_empty()
+                && !previous.ends_with(|c: char| c.is_ascii_punctuation())
+            {
+                previous.push_str(punctuation);
+            }
+        }
+    }
+    words.join(" ")
+}
+
+/// Capitalize sentence starts and "I". Words with capitals of their own,
+/// such as "iPhone", are left as they are.
+fn fix_casing(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut sentence_start = true;
+
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end();
+        let bare = normalized(&word.replace('’', "'"));
+        let lowercase = !word.chars().any(char::is_uppercase);
+
+        if lowercase && (sentence_start || FIRST_PERSON.contains(&bare.as_str())) {
+            output.push_str(&capitalize_first(piece));
+        } else {
+            output.push_str(piece);
+        }
+
+        if !word.is_empty() {
+            sentence_start = word
+                .trim_end_matches(['"', '\'', ')', '”'])
+                .ends_with(['.', '!', '?']);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(processor: PostProcessor) -> PostProcessingStep {
+        PostProcessingStep {
+            processor,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        assert_eq!(
+            trim_fillers("Um, so I was, uh, going there um. Hmm okay"),
+            "So I was going there. Okay"
+        );
+        assert_eq!(
+            fix_casing("i think so. it's an iPhone! then i'm done"),
+            "I think so. It's an iPhone! Then I'm done"
+        );
+
+        let post_processing = PostProcessing {
+            steps: vec![
+                step(PostProcessor::TrimFillers),
+                step(PostProcessor::Regex {
+                    pattern: r"(\d+) percent".to_string(),
+                    replacement: "$1%".to_string(),
+                }),
+                step(PostProcessor::Corrections),
+                PostProcessingStep {
+                    processor: PostProcessor::FixCasing,
+                    enabled: false,
+                },
+            ],
+        };
+        let corrections = [Correction {
+            wrong: "cube control".to_string(),
+            right: "kubectl".to_string(),
+        }];
+        let pipeline = post_processing.compile(&corrections).unwrap();
+        assert_eq!(
+            pipeline.apply("uh cube control uses 50 percent"),
+            "kubectl uses 50%"
+        );
+
+        let invalid = PostProcessing {
+            steps: vec![step(PostProcessor::Regex {
+                pattern: "(".to_string(),
+                replacement: String::new(),
+            })],
+        };
+        assert!(invalid.compile(&[]).is_err());
+
+        let parsed: PostProcessingStep =
+            serde_json::from_str(r#"{ "type": "trimFillers", "enabled": true }"#).unwrap();
+        assert_eq!(parsed, step(PostProcessor::TrimFillers));
+    }
+}
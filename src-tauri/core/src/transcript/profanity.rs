@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+/// Words matched with any ending, e.g. "fucking" or "shitty"
+const STEMS: &[&str] = &[
+    "fuck",
+    "motherfuck",
+    "shit",
+    "bullshit",
+    "bitch",
+    "cunt",
+    "wank",
+];
+
+/// Words only matched as they are, since their stems start harmless words
+/// ("dickens", "assess", "scrapbook")
+const WORDS: &[&str] = &[
+    "ass", "asses", "asshole", "assholes", "bastard", "bastards", "bollocks", "crap", "crappy",
+    "damn", "damned", "dick", "dicks", "goddamn", "piss", "pissed", "prick", "pricks", "slut",
+    "sluts", "twat", "twats", "whore", "whores",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProfanityMode {
+    #[default]
+    Off,
+    /// Keep the first letter: "f***"
+    Mask,
+    /// Drop the word and the space before it
+    Remove,
+}
+
+/// Profanity filter applied to finished transcripts, e.g. before publishing
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProfanityFilter {
+    pub mode: ProfanityMode,
+    /// Filtered in addition to the built-in English list, matched whole
+    pub extra_words: Vec<String>,
+}
+
+impl ProfanityFilter {
+    fn is_profane(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        STEMS.iter().any(|stem| word.starts_with(stem))
+            || WORDS.contains(&word.as_str())
+            || self
+                .extra_words
+                .iter()
+                .any(|extra| extra.trim().to_lowercase() == word)
+    }
+
+    /// Mask or remove profane words, leaving everything else as it is
+    pub fn apply(&self, text: &str) -> String {
+        if self.mode == ProfanityMode::Off {
+            return text.to_string();
+        }
+
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '\'';
+        let mut filtered = String::with_capacity(text.len());
+        let mut skip_space = false;
+        let mut rest = text;
+
+        while let Some(start) = rest.find(is_word_char) {
+            let (before, from_word) = rest.split_at(start);
+            let end = from_word
+                .find(|c| !is_word_char(c))
+                .unwrap_or(from_word.len());
+            let (word, after) = from_word.split_at(end);
+            rest = after;
+
+            filtered.push_str(match skip_space {
+                true => before.trim_start_matches(' '),
+                false => before,
+            });
+            skip_space = false;
+
+            if !self.is_profane(word) {
+                filtered.push_str(word);
+                continue;
+            }
+            match self.mode {
+                ProfanityMode::Mask => {
+                    let mut chars = word.chars();
+                    filtered.extend(chars.next());
+                    filtered.extend(chars.map(|_| '*'));
+                }
+                _ => {
+                    // Keep one space between the neighbours of the word
+                    if filtered.ends_with(' ') {
+                        filtered.truncate(filtered.trim_end_matches(' ').len());
+                    } else {
+                        skip_space = true;
+                    }
+                }
+            }
+        }
+
+        filtered.push_str(match skip_space {
+            true => rest.trim_start_matches(' '),
+            false => rest,
+        });
+        filtered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(mode: ProfanityMode) -> ProfanityFilter {
+        ProfanityFilter {
+            mode,
+            extra_words: vec!["Frak".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_mask_and_remove() {
+        let text = "Well, shit, that was a damn fine assessment. Frak!";
+
+        assert_eq!(filter(ProfanityMode::Off).apply(text), text);
+        assert_eq!(
+            filter(ProfanityMode::Mask).apply(text),
+            "Well, s***, that was a d*** fine assessment. F***!"
+        );
+        assert_eq!(
+            filter(ProfanityMode::Remove).apply("Shit that was a damn fine fucking assessment"),
+            "that was a fine assessment"
+        );
+        assert_eq!(
+            filter(ProfanityMode::Remove).apply("What the fuck, Scrooge?"),
+            "What the, Scrooge?"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use super::dictation::{is_code_marker, normalized};
+
+/// Punctuation said out loud, e.g. "see you tomorrow period"
+const SPOKEN_MARKS: &[(&[&str], char)] = &[
+    (&["question", "mark"], '?'),
+    (&["exclamation", "mark"], '!'),
+    (&["exclamation", "point"], '!'),
+    (&["full", "stop"], '.'),
+    (&["period"], '.'),
+    (&["comma"], ','),
+    (&["colon"], ':'),
+    (&["semicolon"], ';'),
+];
+
+/// Verbs that open a yes/no question when a subject follows ("do you")
+const AUXILIARIES: &[&str] = &[
+    "am",
+    "are",
+    "is",
+    "was",
+    "were",
+    "do",
+    "does",
+    "did",
+    "can",
+    "could",
+    "will",
+    "would",
+    "shall",
+    "should",
+    "have",
+    "has",
+    "had",
+    "may",
+    "might",
+    "isn't",
+    "aren't",
+    "don't",
+    "doesn't",
+    "didn't",
+    "can't",
+    "won't",
+    "wouldn't",
+    "shouldn't",
+];
+
+const SUBJECTS: &[&str] = &[
+    "i", "you", "we", "they", "he", "she", "it", "this", "that", "there", "the", "a", "an", "your",
+    "my", "our", "their", "anyone", "someone",
+];
+
+const QUESTION_WORDS: &[&str] = &[
+    "what", "why", "how", "where", "when", "who", "which", "whose",
+];
+
+/// Whether `text` looks like it came from an engine that leaves out
+/// punctuation and capitals
+pub fn needs_restoration(text: &str) -> bool {
+    text.chars().any(char::is_alphabetic)
+        && !text
+            .chars()
+            .any(|c| matches!(c, '.' | '?' | '!') || c.is_uppercase())
+}
+
+/// "What is", "how do" or "do you" at the start of a sentence
+fn is_question(words: &[String]) -> bool {
+    let first = words
+        .first()
+        .map(|word| normalized(word))
+        .unwrap_or_default();
+    let second = words
+        .get(1)
+        .map(|word| normalized(word))
+        .unwrap_or_default();
+
+    (QUESTION_WORDS.contains(&first.as_str()) && AUXILIARIES.contains(&second.as_str()))
+        || (AUXILIARIES.contains(&first.as_str()) && SUBJECTS.contains(&second.as_str()))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// The spoken mark starting at `index`, and how many words it takes
+fn spoken_mark(words: &[&str], index: usize) -> Option<(char, usize)> {
+    SPOKEN_MARKS.iter().find_map(|(spoken, mark)| {
+        let matches = spoken
+            .iter()
+            .enumerate()
+            .all(|(offset, expected)| words.get(index + offset) == Some(expected));
+        matches.then_some((*mark, spoken.len()))
+    })
+}
+
+/// Words of the restored text, by phrase, and the sentence being built
+struct Restorer {
+    phrases: Vec<Vec<String>>,
+    /// Phrase and position of each word of the current sentence
+    sentence: Vec<(usize, usize)>,
+}
+
+impl Restorer {
+    fn push(&mut self, phrase: usize, word: String) {
+        self.sentence.push((phrase, self.phrases[phrase].len()));
+        self.phrases[phrase].push(word);
+    }
+
+    fn last_word(&mut self) -> Option<&mut String> {
+        let &(phrase, index) = self.sentence.last()?;
+        Some(&mut self.phrases[phrase][index])
+    }
+
+    /// Capitalize the sentence and end it with `mark`, or with a question
+    /// mark or period when none was spoken
+    fn end_sentence(&mut self, mark: Option<char>) {
+        let words: Vec<String> = self
+            .sentence
+            .iter()
+            .map(|&(phrase, index)| self.phrases[phrase][index].clone())
+            .collect();
+        let mark = mark.unwrap_or(if is_question(&words) { '?' } else { '.' });
+
+        if let Some(&(phrase, index)) = self.sentence.first() {
+            let first = &mut self.phrases[phrase][index];
+            *first = capitalize(first);
+        }
+        if let Some(last) = self.last_word() {
+            last.truncate(last.trim_end_matches([',', ';', ':']).len());
+            last.push(mark);
+        }
+        self.sentence.clear();
+    }
+}
+
+/// Punctuate and capitalize phrases from an engine that does neither. Each
+/// phrase is given with whether a sentence ends after it, e.g. because the
+/// speaker paused; spoken punctuation ends sentences anywhere. Dictated code
+/// blocks are left as they are. Returns the restored phrases.
+pub fn restore_phrases(phrases: &[(&str, bool)]) -> Vec<String> {
+    let mut restorer = Restorer {
+        phrases: vec![Vec::new(); phrases.len()],
+        sentence: Vec::new(),
+    };
+    let mut in_code = false;
+
+    for (phrase, &(text, ends_sentence)) in phrases.iter().enumerate() {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut index = 0;
+
+        while index < words.len() {
+            if in_code || is_code_marker(&words, index, &["begin", "start"]) {
+                if !in_code {
+                    restorer.end_sentence(None);
+                }
+                in_code = !is_code_marker(&words, index, &["end", "stop"]);
+                let consumed = if in_code { 1 } else { 2 };
+                for word in &words[index..(index + consumed).min(words.len())] {
+                    restorer.phrases[phrase].push(word.to_string());
+                }
+                index += consumed;
+                continue;
+            }
+
+            if let Some((mark, consumed)) = spoken_mark(&words, index) {
+                // A mark before any words of the sentence is dropped
+                if let Some(last) = restorer.last_word() {
+                    if matches!(mark, '.' | '?' | '!') {
+                        restorer.end_sentence(Some(mark));
+                    } else {
+                        last.push(mark);
+                    }
+                }
+                index += consumed;
+                continue;
+            }
+
+            let word = match words[index] {
+                "i" | "i'm" | "i'll" | "i've" | "i'd" => capitalize(words[index]),
+                word => word.to_string(),
+            };
+            restorer.push(phrase, word);
+            index += 1;
+        }
+
+        if ends_sentence && !in_code {
+            restorer.end_sentence(None);
+        }
+    }
+    restorer.end_sentence(None);
+
+    restorer
+        .phrases
+        .into_iter()
+        .map(|words| words.join(" "))
+        .collect()
+}
+
+/// `restore_phrases` for text without timing, as one phrase
+pub fn restore(text: &str) -> String {
+    restore_phrases(&[(text, true)]).remove(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore() {
+        assert!(needs_restoration("so i think we should ship it"));
+        assert!(!needs_restoration("So I think we should ship it."));
+        assert!(!needs_restoration("42"));
+
+        assert_eq!(
+            restore("so i think we should ship it comma but not today period do you agree"),
+            "So I think we should ship it, but not today. Do you agree?"
+        );
+        assert_eq!(
+            restore("when i got home i slept"),
+            "When I got home I slept."
+        );
+
+        // Pauses end sentences; code blocks are not touched
+        let phrases = restore_phrases(&[
+            ("what is the plan", true),
+            ("we ship on friday and", false),
+            ("then rest begin code x equals one end code", true),
+            ("okay", false),
+        ]);
+        assert_eq!(
+            phrases,
+            [
+                "What is the plan?",
+                "We ship on friday and",
+                "then rest. begin code x equals one end code",
+                "Okay.",
+            ]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+/// Block-level structure of the Markdown subset the formatter produces:
+/// headings, paragraphs, bullet and numbered lists, and fenced code
+#[derive(Debug, PartialEq, Eq)]
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Bullets(Vec<String>),
+    Numbered(Vec<String>),
+    Code(String),
+}
+
+/// Inline spans: `**bold**`, `*italic*` and `` `code` ``
+#[derive(Debug, PartialEq, Eq)]
+enum Inline {
+    Text(String),
+    Bold(String),
+    Italic(String),
+    Code(String),
+}
+
+fn numbered_item(line: &str) -> Option<&str> {
+    let (number, rest) = line.split_once(". ")?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then_some(rest)
+}
+
+fn bullet_item(line: &str) -> Option<&str> {
+    line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = markdown.lines().peekable();
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(paragraph.join(" ")));
+            paragraph.clear();
+        }
+    };
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut blocks);
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim().starts_with("```") {
+                    break;
+                }
+                code.push(line);
+            }
+            blocks.push(Block::Code(code.join("\n")));
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if let Some((hashes, text)) = trimmed.split_once(' ').filter(|(hashes, _)| {
+            (1..=3).contains(&hashes.len()) && hashes.chars().all(|c| c == '#')
+        }) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading(hashes.len(), text.trim().to_string()));
+        } else if let Some(item) = bullet_item(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            let mut items = vec![item.to_string()];
+            while let Some(item) = lines.peek().and_then(|line| bullet_item(line.trim())) {
+                items.push(item.to_string());
+                lines.next();
+            }
+            blocks.push(Block::Bullets(items));
+        } else if let Some(item) = numbered_item(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            let mut items = vec![item.to_string()];
+            while let Some(item) = lines.peek().and_then(|line| numbered_item(line.trim())) {
+                items.push(item.to_string());
+                lines.next();
+            }
+            blocks.push(Block::Numbered(items));
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['*', '`']) {
+        let marker = if rest[start..].starts_with("**") {
+            "**"
+        } else {
+            &rest[start..start + 1]
+        };
+        let content_start = start + marker.len();
+
+        // Unmatched markers and ones padded with spaces ("2 * 3") are literal text
+        let Some(length) = rest[content_start..].find(marker).filter(|&len| {
+            let content = &rest[content_start..content_start + len];
+            !content.is_empty() && content.trim() == content
+        }) else {
+            spans.push(Inline::Text(rest[..content_start].to_string()));
+            rest = &rest[content_start..];
+            continue;
+        };
+
+        if start > 0 {
+            spans.push(Inline::Text(rest[..start].to_string()));
+        }
+        let content = rest[content_start..content_start + length].to_string();
+        spans.push(match marker {
+            "**" => Inline::Bold(content),
+            "`" => Inline::Code(content),
+            _ => Inline::Italic(content),
+        });
+        rest = &rest[content_start + length + marker.len()..];
+    }
+
+    if !rest.is_empty() {
+        spans.push(Inline::Text(rest.to_string()));
+    }
+
+    // Merge adjacent text produced by unmatched markers
+    spans.into_iter().fold(Vec::new(), |mut merged, span| {
+        match (merged.last_mut(), span) {
+            (Some(Inline::Text(previous)), Inline::Text(text)) => previous.push_str(&text),
+            (_, span) => merged.push(span),
+        }
+        merged
+    })
+}
+
+fn plain_inline(text: &str) -> String {
+    parse_inline(text)
+        .into_iter()
+        .map(|span| match span {
+            Inline::Text(text) | Inline::Bold(text) | Inline::Italic(text) | Inline::Code(text) => {
+                text
+            }
+        })
+        .collect()
+}
+
+/// Plain text for terminals and plain editors: markup removed, list markers kept
+pub fn to_plain(markdown: &str) -> String {
+    parse_blocks(markdown)
+        .iter()
+        .map(|block| match block {
+            Block::Heading(_, text) | Block::Paragraph(text) => plain_inline(text),
+            Block::Bullets(items) => items
+                .iter()
+                .map(|item| format!("- {}", plain_inline(item)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Numbered(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| format!("{}. {}", i + 1, plain_inline(item)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Code(code) => code.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_inline(text: &str) -> String {
+    parse_inline(text)
+        .into_iter()
+        .map(|span| match span {
+            Inline::Text(text) => escape_html(&text),
+            Inline::Bold(text) => format!("<strong>{}</strong>", escape_html(&text)),
+            Inline::Italic(text) => format!("<em>{}</em>", escape_html(&text)),
+            Inline::Code(text) => format!("<code>{}</code>", escape_html(&text)),
+        })
+        .collect()
+}
+
+/// HTML fragment for word processors and rich text editors
+pub fn to_html(markdown: &str) -> String {
+    parse_blocks(markdown)
+        .iter()
+        .map(|block| match block {
+            Block::Heading(level, text) => format!("<h{0}>{1}</h{0}>", level, html_inline(text)),
+            Block::Paragraph(text) => format!("<p>{}</p>", html_inline(text)),
+            Block::Bullets(items) => format!(
+                "<ul>{}</ul>",
+                items
+                    .iter()
+                    .map(|item| format!("<li>{}</li>", html_inline(item)))
+                    .collect::<String>()
+            ),
+            Block::Numbered(items) => format!(
+                "<ol>{}</ol>",
+                items
+                    .iter()
+                    .map(|item| format!("<li>{}</li>", html_inline(item)))
+                    .collect::<String>()
+            ),
+            Block::Code(code) => format!("<pre><code>{}</code></pre>", escape_html(code)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape RTF control characters; non-ASCII becomes `\uN?`
+fn escape_rtf(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\line "),
+            c if c.is_ascii() => escaped.push(c),
+            c => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+fn rtf_inline(text: &str) -> String {
+    parse_inline(text)
+        .into_iter()
+        .map(|span| match span {
+            Inline::Text(text) => escape_rtf(&text),
+            Inline::Bold(text) => format!("{{\\b {}}}", escape_rtf(&text)),
+            Inline::Italic(text) => format!("{{\\i {}}}", escape_rtf(&text)),
+            Inline::Code(text) => format!("{{\\f1 {}}}", escape_rtf(&text)),
+        })
+        .collect()
+}
+
+/// RTF document for apps that prefer it over HTML (TextEdit, older Word)
+pub fn to_rtf(markdown: &str) -> String {
+    let body: String = parse_blocks(markdown)
+        .iter()
+        .map(|block| match block {
+            Block::Heading(level, text) => {
+                let size = [36, 30, 26][level - 1];
+                format!("{{\\b\\fs{} {}}}\\par\n", size, rtf_inline(text))
+            }
+            Block::Paragraph(text) => format!("{}\\par\n", rtf_inline(text)),
+            Block::Bullets(items) => items
+                .iter()
+                .map(|item| format!("\\bullet\\tab {}\\par\n", rtf_inline(item)))
+                .collect(),
+            Block::Numbered(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| format!("{}.\\tab {}\\par\n", i + 1, rtf_inline(item)))
+                .collect(),
+            Block::Code(code) => format!("{{\\f1 {}}}\\par\n", escape_rtf(code)),
+        })
+        .collect();
+
+    format!(
+        "{{\\rtf1\\ansi\\deff0{{\\fonttbl{{\\f0 Helvetica;}}{{\\f1 Courier New;}}}}\n{}}}",
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "# Notes\n\nShip **today**, not *tomorrow*.\n\n- one\n- two\n\n```\nfn x() {}\n```";
+
+    #[test]
+    fn test_blocks_and_inline() {
+        assert_eq!(
+            parse_blocks(SAMPLE),
+            vec![
+                Block::Heading(1, "Notes".to_string()),
+                Block::Paragraph("Ship **today**, not *tomorrow*.".to_string()),
+                Block::Bullets(vec!["one".to_string(), "two".to_string()]),
+                Block::Code("fn x() {}".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_inline("2 * 3 and `a*b`"),
+            vec![
+                Inline::Text("2 * 3 and ".to_string()),
+                Inline::Code("a*b".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_renditions() {
+        assert_eq!(
+            to_plain(SAMPLE),
+            "Notes\n\nShip today, not tomorrow.\n\n- one\n- two\n\nfn x() {}"
+        );
+
+        let html = to_html(SAMPLE);
+        assert!(html.contains("<h1>Notes</h1>"));
+        assert!(html.contains("<strong>today</strong>"));
+        assert!(html.contains("<ul><li>one</li><li>two</li></ul>"));
+
+        let rtf = to_rtf(SAMPLE);
+        assert!(rtf.starts_with("{\\rtf1"));
+        assert!(rtf.contains("{\\b today}"));
+        assert!(rtf.contains("fn x() \\{\\}"));
+        assert_eq!(escape_rtf("é"), "\\u233?");
+    }
+}
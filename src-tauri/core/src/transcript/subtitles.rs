@@ -0,0 +1,227 @@
+use serde::Deserialize;
+
+use crate::transcription::TranscriptSegment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+/// Line and timing limits for cues; the defaults follow common broadcast
+/// guidelines
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SubtitleOptions {
+    /// Characters per line; longer words get a line of their own
+    pub max_line_chars: usize,
+    pub max_lines: usize,
+    /// Cues are cut short after this long
+    pub max_duration_ms: u64,
+    /// Short cues are held this long, unless the next one starts earlier
+    pub min_duration_ms: u64,
+    /// Mark WebVTT cues with their speaker as a `<v Speaker>` voice tag
+    pub voice_tags: bool,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            max_line_chars: 42,
+            max_lines: 2,
+            max_duration_ms: 7000,
+            min_duration_ms: 1000,
+            voice_tags: true,
+        }
+    }
+}
+
+/// A subtitle shown from `start_ms` to `end_ms`
+#[derive(Debug, Clone, PartialEq)]
+struct Cue {
+    start_ms: u64,
+    end_ms: u64,
+    lines: Vec<String>,
+    speaker: Option<String>,
+}
+
+/// Break `text` into lines of at most `max_chars`, grouped into cues of at
+/// most `max_lines`
+fn wrap(text: &str, max_chars: usize, max_lines: usize) -> Vec<Vec<String>> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= max_chars => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+
+    lines
+        .chunks(max_lines.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+/// Cues for the segments: a segment too long for one cue is split, its time
+/// shared by the length of each part, and the duration limits applied
+fn cues(segments: &[TranscriptSegment], options: &SubtitleOptions) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    for segment in segments {
+        let parts = wrap(&segment.text, options.max_line_chars, options.max_lines);
+        let total_chars: usize = parts.iter().flatten().map(|line| line.len()).sum();
+        let duration = segment.end_ms.saturating_sub(segment.start_ms);
+
+        let mut chars_before = 0;
+        for lines in parts {
+            let chars: usize = lines.iter().map(|line| line.len()).sum();
+            let start_ms = segment.start_ms + duration * chars_before as u64 / total_chars as u64;
+            chars_before += chars;
+            let end_ms = segment.start_ms + duration * chars_before as u64 / total_chars as u64;
+            cues.push(Cue {
+                start_ms,
+                end_ms,
+                lines,
+                speaker: segment.speaker.clone(),
+            });
+        }
+    }
+
+    let starts: Vec<u64> = cues.iter().map(|cue| cue.start_ms).skip(1).collect();
+    for (index, cue) in cues.iter_mut().enumerate() {
+        let next_start = starts.get(index).copied().unwrap_or(u64::MAX);
+        cue.end_ms = cue
+            .end_ms
+            .max(cue.start_ms + options.min_duration_ms)
+            .min(next_start.max(cue.start_ms))
+            .min(cue.start_ms + options.max_duration_ms);
+    }
+    cues
+}
+
+/// `HH:MM:SS,mmm` for SRT, `HH:MM:SS.mmm` for WebVTT
+fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// Escape the characters WebVTT cue text reserves for markup
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Render timed segments as SRT or WebVTT. WebVTT cues carry the speaker as
+/// a voice tag unless `options` turn that off.
+pub fn render(
+    segments: &[TranscriptSegment],
+    format: SubtitleFormat,
+    options: &SubtitleOptions,
+) -> String {
+    let mut output = match format {
+        SubtitleFormat::Srt => String::new(),
+        SubtitleFormat::Vtt => "WEBVTT\n\n".to_string(),
+    };
+
+    for (index, cue) in cues(segments, options).into_iter().enumerate() {
+        let text = cue.lines.join("\n");
+        match format {
+            SubtitleFormat::Srt => output.push_str(&format!(
+                "{}\n{} --> {}\n{}\n\n",
+                index + 1,
+                timestamp(cue.start_ms, ','),
+                timestamp(cue.end_ms, ','),
+                text
+            )),
+            SubtitleFormat::Vtt => {
+                let text = match &cue.speaker {
+                    Some(speaker) if options.voice_tags => {
+                        format!("<v {}>{}", escape_vtt(speaker), escape_vtt(&text))
+                    }
+                    _ => escape_vtt(&text),
+                };
+                output.push_str(&format!(
+                    "{} --> {}\n{}\n\n",
+                    timestamp(cue.start_ms, '.'),
+                    timestamp(cue.end_ms, '.'),
+                    text
+                ));
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, end_ms: u64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            speaker: None,
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let options = SubtitleOptions {
+            max_line_chars: 16,
+            max_lines: 1,
+            ..Default::default()
+        };
+        let segments = [
+            segment(0, 4000, "Hello there, how are you today?"),
+            segment(4200, 4400, "Fine."),
+            segment(5000, 20_000, "Long pause"),
+        ];
+
+        assert_eq!(
+            render(&segments, SubtitleFormat::Srt, &options),
+            "1\n00:00:00,000 --> 00:00:02,133\nHello there, how\n\n\
+             2\n00:00:02,133 --> 00:00:04,000\nare you today?\n\n\
+             3\n00:00:04,200 --> 00:00:05,000\nFine.\n\n\
+             4\n00:00:05,000 --> 00:00:12,000\nLong pause\n\n"
+        );
+
+        let mut spoken = segment(3_723_004, 3_724_000, "Hi");
+        spoken.speaker = Some("A".to_string());
+        assert_eq!(
+            render(&[spoken.clone()], SubtitleFormat::Vtt, &options),
+            "WEBVTT\n\n01:02:03.004 --> 01:02:04.004\n<v A>Hi\n\n"
+        );
+
+        spoken.text = "<b> & co".to_string();
+        let untagged = SubtitleOptions {
+            voice_tags: false,
+            ..options
+        };
+        assert_eq!(
+            render(&[spoken], SubtitleFormat::Vtt, &untagged),
+            "WEBVTT\n\n01:02:03.004 --> 01:02:04.004\n&lt;b&gt; &amp; co\n\n"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use crate::analytics::PaceMetrics;
+use crate::transcript::punctuation;
+
+pub mod assemblyai;
+pub mod azure;
+pub mod chunking;
+pub mod deepgram;
+pub mod google;
+pub mod openai;
+pub mod provider;
+pub mod retry;
+
+/// Pause after which a segment ends its sentence when punctuation is restored
+const SENTENCE_PAUSE_MS: u64 = 700;
+
+/// A timed piece of a transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSegment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    /// Speaker label from diarization, e.g. "A" or "Speaker 1"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// How sure the engine is of the text, 0.0 to 1.0, if it says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// The words with their own confidence, from engines that report them.
+    /// They are the engine's words and may differ from `text` once it is
+    /// cleaned up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<TranscriptWord>,
+}
+
+/// A recognized word
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptWord {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    /// 0.0 to 1.0
+    pub confidence: f32,
+}
+
+/// Average confidence of `words`, for segments the engine does not rate
+fn mean_confidence(words: &[TranscriptWord]) -> Option<f32> {
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.iter().map(|word| word.confidence).sum::<f32>() / words.len() as f32)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionResult {
+    pub text: String,
+    /// Spoken (or requested) language
+    pub language: Option<String>,
+    pub segments: Vec<TranscriptSegment>,
+    pub duration_ms: u64,
+    /// Engine and model that produced the transcript
+    pub engine: String,
+    pub model: String,
+    pub pace: PaceMetrics,
+}
+
+/// A possible spoken language and its probability
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageCandidate {
+    pub language: String,
+    pub probability: f32,
+}
+
+/// Result of `detect_language`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageDetection {
+    /// Most likely language, as an ISO 639-1 code
+    pub language: String,
+    /// Its probability, 0.0 to 1.0
+    pub confidence: f32,
+    /// The most likely languages, best first
+    pub candidates: Vec<LanguageCandidate>,
+}
+
+/// Payload of `transcription-interim` and `transcription-final` from live
+/// transcription. Interim results for a stretch of audio are replaced by later
+/// ones until its final result arrives.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveTranscript {
+    pub session_id: String,
+    pub text: String,
+    /// Position in the streamed audio
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Only given for final results by some providers
+    pub confidence: Option<f32>,
+    /// Speaker label from diarization, e.g. "Speaker 1"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// The speaker paused, ending the utterance
+    pub speech_final: bool,
+}
+
+/// Punctuate and capitalize a transcript from an engine that does neither.
+/// Pauses and speaker changes between segments end sentences, and the text
+/// is rebuilt from the segments when there are any. Transcripts with
+/// punctuation or capitals are left as they are.
+pub fn restore_punctuation(result: &mut TranscriptionResult) {
+    if !punctuation::needs_restoration(&result.text) {
+        return;
+    }
+    if result.segments.is_empty() {
+        result.text = punctuation::restore(&result.text);
+        return;
+    }
+
+    let phrases: Vec<(&str, bool)> = result
+        .segments
+        .iter()
+        .zip(result.segments.iter().skip(1).map(Some).chain([None]))
+        .map(|(segment, next)| {
+            let ends_sentence = next.is_none_or(|next| {
+                next.start_ms.saturating_sub(segment.end_ms) >= SENTENCE_PAUSE_MS
+                    || next.speaker != segment.speaker
+            });
+            (segment.text.as_str(), ends_sentence)
+        })
+        .collect();
+    let restored = punctuation::restore_phrases(&phrases);
+
+    for (segment, text) in result.segments.iter_mut().zip(restored) {
+        segment.text = text;
+    }
+    result.text = result
+        .segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+}
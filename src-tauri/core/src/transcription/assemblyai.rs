@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::provider::{
+    parse_options, AudioFile, Credential, Credentials, ProviderCapabilities, ProviderFuture,
+    TranscriptionProvider,
+};
+use super::{mean_confidence, retry, TranscriptSegment, TranscriptWord, TranscriptionResult};
+use crate::analytics::PaceMetrics;
+use crate::Events;
+
+const API_URL: &str = "https://api.assemblyai.com/v2";
+/// Secure storage key of the API key, shared with the frontend
+pub const API_KEY_NAME: &str = "assemblyai_api_key";
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+/// Per request; uploads of long recordings can take minutes
+const REQUEST_TIMEOUT_SECS: u64 = 600;
+const POLL_INTERVAL_SECS: u64 = 3;
+/// AssemblyAI usually needs a fraction of the audio's length
+const MAX_WAIT_SECS: u64 = 60 * 60;
+
+/// Options for AssemblyAI transcription
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AssemblyAiOptions {
+    /// "best", "nano", ...; `None` uses the account's default
+    pub speech_model: Option<String>,
+    /// Language code such as "en" or "de"; `None` detects the language
+    pub language: Option<String>,
+    /// Label segments with speakers ("A", "B", ...)
+    pub diarize: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TranscriptRequest<'a> {
+    audio_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speech_model: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_code: Option<&'a str>,
+    language_detection: bool,
+    speaker_labels: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    upload_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiWord {
+    text: String,
+    start: u64,
+    end: u64,
+    confidence: f32,
+}
+
+impl From<ApiWord> for TranscriptWord {
+    fn from(word: ApiWord) -> Self {
+        TranscriptWord {
+            start_ms: word.start,
+            end_ms: word.end,
+            text: word.text,
+            confidence: word.confidence,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiUtterance {
+    text: String,
+    start: u64,
+    end: u64,
+    speaker: String,
+    confidence: Option<f32>,
+    #[serde(default)]
+    words: Vec<ApiWord>,
+}
+
+/// A transcript job; times are in milliseconds, `audio_duration` in seconds
+#[derive(Debug, Deserialize)]
+struct ApiTranscript {
+    id: String,
+    status: String,
+    error: Option<String>,
+    text: Option<String>,
+    language_code: Option<String>,
+    audio_duration: Option<f64>,
+    speech_model: Option<String>,
+    #[serde(default)]
+    words: Vec<ApiWord>,
+    #[serde(default)]
+    utterances: Option<Vec<ApiUtterance>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: String,
+}
+
+/// Group words into sentence segments
+fn sentences(words: Vec<ApiWord>) -> Vec<TranscriptSegment> {
+    let mut segments: Vec<TranscriptSegment> = Vec::new();
+    let mut open = false;
+
+    for word in words {
+        let ends_sentence = word.text.ends_with(['.', '!', '?']);
+        match segments.last_mut() {
+            Some(segment) if open => {
+                segment.text.push(' ');
+                segment.text.push_str(&word.text);
+                segment.end_ms = word.end;
+                segment.words.push(word.into());
+            }
+            _ => segments.push(TranscriptSegment {
+                start_ms: word.start,
+                end_ms: word.end,
+                text: word.text.clone(),
+                speaker: None,
+                confidence: None,
+                words: vec![word.into()],
+            }),
+        }
+        open = !ends_sentence;
+    }
+
+    for segment in &mut segments {
+        segment.confidence = mean_confidence(&segment.words);
+    }
+    segments
+}
+
+fn to_result(transcript: ApiTranscript) -> TranscriptionResult {
+    let segments: Vec<TranscriptSegment> = match transcript.utterances {
+        Some(utterances) if !utterances.is_empty() => utterances
+            .into_iter()
+            .map(|utterance| {
+                let words: Vec<TranscriptWord> =
+                    utterance.words.into_iter().map(Into::into).collect();
+                TranscriptSegment {
+                    start_ms: utterance.start,
+                    end_ms: utterance.end,
+                    text: utterance.text,
+                    speaker: Some(utterance.speaker),
+                    confidence: utterance.confidence.or_else(|| mean_confidence(&words)),
+                    words,
+                }
+            })
+            .collect(),
+        _ => sentences(transcript.words),
+    };
+    let duration_ms = transcript
+        .audio_duration
+        .map(|seconds| (seconds.max(0.0) * 1000.0).round() as u64)
+        .or_else(|| segments.last().map(|segment| segment.end_ms))
+        .unwrap_or(0);
+
+    TranscriptionResult {
+        text: transcript.text.unwrap_or_default().trim().to_string(),
+        language: transcript.language_code,
+        pace: PaceMetrics::from_segments(&segments, duration_ms),
+        segments,
+        duration_ms,
+        engine: "assemblyai".to_string(),
+        model: transcript.speech_model.unwrap_or_default(),
+    }
+}
+
+/// Send a request and parse the JSON response, turning API errors into messages
+async fn send<T: serde::de::DeserializeOwned>(
+    events: &Events,
+    request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<T, String> {
+    let (status, body) = retry::send(events, "AssemblyAI", request).await?;
+
+    if !status.is_success() {
+        let message = serde_json::from_slice::<ApiErrorBody>(&body)
+            .map(|body| body.error)
+            .unwrap_or_else(|_| status.to_string());
+        return Err(format!("Transcription failed: {}", message));
+    }
+
+    serde_json::from_slice(&body).map_err(|e| format!("Unexpected response from AssemblyAI: {}", e))
+}
+
+/// Upload audio to AssemblyAI and wait for the transcript
+pub async fn transcribe(
+    events: &Events,
+    api_key: &str,
+    audio: Vec<u8>,
+    options: &AssemblyAiOptions,
+) -> Result<TranscriptionResult, String> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let upload: UploadResponse = send(events, || {
+        client
+            .post(format!("{}/upload", API_URL))
+            .header("authorization", api_key)
+            .body(audio.clone())
+    })
+    .await?;
+
+    let request = TranscriptRequest {
+        audio_url: &upload.upload_url,
+        speech_model: options.speech_model.as_deref(),
+        language_code: options.language.as_deref(),
+        language_detection: options.language.is_none(),
+        speaker_labels: options.diarize,
+    };
+    let mut transcript: ApiTranscript = send(events, || {
+        client
+            .post(format!("{}/transcript", API_URL))
+            .header("authorization", api_key)
+            .json(&request)
+    })
+    .await?;
+
+    let started = Instant::now();
+    loop {
+        match transcript.status.as_str() {
+            "completed" => return Ok(to_result(transcript)),
+            "error" => {
+                return Err(format!(
+                    "Transcription failed: {}",
+                    transcript
+                        .error
+                        .unwrap_or_else(|| "unknown error".to_string())
+                ))
+            }
+            _ if started.elapsed() >= Duration::from_secs(MAX_WAIT_SECS) => {
+                return Err("Transcription timed out".to_string())
+            }
+            _ => {}
+        }
+
+        tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)).await;
+        let url = format!("{}/transcript/{}", API_URL, transcript.id);
+        transcript = send(events, || client.get(&url).header("authorization", api_key)).await?;
+    }
+}
+
+/// AssemblyAI's upload and polling API
+pub struct AssemblyAi;
+
+impl TranscriptionProvider for AssemblyAi {
+    fn id(&self) -> &'static str {
+        "assemblyai"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            file: true,
+            diarization: true,
+            language_detection: true,
+            ..Default::default()
+        }
+    }
+
+    fn credentials(&self) -> &'static [Credential] {
+        &[Credential {
+            key: API_KEY_NAME,
+            name: "AssemblyAI API key",
+        }]
+    }
+
+    fn usd_per_minute(&self) -> Option<f64> {
+        // Best tier
+        Some(0.0062)
+    }
+
+    fn transcribe_file(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        audio: AudioFile,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<TranscriptionResult>, String> {
+        let options: AssemblyAiOptions = parse_options(options)?;
+        Ok(Box::pin(async move {
+            transcribe(
+                &events,
+                credentials.get(API_KEY_NAME),
+                audio.bytes,
+                &options,
+            )
+            .await
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completed_transcript() {
+        let body = r#"{
+            "id": "t1",
+            "status": "completed",
+            "text": "Hello there. General Kenobi.",
+            "language_code": "en",
+            "audio_duration": 4.2,
+            "speech_model": "best",
+            "words": [
+                { "text": "Hello", "start": 100, "end": 400, "confidence": 0.9 },
+                { "text": "there.", "start": 450, "end": 900, "confidence": 0.9 },
+                { "text": "General", "start": 1500, "end": 1900, "confidence": 0.8 },
+                { "text": "Kenobi.", "start": 1950, "end": 2600, "confidence": 0.4 }
+            ],
+            "utterances": null
+        }"#;
+        let result = to_result(serde_json::from_str(body).unwrap());
+        assert_eq!(result.duration_ms, 4200);
+        assert_eq!(result.segments.len(), 2);
+        assert_eq!(result.segments[0].text, "Hello there.");
+        assert_eq!(
+            (result.segments[1].start_ms, result.segments[1].end_ms),
+            (1500, 2600)
+        );
+        // Sentences are rated by their words
+        let confidence = result.segments[1].confidence.unwrap();
+        assert!((confidence - 0.6).abs() < 1e-6);
+        assert_eq!(result.segments[1].words[1].text, "Kenobi.");
+        assert_eq!(result.segments[1].words[1].confidence, 0.4);
+
+        let body = r#"{
+            "id": "t2",
+            "status": "completed",
+            "text": "Hi. Hey.",
+            "words": [],
+            "utterances": [
+                { "speaker": "A", "text": "Hi.", "start": 0, "end": 500 },
+                { "speaker": "B", "text": "Hey.", "start": 600, "end": 900 }
+            ]
+        }"#;
+        let result = to_result(serde_json::from_str(body).unwrap());
+        assert_eq!(result.segments[1].speaker.as_deref(), Some("B"));
+        assert_eq!(result.duration_ms, 900);
+    }
+}
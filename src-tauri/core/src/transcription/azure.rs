@@ -0,0 +1,598 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::provider::{
+    parse_options, AudioFile, Credential, Credentials, ProviderCapabilities, ProviderFuture,
+    TranscriptionProvider,
+};
+use super::{retry, LiveTranscript, TranscriptSegment, TranscriptionResult};
+use crate::analytics::PaceMetrics;
+use crate::audio::{self, LiveAudio, SPEECH_SAMPLE_RATE};
+use crate::Events;
+
+/// Secure storage keys of the subscription key and its region, shared with
+/// the frontend
+pub const API_KEY_NAME: &str = "azure_speech_api_key";
+pub const REGION_NAME: &str = "azure_speech_region";
+/// Endpoints below `<region>.`
+const FAST_TRANSCRIPTION_URL: &str =
+    "api.cognitive.microsoft.com/speechtotext/transcriptions:transcribe";
+const CONVERSATION_URL: &str =
+    "stt.speech.microsoft.com/speech/recognition/conversation/cognitiveservices/v1";
+const API_VERSION: &str = "2024-11-15";
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+/// Uploads of long recordings plus transcription can take minutes
+const REQUEST_TIMEOUT_SECS: u64 = 600;
+/// Audio is sent in blocks of this length
+const SEND_BLOCK_MS: usize = 100;
+/// Azure reports offsets and durations in 100-nanosecond ticks
+const TICKS_PER_MS: u64 = 10_000;
+
+/// Options for Azure Speech transcription
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AzureOptions {
+    /// Locale such as "en-US" or "de-DE". Files are transcribed in the
+    /// detected language when `None`; live transcription defaults to "en-US".
+    pub language: Option<String>,
+    /// Label segments of files with speakers
+    pub diarize: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Diarization {
+    enabled: bool,
+    max_speakers: u32,
+}
+
+/// `definition` part of a fast transcription request
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Definition {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locales: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diarization: Option<Diarization>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiPhrase {
+    offset_milliseconds: u64,
+    duration_milliseconds: u64,
+    text: String,
+    locale: Option<String>,
+    speaker: Option<u32>,
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiCombinedPhrase {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiResponse {
+    duration_milliseconds: Option<u64>,
+    #[serde(default)]
+    combined_phrases: Vec<ApiCombinedPhrase>,
+    #[serde(default)]
+    phrases: Vec<ApiPhrase>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: ApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// Azure regions are lowercase names such as "westeurope"; they end up in
+/// host names, so nothing else is accepted
+fn check_region(region: &str) -> Result<(), String> {
+    let valid = !region.is_empty()
+        && region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid Azure region: {}", region))
+    }
+}
+
+fn parse_response(body: &[u8]) -> Result<TranscriptionResult, String> {
+    let response: ApiResponse = serde_json::from_slice(body)
+        .map_err(|e| format!("Unexpected response from Azure: {}", e))?;
+
+    let language = response
+        .phrases
+        .iter()
+        .find_map(|phrase| phrase.locale.clone());
+    let segments: Vec<TranscriptSegment> = response
+        .phrases
+        .into_iter()
+        .map(|phrase| TranscriptSegment {
+            start_ms: phrase.offset_milliseconds,
+            end_ms: phrase.offset_milliseconds + phrase.duration_milliseconds,
+            text: phrase.text,
+            speaker: phrase.speaker.map(|speaker| format!("Speaker {}", speaker)),
+            confidence: phrase.confidence,
+            words: Vec::new(),
+        })
+        .collect();
+    let duration_ms = response
+        .duration_milliseconds
+        .or_else(|| segments.last().map(|segment| segment.end_ms))
+        .unwrap_or(0);
+    let text = response
+        .combined_phrases
+        .iter()
+        .map(|phrase| phrase.text.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(TranscriptionResult {
+        text,
+        language,
+        pace: PaceMetrics::from_segments(&segments, duration_ms),
+        segments,
+        duration_ms,
+        engine: "azure".to_string(),
+        model: "fast-transcription".to_string(),
+    })
+}
+
+/// Transcribe an audio file with Azure's fast transcription API
+pub async fn transcribe(
+    events: &Events,
+    api_key: &str,
+    region: &str,
+    audio: Vec<u8>,
+    file_name: String,
+    options: &AzureOptions,
+) -> Result<TranscriptionResult, String> {
+    check_region(region)?;
+
+    let definition = Definition {
+        locales: options.language.iter().cloned().collect(),
+        diarization: options.diarize.then_some(Diarization {
+            enabled: true,
+            max_speakers: 10,
+        }),
+    };
+    let definition = serde_json::to_string(&definition)
+        .map_err(|e| format!("Failed to serialize request: {}", e))?;
+    let form = || {
+        reqwest::multipart::Form::new()
+            .part(
+                "audio",
+                reqwest::multipart::Part::bytes(audio.clone()).file_name(file_name.clone()),
+            )
+            .text("definition", definition.clone())
+    };
+
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let url = format!(
+        "https://{}.{}?api-version={}",
+        region, FAST_TRANSCRIPTION_URL, API_VERSION
+    );
+    let (status, body) = retry::send(events, "Azure", || {
+        client
+            .post(&url)
+            .header("Ocp-Apim-Subscription-Key", api_key)
+            .multipart(form())
+    })
+    .await?;
+
+    if !status.is_success() {
+        let message = serde_json::from_slice::<ApiErrorBody>(&body)
+            .map(|body| body.error.message)
+            .unwrap_or_else(|_| status.to_string());
+        return Err(format!("Transcription failed: {}", message));
+    }
+
+    parse_response(&body)
+}
+
+/// Random id in the format the speech service expects (32 hex digits)
+fn request_id() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Header of a streamed 16 kHz mono 16-bit WAV file of unknown length
+fn wav_header() -> Vec<u8> {
+    let byte_rate = SPEECH_SAMPLE_RATE * 2;
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&SPEECH_SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&byte_rate.to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header
+}
+
+/// Binary frame of the speech protocol: the length of the headers as a
+/// big-endian u16, the headers, then the audio. Empty audio ends the stream.
+fn audio_message(request_id: &str, audio: &[u8]) -> Vec<u8> {
+    let headers = format!(
+        "Path: audio\r\nX-RequestId: {}\r\nX-Timestamp: {}\r\nContent-Type: audio/x-wav\r\n",
+        request_id,
+        timestamp()
+    );
+    let mut message = Vec::with_capacity(2 + headers.len() + audio.len());
+    message.extend_from_slice(&(headers.len() as u16).to_be_bytes());
+    message.extend_from_slice(headers.as_bytes());
+    message.extend_from_slice(audio);
+    message
+}
+
+fn config_message(request_id: &str) -> String {
+    let config = serde_json::json!({
+        "context": {
+            "system": { "name": "voice-assistant", "version": env!("CARGO_PKG_VERSION") },
+            "os": { "platform": std::env::consts::OS },
+            "audio": { "source": { "type": "Microphones" } }
+        }
+    });
+    format!(
+        "Path: speech.config\r\nX-RequestId: {}\r\nX-Timestamp: {}\r\n\
+         Content-Type: application/json\r\n\r\n{}",
+        request_id,
+        timestamp(),
+        config
+    )
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Hypothesis {
+    text: String,
+    offset: u64,
+    duration: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NBest {
+    confidence: f32,
+    display: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Phrase {
+    recognition_status: String,
+    #[serde(default)]
+    offset: u64,
+    #[serde(default)]
+    duration: u64,
+    #[serde(rename = "NBest", default)]
+    n_best: Vec<NBest>,
+}
+
+/// What a text message from the speech service means for the session
+#[derive(Debug)]
+enum ServerMessage {
+    /// A transcript and whether it is final
+    Transcript(LiveTranscript, bool),
+    /// All audio has been processed
+    TurnEnd,
+    Other,
+}
+
+fn parse_message(message: &str, session_id: &str) -> ServerMessage {
+    let Some((headers, body)) = message.split_once("\r\n\r\n") else {
+        return ServerMessage::Other;
+    };
+    let path = headers
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("path").then(|| value.trim())
+        })
+        .unwrap_or_default();
+
+    let transcript = |text: String, offset: u64, duration: u64| LiveTranscript {
+        session_id: session_id.to_string(),
+        text,
+        start_ms: offset / TICKS_PER_MS,
+        end_ms: (offset + duration) / TICKS_PER_MS,
+        confidence: None,
+        speaker: None,
+        speech_final: false,
+    };
+
+    match path {
+        "turn.end" => ServerMessage::TurnEnd,
+        "speech.hypothesis" => match serde_json::from_str::<Hypothesis>(body) {
+            Ok(hypothesis) if !hypothesis.text.trim().is_empty() => ServerMessage::Transcript(
+                transcript(hypothesis.text, hypothesis.offset, hypothesis.duration),
+                false,
+            ),
+            _ => ServerMessage::Other,
+        },
+        "speech.phrase" => {
+            let Ok(phrase) = serde_json::from_str::<Phrase>(body) else {
+                return ServerMessage::Other;
+            };
+            match phrase.n_best.into_iter().next() {
+                Some(best) if phrase.recognition_status == "Success" => {
+                    let mut transcript = transcript(best.display, phrase.offset, phrase.duration);
+                    transcript.confidence = Some(best.confidence);
+                    transcript.speech_final = true;
+                    ServerMessage::Transcript(transcript, true)
+                }
+                _ => ServerMessage::Other,
+            }
+        }
+        _ => ServerMessage::Other,
+    }
+}
+
+/// Stream live audio to Azure's continuous (conversation) recognition,
+/// emitting transcripts until the audio ends and Azure has processed all of it
+pub async fn stream(
+    events: Events,
+    api_key: String,
+    region: String,
+    session_id: String,
+    live_audio: LiveAudio,
+    options: AzureOptions,
+) -> Result<(), String> {
+    check_region(&region)?;
+    let mut receiver = live_audio.receiver;
+
+    let language = options.language.as_deref().unwrap_or("en-US");
+    let url = reqwest::Url::parse_with_params(
+        &format!("wss://{}.{}", region, CONVERSATION_URL),
+        &[("language", language), ("format", "detailed")],
+    )
+    .map_err(|e| format!("Invalid Azure options: {}", e))?;
+
+    let mut request = url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| format!("Invalid Azure request: {}", e))?;
+    let key = HeaderValue::from_str(&api_key).map_err(|_| "Invalid Azure key".to_string())?;
+    let headers = request.headers_mut();
+    headers.insert("Ocp-Apim-Subscription-Key", key);
+    headers.insert(
+        "X-ConnectionId",
+        HeaderValue::from_str(&request_id()).map_err(|e| e.to_string())?,
+    );
+
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("Failed to connect to Azure: {}", e))?;
+    let (mut sink, mut source) = socket.split();
+
+    let turn_id = request_id();
+    sink.send(Message::text(config_message(&turn_id)))
+        .await
+        .map_err(|e| format!("Failed to configure Azure: {}", e))?;
+
+    let block_samples = SPEECH_SAMPLE_RATE as usize * SEND_BLOCK_MS / 1000;
+    let sender = tokio::spawn(async move {
+        let mut pending: Vec<f32> = Vec::with_capacity(block_samples * 2);
+        sink.send(Message::binary(audio_message(&turn_id, &wav_header())))
+            .await?;
+
+        while let Some(samples) = receiver.recv().await {
+            pending.extend(samples);
+            if pending.len() >= block_samples {
+                let block = audio::encode_pcm16(&pending);
+                pending.clear();
+                sink.send(Message::binary(audio_message(&turn_id, &block)))
+                    .await?;
+            }
+        }
+
+        if !pending.is_empty() {
+            let block = audio::encode_pcm16(&pending);
+            sink.send(Message::binary(audio_message(&turn_id, &block)))
+                .await?;
+        }
+        // An empty audio message tells Azure the audio is complete
+        sink.send(Message::binary(audio_message(&turn_id, &[])))
+            .await
+    });
+
+    let mut result = Ok(());
+    while let Some(message) = source.next().await {
+        match message {
+            Ok(Message::Text(text)) => match parse_message(&text, &session_id) {
+                ServerMessage::Transcript(transcript, is_final) => {
+                    let event = if is_final {
+                        "transcription-final"
+                    } else {
+                        "transcription-interim"
+                    };
+                    events.emit(event, transcript);
+                }
+                ServerMessage::TurnEnd => break,
+                ServerMessage::Other => {}
+            },
+            Ok(Message::Close(frame)) => {
+                if let Some(frame) = frame.filter(|frame| !frame.reason.is_empty()) {
+                    result = Err(format!("Azure closed the stream: {}", frame.reason));
+                }
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                result = Err(format!("Azure connection failed: {}", e));
+                break;
+            }
+        }
+    }
+
+    sender.abort();
+    result
+}
+
+/// Azure Speech: fast transcription for files, conversation recognition live
+pub struct Azure;
+
+impl TranscriptionProvider for Azure {
+    fn id(&self) -> &'static str {
+        "azure"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            file: true,
+            streaming: true,
+            diarization: true,
+            language_detection: true,
+            ..Default::default()
+        }
+    }
+
+    fn credentials(&self) -> &'static [Credential] {
+        &[
+            Credential {
+                key: API_KEY_NAME,
+                name: "Azure Speech key",
+            },
+            Credential {
+                key: REGION_NAME,
+                name: "Azure Speech region",
+            },
+        ]
+    }
+
+    fn usd_per_minute(&self) -> Option<f64> {
+        // Standard speech to text
+        Some(0.0167)
+    }
+
+    fn transcribe_file(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        audio: AudioFile,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<TranscriptionResult>, String> {
+        let options: AzureOptions = parse_options(options)?;
+        check_region(credentials.get(REGION_NAME))?;
+        Ok(Box::pin(async move {
+            let api_key = credentials.get(API_KEY_NAME);
+            let region = credentials.get(REGION_NAME);
+            transcribe(
+                &events,
+                api_key,
+                region,
+                audio.bytes,
+                audio.file_name,
+                &options,
+            )
+            .await
+        }))
+    }
+
+    fn transcribe_stream(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        session_id: String,
+        live_audio: LiveAudio,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<()>, String> {
+        let options: AzureOptions = parse_options(options)?;
+        check_region(credentials.get(REGION_NAME))?;
+        let api_key = credentials.get(API_KEY_NAME).to_string();
+        let region = credentials.get(REGION_NAME).to_string();
+        Ok(Box::pin(stream(
+            events, api_key, region, session_id, live_audio, options,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fast_transcription() {
+        let body = br#"{
+            "durationMilliseconds": 5000,
+            "combinedPhrases": [{ "text": "Hello there. General Kenobi." }],
+            "phrases": [
+                { "offsetMilliseconds": 100, "durationMilliseconds": 900,
+                  "text": "Hello there.", "locale": "en-US", "confidence": 0.9, "speaker": 1 },
+                { "offsetMilliseconds": 2000, "durationMilliseconds": 1200,
+                  "text": "General Kenobi.", "locale": "en-US", "confidence": 0.9, "speaker": 2 }
+            ]
+        }"#;
+        let result = parse_response(body).unwrap();
+        assert_eq!(result.text, "Hello there. General Kenobi.");
+        assert_eq!(result.language.as_deref(), Some("en-US"));
+        assert_eq!(result.segments[1].end_ms, 3200);
+        assert_eq!(result.segments[1].speaker.as_deref(), Some("Speaker 2"));
+        assert_eq!(result.segments[1].confidence, Some(0.9));
+        assert!(check_region("westeurope").is_ok());
+        assert!(check_region("evil.example.com/").is_err());
+    }
+
+    #[test]
+    fn test_parse_stream_messages() {
+        let hypothesis = "X-RequestId: 1\r\nPath: speech.hypothesis\r\n\
+                          Content-Type: application/json\r\n\r\n\
+                          {\"Text\":\"hello\",\"Offset\":5000000,\"Duration\":3000000}";
+        let ServerMessage::Transcript(transcript, false) = parse_message(hypothesis, "s") else {
+            panic!("expected an interim transcript");
+        };
+        assert_eq!((transcript.start_ms, transcript.end_ms), (500, 800));
+
+        let phrase = "Path: speech.phrase\r\n\r\n{\"RecognitionStatus\":\"Success\",\
+                      \"Offset\":5000000,\"Duration\":9000000,\
+                      \"NBest\":[{\"Confidence\":0.93,\"Display\":\"Hello there.\"}]}";
+        let ServerMessage::Transcript(transcript, true) = parse_message(phrase, "s") else {
+            panic!("expected a final transcript");
+        };
+        assert_eq!(transcript.text, "Hello there.");
+        assert_eq!(transcript.confidence, Some(0.93));
+
+        let no_match = "Path: speech.phrase\r\n\r\n{\"RecognitionStatus\":\"NoMatch\"}";
+        assert!(matches!(parse_message(no_match, "s"), ServerMessage::Other));
+        assert!(matches!(
+            parse_message("Path: turn.end\r\n\r\n{}", "s"),
+            ServerMessage::TurnEnd
+        ));
+
+        let frame = audio_message("abc", &[1, 2]);
+        let header_len = u16::from_be_bytes([frame[0], frame[1]]) as usize;
+        assert_eq!(&frame[2 + header_len..], &[1, 2]);
+        assert_eq!(wav_header().len(), 44);
+    }
+}
@@ -0,0 +1,189 @@
+use std::ops::Range;
+use std::path::Path;
+
+use super::provider::AudioFile;
+use super::{TranscriptSegment, TranscriptWord, TranscriptionResult};
+use crate::analytics::PaceMetrics;
+use crate::audio::{self, SPEECH_SAMPLE_RATE};
+
+/// Room left for the WAV header and multipart framing
+const OVERHEAD_BYTES: usize = 64 * 1024;
+/// Length of the frames searched for the quietest place to cut
+const FRAME_MS: usize = 20;
+
+/// A piece of a recording too large to upload at once
+pub struct Chunk {
+    /// Where the chunk starts in the recording
+    pub offset_ms: u64,
+    pub audio: AudioFile,
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt()
+}
+
+/// Cut `samples` into ranges of at most `max_len`, each ending in the
+/// quietest frame of its second half so words are not split
+fn split_at_silences(samples: &[f32], max_len: usize) -> Vec<Range<usize>> {
+    let frame = SPEECH_SAMPLE_RATE as usize * FRAME_MS / 1000;
+    let max_len = max_len.max(frame * 2);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    while samples.len() - start > max_len {
+        let search = start + max_len / 2..start + max_len - frame;
+        let quietest = search
+            .step_by(frame)
+            .min_by(|&a, &b| rms(&samples[a..a + frame]).total_cmp(&rms(&samples[b..b + frame])))
+            .unwrap_or(start + max_len - frame);
+        let end = quietest + frame / 2;
+
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges.push(start..samples.len());
+    ranges
+}
+
+/// Split audio larger than `max_bytes` into 16 kHz mono WAV chunks that fit,
+/// cut at pauses. Returns `None` when the audio fits as it is.
+pub fn split(audio: &AudioFile, max_bytes: usize) -> Result<Option<Vec<Chunk>>, String> {
+    if audio.bytes.len() <= max_bytes {
+        return Ok(None);
+    }
+
+    let samples = audio::decode_speech_samples(&audio.bytes).map_err(|e| {
+        format!(
+            "Audio is larger than the provider accepts and could not be split: {}",
+            e
+        )
+    })?;
+    let max_len = max_bytes.saturating_sub(OVERHEAD_BYTES) / 2;
+    let stem = Path::new(&audio.file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "audio".to_string());
+
+    split_at_silences(&samples, max_len)
+        .into_iter()
+        .enumerate()
+        .map(|(index, range)| {
+            let bytes = audio::encode_speech_wav(&samples[range.clone()])?;
+            Ok(Chunk {
+                offset_ms: range.start as u64 * 1000 / SPEECH_SAMPLE_RATE as u64,
+                audio: AudioFile {
+                    bytes,
+                    file_name: format!("{}.part{}.wav", stem, index + 1),
+                },
+            })
+        })
+        .collect::<Result<_, String>>()
+        .map(Some)
+}
+
+/// Join the transcripts of consecutive chunks, moving each chunk's segments
+/// to where the chunk starts in the recording
+pub fn stitch(parts: Vec<(u64, TranscriptionResult)>) -> TranscriptionResult {
+    let mut text = Vec::new();
+    let mut segments = Vec::new();
+    let mut language = None;
+    let mut duration_ms = 0;
+    let (mut engine, mut model) = (String::new(), String::new());
+
+    for (offset_ms, part) in parts {
+        if !part.text.trim().is_empty() {
+            text.push(part.text.trim().to_string());
+        }
+        segments.extend(part.segments.into_iter().map(|segment| {
+            TranscriptSegment {
+                start_ms: segment.start_ms + offset_ms,
+                end_ms: segment.end_ms + offset_ms,
+                words: segment
+                    .words
+                    .into_iter()
+                    .map(|word| TranscriptWord {
+                        start_ms: word.start_ms + offset_ms,
+                        end_ms: word.end_ms + offset_ms,
+                        ..word
+                    })
+                    .collect(),
+                ..segment
+            }
+        }));
+        language = language.or(part.language);
+        duration_ms = duration_ms.max(offset_ms + part.duration_ms);
+        if engine.is_empty() {
+            (engine, model) = (part.engine, part.model);
+        }
+    }
+
+    TranscriptionResult {
+        text: text.join(" "),
+        language,
+        pace: PaceMetrics::from_segments(&segments, duration_ms),
+        segments,
+        duration_ms,
+        engine,
+        model,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(text: &str, end_ms: u64) -> TranscriptionResult {
+        let segments = vec![TranscriptSegment {
+            start_ms: 0,
+            end_ms,
+            text: text.to_string(),
+            speaker: None,
+            confidence: None,
+            words: Vec::new(),
+        }];
+        TranscriptionResult {
+            text: text.to_string(),
+            language: Some("en".to_string()),
+            pace: PaceMetrics::from_segments(&segments, end_ms),
+            segments,
+            duration_ms: end_ms,
+            engine: "openai".to_string(),
+            model: "whisper-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_split_at_silences() {
+        let second = SPEECH_SAMPLE_RATE as usize;
+        // Speech with a pause from 1.5 s to 1.7 s
+        let samples: Vec<f32> = (0..second * 3)
+            .map(|i| match i {
+                i if (second * 3 / 2..second * 17 / 10).contains(&i) => 0.0,
+                i => (i as f32 * 0.1).sin() * 0.5,
+            })
+            .collect();
+
+        let ranges = split_at_silences(&samples, second * 2);
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].start, 0);
+        assert!((second * 3 / 2..second * 17 / 10).contains(&ranges[0].end));
+        assert_eq!(ranges[1], ranges[0].end..samples.len());
+
+        let whole = split_at_silences(&samples, second * 4);
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0], 0..samples.len());
+    }
+
+    #[test]
+    fn test_stitch_offsets_segments() {
+        let stitched = stitch(vec![
+            (0, result("Hello", 900)),
+            (1000, result("world", 800)),
+        ]);
+        assert_eq!(stitched.text, "Hello world");
+        assert_eq!(stitched.duration_ms, 1800);
+        assert_eq!(stitched.segments[1].start_ms, 1000);
+        assert_eq!(stitched.segments[1].end_ms, 1800);
+        assert_eq!(stitched.pace.word_count, 2);
+    }
+}
@@ -0,0 +1,298 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::provider::{
+    parse_options, Credential, Credentials, ProviderCapabilities, ProviderFuture,
+    TranscriptionProvider,
+};
+use super::LiveTranscript;
+use crate::audio::{self, LiveAudio};
+use crate::Events;
+
+const LISTEN_URL: &str = "wss://api.deepgram.com/v1/listen";
+/// Secure storage key of the API key, shared with the frontend
+pub const API_KEY_NAME: &str = "deepgram_api_key";
+/// Audio is sent in blocks of this length; Deepgram suggests 20 to 100 ms
+const SEND_BLOCK_MS: usize = 100;
+
+/// Options for Deepgram live transcription
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeepgramOptions {
+    /// "nova-3", "nova-2", ...
+    pub model: String,
+    /// BCP-47 code such as "en" or "de-CH"; `None` uses the model's default
+    pub language: Option<String>,
+    /// Punctuation, capitalization and formatting of numbers and dates
+    pub smart_format: bool,
+    /// Label results with speakers
+    pub diarize: bool,
+}
+
+impl Default for DeepgramOptions {
+    fn default() -> Self {
+        Self {
+            model: "nova-3".to_string(),
+            language: None,
+            smart_format: true,
+            diarize: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Word {
+    speaker: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Alternative {
+    transcript: String,
+    #[serde(default)]
+    confidence: f32,
+    #[serde(default)]
+    words: Vec<Word>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Channel {
+    alternatives: Vec<Alternative>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ServerMessage {
+    Results {
+        channel: Channel,
+        start: f64,
+        duration: f64,
+        #[serde(default)]
+        is_final: bool,
+        #[serde(default)]
+        speech_final: bool,
+    },
+    /// Metadata, SpeechStarted, UtteranceEnd, ...
+    #[serde(other)]
+    Other,
+}
+
+fn seconds_to_ms(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 1000.0).round() as u64
+}
+
+/// Parse a server message into a transcript and whether it is final.
+/// Messages without text are skipped.
+fn parse_message(text: &str, session_id: &str) -> Option<(LiveTranscript, bool)> {
+    let ServerMessage::Results {
+        channel,
+        start,
+        duration,
+        is_final,
+        speech_final,
+    } = serde_json::from_str(text).ok()?
+    else {
+        return None;
+    };
+
+    let alternative = channel.alternatives.into_iter().next()?;
+    let transcript = alternative.transcript.trim();
+    if transcript.is_empty() {
+        return None;
+    }
+
+    let speaker = alternative
+        .words
+        .first()
+        .and_then(|word| word.speaker)
+        .map(|speaker| format!("Speaker {}", speaker + 1));
+
+    Some((
+        LiveTranscript {
+            session_id: session_id.to_string(),
+            text: transcript.to_string(),
+            start_ms: seconds_to_ms(start),
+            end_ms: seconds_to_ms(start + duration),
+            confidence: Some(alternative.confidence),
+            speaker,
+            speech_final,
+        },
+        is_final,
+    ))
+}
+
+fn listen_url(options: &DeepgramOptions, format: audio::AudioFormat) -> Result<String, String> {
+    let mut params = vec![
+        ("model", options.model.clone()),
+        ("encoding", "linear16".to_string()),
+        ("sample_rate", format.sample_rate.to_string()),
+        ("channels", format.channels.to_string()),
+        ("interim_results", "true".to_string()),
+        ("smart_format", options.smart_format.to_string()),
+        ("diarize", options.diarize.to_string()),
+    ];
+    if let Some(language) = &options.language {
+        params.push(("language", language.clone()));
+    }
+
+    reqwest::Url::parse_with_params(LISTEN_URL, &params)
+        .map(|url| url.to_string())
+        .map_err(|e| format!("Invalid Deepgram options: {}", e))
+}
+
+/// Stream live audio to Deepgram, emitting transcripts until the audio ends
+/// and Deepgram has sent the results for all of it
+pub async fn stream(
+    events: Events,
+    api_key: String,
+    session_id: String,
+    live_audio: LiveAudio,
+    options: DeepgramOptions,
+) -> Result<(), String> {
+    let LiveAudio {
+        mut receiver,
+        format,
+    } = live_audio;
+
+    let mut request = listen_url(&options, format)?
+        .into_client_request()
+        .map_err(|e| format!("Invalid Deepgram request: {}", e))?;
+    let authorization = HeaderValue::from_str(&format!("Token {}", api_key))
+        .map_err(|_| "Invalid Deepgram API key".to_string())?;
+    request.headers_mut().insert("Authorization", authorization);
+
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("Failed to connect to Deepgram: {}", e))?;
+    let (mut sink, mut source) = socket.split();
+
+    let block_samples =
+        format.sample_rate as usize * format.channels as usize * SEND_BLOCK_MS / 1000;
+    let sender = tokio::spawn(async move {
+        let mut pending: Vec<f32> = Vec::with_capacity(block_samples * 2);
+
+        while let Some(samples) = receiver.recv().await {
+            pending.extend(samples);
+            if pending.len() >= block_samples {
+                let block = audio::encode_pcm16(&pending);
+                pending.clear();
+                sink.send(Message::binary(block)).await?;
+            }
+        }
+
+        if !pending.is_empty() {
+            sink.send(Message::binary(audio::encode_pcm16(&pending)))
+                .await?;
+        }
+        // Deepgram sends the remaining results, then closes the connection
+        sink.send(Message::text(r#"{"type":"CloseStream"}"#)).await
+    });
+
+    let mut result = Ok(());
+    while let Some(message) = source.next().await {
+        match message {
+            Ok(Message::Text(text)) => {
+                if let Some((transcript, is_final)) = parse_message(&text, &session_id) {
+                    let event = if is_final {
+                        "transcription-final"
+                    } else {
+                        "transcription-interim"
+                    };
+                    events.emit(event, transcript);
+                }
+            }
+            Ok(Message::Close(Some(frame))) if frame.code != CloseCode::Normal => {
+                result = Err(format!("Deepgram closed the stream: {}", frame.reason));
+                break;
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                result = Err(format!("Deepgram connection failed: {}", e));
+                break;
+            }
+        }
+    }
+
+    // Once the connection is gone, there is nobody left to send audio to
+    sender.abort();
+    result
+}
+
+/// Deepgram live transcription
+pub struct Deepgram;
+
+impl TranscriptionProvider for Deepgram {
+    fn id(&self) -> &'static str {
+        "deepgram"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            streaming: true,
+            diarization: true,
+            ..Default::default()
+        }
+    }
+
+    fn credentials(&self) -> &'static [Credential] {
+        &[Credential {
+            key: API_KEY_NAME,
+            name: "Deepgram API key",
+        }]
+    }
+
+    fn usd_per_minute(&self) -> Option<f64> {
+        // Nova streaming
+        Some(0.0077)
+    }
+
+    fn transcribe_stream(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        session_id: String,
+        live_audio: LiveAudio,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<()>, String> {
+        let options: DeepgramOptions = parse_options(options)?;
+        let api_key = credentials.get(API_KEY_NAME).to_string();
+        Ok(Box::pin(stream(
+            events, api_key, session_id, live_audio, options,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results() {
+        let message = r#"{
+            "type": "Results",
+            "start": 1.5,
+            "duration": 2.25,
+            "is_final": true,
+            "speech_final": false,
+            "channel": { "alternatives": [{
+                "transcript": "Hello there.",
+                "confidence": 0.98,
+                "words": [{ "word": "hello", "start": 1.5, "end": 1.8, "speaker": 1 }]
+            }] }
+        }"#;
+        let (transcript, is_final) = parse_message(message, "abc").unwrap();
+        assert!(is_final);
+        assert_eq!(transcript.text, "Hello there.");
+        assert_eq!((transcript.start_ms, transcript.end_ms), (1500, 3750));
+        assert_eq!(transcript.speaker.as_deref(), Some("Speaker 2"));
+
+        let silence = r#"{ "type": "Results", "start": 0, "duration": 1,
+            "channel": { "alternatives": [{ "transcript": "" }] } }"#;
+        assert!(parse_message(silence, "abc").is_none());
+        assert!(parse_message(r#"{ "type": "Metadata", "request_id": "x" }"#, "abc").is_none());
+    }
+}
@@ -0,0 +1,655 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::provider::{
+    parse_options, AudioFile, Credential, Credentials, ProviderCapabilities, ProviderFuture,
+    TranscriptionProvider,
+};
+use super::{
+    mean_confidence, retry, LiveTranscript, TranscriptSegment, TranscriptWord, TranscriptionResult,
+};
+use crate::analytics::PaceMetrics;
+use crate::audio::{self, LiveAudio, SPEECH_SAMPLE_RATE};
+use crate::Events;
+
+const API_URL: &str = "https://speech.googleapis.com/v1";
+/// Secure storage key of the API key, shared with the frontend
+pub const API_KEY_NAME: &str = "google_speech_api_key";
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+const REQUEST_TIMEOUT_SECS: u64 = 600;
+/// Longest audio `speech:recognize` accepts; longer audio is recognized as
+/// a long-running operation
+const SYNC_LIMIT_MS: u64 = 60 * 1000;
+const POLL_INTERVAL_SECS: u64 = 3;
+const MAX_WAIT_SECS: u64 = 60 * 60;
+/// Largest audio sent inline; requests are limited to 10 MB and base64 adds
+/// a third
+const MAX_FILE_BYTES: usize = 7 * 1000 * 1000;
+/// Live audio is cut into utterances at pauses of this length...
+const UTTERANCE_PAUSE_MS: usize = 700;
+/// ...or when it gets this long
+const MAX_UTTERANCE_MS: usize = 30 * 1000;
+/// RMS level below which live audio counts as a pause
+const PAUSE_THRESHOLD: f32 = 0.01;
+
+/// Options for Google Cloud Speech-to-Text
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GoogleOptions {
+    /// "latest_long", "latest_short", "phone_call", ...; `None` picks one
+    /// that fits the audio
+    pub model: Option<String>,
+    /// BCP-47 code such as "en-US" or "de-DE"; Google needs one
+    pub language: String,
+    /// Add punctuation to the transcript
+    pub punctuate: bool,
+    /// Label segments with speakers
+    pub diarize: bool,
+}
+
+impl Default for GoogleOptions {
+    fn default() -> Self {
+        Self {
+            model: None,
+            language: "en-US".to_string(),
+            punctuate: true,
+            diarize: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpeakerDiarizationConfig {
+    enable_speaker_diarization: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecognitionConfig<'a> {
+    /// Left out for WAV files, whose header describes the audio
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_rate_hertz: Option<u32>,
+    language_code: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    enable_automatic_punctuation: bool,
+    enable_word_time_offsets: bool,
+    enable_word_confidence: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diarization_config: Option<SpeakerDiarizationConfig>,
+}
+
+#[derive(Debug, Serialize)]
+struct RecognitionAudio {
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RecognizeRequest<'a> {
+    config: RecognitionConfig<'a>,
+    audio: RecognitionAudio,
+}
+
+/// Times are durations such as "1.500s"
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiWord {
+    word: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    speaker_tag: u32,
+    /// Only with `enable_word_confidence`
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiAlternative {
+    #[serde(default)]
+    transcript: String,
+    confidence: Option<f32>,
+    #[serde(default)]
+    words: Vec<ApiWord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiResult {
+    #[serde(default)]
+    alternatives: Vec<ApiAlternative>,
+    result_end_time: Option<String>,
+    language_code: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecognizeResponse {
+    #[serde(default)]
+    results: Vec<ApiResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiStatus {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Operation {
+    name: String,
+    #[serde(default)]
+    done: bool,
+    error: Option<ApiStatus>,
+    response: Option<RecognizeResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: ApiStatus,
+}
+
+/// Parse a duration such as "1.500s"
+fn duration_ms(duration: Option<&str>) -> Option<u64> {
+    let seconds: f64 = duration?.strip_suffix('s')?.parse().ok()?;
+    Some((seconds.max(0.0) * 1000.0).round() as u64)
+}
+
+/// A word with its timing, if Google rated it
+fn transcript_word(word: &ApiWord) -> Option<TranscriptWord> {
+    let start_ms = duration_ms(word.start_time.as_deref()).unwrap_or(0);
+    Some(TranscriptWord {
+        start_ms,
+        end_ms: duration_ms(word.end_time.as_deref()).unwrap_or(start_ms),
+        text: word.word.clone(),
+        confidence: word.confidence?,
+    })
+}
+
+/// Group diarized words into segments of consecutive words by one speaker
+fn speaker_segments(words: &[ApiWord]) -> Vec<TranscriptSegment> {
+    let mut segments: Vec<TranscriptSegment> = Vec::new();
+    for word in words {
+        let speaker = format!("Speaker {}", word.speaker_tag);
+        let start_ms = duration_ms(word.start_time.as_deref()).unwrap_or(0);
+        let end_ms = duration_ms(word.end_time.as_deref()).unwrap_or(start_ms);
+
+        match segments.last_mut() {
+            Some(segment) if segment.speaker.as_ref() == Some(&speaker) => {
+                segment.text.push(' ');
+                segment.text.push_str(&word.word);
+                segment.end_ms = end_ms;
+                segment.words.extend(transcript_word(word));
+            }
+            _ => segments.push(TranscriptSegment {
+                start_ms,
+                end_ms,
+                text: word.word.clone(),
+                speaker: Some(speaker),
+                confidence: None,
+                words: transcript_word(word).into_iter().collect(),
+            }),
+        }
+    }
+
+    for segment in &mut segments {
+        segment.confidence = mean_confidence(&segment.words);
+    }
+    segments
+}
+
+/// Turn recognition results into a transcript; times are relative to the
+/// start of the audio
+fn to_result(response: RecognizeResponse, model: &str) -> TranscriptionResult {
+    let language = response
+        .results
+        .iter()
+        .find_map(|result| result.language_code.clone());
+
+    // With diarization, the last result repeats all words with speaker tags
+    let diarized = response
+        .results
+        .last()
+        .and_then(|result| result.alternatives.first())
+        .filter(|alternative| alternative.words.iter().any(|word| word.speaker_tag > 0))
+        .map(|alternative| speaker_segments(&alternative.words));
+
+    let mut segments = Vec::new();
+    let mut texts = Vec::new();
+    let mut previous_end_ms = 0;
+    for result in &response.results {
+        let Some(alternative) = result.alternatives.first() else {
+            continue;
+        };
+        let text = alternative.transcript.trim();
+        let end_ms = duration_ms(result.result_end_time.as_deref()).unwrap_or(previous_end_ms);
+        if !text.is_empty() {
+            let start_ms = alternative
+                .words
+                .first()
+                .and_then(|word| duration_ms(word.start_time.as_deref()))
+                .unwrap_or(previous_end_ms);
+            texts.push(text.to_string());
+            segments.push(TranscriptSegment {
+                start_ms,
+                end_ms,
+                text: text.to_string(),
+                speaker: None,
+                confidence: alternative.confidence,
+                words: alternative
+                    .words
+                    .iter()
+                    .filter_map(transcript_word)
+                    .collect(),
+            });
+        }
+        previous_end_ms = end_ms;
+    }
+
+    let segments = diarized.unwrap_or(segments);
+    let duration_ms = segments.last().map(|segment| segment.end_ms).unwrap_or(0);
+
+    TranscriptionResult {
+        text: texts.join(" "),
+        language,
+        pace: PaceMetrics::from_segments(&segments, duration_ms),
+        segments,
+        duration_ms,
+        engine: "google".to_string(),
+        model: model.to_string(),
+    }
+}
+
+/// Length of a WAV file, if `audio` is one
+fn wav_duration_ms(audio: &[u8]) -> Option<u64> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(audio)).ok()?;
+    let sample_rate = reader.spec().sample_rate as u64;
+    Some(reader.duration() as u64 * 1000 / sample_rate.max(1))
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Send a request and parse the JSON response, turning API errors into messages
+async fn send<T: serde::de::DeserializeOwned>(
+    events: &Events,
+    request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<T, String> {
+    let (status, body) = retry::send(events, "Google", request).await?;
+
+    if !status.is_success() {
+        let message = serde_json::from_slice::<ApiErrorBody>(&body)
+            .map(|body| body.error.message)
+            .unwrap_or_else(|_| status.to_string());
+        return Err(format!("Transcription failed: {}", message));
+    }
+
+    serde_json::from_slice(&body).map_err(|e| format!("Unexpected response from Google: {}", e))
+}
+
+/// Recognize a whole recording at once
+async fn recognize(
+    events: &Events,
+    client: &reqwest::Client,
+    api_key: &str,
+    request: &RecognizeRequest<'_>,
+) -> Result<RecognizeResponse, String> {
+    send(events, || {
+        client
+            .post(format!("{}/speech:recognize", API_URL))
+            .header("X-Goog-Api-Key", api_key)
+            .json(request)
+    })
+    .await
+}
+
+/// Start a long-running recognition and wait for it to finish
+async fn recognize_long(
+    events: &Events,
+    client: &reqwest::Client,
+    api_key: &str,
+    request: &RecognizeRequest<'_>,
+) -> Result<RecognizeResponse, String> {
+    let mut operation: Operation = send(events, || {
+        client
+            .post(format!("{}/speech:longrunningrecognize", API_URL))
+            .header("X-Goog-Api-Key", api_key)
+            .json(request)
+    })
+    .await?;
+
+    let started = Instant::now();
+    loop {
+        if let Some(error) = operation.error {
+            return Err(format!("Transcription failed: {}", error.message));
+        }
+        if operation.done {
+            // Google leaves out `results` when nothing was recognized
+            return Ok(operation.response.unwrap_or_default());
+        }
+        if started.elapsed() >= Duration::from_secs(MAX_WAIT_SECS) {
+            return Err("Transcription timed out".to_string());
+        }
+
+        tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)).await;
+        let url = format!("{}/operations/{}", API_URL, operation.name);
+        operation = send(events, || {
+            client.get(&url).header("X-Goog-Api-Key", api_key)
+        })
+        .await?;
+    }
+}
+
+/// Transcribe an audio file with Google Cloud Speech-to-Text. Short audio is
+/// recognized synchronously, longer audio as a long-running operation.
+pub async fn transcribe(
+    events: &Events,
+    api_key: &str,
+    audio: Vec<u8>,
+    options: &GoogleOptions,
+) -> Result<TranscriptionResult, String> {
+    let long = wav_duration_ms(&audio).is_none_or(|duration| duration > SYNC_LIMIT_MS);
+    let model =
+        options
+            .model
+            .as_deref()
+            .unwrap_or(if long { "latest_long" } else { "latest_short" });
+
+    let request = RecognizeRequest {
+        config: RecognitionConfig {
+            encoding: None,
+            sample_rate_hertz: None,
+            language_code: &options.language,
+            model: Some(model),
+            enable_automatic_punctuation: options.punctuate,
+            enable_word_time_offsets: true,
+            enable_word_confidence: true,
+            diarization_config: options.diarize.then_some(SpeakerDiarizationConfig {
+                enable_speaker_diarization: true,
+            }),
+        },
+        audio: RecognitionAudio {
+            content: base64::engine::general_purpose::STANDARD.encode(&audio),
+        },
+    };
+
+    let client = client()?;
+    let response = if long {
+        recognize_long(events, &client, api_key, &request).await?
+    } else {
+        recognize(events, &client, api_key, &request).await?
+    };
+
+    Ok(to_result(response, model))
+}
+
+/// Whether the end of `samples` is a pause long enough to end an utterance
+fn ends_with_pause(samples: &[f32]) -> bool {
+    let pause = SPEECH_SAMPLE_RATE as usize * UTTERANCE_PAUSE_MS / 1000;
+    if samples.len() < pause * 2 {
+        return false;
+    }
+
+    let tail = &samples[samples.len() - pause..];
+    let rms = (tail.iter().map(|s| s * s).sum::<f32>() / tail.len() as f32).sqrt();
+    rms < PAUSE_THRESHOLD
+}
+
+/// Recognize one utterance of live audio and emit it as a final transcript
+async fn recognize_utterance(
+    events: &Events,
+    client: &reqwest::Client,
+    api_key: &str,
+    session_id: &str,
+    samples: &[f32],
+    offset_ms: u64,
+    options: &GoogleOptions,
+) -> Result<(), String> {
+    let audio = audio::encode_pcm16(samples);
+    let request = RecognizeRequest {
+        config: RecognitionConfig {
+            encoding: Some("LINEAR16"),
+            sample_rate_hertz: Some(SPEECH_SAMPLE_RATE),
+            language_code: &options.language,
+            model: Some(options.model.as_deref().unwrap_or("latest_short")),
+            enable_automatic_punctuation: options.punctuate,
+            enable_word_time_offsets: true,
+            enable_word_confidence: false,
+            diarization_config: None,
+        },
+        audio: RecognitionAudio {
+            content: base64::engine::general_purpose::STANDARD.encode(&audio),
+        },
+    };
+
+    let response = recognize(events, client, api_key, &request).await?;
+    for result in response.results {
+        let Some(alternative) = result.alternatives.into_iter().next() else {
+            continue;
+        };
+        let text = alternative.transcript.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let start_ms = alternative
+            .words
+            .first()
+            .and_then(|word| duration_ms(word.start_time.as_deref()))
+            .unwrap_or(0);
+        let end_ms = duration_ms(result.result_end_time.as_deref())
+            .unwrap_or(samples.len() as u64 * 1000 / SPEECH_SAMPLE_RATE as u64);
+        events.emit(
+            "transcription-final",
+            LiveTranscript {
+                session_id: session_id.to_string(),
+                text: text.to_string(),
+                start_ms: offset_ms + start_ms,
+                end_ms: offset_ms + end_ms,
+                confidence: alternative.confidence,
+                speaker: None,
+                speech_final: true,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Transcribe live audio utterance by utterance. Google only offers
+/// streaming recognition over gRPC, so the audio is cut at pauses and each
+/// utterance is recognized as soon as it ends; there are no interim results.
+pub async fn stream(
+    events: Events,
+    api_key: String,
+    session_id: String,
+    live_audio: LiveAudio,
+    options: GoogleOptions,
+) -> Result<(), String> {
+    let mut receiver = live_audio.receiver;
+    let client = client()?;
+    let max_samples = SPEECH_SAMPLE_RATE as usize * MAX_UTTERANCE_MS / 1000;
+
+    let mut utterance: Vec<f32> = Vec::with_capacity(max_samples);
+    let mut offset_ms = 0;
+    loop {
+        let samples = receiver.recv().await;
+        let ended = samples.is_none();
+        if let Some(samples) = samples {
+            utterance.extend(samples);
+        }
+
+        if ended || utterance.len() >= max_samples || ends_with_pause(&utterance) {
+            if !utterance.is_empty() {
+                recognize_utterance(
+                    &events,
+                    &client,
+                    &api_key,
+                    &session_id,
+                    &utterance,
+                    offset_ms,
+                    &options,
+                )
+                .await?;
+            }
+            offset_ms += utterance.len() as u64 * 1000 / SPEECH_SAMPLE_RATE as u64;
+            utterance.clear();
+        }
+
+        if ended {
+            return Ok(());
+        }
+    }
+}
+
+/// Google Cloud Speech-to-Text
+pub struct Google;
+
+impl TranscriptionProvider for Google {
+    fn id(&self) -> &'static str {
+        "google"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            file: true,
+            streaming: true,
+            diarization: true,
+            ..Default::default()
+        }
+    }
+
+    fn credentials(&self) -> &'static [Credential] {
+        &[Credential {
+            key: API_KEY_NAME,
+            name: "Google API key",
+        }]
+    }
+
+    fn usd_per_minute(&self) -> Option<f64> {
+        // Standard recognition, v1
+        Some(0.024)
+    }
+
+    fn max_file_bytes(&self) -> Option<usize> {
+        Some(MAX_FILE_BYTES)
+    }
+
+    fn transcribe_file(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        audio: AudioFile,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<TranscriptionResult>, String> {
+        let options: GoogleOptions = parse_options(options)?;
+        Ok(Box::pin(async move {
+            transcribe(
+                &events,
+                credentials.get(API_KEY_NAME),
+                audio.bytes,
+                &options,
+            )
+            .await
+        }))
+    }
+
+    fn transcribe_stream(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        session_id: String,
+        live_audio: LiveAudio,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<()>, String> {
+        let options: GoogleOptions = parse_options(options)?;
+        let api_key = credentials.get(API_KEY_NAME).to_string();
+        Ok(Box::pin(stream(
+            events, api_key, session_id, live_audio, options,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results() {
+        let body = r#"{
+            "results": [
+                {
+                    "alternatives": [{
+                        "transcript": "hello there",
+                        "confidence": 0.9,
+                        "words": [
+                            { "word": "hello", "startTime": "0.300s", "endTime": "0.700s",
+                              "confidence": 0.95 },
+                            { "word": "there", "startTime": "0.800s", "endTime": "1.200s",
+                              "confidence": 0.6 }
+                        ]
+                    }],
+                    "resultEndTime": "1.500s",
+                    "languageCode": "en-us"
+                },
+                {
+                    "alternatives": [{ "transcript": " general kenobi", "words": [] }],
+                    "resultEndTime": "4s"
+                }
+            ]
+        }"#;
+        let result = to_result(serde_json::from_str(body).unwrap(), "latest_short");
+        assert_eq!(result.text, "hello there general kenobi");
+        assert_eq!(result.language.as_deref(), Some("en-us"));
+        assert_eq!(
+            (result.segments[0].start_ms, result.segments[0].end_ms),
+            (300, 1500)
+        );
+        assert_eq!(
+            (result.segments[1].start_ms, result.segments[1].end_ms),
+            (1500, 4000)
+        );
+        assert_eq!(result.segments[0].confidence, Some(0.9));
+        assert_eq!(result.segments[0].words[1].confidence, 0.6);
+        assert!(result.segments[1].confidence.is_none());
+        assert_eq!(result.duration_ms, 4000);
+    }
+
+    #[test]
+    fn test_diarized_results() {
+        let body = r#"{
+            "results": [
+                { "alternatives": [{ "transcript": "hi hey there" }], "resultEndTime": "2s" },
+                {
+                    "alternatives": [{ "words": [
+                        { "word": "hi", "startTime": "0s", "endTime": "0.5s", "speakerTag": 1 },
+                        { "word": "hey", "startTime": "1s", "endTime": "1.4s", "speakerTag": 2 },
+                        { "word": "there", "startTime": "1.4s", "endTime": "2s", "speakerTag": 2 }
+                    ] }]
+                }
+            ]
+        }"#;
+        let result = to_result(serde_json::from_str(body).unwrap(), "latest_long");
+        assert_eq!(result.text, "hi hey there");
+        assert_eq!(result.segments.len(), 2);
+        assert_eq!(result.segments[1].text, "hey there");
+        assert_eq!(result.segments[1].speaker.as_deref(), Some("Speaker 2"));
+    }
+
+    #[test]
+    fn test_pause_detection() {
+        let rate = SPEECH_SAMPLE_RATE as usize;
+        let mut samples = vec![0.5; rate];
+        assert!(!ends_with_pause(&samples));
+        samples.extend(vec![0.0; rate]);
+        assert!(ends_with_pause(&samples));
+        assert!(!ends_with_pause(&vec![0.0; rate / 2]));
+    }
+}
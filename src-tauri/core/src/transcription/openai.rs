@@ -0,0 +1,245 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::provider::{
+    parse_options, AudioFile, Credential, Credentials, ProviderCapabilities, ProviderFuture,
+    TranscriptionProvider,
+};
+use super::{retry, TranscriptSegment, TranscriptionResult};
+use crate::analytics::PaceMetrics;
+use crate::Events;
+
+const TRANSCRIPTIONS_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+/// Secure storage key of the API key, shared with the frontend
+pub const API_KEY_NAME: &str = "openai_api_key";
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+/// Uploads of long recordings plus transcription can take minutes
+const REQUEST_TIMEOUT_SECS: u64 = 600;
+/// Upload limit of the transcriptions endpoint
+const MAX_FILE_BYTES: usize = 25 * 1000 * 1000;
+
+/// Options for OpenAI transcription
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OpenAiOptions {
+    /// "whisper-1", "gpt-4o-transcribe" or "gpt-4o-mini-transcribe"
+    pub model: String,
+    /// ISO 639-1 code; `None` lets the API detect the language
+    pub language: Option<String>,
+    /// Vocabulary or context that guides the transcription
+    pub prompt: Option<String>,
+}
+
+impl Default for OpenAiOptions {
+    fn default() -> Self {
+        Self {
+            model: "whisper-1".to_string(),
+            language: None,
+            prompt: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    /// Mean log probability of the segment's tokens
+    avg_logprob: Option<f64>,
+}
+
+/// `verbose_json` response; the GPT-4o models only return `text`
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    text: String,
+    language: Option<String>,
+    duration: Option<f64>,
+    #[serde(default)]
+    segments: Vec<ApiSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: ApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+fn seconds_to_ms(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 1000.0).round() as u64
+}
+
+fn parse_response(body: &[u8], model: &str) -> Result<TranscriptionResult, String> {
+    let response: ApiResponse = serde_json::from_slice(body)
+        .map_err(|e| format!("Unexpected response from OpenAI: {}", e))?;
+
+    let segments: Vec<TranscriptSegment> = response
+        .segments
+        .into_iter()
+        .map(|segment| TranscriptSegment {
+            start_ms: seconds_to_ms(segment.start),
+            end_ms: seconds_to_ms(segment.end),
+            text: segment.text.trim().to_string(),
+            speaker: None,
+            confidence: segment
+                .avg_logprob
+                .map(|logprob| logprob.exp().clamp(0.0, 1.0) as f32),
+            words: Vec::new(),
+        })
+        .collect();
+    let duration_ms = response
+        .duration
+        .map(seconds_to_ms)
+        .or_else(|| segments.last().map(|segment| segment.end_ms))
+        .unwrap_or(0);
+
+    Ok(TranscriptionResult {
+        text: response.text.trim().to_string(),
+        language: response.language,
+        pace: PaceMetrics::from_segments(&segments, duration_ms),
+        segments,
+        duration_ms,
+        engine: "openai".to_string(),
+        model: model.to_string(),
+    })
+}
+
+/// Upload audio to the OpenAI transcription endpoint
+pub async fn transcribe(
+    events: &Events,
+    api_key: &str,
+    audio: Vec<u8>,
+    file_name: String,
+    options: &OpenAiOptions,
+) -> Result<TranscriptionResult, String> {
+    // Only whisper-1 returns timed segments
+    let response_format = if options.model == "whisper-1" {
+        "verbose_json"
+    } else {
+        "json"
+    };
+
+    let form = || {
+        let mut form = reqwest::multipart::Form::new()
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(audio.clone()).file_name(file_name.clone()),
+            )
+            .text("model", options.model.clone())
+            .text("response_format", response_format);
+        if let Some(language) = &options.language {
+            form = form.text("language", language.clone());
+        }
+        if let Some(prompt) = &options.prompt {
+            form = form.text("prompt", prompt.clone());
+        }
+        form
+    };
+
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let (status, body) = retry::send(events, "OpenAI", || {
+        client
+            .post(TRANSCRIPTIONS_URL)
+            .bearer_auth(api_key)
+            .multipart(form())
+    })
+    .await?;
+
+    if !status.is_success() {
+        let message = serde_json::from_slice::<ApiErrorBody>(&body)
+            .map(|body| body.error.message)
+            .unwrap_or_else(|_| status.to_string());
+        return Err(format!("Transcription failed: {}", message));
+    }
+
+    parse_response(&body, &options.model)
+}
+
+/// OpenAI's transcription API
+pub struct OpenAi;
+
+impl TranscriptionProvider for OpenAi {
+    fn id(&self) -> &'static str {
+        "openai"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            file: true,
+            language_detection: true,
+            prompt: true,
+            ..Default::default()
+        }
+    }
+
+    fn credentials(&self) -> &'static [Credential] {
+        &[Credential {
+            key: API_KEY_NAME,
+            name: "OpenAI API key",
+        }]
+    }
+
+    fn usd_per_minute(&self) -> Option<f64> {
+        // Whisper API
+        Some(0.006)
+    }
+
+    fn max_file_bytes(&self) -> Option<usize> {
+        Some(MAX_FILE_BYTES)
+    }
+
+    fn transcribe_file(
+        &self,
+        events: Events,
+        credentials: Credentials,
+        audio: AudioFile,
+        options: serde_json::Value,
+    ) -> Result<ProviderFuture<TranscriptionResult>, String> {
+        let options: OpenAiOptions = parse_options(options)?;
+        Ok(Box::pin(async move {
+            let api_key = credentials.get(API_KEY_NAME);
+            transcribe(&events, api_key, audio.bytes, audio.file_name, &options).await
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verbose_and_plain_responses() {
+        let verbose = br#"{
+            "text": " Hello there. General Kenobi. ",
+            "language": "english",
+            "duration": 4.5,
+            "segments": [
+                { "id": 0, "start": 0.0, "end": 1.2, "text": " Hello there." },
+                { "id": 1, "start": 2.0, "end": 3.25, "text": " General Kenobi.",
+                  "avg_logprob": -0.5 }
+            ]
+        }"#;
+        let result = parse_response(verbose, "whisper-1").unwrap();
+        assert_eq!(result.text, "Hello there. General Kenobi.");
+        assert_eq!(result.duration_ms, 4500);
+        assert_eq!(result.segments[1].start_ms, 2000);
+        assert_eq!(result.segments[1].end_ms, 3250);
+        assert!(result.segments[0].confidence.is_none());
+        assert!((result.segments[1].confidence.unwrap() - 0.6065).abs() < 1e-3);
+        assert_eq!(result.pace.word_count, 4);
+
+        let plain = br#"{ "text": "Hi" }"#;
+        let result = parse_response(plain, "gpt-4o-transcribe").unwrap();
+        assert_eq!((result.text.as_str(), result.duration_ms), ("Hi", 0));
+        assert!(result.segments.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use super::TranscriptionResult;
+use crate::audio::LiveAudio;
+use crate::Events;
+
+/// Work a provider does once its options are accepted
+pub type ProviderFuture<T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send>>;
+
+/// What a provider can do, for the frontend to offer the right choices
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCapabilities {
+    /// Transcribes complete audio files
+    pub file: bool,
+    /// Transcribes live audio while recording
+    pub streaming: bool,
+    pub diarization: bool,
+    /// Detects the spoken language when none is given
+    pub language_detection: bool,
+    /// Runs on this machine, so managed policy cannot disable it
+    pub offline: bool,
+    /// Takes a `prompt` option with vocabulary that guides recognition
+    pub prompt: bool,
+}
+
+/// A secret a provider needs from secure storage
+pub struct Credential {
+    pub key: &'static str,
+    /// Used in "No <name> is set"
+    pub name: &'static str,
+}
+
+/// Secrets read for a provider, by key
+#[derive(Clone)]
+pub struct Credentials(HashMap<&'static str, String>);
+
+impl Credentials {
+    pub fn get(&self, key: &str) -> &str {
+        self.0.get(key).map(String::as_str).unwrap_or_default()
+    }
+}
+
+impl FromIterator<(&'static str, String)> for Credentials {
+    fn from_iter<I: IntoIterator<Item = (&'static str, String)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Audio handed to `transcribe_file`
+pub struct AudioFile {
+    pub bytes: Vec<u8>,
+    /// Lets services infer the format from the extension
+    pub file_name: String,
+}
+
+/// A transcription service or engine. Options arrive as the JSON the frontend
+/// sent; each method checks them before returning the work as a future, so
+/// bad options fail the command instead of the job.
+pub trait TranscriptionProvider: Send + Sync {
+    /// Name used as `{ "provider": "<id>" }`
+    fn id(&self) -> &'static str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Secrets the caller reads (in the app, from secure storage) before any
+    /// work starts
+    fn credentials(&self) -> &'static [Credential] {
+        &[]
+    }
+
+    /// List price in US dollars per minute of audio, for usage estimates;
+    /// `None` for engines that cost nothing to run
+    fn usd_per_minute(&self) -> Option<f64> {
+        None
+    }
+
+    /// Largest file `transcribe_file` can upload; larger audio is split at
+    /// pauses and transcribed in chunks
+    fn max_file_bytes(&self) -> Option<usize> {
+        None
+    }
+
+    fn transcribe_file(
+        &self,
+        _events: Events,
+        _credentials: Credentials,
+        _audio: AudioFile,
+        _options: serde_json::Value,
+    ) -> Result<ProviderFuture<TranscriptionResult>, String> {
+        Err(format!("{} cannot transcribe files", self.id()))
+    }
+
+    /// Emit `transcription-interim` and `transcription-final` events until
+    /// the live audio ends. Live audio arrives as 16 kHz mono.
+    fn transcribe_stream(
+        &self,
+        _events: Events,
+        _credentials: Credentials,
+        _session_id: String,
+        _live_audio: LiveAudio,
+        _options: serde_json::Value,
+    ) -> Result<ProviderFuture<()>, String> {
+        Err(format!("{} does not support live transcription", self.id()))
+    }
+}
+
+/// Parse a provider's options from the request
+pub fn parse_options<T: DeserializeOwned>(options: serde_json::Value) -> Result<T, String> {
+    serde_json::from_value(options).map_err(|e| format!("Invalid provider options: {}", e))
+}
+
+/// Provider and options chosen by the frontend, e.g.
+/// `{ "provider": "assemblyai", "diarize": true }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRequest {
+    pub provider: String,
+    #[serde(flatten)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
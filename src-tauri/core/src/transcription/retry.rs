@@ -0,0 +1,204 @@
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::Events;
+
+/// Attempts per request, the first one included
+const MAX_ATTEMPTS: u32 = 4;
+const BASE_DELAY_MS: u64 = 500;
+const MAX_DELAY_MS: u64 = 8000;
+/// Longest `Retry-After` a service may ask for before the request gives up
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// Why a request to a transcription service failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    /// The service could not be reached, or the connection dropped
+    Network,
+    /// 429
+    RateLimited,
+    /// 5xx
+    Server,
+    /// Any other error status; retrying would not help
+    Client,
+}
+
+impl FailureKind {
+    fn from_status(status: StatusCode) -> Self {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            FailureKind::RateLimited
+        } else if status.is_server_error() {
+            FailureKind::Server
+        } else {
+            FailureKind::Client
+        }
+    }
+
+    fn is_transient(self) -> bool {
+        self != FailureKind::Client
+    }
+}
+
+/// Payload of `transcription-retry`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RetryScheduled {
+    service: String,
+    /// The attempt that failed, starting at 1
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    kind: FailureKind,
+    message: String,
+}
+
+/// Payload of `transcription-request-failed`, emitted once a request has
+/// failed for good
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestFailed {
+    service: String,
+    kind: FailureKind,
+    /// HTTP status, when the service answered
+    status: Option<u16>,
+    attempts: u32,
+    message: String,
+}
+
+/// Exponential backoff before retrying after `attempt`, with `jitter` in
+/// 0..=1 spreading the delay over its upper half so clients that failed
+/// together do not retry together
+fn backoff_delay(attempt: u32, jitter: f64) -> Duration {
+    let delay = BASE_DELAY_MS
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_DELAY_MS);
+    Duration::from_millis(delay / 2 + (delay as f64 / 2.0 * jitter.clamp(0.0, 1.0)) as u64)
+}
+
+/// Delay a 429 or 503 response asks for in seconds; HTTP dates are ignored
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let seconds: u64 = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Send a request to `service`, retrying network errors, 429 and 5xx with
+/// exponential backoff. `build` is called for each attempt since request
+/// bodies cannot be reused. Retries are announced with `transcription-retry`,
+/// and a request that fails for good with `transcription-request-failed`.
+/// Returns the status and body of the last response; network errors read
+/// "Failed to reach <service>: ...".
+pub async fn send(
+    events: &Events,
+    service: &str,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<(StatusCode, Vec<u8>), String> {
+    let mut attempt = 1;
+    loop {
+        let (failure, server_delay) = match build().send().await {
+            Ok(response) => {
+                let status = response.status();
+                let server_delay = retry_after(&response);
+                match response.bytes().await {
+                    Ok(body) if status.is_success() => return Ok((status, body.to_vec())),
+                    Ok(body) => {
+                        let kind = FailureKind::from_status(status);
+                        if !kind.is_transient() || attempt == MAX_ATTEMPTS {
+                            emit_failed(events, service, kind, Some(status), attempt, status);
+                            return Ok((status, body.to_vec()));
+                        }
+                        ((kind, status.to_string()), server_delay)
+                    }
+                    Err(e) => ((FailureKind::Network, e.to_string()), None),
+                }
+            }
+            Err(e) => ((FailureKind::Network, e.to_string()), None),
+        };
+
+        let (kind, message) = failure;
+        if attempt == MAX_ATTEMPTS {
+            emit_failed(events, service, kind, None, attempt, &message);
+            return Err(format!("Failed to reach {}: {}", service, message));
+        }
+
+        let delay = match server_delay {
+            Some(delay) if delay > Duration::from_secs(MAX_RETRY_AFTER_SECS) => {
+                emit_failed(events, service, kind, None, attempt, &message);
+                return Err(format!(
+                    "{} asked to wait {} seconds before retrying",
+                    service,
+                    delay.as_secs()
+                ));
+            }
+            Some(delay) => delay,
+            None => backoff_delay(attempt, rand::thread_rng().gen()),
+        };
+        events.emit(
+            "transcription-retry",
+            RetryScheduled {
+                service: service.to_string(),
+                attempt,
+                max_attempts: MAX_ATTEMPTS,
+                delay_ms: delay.as_millis() as u64,
+                kind,
+                message,
+            },
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+fn emit_failed(
+    events: &Events,
+    service: &str,
+    kind: FailureKind,
+    status: Option<StatusCode>,
+    attempts: u32,
+    message: impl ToString,
+) {
+    events.emit(
+        "transcription-request-failed",
+        RequestFailed {
+            service: service.to_string(),
+            kind,
+            status: status.map(|status| status.as_u16()),
+            attempts,
+            message: message.to_string(),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(1, 0.0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(1, 1.0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(3, 1.0), Duration::from_millis(2000));
+        assert_eq!(backoff_delay(30, 1.0), Duration::from_millis(MAX_DELAY_MS));
+    }
+
+    #[test]
+    fn test_failure_kind() {
+        let kind = FailureKind::from_status;
+        assert_eq!(
+            kind(StatusCode::TOO_MANY_REQUESTS),
+            FailureKind::RateLimited
+        );
+        assert_eq!(kind(StatusCode::BAD_GATEWAY), FailureKind::Server);
+        assert!(!kind(StatusCode::UNAUTHORIZED).is_transient());
+        assert!(FailureKind::Network.is_transient());
+    }
+}
@@ -0,0 +1,508 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+use crate::timestamps::{self, Formatter};
+use crate::transcription::TranscriptSegment;
+pub use transcriber_core::analytics::{words_per_minute, PaceMetrics, PaceTracker};
+
+/// Filler words and phrases. Ambiguous ones ("I like it", "kind of blue") only
+/// count when set off by a comma, which is how recognizers punctuate fillers.
+const FILLERS: &[(&str, bool)] = &[
+    ("um", false),
+    ("umm", false),
+    ("uh", false),
+    ("uhm", false),
+    ("er", false),
+    ("erm", false),
+    ("ah", false),
+    ("hmm", false),
+    ("like", true),
+    ("so", true),
+    ("well", true),
+    ("actually", true),
+    ("basically", true),
+    ("literally", true),
+    ("you know", true),
+    ("i mean", true),
+    ("kind of", true),
+    ("sort of", true),
+];
+
+/// Gaps between segments at least this long count as long pauses
+const LONG_PAUSE_MS: u64 = 2000;
+/// Segments shorter than this are too short for a meaningful pace
+const MIN_PACE_SEGMENT_WORDS: usize = 3;
+
+/// Pace over many entries
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaceStats {
+    /// Entries with speech; silent recordings are left out
+    entries: u32,
+    total_words: u64,
+    total_speaking_ms: u64,
+    average_words_per_minute: f32,
+    fastest_words_per_minute: f32,
+    slowest_words_per_minute: f32,
+}
+
+fn pace_stats(metrics: &[PaceMetrics]) -> PaceStats {
+    let spoken: Vec<&PaceMetrics> = metrics
+        .iter()
+        .filter(|metrics| metrics.word_count > 0 && metrics.speaking_ms > 0)
+        .collect();
+    if spoken.is_empty() {
+        return PaceStats::default();
+    }
+
+    let total_words: u64 = spoken.iter().map(|m| m.word_count as u64).sum();
+    let total_speaking_ms: u64 = spoken.iter().map(|m| m.speaking_ms).sum();
+    let paces = spoken.iter().map(|m| m.words_per_minute);
+
+    PaceStats {
+        entries: spoken.len() as u32,
+        total_words,
+        total_speaking_ms,
+        average_words_per_minute: total_words as f32 * 60_000.0 / total_speaking_ms as f32,
+        fastest_words_per_minute: paces.clone().fold(f32::MIN, f32::max),
+        slowest_words_per_minute: paces.fold(f32::MAX, f32::min),
+    }
+}
+
+/// Aggregate the pace metrics stored on entries
+#[tauri::command]
+pub fn get_pace_stats(metrics: Vec<PaceMetrics>) -> PaceStats {
+    pace_stats(&metrics)
+}
+
+/// An entry to analyze, as sent by the frontend
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsEntry {
+    id: String,
+    /// ISO 8601 timestamp; entries are grouped by its date in the user's
+    /// time zone
+    created_at: String,
+    transcript: String,
+    /// Timed segments, when the entry was transcribed locally; needed for
+    /// pauses and pace variability
+    #[serde(default)]
+    segments: Vec<TranscriptSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillerCount {
+    filler: String,
+    count: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryAnalytics {
+    id: String,
+    created_at: String,
+    word_count: u32,
+    filler_count: u32,
+    fillers_per_100_words: f32,
+    /// Most frequent first
+    fillers: Vec<FillerCount>,
+    long_pauses: u32,
+    longest_pause_ms: u64,
+    /// Coefficient of variation of the per-segment pace: 0 is perfectly even,
+    /// 0.3 and above is noticeably uneven. `None` without enough timed segments.
+    pace_variability: Option<f32>,
+}
+
+/// Entries of one day, for charting progress over time
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyAnalytics {
+    date: String,
+    entries: u32,
+    fillers_per_100_words: f32,
+    long_pauses: u32,
+    pace_variability: Option<f32>,
+}
+
+/// Result of `get_speaking_analytics`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakingAnalytics {
+    entries: Vec<EntryAnalytics>,
+    /// Oldest day first
+    daily: Vec<DailyAnalytics>,
+    /// Across all entries, most frequent first
+    top_fillers: Vec<FillerCount>,
+}
+
+fn normalized(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// Count fillers in a transcript
+fn count_fillers(transcript: &str) -> BTreeMap<&'static str, u32> {
+    let words: Vec<&str> = transcript.split_whitespace().collect();
+    let mut counts = BTreeMap::new();
+    let mut index = 0;
+
+    while index < words.len() {
+        let found = FILLERS.iter().find(|(filler, ambiguous)| {
+            let parts: Vec<&str> = filler.split(' ').collect();
+            let end = index + parts.len();
+            if end > words.len() {
+                return false;
+            }
+
+            let matches = words[index..end]
+                .iter()
+                .zip(&parts)
+                .all(|(word, part)| normalized(word) == *part);
+            let set_off =
+                words[end - 1].ends_with(',') || (index > 0 && words[index - 1].ends_with(','));
+
+            matches && (!ambiguous || set_off)
+        });
+
+        match found {
+            Some((filler, _)) => {
+                *counts.entry(*filler).or_insert(0) += 1;
+                index += filler.split(' ').count();
+            }
+            None => index += 1,
+        }
+    }
+
+    counts
+}
+
+fn sorted_counts(counts: BTreeMap<&str, u32>) -> Vec<FillerCount> {
+    let mut fillers: Vec<FillerCount> = counts
+        .into_iter()
+        .map(|(filler, count)| FillerCount {
+            filler: filler.to_string(),
+            count,
+        })
+        .collect();
+    fillers.sort_by_key(|filler| std::cmp::Reverse(filler.count));
+    fillers
+}
+
+/// Gaps between consecutive segments
+fn pauses(segments: &[TranscriptSegment]) -> impl Iterator<Item = u64> + '_ {
+    segments
+        .windows(2)
+        .map(|pair| pair[1].start_ms.saturating_sub(pair[0].end_ms))
+}
+
+fn pace_variability(segments: &[TranscriptSegment]) -> Option<f32> {
+    let paces: Vec<f32> = segments
+        .iter()
+        .filter(|segment| segment.text.split_whitespace().count() >= MIN_PACE_SEGMENT_WORDS)
+        .filter(|segment| segment.end_ms > segment.start_ms)
+        .map(|segment| {
+            let words = segment.text.split_whitespace().count() as u32;
+            words_per_minute(words, segment.end_ms - segment.start_ms)
+        })
+        .collect();
+    if paces.len() < 2 {
+        return None;
+    }
+
+    let mean = paces.iter().sum::<f32>() / paces.len() as f32;
+    let variance = paces.iter().map(|pace| (pace - mean).powi(2)).sum::<f32>() / paces.len() as f32;
+    Some(variance.sqrt() / mean)
+}
+
+fn per_100_words(count: u32, words: u32) -> f32 {
+    if words == 0 {
+        0.0
+    } else {
+        count as f32 * 100.0 / words as f32
+    }
+}
+
+fn analyze_entry(entry: AnalyticsEntry) -> (EntryAnalytics, BTreeMap<&'static str, u32>) {
+    let word_count = entry.transcript.split_whitespace().count() as u32;
+    let counts = count_fillers(&entry.transcript);
+    let filler_count = counts.values().sum();
+
+    let analytics = EntryAnalytics {
+        word_count,
+        filler_count,
+        fillers_per_100_words: per_100_words(filler_count, word_count),
+        fillers: sorted_counts(counts.clone()),
+        long_pauses: pauses(&entry.segments)
+            .filter(|&pause| pause >= LONG_PAUSE_MS)
+            .count() as u32,
+        longest_pause_ms: pauses(&entry.segments).max().unwrap_or(0),
+        pace_variability: pace_variability(&entry.segments),
+        id: entry.id,
+        created_at: entry.created_at,
+    };
+
+    (analytics, counts)
+}
+
+fn speaking_analytics(entries: Vec<AnalyticsEntry>, formatter: &Formatter) -> SpeakingAnalytics {
+    let mut analyzed = Vec::with_capacity(entries.len());
+    let mut totals: BTreeMap<&str, u32> = BTreeMap::new();
+
+    for entry in entries {
+        let (analytics, counts) = analyze_entry(entry);
+        for (filler, count) in counts {
+            *totals.entry(filler).or_insert(0) += count;
+        }
+        analyzed.push(analytics);
+    }
+
+    let mut days: BTreeMap<String, Vec<&EntryAnalytics>> = BTreeMap::new();
+    for entry in &analyzed {
+        days.entry(formatter.local_date(&entry.created_at))
+            .or_default()
+            .push(entry);
+    }
+
+    let daily = days
+        .into_iter()
+        .map(|(date, entries)| {
+            let words = entries.iter().map(|e| e.word_count).sum();
+            let fillers = entries.iter().map(|e| e.filler_count).sum();
+            let variabilities: Vec<f32> =
+                entries.iter().filter_map(|e| e.pace_variability).collect();
+
+            DailyAnalytics {
+                date,
+                entries: entries.len() as u32,
+                fillers_per_100_words: per_100_words(fillers, words),
+                long_pauses: entries.iter().map(|e| e.long_pauses).sum(),
+                pace_variability: (!variabilities.is_empty())
+                    .then(|| variabilities.iter().sum::<f32>() / variabilities.len() as f32),
+            }
+        })
+        .collect();
+
+    SpeakingAnalytics {
+        daily,
+        top_fillers: sorted_counts(totals),
+        entries: analyzed,
+    }
+}
+
+/// Count fillers, long pauses and pace variability per entry and per day
+#[tauri::command]
+pub fn get_speaking_analytics(app: AppHandle, entries: Vec<AnalyticsEntry>) -> SpeakingAnalytics {
+    speaking_analytics(entries, &timestamps::formatter(&app))
+}
+
+/// Talk time of one meeting participant
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerTalkTime {
+    speaker: String,
+    talk_time_ms: u64,
+    /// Fraction of the total talk time, 0.0 to 1.0
+    share: f32,
+    /// Uninterrupted stretches of speech, counting consecutive segments as one
+    turns: u32,
+    word_count: u32,
+    /// Times this speaker started while someone else was still talking
+    interruptions: u32,
+    /// Times someone else started while this speaker was talking
+    interrupted: u32,
+}
+
+/// Per-speaker talk time from diarized segments, most talkative first.
+/// Segments without a speaker label are ignored.
+pub fn talk_time(segments: &[TranscriptSegment]) -> Vec<SpeakerTalkTime> {
+    let mut labeled: Vec<(&str, &TranscriptSegment)> = segments
+        .iter()
+        .filter_map(|segment| segment.speaker.as_deref().map(|speaker| (speaker, segment)))
+        .collect();
+    labeled.sort_by_key(|(_, segment)| segment.start_ms);
+
+    let mut speakers: Vec<SpeakerTalkTime> = Vec::new();
+    // End of each speaker's latest segment, indexed like `speakers`
+    let mut speaking_until: Vec<u64> = Vec::new();
+    let mut previous: Option<usize> = None;
+
+    for (speaker, segment) in labeled {
+        let index = match speakers.iter().position(|s| s.speaker == speaker) {
+            Some(index) => index,
+            None => {
+                speakers.push(SpeakerTalkTime {
+                    speaker: speaker.to_string(),
+                    talk_time_ms: 0,
+                    share: 0.0,
+                    turns: 0,
+                    word_count: 0,
+                    interruptions: 0,
+                    interrupted: 0,
+                });
+                speaking_until.push(0);
+                speakers.len() - 1
+            }
+        };
+
+        // The other speaker talking the longest past this segment's start
+        let overlapped = (0..speakers.len())
+            .filter(|&other| other != index && speaking_until[other] > segment.start_ms)
+            .max_by_key(|&other| speaking_until[other]);
+        if let Some(other) = overlapped {
+            speakers[index].interruptions += 1;
+            speakers[other].interrupted += 1;
+        }
+
+        let current = &mut speakers[index];
+        current.talk_time_ms += segment.end_ms.saturating_sub(segment.start_ms);
+        current.word_count += segment.text.split_whitespace().count() as u32;
+        if previous != Some(index) {
+            current.turns += 1;
+        }
+
+        speaking_until[index] = speaking_until[index].max(segment.end_ms);
+        previous = Some(index);
+    }
+
+    let total: u64 = speakers.iter().map(|s| s.talk_time_ms).sum();
+    for speaker in &mut speakers {
+        if total > 0 {
+            speaker.share = speaker.talk_time_ms as f32 / total as f32;
+        }
+    }
+    speakers.sort_by_key(|s| std::cmp::Reverse(s.talk_time_ms));
+    speakers
+}
+
+/// Per-speaker talk time and interruptions of a diarized meeting
+#[tauri::command]
+pub fn get_talk_time(segments: Vec<TranscriptSegment>) -> Vec<SpeakerTalkTime> {
+    talk_time(&segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, end_ms: u64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            speaker: None,
+            confidence: None,
+            words: Vec::new(),
+        }
+    }
+
+    fn spoken(speaker: &str, start_ms: u64, end_ms: u64) -> TranscriptSegment {
+        TranscriptSegment {
+            speaker: Some(speaker.to_string()),
+            ..segment(start_ms, end_ms, "a few words")
+        }
+    }
+
+    #[test]
+    fn test_pace_stats_skip_silent_entries() {
+        let metrics = [
+            PaceMetrics {
+                word_count: 100,
+                duration_ms: 60_000,
+                speaking_ms: 60_000,
+                words_per_minute: 100.0,
+            },
+            PaceMetrics {
+                word_count: 300,
+                duration_ms: 60_000,
+                speaking_ms: 60_000,
+                words_per_minute: 300.0,
+            },
+            PaceMetrics::default(),
+        ];
+
+        let stats = pace_stats(&metrics);
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.average_words_per_minute, 200.0);
+        assert_eq!(stats.fastest_words_per_minute, 300.0);
+        assert_eq!(stats.slowest_words_per_minute, 100.0);
+    }
+
+    #[test]
+    fn test_fillers_need_commas_when_ambiguous() {
+        let counts =
+            count_fillers("Um, I like it. It was, like, huge, you know, and uh big. I mean it");
+
+        assert_eq!(counts.get("um"), Some(&1));
+        assert_eq!(counts.get("uh"), Some(&1));
+        assert_eq!(counts.get("like"), Some(&1));
+        assert_eq!(counts.get("you know"), Some(&1));
+        assert_eq!(counts.get("i mean"), None);
+    }
+
+    #[test]
+    fn test_speaking_analytics() {
+        let entries = vec![
+            AnalyticsEntry {
+                id: "a".to_string(),
+                created_at: "2024-05-01T09:00:00.000Z".to_string(),
+                transcript: "um so, one two three four five six seven".to_string(),
+                segments: vec![
+                    segment(0, 2000, "um so, one two"),
+                    segment(5000, 7000, "three four five six seven"),
+                ],
+            },
+            AnalyticsEntry {
+                id: "b".to_string(),
+                // Already May 2 in Tokyo
+                created_at: "2024-05-01T20:00:00.000Z".to_string(),
+                transcript: "clear and steady".to_string(),
+                segments: Vec::new(),
+            },
+        ];
+
+        let tokyo = Formatter::new(Some("Asia/Tokyo"), None).unwrap();
+        let analytics = speaking_analytics(entries, &tokyo);
+        let first = &analytics.entries[0];
+        assert_eq!((first.word_count, first.filler_count), (9, 2));
+        assert_eq!((first.long_pauses, first.longest_pause_ms), (1, 3000));
+        // 120 and 150 words per minute
+        assert!((first.pace_variability.unwrap() - 0.111).abs() < 0.001);
+
+        assert_eq!(analytics.daily.len(), 2);
+        assert_eq!(analytics.daily[1].date, "2024-05-02");
+        assert_eq!(analytics.daily[1].fillers_per_100_words, 0.0);
+        assert_eq!(analytics.daily[1].pace_variability, None);
+    }
+
+    #[test]
+    fn test_talk_time_and_interruptions() {
+        let segments = vec![
+            spoken("A", 0, 6000),
+            spoken("A", 6000, 9000),
+            // B cuts in before A is done
+            spoken("B", 8000, 10000),
+            spoken("A", 10500, 11500),
+            segment(12000, 13000, "unlabeled"),
+        ];
+
+        let speakers = talk_time(&segments);
+        assert_eq!(speakers.len(), 2);
+
+        let a = &speakers[0];
+        assert_eq!(
+            (a.speaker.as_str(), a.talk_time_ms, a.turns),
+            ("A", 10000, 2)
+        );
+        assert_eq!((a.interruptions, a.interrupted), (0, 1));
+        assert!((a.share - 10.0 / 12.0).abs() < 1e-6);
+
+        let b = &speakers[1];
+        assert_eq!(
+            (b.speaker.as_str(), b.talk_time_ms, b.turns),
+            ("B", 2000, 1)
+        );
+        assert_eq!((b.interruptions, b.interrupted), (1, 0));
+    }
+}
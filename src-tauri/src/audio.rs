@@ -1,11 +1,126 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use crate::settings;
+use crate::window_context::{self, WindowContext};
+
+mod helper;
+pub mod preflight;
+
+pub use helper::run_if_requested as run_capture_helper_if_requested;
+use transcriber_core::audio::chunk_stream::ChunkStreamer;
+use transcriber_core::audio::pipeline::{self, Pipeline};
+use transcriber_core::audio::wav_info::{self, WavMetadata};
+pub use transcriber_core::audio::{
+    decode_speech_samples, encode_speech_wav, load_speech_samples, remove_silence, AudioFormat,
+    LiveAudio, RecordingConfig, VadConfig, SPEECH_SAMPLE_RATE,
+};
+use transcriber_core::audio::{devices, to_speech_samples};
+
+/// Maximum delay between capture and monitor playback before old samples are dropped
+const MONITOR_MAX_LATENCY_MS: usize = 50;
+
+/// How often a failed input stream is rebuilt before the recording is given up
+const MAX_STREAM_RECOVERY_ATTEMPTS: u32 = 3;
+
+/// Delay before the first rebuild attempt, doubled on every further attempt
+const STREAM_RECOVERY_BASE_DELAY_MS: u64 = 250;
+
+/// How long `test_input_device` listens
+const DEVICE_TEST_DURATION_MS: u64 = 1000;
+
+/// Mono ring buffer shared between the input callback and the monitor output stream
+#[derive(Clone, Default)]
+struct MonitorTap {
+    enabled: Arc<AtomicBool>,
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    max_frames: Arc<AtomicUsize>,
+    /// Converts the downmixed input to the rate of the output device
+    resampler: Arc<Mutex<Option<Pipeline>>>,
+}
+
+impl MonitorTap {
+    /// Downmix interleaved input to mono, convert it to the output rate and
+    /// queue it for playback
+    fn push(&self, data: &[f32], channels: usize) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut mono: Vec<f32> = data
+            .chunks(channels.max(1))
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+        if let Ok(mut resampler) = self.resampler.lock() {
+            if let Some(resampler) = resampler.as_mut() {
+                mono = resampler.process(mono);
+            }
+        }
+
+        if let Ok(mut buffer) = self.buffer.lock() {
+            buffer.extend(mono);
+
+            // Keep latency bounded by discarding the oldest frames
+            let max_frames = self.max_frames.load(Ordering::Relaxed);
+            if buffer.len() > max_frames {
+                let excess = buffer.len() - max_frames;
+                buffer.drain(..excess);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut buffer) = self.buffer.lock() {
+            buffer.clear();
+        }
+    }
+}
 
 /// Audio recorder state - stores samples and metadata
 pub struct AudioRecorder {
     samples: Arc<Mutex<Vec<f32>>>,
     sample_rate: Arc<Mutex<u32>>,
     stream: Mutex<Option<Box<dyn std::any::Any>>>,
+    config: Mutex<RecordingConfig>,
+    /// Format of the processed samples in `samples`
+    output_format: Mutex<AudioFormat>,
+    /// Name of the device the current recording is captured from
+    device_name: Mutex<Option<String>>,
+    monitor: MonitorTap,
+    monitor_stream: Mutex<Option<Box<dyn std::any::Any>>>,
+    recovery_pending: Arc<AtomicBool>,
+    recovery_attempts: Arc<AtomicU32>,
+    /// Sequence number of the next streamed chunk, continuous across stream recovery
+    chunk_sequence: Arc<AtomicU64>,
+    /// Receives processed samples while recording, for live transcription
+    live_audio: LiveAudioTap,
+    /// Samples of the last stopped recording, kept so the next one can append to it
+    last_take: Mutex<Option<Take>>,
+    /// Where the current recording was appended to the last take
+    append_point: Mutex<Option<AppendPoint>>,
+    /// App the user was in when the recording started, if captured
+    window_context: Mutex<Option<WindowContext>>,
+}
+
+type LiveAudioTap = Arc<Mutex<Option<UnboundedSender<Vec<f32>>>>>;
+
+/// Processed samples of a finished recording
+struct Take {
+    samples: Vec<f32>,
+    format: AudioFormat,
+}
+
+/// Join between a resumed take and the newly captured audio
+struct AppendPoint {
+    /// Sample offset where the new audio starts
+    offset: usize,
+    crossfade_ms: u32,
 }
 
 impl Default for AudioRecorder {
@@ -14,6 +129,21 @@ impl Default for AudioRecorder {
             samples: Arc::new(Mutex::new(Vec::new())),
             sample_rate: Arc::new(Mutex::new(44100)),
             stream: Mutex::new(None),
+            config: Mutex::new(RecordingConfig::default()),
+            output_format: Mutex::new(AudioFormat {
+                sample_rate: 44100,
+                channels: 1,
+            }),
+            device_name: Mutex::new(None),
+            monitor: MonitorTap::default(),
+            monitor_stream: Mutex::new(None),
+            recovery_pending: Arc::new(AtomicBool::new(false)),
+            recovery_attempts: Arc::new(AtomicU32::new(0)),
+            chunk_sequence: Arc::new(AtomicU64::new(0)),
+            live_audio: LiveAudioTap::default(),
+            last_take: Mutex::new(None),
+            append_point: Mutex::new(None),
+            window_context: Mutex::new(None),
         }
     }
 }
@@ -24,41 +154,399 @@ impl Default for AudioRecorder {
 unsafe impl Send for AudioRecorder {}
 unsafe impl Sync for AudioRecorder {}
 
+/// Check that the default input device exists and reports a usable format.
+/// Microphone permission is only requested once a stream is opened, so a
+/// denied permission does not show up here.
+pub fn check_input_device() -> Result<String, String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("No input device found")?;
+    device
+        .default_input_config()
+        .map_err(|e| format!("The input device cannot be used: {}", e))?;
+
+    Ok(format!(
+        "Input device \"{}\" is available",
+        device.name().unwrap_or_default()
+    ))
+}
+
+/// Input device as presented to the settings UI
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputDeviceInfo {
+    /// Stable id, used for the preferred device and aliases
+    id: String,
+    /// Name reported by the system, which may change between sessions
+    name: String,
+    /// Name the user gave the device
+    alias: Option<String>,
+    channels: u16,
+    is_default: bool,
+}
+
+/// Payload of the `input-device-fallback` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InputDeviceFallback {
+    preferred_id: String,
+    message: String,
+}
+
+/// Payload of the `monitor-unavailable` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MonitorUnavailableEvent {
+    message: String,
+}
+
+/// Payload of the `recording-stream-recovery` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamRecoveryEvent {
+    attempt: u32,
+    max_attempts: u32,
+    error: String,
+    recovered: bool,
+    message: String,
+}
+
+/// Pick the device to record from: the persisted preference if it is still
+/// connected, otherwise the default input device (emitting a warning event)
+fn select_input_device(app: &AppHandle, host: &cpal::Host) -> Result<cpal::Device, String> {
+    let current = settings::load_settings(app)?;
+
+    if let Some(preferred_id) = current.preferred_input_device.clone() {
+        if let Some(found) = devices::find(host, &preferred_id) {
+            // Preferences saved before stable ids hold the device name
+            if found.id != preferred_id {
+                settings::update_settings(app, |current| {
+                    current.preferred_input_device = Some(found.id);
+                    Ok(())
+                })?;
+            }
+            return Ok(found.device);
+        }
+
+        let name = current
+            .input_device_aliases
+            .get(&preferred_id)
+            .unwrap_or(&preferred_id);
+        let _ = app.emit(
+            "input-device-fallback",
+            InputDeviceFallback {
+                message: format!(
+                    "Preferred input device \"{}\" is not available, using the default device",
+                    name
+                ),
+                preferred_id,
+            },
+        );
+    }
+
+    host.default_input_device()
+        .ok_or_else(|| "No input device available".to_string())
+}
+
+/// List available input devices
+#[tauri::command]
+pub fn list_input_devices(app: AppHandle) -> Result<Vec<InputDeviceInfo>, String> {
+    let aliases = settings::load_settings(&app)?.input_device_aliases;
+    let host = cpal::default_host();
+    let devices = devices::input_devices(&host)?;
+    let default_id = devices::default_id(&host, &devices);
+
+    Ok(devices
+        .into_iter()
+        .map(|device| InputDeviceInfo {
+            is_default: default_id.as_deref() == Some(device.id.as_str()),
+            alias: aliases.get(&device.id).cloned(),
+            name: device.name,
+            channels: device.channels,
+            id: device.id,
+        })
+        .collect())
+}
 
-/// Start recording audio from the default input device
+/// Name an input device, or remove its name with `None`. Aliases are kept by
+/// stable id, so they survive the device being renumbered or reconnected.
 #[tauri::command]
-pub fn start_recording(recorder: tauri::State<AudioRecorder>) -> Result<(), String> {
-    // Clear previous samples
+pub fn set_input_device_alias(
+    app: AppHandle,
+    device_id: String,
+    alias: Option<String>,
+) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        match alias.map(|alias| alias.trim().to_string()) {
+            Some(alias) if !alias.is_empty() => {
+                current.input_device_aliases.insert(device_id, alias);
+            }
+            _ => {
+                current.input_device_aliases.remove(&device_id);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Get the persisted preferred input device id, if any
+#[tauri::command]
+pub fn get_preferred_input_device(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::load_settings(&app)?.preferred_input_device)
+}
+
+/// Persist the preferred input device id (`None` resets to the system default)
+#[tauri::command]
+pub fn set_preferred_input_device(app: AppHandle, device_id: Option<String>) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        current.preferred_input_device = device_id;
+        Ok(())
+    })
+}
+
+/// Result of `test_input_device`: levels are linear (0.0-1.0) and in dBFS
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputLevel {
+    device_name: String,
+    peak: f32,
+    rms: f32,
+    peak_dbfs: f32,
+    rms_dbfs: f32,
+}
+
+/// Record from a device for `duration_ms` outside of any recording, keeping
+/// every channel so a single dead channel does not hide the level
+fn capture_briefly(device: &cpal::Device, duration_ms: u64) -> Result<Vec<f32>, String> {
+    let config = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get input config: {}", e))?;
+    let input_format = AudioFormat {
+        sample_rate: config.sample_rate().0,
+        channels: config.channels(),
+    };
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let capture = CaptureTarget {
+        samples: Arc::clone(&samples),
+        monitor: MonitorTap::default(),
+        pipeline: Pipeline::new(
+            &RecordingConfig {
+                downmix: false,
+                ..Default::default()
+            },
+            input_format,
+        ),
+        chunks: None,
+        live_audio: LiveAudioTap::default(),
+    };
+    let on_error = |err| eprintln!("An error occurred on the test stream: {}", err);
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => {
+            build_input_stream::<f32>(device, &config.into(), capture, on_error)
+        }
+        cpal::SampleFormat::I16 => {
+            build_input_stream::<i16>(device, &config.into(), capture, on_error)
+        }
+        cpal::SampleFormat::U16 => {
+            build_input_stream::<u16>(device, &config.into(), capture, on_error)
+        }
+        _ => return Err("Unsupported sample format".to_string()),
+    }
+    .map_err(|e| format!("Failed to build input stream: {}", e))?;
+
+    stream
+        .play()
+        .map_err(|e| format!("Failed to play stream: {}", e))?;
+    std::thread::sleep(Duration::from_millis(duration_ms));
+    drop(stream);
+
+    let samples = std::mem::take(&mut *samples.lock().unwrap());
+    Ok(samples)
+}
+
+/// Record briefly from a device (the default one if `device_id` is `None`)
+/// and report its level, for a "test microphone" button in the settings UI.
+/// Runs independently of any recording in progress.
+#[tauri::command]
+pub async fn test_input_device(device_id: Option<String>) -> Result<InputLevel, String> {
+    tokio::task::spawn_blocking(move || {
+        let host = cpal::default_host();
+        let device = match &device_id {
+            Some(id) => devices::find(&host, id)
+                .map(|found| found.device)
+                .ok_or_else(|| format!("Input device \"{}\" is not available", id))?,
+            None => host
+                .default_input_device()
+                .ok_or_else(|| "No input device available".to_string())?,
+        };
+
+        let samples = capture_briefly(&device, DEVICE_TEST_DURATION_MS)?;
+        if samples.is_empty() {
+            return Err("No audio received from the input device".to_string());
+        }
+
+        let (peak, rms) = pipeline::peak_and_rms(&samples);
+        Ok(InputLevel {
+            device_name: device.name().unwrap_or_default(),
+            peak,
+            rms,
+            peak_dbfs: pipeline::to_dbfs(peak),
+            rms_dbfs: pipeline::to_dbfs(rms),
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Start recording audio from the preferred (or default) input device,
+/// processing captured audio according to `config` (defaults if omitted).
+/// With `append`, the new audio continues the last stopped take, joined by a
+/// crossfade of `crossfade_ms` (none if omitted).
+#[tauri::command]
+pub fn start_recording(
+    app: AppHandle,
+    recorder: tauri::State<AudioRecorder>,
+    config: Option<RecordingConfig>,
+    append: Option<bool>,
+    crossfade_ms: Option<u32>,
+) -> Result<(), String> {
+    let previous = if append.unwrap_or(false) {
+        let take = recorder.last_take.lock().unwrap().take();
+        Some(take.ok_or("No previous recording to append to")?)
+    } else {
+        None
+    };
+
+    // Start from the previous take, or from an empty buffer
     {
         let mut samples = recorder.samples.lock().unwrap();
         samples.clear();
+        if let Some(previous) = &previous {
+            samples.extend_from_slice(&previous.samples);
+        }
+    }
+
+    let config = config.unwrap_or_default();
+
+    // An appended take keeps the context of the recording it continues
+    if previous.is_none() {
+        *recorder.window_context.lock().unwrap() = if config.capture_window_context {
+            window_context::capture()
+        } else {
+            None
+        };
     }
 
-    // Get the default host and input device
+    *recorder.config.lock().unwrap() = config;
+    *recorder.append_point.lock().unwrap() = previous.as_ref().map(|take| AppendPoint {
+        offset: take.samples.len(),
+        crossfade_ms: crossfade_ms.unwrap_or(0),
+    });
+    recorder.recovery_attempts.store(0, Ordering::Relaxed);
+    recorder.recovery_pending.store(false, Ordering::Relaxed);
+    recorder.chunk_sequence.store(0, Ordering::Relaxed);
+
+    let opened = open_input_stream(&app, &recorder);
+
+    // Appending only works if the new audio has the same layout as the take
+    let opened = match (opened, &previous) {
+        (Ok((_, output_format)), Some(take)) if output_format != take.format => Err(format!(
+            "Cannot append: the previous take is {} Hz / {} ch, the new one {} Hz / {} ch",
+            take.format.sample_rate,
+            take.format.channels,
+            output_format.sample_rate,
+            output_format.channels
+        )),
+        (opened, _) => opened,
+    };
+
+    let (input_format, output_format) = match opened {
+        Ok(formats) => formats,
+        Err(e) => {
+            *recorder.stream.lock().unwrap() = None;
+            recorder.samples.lock().unwrap().clear();
+            *recorder.append_point.lock().unwrap() = None;
+            // Keep the previous take available for another attempt
+            *recorder.last_take.lock().unwrap() = previous;
+            return Err(e);
+        }
+    };
+
+    // Store the device sample rate and the processed output format
+    {
+        let mut sample_rate = recorder.sample_rate.lock().unwrap();
+        *sample_rate = input_format.sample_rate;
+    }
+    *recorder.output_format.lock().unwrap() = output_format;
+
+    // The recording is running; without monitoring it still goes on
+    if recorder.monitor.enabled.load(Ordering::Relaxed) {
+        if let Err(e) = start_monitor(&recorder) {
+            eprintln!("Failed to start monitoring: {}", e);
+            let _ = app.emit(
+                "monitor-unavailable",
+                MonitorUnavailableEvent {
+                    message: format!("Recording without monitoring: {}", e),
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the app and window the current (or last) recording was dictated into,
+/// if `captureWindowContext` was enabled
+#[tauri::command]
+pub fn get_recording_context(recorder: tauri::State<AudioRecorder>) -> Option<WindowContext> {
+    recorder.window_context.lock().unwrap().clone()
+}
+
+/// Build, start and store the input stream, returning the device format and
+/// the format produced by the processing pipeline. Captured samples are
+/// appended to the recorder's existing buffer.
+fn open_input_stream(
+    app: &AppHandle,
+    recorder: &AudioRecorder,
+) -> Result<(AudioFormat, AudioFormat), String> {
+    // Get the default host and the input device to record from
     let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or("No input device available")?;
+    let device = select_input_device(app, &host)?;
+    *recorder.device_name.lock().unwrap() = device.name().ok();
+
+    if settings::load_settings(app)?.capture_helper {
+        match open_helper_stream(app, recorder, device.name().ok()) {
+            Ok(formats) => return Ok(formats),
+            Err(e) => eprintln!("Capturing in process instead: {}", e),
+        }
+    }
 
     // Get the default input config
     let config = device
         .default_input_config()
         .map_err(|e| format!("Failed to get input config: {}", e))?;
+    let input_format = AudioFormat {
+        sample_rate: config.sample_rate().0,
+        channels: config.channels(),
+    };
 
-    // Store the sample rate
-    {
-        let mut sample_rate = recorder.sample_rate.lock().unwrap();
-        *sample_rate = config.sample_rate().0;
-    }
-
-    // Clone Arc references for the audio callback thread
-    let samples_arc = Arc::clone(&recorder.samples);
+    let capture = CaptureTarget::new(app, recorder, input_format);
+    let output_format = capture.pipeline.output_format();
+    let on_error = stream_error_handler(app.clone(), Arc::clone(&recorder.recovery_pending));
 
     // Build the input stream - directly collect samples without channel
     let stream = match config.sample_format() {
-        cpal::SampleFormat::F32 => build_input_stream::<f32>(&device, &config.into(), samples_arc),
-        cpal::SampleFormat::I16 => build_input_stream::<i16>(&device, &config.into(), samples_arc),
-        cpal::SampleFormat::U16 => build_input_stream::<u16>(&device, &config.into(), samples_arc),
+        cpal::SampleFormat::F32 => {
+            build_input_stream::<f32>(&device, &config.into(), capture, on_error)
+        }
+        cpal::SampleFormat::I16 => {
+            build_input_stream::<i16>(&device, &config.into(), capture, on_error)
+        }
+        cpal::SampleFormat::U16 => {
+            build_input_stream::<u16>(&device, &config.into(), capture, on_error)
+        }
         _ => return Err("Unsupported sample format".to_string()),
     }
     .map_err(|e| format!("Failed to build input stream: {}", e))?;
@@ -72,89 +560,542 @@ pub fn start_recording(recorder: tauri::State<AudioRecorder>) -> Result<(), Stri
     let mut stream_lock = recorder.stream.lock().unwrap();
     *stream_lock = Some(Box::new(stream));
 
+    Ok((input_format, output_format))
+}
+
+/// Capture in a helper process, which keeps recording if the app crashes
+fn open_helper_stream(
+    app: &AppHandle,
+    recorder: &AudioRecorder,
+    device_name: Option<String>,
+) -> Result<(AudioFormat, AudioFormat), String> {
+    let dir = helper::new_session_dir(&helper::capture_dir(app)?)?;
+    let config = recorder.config.lock().unwrap().clone();
+    let result = helper::save_config(&dir, &config)
+        .and_then(|()| helper::spawn(&dir, device_name.as_deref()))
+        .and_then(|(state, child)| attach_helper(app, recorder, &state, Some(child)));
+
+    // Leave nothing behind for the next launch to mistake for a recording
+    if result.is_err() {
+        helper::remove_session(&dir);
+    }
+    result
+}
+
+/// Feed the samples of a running helper into the recorder and store the
+/// connection as the recording's stream. The helper replays everything it
+/// captured so far first.
+fn attach_helper(
+    app: &AppHandle,
+    recorder: &AudioRecorder,
+    state: &helper::HelperState,
+    child: Option<std::process::Child>,
+) -> Result<(AudioFormat, AudioFormat), String> {
+    let input_format = AudioFormat {
+        sample_rate: state.sample_rate,
+        channels: state.channels,
+    };
+    let connection = match helper::connect(state) {
+        Ok(connection) => connection,
+        Err(e) => {
+            // A helper started for this recording must not keep capturing
+            if let Some(mut child) = child {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            return Err(e);
+        }
+    };
+
+    let mut capture = CaptureTarget::new(app, recorder, input_format);
+    let output_format = capture.pipeline.output_format();
+    let channels = input_format.channels as usize;
+    let mut on_error = stream_error_handler(app.clone(), Arc::clone(&recorder.recovery_pending));
+    let stream = helper::start_reader(
+        connection,
+        child,
+        move |block| capture.feed(block, channels),
+        move || {
+            on_error(cpal::StreamError::BackendSpecific {
+                err: cpal::BackendSpecificError {
+                    description: "Capture helper stopped unexpectedly".to_string(),
+                },
+            })
+        },
+    )?;
+
+    *recorder.stream.lock().unwrap() = Some(Box::new(stream));
+
+    Ok((input_format, output_format))
+}
+
+/// Payload of the `recording-restored` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingRestoredEvent {
+    /// The helper is still recording; otherwise the audio it captured before
+    /// it exited is ready to be stopped
+    reattached: bool,
+    device_name: Option<String>,
+}
+
+/// Pick up a recording whose capture helper outlived the previous app
+/// session. A running helper is re-attached; the spool of one that exited is
+/// loaded so `stop_recording` returns it.
+pub fn reattach_on_startup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = reattach(&app) {
+            eprintln!("Failed to restore the previous recording: {}", e);
+        }
+    });
+}
+
+fn reattach(app: &AppHandle) -> Result<(), String> {
+    let Some((dir, state)) = helper::find_session(&helper::capture_dir(app)?) else {
+        return Ok(());
+    };
+
+    let recorder = app.state::<AudioRecorder>();
+    let config = helper::load_config(&dir).unwrap_or_default();
+    *recorder.config.lock().unwrap() = config.clone();
+    *recorder.device_name.lock().unwrap() = state.device_name.clone();
+    *recorder.sample_rate.lock().unwrap() = state.sample_rate;
+    recorder.samples.lock().unwrap().clear();
+
+    let reattached = match attach_helper(app, &recorder, &state, None) {
+        Ok((_, output_format)) => {
+            *recorder.output_format.lock().unwrap() = output_format;
+            true
+        }
+        Err(_) => {
+            let samples = helper::read_spool(&dir)?;
+            helper::remove_session(&dir);
+
+            let input_format = AudioFormat {
+                sample_rate: state.sample_rate,
+                channels: state.channels,
+            };
+            let mut pipeline = Pipeline::new(&config, input_format);
+            *recorder.output_format.lock().unwrap() = pipeline.output_format();
+            *recorder.samples.lock().unwrap() = pipeline.process(samples);
+            false
+        }
+    };
+
+    let _ = app.emit(
+        "recording-restored",
+        RecordingRestoredEvent {
+            reattached,
+            device_name: state.device_name,
+        },
+    );
+
     Ok(())
 }
 
-/// Stop recording and return the audio data as base64-encoded WAV
+/// Create the error callback for an input stream. The first error schedules
+/// a rebuild; further errors from the same dead stream are ignored.
+fn stream_error_handler(
+    app: AppHandle,
+    recovery_pending: Arc<AtomicBool>,
+) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    move |err| {
+        eprintln!("An error occurred on the audio stream: {}", err);
+
+        if recovery_pending.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let app = app.clone();
+        let error = err.to_string();
+        let attempt = app
+            .state::<AudioRecorder>()
+            .recovery_attempts
+            .fetch_add(1, Ordering::SeqCst)
+            + 1;
+
+        // Back off outside the audio thread, then rebuild on the main thread
+        // where the (non-Send) stream is owned
+        std::thread::spawn(move || {
+            let delay = STREAM_RECOVERY_BASE_DELAY_MS << (attempt.min(8) - 1);
+            std::thread::sleep(Duration::from_millis(delay));
+
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || recover_input_stream(&handle, attempt, error));
+        });
+    }
+}
+
+/// Tear down the failed input stream and try to rebuild it, reporting the
+/// outcome to the frontend. Samples captured so far are kept either way.
+fn recover_input_stream(app: &AppHandle, attempt: u32, error: String) {
+    let recorder = app.state::<AudioRecorder>();
+
+    // The recording may have been stopped while we were waiting
+    let was_recording = recorder.stream.lock().unwrap().take().is_some();
+    if !was_recording {
+        recorder.recovery_pending.store(false, Ordering::SeqCst);
+        return;
+    }
+
+    let expected_format = *recorder.output_format.lock().unwrap();
+
+    let result = if attempt > MAX_STREAM_RECOVERY_ATTEMPTS {
+        Err(format!(
+            "Gave up after {} attempts to restart the audio stream",
+            MAX_STREAM_RECOVERY_ATTEMPTS
+        ))
+    } else {
+        match open_input_stream(app, &recorder) {
+            Ok((_, format)) if format == expected_format => Ok(()),
+            Ok((_, format)) => {
+                // Appending samples in a different format would corrupt the recording
+                recorder.stream.lock().unwrap().take();
+                Err(format!(
+                    "Recovered device produces {} Hz / {} channels instead of {} Hz / {} channels",
+                    format.sample_rate,
+                    format.channels,
+                    expected_format.sample_rate,
+                    expected_format.channels
+                ))
+            }
+            Err(e) => Err(e),
+        }
+    };
+
+    match result {
+        // A later, unrelated failure gets the full number of attempts again
+        Ok(()) => recorder.recovery_attempts.store(0, Ordering::Relaxed),
+        Err(_) => stop_monitor(&recorder),
+    }
+
+    recorder.recovery_pending.store(false, Ordering::SeqCst);
+
+    let _ = app.emit(
+        "recording-stream-recovery",
+        StreamRecoveryEvent {
+            attempt,
+            max_attempts: MAX_STREAM_RECOVERY_ATTEMPTS,
+            error,
+            recovered: result.is_ok(),
+            message: match result {
+                Ok(()) => "Audio stream was restarted after an error".to_string(),
+                Err(e) => format!("Recording stopped: {}", e),
+            },
+        },
+    );
+}
+
+/// Enable or disable routing captured input to the default output device.
+/// Takes effect immediately if a recording is in progress.
 #[tauri::command]
-pub fn stop_recording(recorder: tauri::State<AudioRecorder>) -> Result<String, String> {
+pub fn set_monitoring(recorder: tauri::State<AudioRecorder>, enabled: bool) -> Result<(), String> {
+    recorder.monitor.enabled.store(enabled, Ordering::Relaxed);
+
+    let is_recording = recorder.stream.lock().unwrap().is_some();
+
+    if enabled && is_recording {
+        start_monitor(&recorder)
+    } else {
+        stop_monitor(&recorder);
+        Ok(())
+    }
+}
+
+/// Open the monitor output stream in the output device's own format,
+/// resampling the captured audio to its rate
+fn start_monitor(recorder: &AudioRecorder) -> Result<(), String> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or("No output device available for monitoring")?;
+
+    let output_config = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get output config: {}", e))?;
+    let config: cpal::StreamConfig = output_config.config();
+    let output_rate = config.sample_rate.0;
+
+    let capture_format = AudioFormat {
+        sample_rate: *recorder.sample_rate.lock().unwrap(),
+        channels: 1,
+    };
+    let resample = RecordingConfig {
+        target_sample_rate: Some(output_rate),
+        ..Default::default()
+    };
+    *recorder.monitor.resampler.lock().unwrap() = Some(Pipeline::new(&resample, capture_format));
+
+    recorder.monitor.clear();
+    recorder.monitor.max_frames.store(
+        output_rate as usize * MONITOR_MAX_LATENCY_MS / 1000,
+        Ordering::Relaxed,
+    );
+
+    let monitor = recorder.monitor.clone();
+    let stream = match output_config.sample_format() {
+        cpal::SampleFormat::F32 => build_monitor_stream::<f32>(&device, &config, monitor),
+        cpal::SampleFormat::I16 => build_monitor_stream::<i16>(&device, &config, monitor),
+        cpal::SampleFormat::U16 => build_monitor_stream::<u16>(&device, &config, monitor),
+        _ => return Err("Unsupported output sample format".to_string()),
+    }
+    .map_err(|e| format!("Failed to build monitor stream: {}", e))?;
+
+    stream
+        .play()
+        .map_err(|e| format!("Failed to play monitor stream: {}", e))?;
+
+    let mut monitor_lock = recorder.monitor_stream.lock().unwrap();
+    *monitor_lock = Some(Box::new(stream));
+
+    Ok(())
+}
+
+/// Close the monitor output stream and drop any queued audio
+fn stop_monitor(recorder: &AudioRecorder) {
+    let mut monitor_lock = recorder.monitor_stream.lock().unwrap();
+    *monitor_lock = None;
+    *recorder.monitor.resampler.lock().unwrap() = None;
+    recorder.monitor.clear();
+}
+
+/// Stop recording and return the audio data as base64-encoded WAV.
+/// The file carries an INFO chunk with creation time, app version, device
+/// name and the optional title/comment. With auto-transcription on, the
+/// recording is then transcribed; see `set_auto_transcribe_settings`.
+#[tauri::command]
+pub fn stop_recording(
+    app: AppHandle,
+    recorder: tauri::State<AudioRecorder>,
+    title: Option<String>,
+    comment: Option<String>,
+) -> Result<String, String> {
+    let wav = finish_recording(&recorder, title, comment)?;
+    crate::auto_transcribe::on_recording_stopped(&app);
+
+    Ok(wav)
+}
+
+/// `stop_recording` without auto-transcription, for callers that handle
+/// the take themselves
+pub fn finish_recording(
+    recorder: &AudioRecorder,
+    title: Option<String>,
+    comment: Option<String>,
+) -> Result<String, String> {
     // Stop the stream by dropping it
     {
         let mut stream_lock = recorder.stream.lock().unwrap();
         *stream_lock = None;
     }
+    stop_monitor(recorder);
+    close_live_audio(recorder);
 
-    // Get the recorded samples
-    let samples = {
-        let mut samples = recorder.samples.lock().unwrap();
-        let data = samples.clone();
-        samples.clear(); // Clear for next recording
-        data
-    };
+    // Get the recorded samples, leaving the buffer empty for the next recording
+    let mut samples = std::mem::take(&mut *recorder.samples.lock().unwrap());
 
-    let sample_rate = *recorder.sample_rate.lock().unwrap();
+    let format = *recorder.output_format.lock().unwrap();
+    let encoding = recorder.config.lock().unwrap().encoding;
+
+    if let Some(point) = recorder.append_point.lock().unwrap().take() {
+        let frames = (format.sample_rate as u64 * point.crossfade_ms as u64 / 1000) as usize;
+        pipeline::crossfade(&mut samples, point.offset, frames, format.channels);
+    }
 
     if samples.is_empty() {
         return Err("No audio data recorded".to_string());
     }
 
     // Convert to WAV format
-    let wav_data = samples_to_wav(&samples, sample_rate)
+    let wav_data = pipeline::encode_wav(&samples, format, encoding)
         .map_err(|e| format!("Failed to convert to WAV: {}", e))?;
 
+    let device_name = recorder.device_name.lock().unwrap().clone();
+    let metadata = WavMetadata::for_recording(device_name, title, comment);
+    let wav_data = wav_info::append_info_chunk(wav_data, &metadata);
+
     // Encode as base64
     use base64::Engine;
     let base64_data = base64::engine::general_purpose::STANDARD.encode(&wav_data);
 
+    *recorder.last_take.lock().unwrap() = Some(Take { samples, format });
+
     Ok(base64_data)
 }
 
+/// Whether a recording is in progress
+pub fn is_recording(recorder: &AudioRecorder) -> bool {
+    recorder.stream.lock().unwrap().is_some()
+}
+
+/// Start receiving the processed audio of the current recording. Only one
+/// receiver can be open at a time; it ends when the recording stops or
+/// `close_live_audio` is called.
+pub fn open_live_audio(recorder: &AudioRecorder) -> Result<LiveAudio, String> {
+    if !is_recording(recorder) {
+        return Err("No recording in progress".to_string());
+    }
+
+    let mut tap = recorder.live_audio.lock().unwrap();
+    // A receiver that was dropped without closing the tap does not count
+    if tap.as_ref().is_some_and(|sender| !sender.is_closed()) {
+        return Err("Live audio is already being streamed".to_string());
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    *tap = Some(sender);
+
+    Ok(LiveAudio {
+        receiver,
+        format: *recorder.output_format.lock().unwrap(),
+    })
+}
+
+/// End the live audio receiver, if any, after the audio already captured
+pub fn close_live_audio(recorder: &AudioRecorder) {
+    recorder.live_audio.lock().unwrap().take();
+}
+
+/// Stop the current recording and drop its audio, keeping the last take
+pub fn discard_recording(recorder: &AudioRecorder) {
+    *recorder.stream.lock().unwrap() = None;
+    stop_monitor(recorder);
+    close_live_audio(recorder);
+    recorder.samples.lock().unwrap().clear();
+    *recorder.append_point.lock().unwrap() = None;
+}
+
+/// Stop the current recording and drop its audio and the last take, e.g.
+/// before all data is wiped. Must run on the main thread, like everything
+/// that touches the stream. Returns a thread that ends once the recording's
+/// capture helper has exited.
+pub fn stop_capture(recorder: &AudioRecorder) -> Option<std::thread::JoinHandle<()>> {
+    let stream = recorder.stream.lock().unwrap().take();
+    discard_recording(recorder);
+    *recorder.last_take.lock().unwrap() = None;
+
+    let helper = stream?.downcast::<helper::HelperStream>().ok()?;
+    Some(std::thread::spawn(move || helper.stop_and_wait()))
+}
+
+/// The last stopped recording as 16 kHz mono
+pub fn last_take_speech_samples(recorder: &AudioRecorder) -> Result<Vec<f32>, String> {
+    let last_take = recorder.last_take.lock().unwrap();
+    let take = last_take.as_ref().ok_or("No recording to transcribe")?;
+
+    Ok(to_speech_samples(take.samples.clone(), take.format))
+}
+
+/// The last stopped recording as a 16 kHz mono, 16-bit WAV file, the
+/// smallest upload transcription APIs accept without quality loss
+pub fn last_take_speech_wav(recorder: &AudioRecorder) -> Result<Vec<u8>, String> {
+    encode_speech_wav(&last_take_speech_samples(recorder)?)
+}
+
+/// Everything the input callback feeds: the monitor gets raw audio, the
+/// sample buffer, chunk streamer and live audio tap get the pipeline's output
+struct CaptureTarget {
+    samples: Arc<Mutex<Vec<f32>>>,
+    monitor: MonitorTap,
+    pipeline: Pipeline,
+    chunks: Option<ChunkStreamer>,
+    live_audio: LiveAudioTap,
+}
+
+impl CaptureTarget {
+    fn new(app: &AppHandle, recorder: &AudioRecorder, input_format: AudioFormat) -> Self {
+        let recording_config = recorder.config.lock().unwrap().clone();
+        let pipeline = Pipeline::new(&recording_config, input_format);
+        let output_format = pipeline.output_format();
+        let chunks = recording_config.stream_chunks.map(|chunk_config| {
+            ChunkStreamer::spawn(
+                crate::events::app_events(app),
+                chunk_config,
+                output_format,
+                Arc::clone(&recorder.chunk_sequence),
+            )
+        });
+
+        // Clone Arc references for the audio callback thread
+        Self {
+            samples: Arc::clone(&recorder.samples),
+            monitor: recorder.monitor.clone(),
+            pipeline,
+            chunks,
+            live_audio: Arc::clone(&recorder.live_audio),
+        }
+    }
+
+    /// Process a block of raw interleaved input
+    fn feed(&mut self, chunk: Vec<f32>, channels: usize) {
+        self.monitor.push(&chunk, channels);
+        let processed = self.pipeline.process(chunk);
+        if let Some(chunks) = &self.chunks {
+            chunks.push(&processed);
+        }
+        if let Ok(live_audio) = self.live_audio.lock() {
+            if let Some(sender) = live_audio.as_ref() {
+                let _ = sender.send(processed.clone());
+            }
+        }
+        if let Ok(mut samples) = self.samples.lock() {
+            samples.extend(processed);
+        }
+    }
+}
+
 /// Build an input stream for a specific sample format
 fn build_input_stream<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
-    samples_arc: Arc<Mutex<Vec<f32>>>,
+    mut capture: CaptureTarget,
+    err_fn: impl FnMut(cpal::StreamError) + Send + 'static,
 ) -> Result<cpal::Stream, cpal::BuildStreamError>
 where
     T: cpal::Sample + cpal::SizedSample,
     f32: cpal::FromSample<T>,
 {
-    let err_fn = |err| eprintln!("An error occurred on the audio stream: {}", err);
+    let channels = config.channels as usize;
 
     device.build_input_stream(
         config,
         move |data: &[T], _: &cpal::InputCallbackInfo| {
-            let chunk: Vec<f32> = data.iter().map(|&s| s.to_sample()).collect();
-            if let Ok(mut samples) = samples_arc.lock() {
-                samples.extend(chunk);
-            }
+            capture.feed(data.iter().map(|&s| s.to_sample()).collect(), channels);
         },
         err_fn,
         None,
     )
 }
 
-/// Convert f32 samples to WAV format (16-bit PCM)
-fn samples_to_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, hound::Error> {
-    let mut cursor = std::io::Cursor::new(Vec::new());
-
-    {
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-
-        let mut writer = hound::WavWriter::new(&mut cursor, spec)?;
-
-        for &sample in samples {
-            let amplitude = (sample * i16::MAX as f32) as i16;
-            writer.write_sample(amplitude)?;
-        }
-
-        writer.finalize()?;
-    }
+/// Build an output stream that plays back the monitor buffer
+fn build_monitor_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    monitor: MonitorTap,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::Sample + cpal::SizedSample + cpal::FromSample<f32>,
+{
+    let err_fn = |err| eprintln!("An error occurred on the monitor stream: {}", err);
+    let channels = config.channels as usize;
 
-    Ok(cursor.into_inner())
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut buffer = monitor.buffer.lock().ok();
+            for frame in data.chunks_mut(channels) {
+                // Output silence when the input hasn't caught up yet
+                let value = buffer
+                    .as_mut()
+                    .and_then(|buffer| buffer.pop_front())
+                    .unwrap_or(0.0);
+                for sample in frame.iter_mut() {
+                    *sample = T::from_sample(value);
+                }
+            }
+        },
+        err_fn,
+        None,
+    )
 }
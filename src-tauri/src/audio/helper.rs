@@ -0,0 +1,818 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+use super::RecordingConfig;
+
+/// First argument that makes the app binary run as the capture helper
+const HELPER_ARG: &str = "--capture-helper";
+/// Prefix of the folder each helper keeps its files in, inside the capture
+/// directory
+const SESSION_DIR_PREFIX: &str = "session-";
+/// Written by the helper: how to reach it and what it captures
+const STATE_FILE_NAME: &str = "helper.json";
+/// Written by the app: how the helper's samples are processed
+const CONFIG_FILE_NAME: &str = "recording.json";
+/// Raw interleaved f32 samples captured so far
+const SPOOL_FILE_NAME: &str = "spool.f32";
+/// How long the helper keeps recording while no app is attached
+const ORPHAN_TIMEOUT_SECS: u64 = 10 * 60;
+const CONNECT_TIMEOUT_MS: u64 = 1000;
+/// How long a new helper may take to open the device and report its state
+const HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+/// How long a stopped helper may take to exit before it is killed
+const STOP_TIMEOUT_SECS: u64 = 5;
+/// Upper bound for one frame, to reject garbage from a stale port
+const MAX_FRAME_SAMPLES: usize = 1 << 20;
+/// Samples per frame when replaying the spool file
+const REPLAY_FRAME_SAMPLES: usize = 64 * 1024;
+
+/// Everything needed to (re-)attach to a running helper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelperState {
+    pub pid: u32,
+    pub port: u16,
+    pub token: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub device_name: Option<String>,
+}
+
+/// Directory the helpers' session folders are in
+pub fn capture_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    use tauri::Manager;
+
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("capture");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create capture directory: {}", e))?;
+
+    Ok(dir)
+}
+
+/// Create the folder for a new helper's state, config and spool. Every
+/// helper gets its own, so one that is still shutting down never touches
+/// the files of the next. Names sort by creation time.
+pub fn new_session_dir(capture_dir: &Path) -> Result<PathBuf, String> {
+    let created_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let dir = capture_dir.join(format!(
+        "{}{:016}-{:08x}",
+        SESSION_DIR_PREFIX,
+        created_ms,
+        rand::random::<u32>()
+    ));
+
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(&dir)
+        .map_err(|e| format!("Failed to create capture session: {}", e))?;
+
+    Ok(dir)
+}
+
+/// The newest session whose helper was running when the app last exited,
+/// with its state
+pub fn find_session(capture_dir: &Path) -> Option<(PathBuf, HelperState)> {
+    let mut sessions: Vec<PathBuf> = fs::read_dir(capture_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(SESSION_DIR_PREFIX)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    sessions.sort();
+
+    sessions
+        .into_iter()
+        .rev()
+        .find_map(|dir| load_state(&dir).map(|state| (dir, state)))
+}
+
+/// Remove the folder of a finished helper with its state and spool
+pub fn remove_session(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+}
+
+/// Create or truncate a file only the current user can read. The state file
+/// holds the token that lets a connection receive the recording, the spool
+/// the recording itself.
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let file = options.open(path)?;
+    // `mode` only applies to new files
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+
+    Ok(file)
+}
+
+fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    create_private(path)?.write_all(data)
+}
+
+/// Write a frame: sample count as u32 LE, then the samples as f32 LE
+fn write_frame(writer: &mut impl Write, samples: &[f32]) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(4 + samples.len() * 4);
+    bytes.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    writer.write_all(&bytes)
+}
+
+fn read_frame(reader: &mut impl Read) -> io::Result<Vec<f32>> {
+    let mut count = [0u8; 4];
+    reader.read_exact(&mut count)?;
+    let count = u32::from_le_bytes(count) as usize;
+    if count > MAX_FRAME_SAMPLES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Frame too large",
+        ));
+    }
+
+    let mut bytes = vec![0u8; count * 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(decode_samples(&bytes))
+}
+
+fn decode_samples(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn generate_token() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Connection to a capture helper; dropping it ends the recording
+pub struct HelperStream {
+    writer: TcpStream,
+    stopped: Arc<AtomicBool>,
+    child: Option<Child>,
+}
+
+impl HelperStream {
+    /// End the recording and wait until a helper started by this app has
+    /// exited, e.g. before its files are deleted. One that does not exit in
+    /// time is killed.
+    pub fn stop_and_wait(mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let _ = self.writer.write_all(b"stop\n");
+
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        let deadline = Instant::now() + Duration::from_secs(STOP_TIMEOUT_SECS);
+        while matches!(child.try_wait(), Ok(None)) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl Drop for HelperStream {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let _ = self.writer.write_all(b"stop\n");
+
+        // Reap the helper once it has cleaned up
+        if let Some(mut child) = self.child.take() {
+            std::thread::spawn(move || child.wait());
+        }
+    }
+}
+
+/// Save the recording config so samples can be processed the same way after
+/// re-attaching
+pub fn save_config(dir: &Path, config: &RecordingConfig) -> Result<(), String> {
+    let content = serde_json::to_string(config)
+        .map_err(|e| format!("Failed to serialize recording config: {}", e))?;
+    write_private(&dir.join(CONFIG_FILE_NAME), content.as_bytes())
+        .map_err(|e| format!("Failed to save recording config: {}", e))
+}
+
+pub fn load_config(dir: &Path) -> Option<RecordingConfig> {
+    let content = fs::read_to_string(dir.join(CONFIG_FILE_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// State of the helper of the session in `dir`, if it started recording
+fn load_state(dir: &Path) -> Option<HelperState> {
+    let content = fs::read_to_string(dir.join(STATE_FILE_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Samples left behind by a helper that exited without being stopped
+pub fn read_spool(dir: &Path) -> Result<Vec<f32>, String> {
+    let bytes = fs::read(dir.join(SPOOL_FILE_NAME))
+        .map_err(|e| format!("Failed to read capture spool: {}", e))?;
+    Ok(decode_samples(&bytes))
+}
+
+/// Start a helper for the session in `dir` capturing from `device_name` (or
+/// the default input) and wait until it is recording
+pub fn spawn(dir: &Path, device_name: Option<&str>) -> Result<(HelperState, Child), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate the app executable: {}", e))?;
+
+    let mut command = Command::new(exe);
+    command
+        .arg(HELPER_ARG)
+        .arg("--dir")
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+    if let Some(name) = device_name {
+        command.arg("--device").arg(name);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start capture helper: {}", e))?;
+
+    // The helper prints its state once the stream is running, or exits. One
+    // stuck on the device or a permission prompt is given up on.
+    let stdout = child.stdout.take().ok_or("Capture helper has no output")?;
+    let state =
+        read_handshake(stdout, Duration::from_secs(HANDSHAKE_TIMEOUT_SECS)).and_then(|line| {
+            serde_json::from_str(&line)
+                .map_err(|_| "Capture helper failed to start recording".to_string())
+        });
+
+    match state {
+        Ok(state) => Ok((state, child)),
+        Err(e) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(e)
+        }
+    }
+}
+
+/// The first line of `output`, unless it takes longer than `timeout`. The
+/// reading thread ends once the output is closed.
+fn read_handshake(output: impl Read + Send + 'static, timeout: Duration) -> Result<String, String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let result = BufReader::new(output).read_line(&mut line).map(|_| line);
+        let _ = sender.send(result);
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(Ok(line)) => Ok(line),
+        Ok(Err(e)) => Err(format!("Failed to read from capture helper: {}", e)),
+        Err(_) => Err("Capture helper did not start recording in time".to_string()),
+    }
+}
+
+/// Connect and authenticate to a running helper
+pub fn connect(state: &HelperState) -> Result<TcpStream, String> {
+    let address = SocketAddr::from(([127, 0, 0, 1], state.port));
+    let mut stream =
+        TcpStream::connect_timeout(&address, Duration::from_millis(CONNECT_TIMEOUT_MS))
+            .map_err(|e| format!("Failed to connect to capture helper: {}", e))?;
+
+    let mut reply = [0u8; 3];
+    stream
+        .set_read_timeout(Some(Duration::from_millis(CONNECT_TIMEOUT_MS)))
+        .and_then(|_| stream.write_all(format!("{}\n", state.token).as_bytes()))
+        .and_then(|_| stream.read_exact(&mut reply))
+        .and_then(|_| stream.set_read_timeout(None))
+        .map_err(|e| format!("Capture helper did not respond: {}", e))?;
+    if &reply != b"ok\n" {
+        return Err("Capture helper rejected the connection".to_string());
+    }
+
+    Ok(stream)
+}
+
+/// Feed every block the helper sends, starting with a replay of everything
+/// captured so far. `on_lost` is called if the helper goes away before the
+/// returned stream is dropped.
+pub fn start_reader(
+    connection: TcpStream,
+    child: Option<Child>,
+    mut feed: impl FnMut(Vec<f32>) + Send + 'static,
+    on_lost: impl FnOnce() + Send + 'static,
+) -> Result<HelperStream, String> {
+    let writer = connection
+        .try_clone()
+        .map_err(|e| format!("Failed to set up capture helper connection: {}", e))?;
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let reader_stopped = Arc::clone(&stopped);
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(connection);
+        while let Ok(block) = read_frame(&mut reader) {
+            // Late blocks must not leak into the next recording
+            if reader_stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            feed(block);
+        }
+        if !reader_stopped.load(Ordering::SeqCst) {
+            on_lost();
+        }
+    });
+
+    Ok(HelperStream {
+        writer,
+        stopped,
+        child,
+    })
+}
+
+/// Run as the capture helper if the process was started as one, returning
+/// its exit code
+pub fn run_if_requested() -> Option<i32> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) != Some(HELPER_ARG) {
+        return None;
+    }
+
+    let value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+    let Some(dir) = value("--dir") else {
+        eprintln!("Capture helper needs --dir");
+        return Some(2);
+    };
+
+    Some(match run(Path::new(&dir), value("--device").as_deref()) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Capture helper failed: {}", e);
+            1
+        }
+    })
+}
+
+enum HelperEvent {
+    Stop,
+    Failed(String),
+}
+
+/// The attached app, numbered so a closed connection can tell whether it is
+/// still the current one
+type Client = Arc<Mutex<Option<(u64, TcpStream)>>>;
+
+fn run(dir: &Path, device_name: Option<&str>) -> Result<(), String> {
+    let host = cpal::default_host();
+    let device = device_name
+        .and_then(|name| {
+            host.input_devices()
+                .ok()?
+                .find(|device| device.name().ok().as_deref() == Some(name))
+        })
+        .or_else(|| host.default_input_device())
+        .ok_or("No input device available")?;
+    let config = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get input config: {}", e))?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to open capture socket: {}", e))?;
+    let state = HelperState {
+        pid: std::process::id(),
+        port: listener
+            .local_addr()
+            .map_err(|e| format!("Failed to open capture socket: {}", e))?
+            .port(),
+        token: generate_token(),
+        sample_rate: config.sample_rate().0,
+        channels: config.channels(),
+        device_name: device.name().ok(),
+    };
+
+    let spool_path = dir.join(SPOOL_FILE_NAME);
+    let mut spool =
+        create_private(&spool_path).map_err(|e| format!("Failed to create spool: {}", e))?;
+
+    let (events, event_receiver) = mpsc::channel();
+    let (blocks, block_receiver) = mpsc::channel::<Vec<f32>>();
+    let stream_events = events.clone();
+    let on_error = move |err: cpal::StreamError| {
+        let _ = stream_events.send(HelperEvent::Failed(err.to_string()));
+    };
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config.into(), blocks, on_error),
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config.into(), blocks, on_error),
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config.into(), blocks, on_error),
+        _ => return Err("Unsupported sample format".to_string()),
+    }
+    .map_err(|e| format!("Failed to build input stream: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to play stream: {}", e))?;
+
+    let content = serde_json::to_string(&state)
+        .map_err(|e| format!("Failed to serialize helper state: {}", e))?;
+    write_private(&dir.join(STATE_FILE_NAME), content.as_bytes())
+        .map_err(|e| format!("Failed to save helper state: {}", e))?;
+
+    // Tell the app we're recording
+    let mut stdout = io::stdout();
+    writeln!(stdout, "{}", content)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("Failed to report helper state: {}", e))?;
+
+    let client: Client = Arc::default();
+    let detached_since = Arc::new(Mutex::new(Some(Instant::now())));
+
+    // Spool every block and forward it to the app. Both happen under the
+    // client lock so a replay never misses or repeats a block.
+    let writer_client = Arc::clone(&client);
+    let writer_detached = Arc::clone(&detached_since);
+    let writer_events = events.clone();
+    let writer = std::thread::spawn(move || {
+        for block in block_receiver {
+            let mut client = writer_client.lock().unwrap();
+            let bytes: Vec<u8> = block.iter().flat_map(|s| s.to_le_bytes()).collect();
+            if let Err(e) = spool.write_all(&bytes) {
+                let _ = writer_events.send(HelperEvent::Failed(format!("Spool failed: {}", e)));
+                return;
+            }
+            if let Some((_, connection)) = client.as_mut() {
+                if write_frame(connection, &block).is_err() {
+                    *client = None;
+                    *writer_detached.lock().unwrap() = Some(Instant::now());
+                }
+            }
+        }
+    });
+
+    let accept_client = Arc::clone(&client);
+    let accept_detached = Arc::clone(&detached_since);
+    std::thread::spawn(move || {
+        let next_id = AtomicU64::new(0);
+        for connection in listener.incoming().flatten() {
+            let id = next_id.fetch_add(1, Ordering::Relaxed);
+            let attached = attach(
+                connection,
+                id,
+                &state.token,
+                &spool_path,
+                &accept_client,
+                &accept_detached,
+                events.clone(),
+            );
+            if let Err(e) = attached {
+                eprintln!("Capture helper refused a connection: {}", e);
+            }
+        }
+    });
+
+    // Watchdog: end the recording once no app has been attached for too long
+    let result = loop {
+        match event_receiver.recv_timeout(Duration::from_secs(1)) {
+            Ok(HelperEvent::Stop) => break Ok(()),
+            Ok(HelperEvent::Failed(e)) => break Err(e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let orphaned = detached_since.lock().unwrap().is_some_and(|since| {
+                    since.elapsed() >= Duration::from_secs(ORPHAN_TIMEOUT_SECS)
+                });
+                if orphaned {
+                    break Err("No app attached, recording stopped".to_string());
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break Ok(()),
+        }
+    };
+
+    // Flush the last blocks to the spool and the app
+    drop(stream);
+    let _ = writer.join();
+    if let Some((_, connection)) = client.lock().unwrap().take() {
+        let _ = connection.shutdown(std::net::Shutdown::Both);
+    }
+
+    // Keep the spool after a failure so the app can recover the audio
+    if result.is_ok() {
+        remove_session(dir);
+    }
+
+    result
+}
+
+/// Authenticate a connection, replay the spool to it and make it the client
+fn attach(
+    mut connection: TcpStream,
+    id: u64,
+    token: &str,
+    spool_path: &Path,
+    client: &Client,
+    detached_since: &Arc<Mutex<Option<Instant>>>,
+    events: mpsc::Sender<HelperEvent>,
+) -> io::Result<()> {
+    connection.set_read_timeout(Some(Duration::from_millis(CONNECT_TIMEOUT_MS)))?;
+    let mut reader = BufReader::new(connection.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != token {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Wrong token",
+        ));
+    }
+    connection.set_read_timeout(None)?;
+
+    {
+        let mut client = client.lock().unwrap();
+        connection.write_all(b"ok\n")?;
+
+        // The spool only holds whole blocks while the client lock is held
+        let mut spool = File::open(spool_path)?;
+        let mut bytes = Vec::with_capacity(REPLAY_FRAME_SAMPLES * 4);
+        loop {
+            bytes.clear();
+            (&mut spool)
+                .take(REPLAY_FRAME_SAMPLES as u64 * 4)
+                .read_to_end(&mut bytes)?;
+            if bytes.is_empty() {
+                break;
+            }
+            write_frame(&mut connection, &decode_samples(&bytes))?;
+        }
+
+        // A new app replaces one that may still be attached
+        *client = Some((id, connection));
+        *detached_since.lock().unwrap() = None;
+    }
+
+    // Listen for commands until the app goes away
+    let client = Arc::clone(client);
+    let detached_since = Arc::clone(detached_since);
+    std::thread::spawn(move || {
+        for line in reader.lines() {
+            match line.as_deref().map(str::trim) {
+                Ok("stop") => {
+                    let _ = events.send(HelperEvent::Stop);
+                    return;
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+
+        let mut client = client.lock().unwrap();
+        if client.as_ref().is_some_and(|(current, _)| *current == id) {
+            *client = None;
+            *detached_since.lock().unwrap() = Some(Instant::now());
+        }
+    });
+
+    Ok(())
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    blocks: mpsc::Sender<Vec<f32>>,
+    err_fn: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::Sample + cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let _ = blocks.send(data.iter().map(|&s| s.to_sample()).collect());
+        },
+        err_fn,
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, &[0.5, -1.0, 0.25]).unwrap();
+        write_frame(&mut bytes, &[]).unwrap();
+        assert_eq!(bytes.len(), 4 + 12 + 4);
+
+        let mut reader = io::Cursor::new(bytes);
+        assert_eq!(read_frame(&mut reader).unwrap(), vec![0.5, -1.0, 0.25]);
+        assert!(read_frame(&mut reader).unwrap().is_empty());
+        assert!(read_frame(&mut reader).is_err());
+
+        let mut reader = io::Cursor::new(u32::MAX.to_le_bytes().to_vec());
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_sessions() {
+        let capture_dir =
+            std::env::temp_dir().join(format!("capture-test-{}", rand::random::<u64>()));
+        fs::create_dir_all(&capture_dir).unwrap();
+        assert!(find_session(&capture_dir).is_none());
+
+        let state = |port| HelperState {
+            pid: 0,
+            port,
+            token: generate_token(),
+            sample_rate: 48000,
+            channels: 2,
+            device_name: None,
+        };
+        let write_state = |dir: &Path, state: &HelperState| {
+            let content = serde_json::to_vec(state).unwrap();
+            write_private(&dir.join(STATE_FILE_NAME), &content).unwrap();
+        };
+
+        let old = new_session_dir(&capture_dir).unwrap();
+        write_state(&old, &state(1));
+        std::thread::sleep(Duration::from_millis(2));
+        let new = new_session_dir(&capture_dir).unwrap();
+        write_state(&new, &state(2));
+        // A helper that never started recording
+        std::thread::sleep(Duration::from_millis(2));
+        let failed = new_session_dir(&capture_dir).unwrap();
+        assert_ne!(failed, new);
+
+        let (dir, found) = find_session(&capture_dir).unwrap();
+        assert_eq!((dir.as_path(), found.port), (new.as_path(), 2));
+
+        // The old helper cleaning up leaves the new session alone
+        remove_session(&old);
+        assert_eq!(find_session(&capture_dir).unwrap().0, new);
+        remove_session(&new);
+        assert!(find_session(&capture_dir).is_none());
+
+        fs::remove_dir_all(&capture_dir).unwrap();
+    }
+
+    #[test]
+    fn test_private_files() {
+        let path = std::env::temp_dir().join(format!("helper-test-{}.json", rand::random::<u64>()));
+        fs::write(&path, "stale").unwrap();
+        #[cfg(unix)]
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, b"{}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        #[cfg(unix)]
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_handshake_timeout() {
+        let line = read_handshake(
+            io::Cursor::new(b"{}\nrest".to_vec()),
+            Duration::from_secs(1),
+        );
+        assert_eq!(line.unwrap(), "{}\n");
+
+        // A helper that never reports its state
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let silent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        assert!(read_handshake(silent, Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_attach_replay_and_stop() {
+        let spool_path =
+            std::env::temp_dir().join(format!("spool-test-{}.f32", rand::random::<u64>()));
+        let spooled: Vec<u8> = [0.1f32, 0.2, 0.3]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        fs::write(&spool_path, spooled).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let state = HelperState {
+            pid: 0,
+            port: listener.local_addr().unwrap().port(),
+            token: generate_token(),
+            sample_rate: 16000,
+            channels: 1,
+            device_name: None,
+        };
+        let client: Client = Arc::default();
+        let detached_since = Arc::new(Mutex::new(Some(Instant::now())));
+        let (events, event_receiver) = mpsc::channel();
+        {
+            let (token, spool_path) = (state.token.clone(), spool_path.clone());
+            let (client, detached_since) = (Arc::clone(&client), Arc::clone(&detached_since));
+            std::thread::spawn(move || {
+                for (id, connection) in listener.incoming().flatten().enumerate() {
+                    let _ = attach(
+                        connection,
+                        id as u64,
+                        &token,
+                        &spool_path,
+                        &client,
+                        &detached_since,
+                        events.clone(),
+                    );
+                }
+            });
+        }
+        let timeout = Duration::from_secs(2);
+
+        let wrong = HelperState {
+            token: generate_token(),
+            ..state.clone()
+        };
+        assert!(connect(&wrong).is_err());
+
+        // Attaching replays the spool, then forwards new blocks
+        let (blocks, received) = mpsc::channel();
+        let first = start_reader(
+            connect(&state).unwrap(),
+            None,
+            move |block| {
+                let _ = blocks.send(block);
+            },
+            || {},
+        )
+        .unwrap();
+        assert_eq!(received.recv_timeout(timeout).unwrap(), vec![0.1, 0.2, 0.3]);
+        assert!(detached_since.lock().unwrap().is_none());
+        if let Some((_, connection)) = client.lock().unwrap().as_mut() {
+            write_frame(connection, &[0.4]).unwrap();
+        }
+        assert_eq!(received.recv_timeout(timeout).unwrap(), vec![0.4]);
+
+        // Re-attaching, as a restarted app does, replays again
+        let (blocks, received) = mpsc::channel();
+        let second = start_reader(
+            connect(&state).unwrap(),
+            None,
+            move |block| {
+                let _ = blocks.send(block);
+            },
+            || {},
+        )
+        .unwrap();
+        assert_eq!(received.recv_timeout(timeout).unwrap(), vec![0.1, 0.2, 0.3]);
+        assert_eq!(client.lock().unwrap().as_ref().map(|(id, _)| *id), Some(2));
+
+        drop(second);
+        assert!(matches!(
+            event_receiver.recv_timeout(timeout),
+            Ok(HelperEvent::Stop)
+        ));
+
+        drop(first);
+        fs::remove_file(&spool_path).unwrap();
+    }
+}
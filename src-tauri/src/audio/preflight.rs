@@ -0,0 +1,328 @@
+use cpal::traits::DeviceTrait;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::pipeline::{Pipeline, WavEncoding};
+use super::{capture_briefly, devices, AudioFormat, RecordingConfig};
+use crate::health::{self, HealthItem, HealthStatus};
+use crate::settings;
+
+/// How long the device is listened to for a permission check
+const PROBE_DURATION_MS: u64 = 300;
+/// Resampling targets the pipeline handles
+const MIN_TARGET_SAMPLE_RATE: u32 = 8000;
+const MAX_TARGET_SAMPLE_RATE: u32 = 192_000;
+
+/// What `preflight_recording` checks; everything is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PreflightOptions {
+    /// Device to check instead of the preferred one
+    device_id: Option<String>,
+    /// Processing the recording will be started with
+    config: Option<RecordingConfig>,
+    /// Expected length of the recording, to check there is room to save it
+    expected_minutes: Option<u32>,
+}
+
+/// The device a recording would use
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightDevice {
+    id: String,
+    name: String,
+    alias: Option<String>,
+    sample_rate: u32,
+    channels: u16,
+    /// Sample format the device delivers, e.g. "f32" or "i16"
+    sample_format: String,
+}
+
+/// Result of `preflight_recording`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    /// Whether `start_recording` is expected to succeed; warnings do not
+    /// prevent it
+    ready: bool,
+    /// Worst status of all items
+    status: HealthStatus,
+    device: Option<PreflightDevice>,
+    /// "device", "format", "permission" and "diskSpace"
+    items: Vec<HealthItem>,
+}
+
+/// The device `start_recording` would pick, without emitting
+/// `input-device-fallback`
+fn resolve_device(
+    app: &AppHandle,
+    host: &cpal::Host,
+    device_id: Option<String>,
+) -> Result<(Option<devices::InputDevice>, HealthItem), String> {
+    let current = settings::load_settings(app)?;
+    let display_name = |id: &str| {
+        current
+            .input_device_aliases
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    };
+    let action = "Connect a microphone or choose another input device";
+
+    let explicit = device_id.is_some();
+    let mut fallback = None;
+    if let Some(id) = device_id.or(current.preferred_input_device.clone()) {
+        if let Some(found) = devices::find(host, &id) {
+            let message = format!("Input device \"{}\" is available", found.name);
+            return Ok((
+                Some(found),
+                HealthItem::new("device", Ok(message), HealthStatus::Ok, ""),
+            ));
+        }
+
+        let message = format!("Input device \"{}\" is not connected", display_name(&id));
+        if explicit {
+            let item = HealthItem::new("device", Err(message), HealthStatus::Error, action);
+            return Ok((None, item));
+        }
+        fallback = Some(message);
+    }
+
+    let list = devices::input_devices(host)?;
+    let default = devices::default_id(host, &list)
+        .and_then(|id| list.into_iter().find(|device| device.id == id));
+    let item = match (&default, fallback) {
+        (None, _) => HealthItem::new(
+            "device",
+            Err("No input device found".to_string()),
+            HealthStatus::Error,
+            action,
+        ),
+        (Some(_), Some(message)) => HealthItem::new(
+            "device",
+            Err(format!("{}; the default device will be used", message)),
+            HealthStatus::Warning,
+            "Reconnect the preferred input device or choose another one",
+        ),
+        (Some(device), None) => HealthItem::new(
+            "device",
+            Ok(format!(
+                "Default input device \"{}\" is available",
+                device.name
+            )),
+            HealthStatus::Ok,
+            "",
+        ),
+    };
+    Ok((default, item))
+}
+
+/// Check the device's format and that `config` can process it. Returns the
+/// format of the recorded audio.
+fn check_format(
+    device: &devices::InputDevice,
+    config: &RecordingConfig,
+) -> (
+    Option<(cpal::SupportedStreamConfig, AudioFormat)>,
+    HealthItem,
+) {
+    let action = "Choose another input device or recording format";
+    let failed = |message: String| {
+        let item = HealthItem::new("format", Err(message), HealthStatus::Error, action);
+        (None, item)
+    };
+
+    let supported = match device.device.default_input_config() {
+        Ok(supported) => supported,
+        Err(e) => return failed(format!("The input device cannot be used: {}", e)),
+    };
+    if !matches!(
+        supported.sample_format(),
+        cpal::SampleFormat::F32 | cpal::SampleFormat::I16 | cpal::SampleFormat::U16
+    ) {
+        return failed(format!(
+            "Unsupported sample format {}",
+            supported.sample_format()
+        ));
+    }
+    if let Some(rate) = config.target_sample_rate {
+        if !(MIN_TARGET_SAMPLE_RATE..=MAX_TARGET_SAMPLE_RATE).contains(&rate) {
+            return failed(format!("Cannot resample to {} Hz", rate));
+        }
+    }
+
+    let input_format = AudioFormat {
+        sample_rate: supported.sample_rate().0,
+        channels: supported.channels(),
+    };
+    let output_format = Pipeline::new(config, input_format).output_format();
+    let message = format!(
+        "{} Hz / {} ch {}, recorded as {} Hz / {} ch",
+        input_format.sample_rate,
+        input_format.channels,
+        supported.sample_format(),
+        output_format.sample_rate,
+        output_format.channels
+    );
+    let item = HealthItem::new("format", Ok(message), HealthStatus::Ok, "");
+    (Some((supported, output_format)), item)
+}
+
+/// Listen to the device briefly. Opening a stream is what prompts for
+/// microphone access, and a denied permission shows up as no or silent audio
+/// rather than an error on most systems.
+fn check_permission(device: &cpal::Device) -> HealthItem {
+    let action = "Allow the app to use the microphone in the system settings";
+    let result = capture_briefly(device, PROBE_DURATION_MS);
+    match result {
+        Err(e) => HealthItem::new(
+            "permission",
+            Err(format!("The input device could not be opened: {}", e)),
+            HealthStatus::Error,
+            action,
+        ),
+        Ok(samples) if samples.is_empty() => HealthItem::new(
+            "permission",
+            Err("No audio received; the app may not be allowed to use the microphone".to_string()),
+            HealthStatus::Error,
+            action,
+        ),
+        Ok(samples) if samples.iter().all(|&sample| sample == 0.0) => HealthItem::new(
+            "permission",
+            Err("The input device only delivers silence; it may be muted or blocked".to_string()),
+            HealthStatus::Warning,
+            "Unmute the microphone and check that the app may use it",
+        ),
+        Ok(_) => HealthItem::new(
+            "permission",
+            Ok("Audio is coming in".to_string()),
+            HealthStatus::Ok,
+            "",
+        ),
+    }
+}
+
+/// Bytes a minute of recording takes once saved
+fn bytes_per_minute(format: AudioFormat, encoding: WavEncoding) -> u64 {
+    let sample_bytes = match encoding {
+        WavEncoding::Pcm16 => 2,
+        WavEncoding::Float32 => 4,
+    };
+    format.sample_rate as u64 * format.channels as u64 * sample_bytes * 60
+}
+
+fn check_disk_space(
+    app: &AppHandle,
+    output: Option<(AudioFormat, WavEncoding)>,
+    expected_minutes: Option<u32>,
+) -> HealthItem {
+    let action = "Free up disk space so the recording can be saved";
+    let available = match health::app_data_space(app) {
+        Ok(available) => available,
+        Err(e) => {
+            return HealthItem::new(
+                "diskSpace",
+                Err(format!("Failed to check free disk space: {}", e)),
+                HealthStatus::Warning,
+                action,
+            )
+        }
+    };
+
+    let per_minute = output.map(|(format, encoding)| bytes_per_minute(format, encoding));
+    let mut message = format!("{:.1} GB free", available as f64 / 1e9);
+    if let Some(per_minute) = per_minute.filter(|&bytes| bytes > 0) {
+        message.push_str(&format!(
+            ", room for about {} minutes of recording",
+            available / per_minute
+        ));
+    }
+
+    let needed = per_minute.zip(expected_minutes).map(|(b, m)| b * m as u64);
+    let status = match needed {
+        Some(needed) if needed > available => HealthStatus::Error,
+        _ => health::disk_space_status(available),
+    };
+    HealthItem {
+        id: "diskSpace".to_string(),
+        status,
+        message,
+        action: (status != HealthStatus::Ok).then(|| action.to_string()),
+    }
+}
+
+/// Check in one call what `start_recording` needs: the selected (or
+/// preferred) device, a format the pipeline can record, microphone access
+/// and free disk space. Takes a fraction of a second, as it listens to the
+/// device briefly; the report lists what to fix before the user starts talking.
+#[tauri::command]
+pub async fn preflight_recording(
+    app: AppHandle,
+    options: Option<PreflightOptions>,
+) -> Result<PreflightReport, String> {
+    let options = options.unwrap_or_default();
+    tokio::task::spawn_blocking(move || {
+        let config = options.config.unwrap_or_default();
+        let host = cpal::default_host();
+        let (device, device_item) = resolve_device(&app, &host, options.device_id)?;
+        let mut items = vec![device_item];
+
+        let mut output = None;
+        let mut report_device = None;
+        if let Some(device) = &device {
+            let (format, format_item) = check_format(device, &config);
+            items.push(format_item);
+
+            if let Some((supported, output_format)) = format {
+                output = Some((output_format, config.encoding));
+                items.push(check_permission(&device.device));
+                report_device = Some(PreflightDevice {
+                    alias: settings::load_settings(&app)?
+                        .input_device_aliases
+                        .get(&device.id)
+                        .cloned(),
+                    id: device.id.clone(),
+                    name: device.name.clone(),
+                    sample_rate: supported.sample_rate().0,
+                    channels: supported.channels(),
+                    sample_format: supported.sample_format().to_string(),
+                });
+            }
+        }
+        items.push(check_disk_space(&app, output, options.expected_minutes));
+
+        let status = items
+            .iter()
+            .map(|item| item.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok);
+        Ok(PreflightReport {
+            ready: status != HealthStatus::Error,
+            status,
+            device: report_device,
+            items,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes_per_minute() {
+        let speech = AudioFormat {
+            sample_rate: 16000,
+            channels: 1,
+        };
+        assert_eq!(bytes_per_minute(speech, WavEncoding::Pcm16), 1_920_000);
+
+        let stereo = AudioFormat {
+            sample_rate: 48000,
+            channels: 2,
+        };
+        assert_eq!(bytes_per_minute(stereo, WavEncoding::Float32), 23_040_000);
+    }
+}
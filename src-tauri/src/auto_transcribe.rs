@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::audio::AudioRecorder;
+use crate::transcription::{self, provider, provider::ProviderRequest, Source};
+use crate::{settings, transcript};
+
+/// What to do with the transcript once it is ready
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputAction {
+    /// Only emit `auto-transcription-completed`
+    #[default]
+    Event,
+    /// Also copy it to the clipboard, as `copy_transcript` does
+    Copy,
+}
+
+/// Transcribe every recording when it stops:
+/// record → transcribe → format → output
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoTranscribeSettings {
+    pub enabled: bool,
+    /// Engine and options, as for `submit_transcription`
+    pub provider: Option<ProviderRequest>,
+    /// Write out spoken URLs, emails and code sections; see `format_transcript`
+    pub format: bool,
+    pub output: OutputAction,
+}
+
+/// Payload of `auto-transcription-started`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoTranscriptionStarted {
+    job_id: String,
+}
+
+/// Payload of `auto-transcription-completed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoTranscriptionCompleted {
+    job_id: String,
+    /// Transcript after formatting
+    text: String,
+    output: OutputAction,
+}
+
+/// Payload of `auto-transcription-failed`, for failures outside the
+/// transcription job itself, which end with `transcription-failed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoTranscriptionFailed {
+    job_id: Option<String>,
+    error: String,
+}
+
+/// Format the transcript and carry out the output action
+async fn finish(
+    app: AppHandle,
+    settings: AutoTranscribeSettings,
+    job_id: String,
+    text: String,
+) -> Result<(), String> {
+    let text = match settings.format {
+        true => transcript::format_text(&text),
+        false => text,
+    };
+    if settings.output == OutputAction::Copy {
+        transcript::copy_transcript(text.clone()).await?;
+    }
+
+    let _ = app.emit(
+        "auto-transcription-completed",
+        AutoTranscriptionCompleted {
+            job_id,
+            text,
+            output: settings.output,
+        },
+    );
+    Ok(())
+}
+
+fn emit_failed(app: &AppHandle, job_id: Option<String>, error: String) {
+    let _ = app.emit(
+        "auto-transcription-failed",
+        AutoTranscriptionFailed { job_id, error },
+    );
+}
+
+/// Submit the recording that just stopped if auto-transcription is on. The
+/// job is announced with `auto-transcription-started`; its progress follows
+/// as for `submit_transcription`, and the formatted transcript arrives as
+/// `auto-transcription-completed` once the output action is done.
+pub fn on_recording_stopped(app: &AppHandle) {
+    let settings = match settings::load_effective_settings(app) {
+        Ok(current) if current.auto_transcribe.enabled => current.auto_transcribe,
+        Ok(_) => return,
+        Err(e) => return emit_failed(app, None, e),
+    };
+    let Some(provider) = settings.provider.clone() else {
+        return emit_failed(app, None, "No transcription engine is set".to_string());
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let recorder = app.state::<AudioRecorder>();
+        let submitted = transcription::submit_with(
+            app.clone(),
+            &recorder,
+            Source::LastTake,
+            provider,
+            move |app, job_id, outcome| {
+                // Failed jobs already end with `transcription-failed`
+                let Ok(result) = outcome else {
+                    return;
+                };
+                let (app, job_id, text) = (app.clone(), job_id.to_string(), result.text.clone());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = finish(app.clone(), settings, job_id.clone(), text).await {
+                        emit_failed(&app, Some(job_id), e);
+                    }
+                });
+            },
+        )
+        .await;
+
+        match submitted {
+            Ok(job_id) => {
+                let _ = app.emit(
+                    "auto-transcription-started",
+                    AutoTranscriptionStarted { job_id },
+                );
+            }
+            Err(e) => emit_failed(&app, None, e),
+        }
+    });
+}
+
+/// Get the auto-transcription settings
+#[tauri::command]
+pub fn get_auto_transcribe_settings(app: AppHandle) -> Result<AutoTranscribeSettings, String> {
+    Ok(settings::load_settings(&app)?.auto_transcribe)
+}
+
+/// Change what happens when a recording stops. Enabling it needs an engine
+/// that transcribes files.
+#[tauri::command]
+pub fn set_auto_transcribe_settings(
+    app: AppHandle,
+    auto_transcribe: AutoTranscribeSettings,
+) -> Result<(), String> {
+    if auto_transcribe.enabled {
+        let request = auto_transcribe
+            .provider
+            .as_ref()
+            .ok_or("Choose a transcription engine to transcribe automatically")?;
+        if !provider::get(&request.provider)?.capabilities().file {
+            return Err(format!("{} cannot transcribe recordings", request.provider));
+        }
+    }
+
+    settings::update_settings(&app, |current| {
+        current.auto_transcribe = auto_transcribe;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_json() {
+        let settings: AutoTranscribeSettings = serde_json::from_str(
+            r#"{ "enabled": true, "provider": { "provider": "openai", "language": "de" },
+                 "output": "copy" }"#,
+        )
+        .unwrap();
+        let provider = settings.provider.unwrap();
+        assert_eq!(provider.provider, "openai");
+        assert_eq!(provider.options["language"], "de");
+        assert_eq!(settings.output, OutputAction::Copy);
+        assert!(!settings.format);
+
+        assert_eq!(
+            AutoTranscribeSettings::default().output,
+            OutputAction::Event
+        );
+    }
+}
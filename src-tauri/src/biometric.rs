@@ -0,0 +1,200 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::settings;
+
+/// Shown in the system prompt after "<app> is trying to"
+const PROMPT_REASON: &str = "show your saved API keys";
+
+/// Touch ID or Windows Hello before secure values reach the frontend, for
+/// shared machines. The backend reads credentials for its own requests
+/// without asking.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BiometricUnlock {
+    pub enabled: bool,
+    /// How long one successful check is trusted
+    pub grace_period_secs: u64,
+}
+
+impl Default for BiometricUnlock {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grace_period_secs: 300,
+        }
+    }
+}
+
+/// When the user last passed a check
+static LAST_VERIFIED: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Held while a prompt is open, so parallel reads share one prompt
+static PROMPT_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn within_grace(last_verified: Option<Instant>, now: Instant, grace_period: Duration) -> bool {
+    last_verified.is_some_and(|last| now.duration_since(last) < grace_period)
+}
+
+#[cfg(target_os = "macos")]
+fn available() -> bool {
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    // SAFETY: a fresh context is only queried
+    unsafe { LAContext::new().canEvaluatePolicy_error(LAPolicy::DeviceOwnerAuthentication) }.is_ok()
+}
+
+/// Ask for Touch ID, falling back to the account password
+#[cfg(target_os = "macos")]
+fn prompt(reason: &str) -> Result<bool, String> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+    use std::sync::mpsc;
+
+    let (sender, receiver) = mpsc::channel();
+    let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+        let _ = sender.send(success.as_bool());
+    });
+
+    // SAFETY: the reply block only sends on a channel, which works from any
+    // thread, and `context` lives until the reply arrives
+    unsafe {
+        let context = LAContext::new();
+        context.evaluatePolicy_localizedReason_reply(
+            LAPolicy::DeviceOwnerAuthentication,
+            &NSString::from_str(reason),
+            &reply,
+        );
+        receiver
+            .recv()
+            .map_err(|_| "Touch ID did not answer".to_string())
+    }
+}
+
+#[cfg(windows)]
+fn available() -> bool {
+    use windows::Security::Credentials::UI::{
+        UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+
+    UserConsentVerifier::CheckAvailabilityAsync()
+        .and_then(|operation| operation.get())
+        .is_ok_and(|availability| availability == UserConsentVerifierAvailability::Available)
+}
+
+/// Ask for Windows Hello (face, fingerprint or PIN)
+#[cfg(windows)]
+fn prompt(reason: &str) -> Result<bool, String> {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+
+    let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+        .and_then(|operation| operation.get())
+        .map_err(|e| format!("Windows Hello failed: {}", e))?;
+    Ok(result == UserConsentVerificationResult::Verified)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn available() -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn prompt(_reason: &str) -> Result<bool, String> {
+    Err("Biometric unlock is not supported on this platform".to_string())
+}
+
+/// Prompt unless a check passed within `grace_period`. Blocking.
+fn verify(grace_period: Duration) -> Result<(), String> {
+    let _prompt = PROMPT_LOCK.lock();
+    if within_grace(*LAST_VERIFIED.lock(), Instant::now(), grace_period) {
+        return Ok(());
+    }
+
+    if !prompt(PROMPT_REASON)? {
+        return Err("Identity verification failed".to_string());
+    }
+    *LAST_VERIFIED.lock() = Some(Instant::now());
+    Ok(())
+}
+
+/// Require the user to be present before secure values are returned, if
+/// biometric unlock is on
+pub async fn ensure_verified(app: &AppHandle) -> Result<(), String> {
+    let settings = settings::load_settings(app)?.biometric_unlock;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let grace_period = Duration::from_secs(settings.grace_period_secs);
+    tokio::task::spawn_blocking(move || verify(grace_period))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BiometricStatus {
+    /// Touch ID or Windows Hello is set up on this machine
+    pub available: bool,
+    pub settings: BiometricUnlock,
+}
+
+#[tauri::command]
+pub async fn get_biometric_status(app: AppHandle) -> Result<BiometricStatus, String> {
+    let settings = settings::load_settings(&app)?.biometric_unlock;
+    let available = tokio::task::spawn_blocking(available)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?;
+
+    Ok(BiometricStatus {
+        available,
+        settings,
+    })
+}
+
+/// Change biometric unlock. Always asks first, so it can neither be turned
+/// off by someone else nor turned on where the check does not work.
+#[tauri::command]
+pub async fn set_biometric_unlock(
+    app: AppHandle,
+    biometric_unlock: BiometricUnlock,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(|| verify(Duration::ZERO))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+
+    settings::update_settings(&app, |current| {
+        current.biometric_unlock = biometric_unlock;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_grace() {
+        let now = Instant::now();
+        let grace = Duration::from_secs(300);
+
+        assert!(!within_grace(None, now, grace));
+        assert!(within_grace(
+            Some(now),
+            now + Duration::from_secs(299),
+            grace
+        ));
+        assert!(!within_grace(
+            Some(now),
+            now + Duration::from_secs(300),
+            grace
+        ));
+        assert!(!within_grace(Some(now), now, Duration::ZERO));
+    }
+}
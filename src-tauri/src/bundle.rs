@@ -0,0 +1,360 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::files::{self, FileDialogFilter};
+use crate::timestamps;
+use transcriber_core::audio;
+use transcriber_core::transcript::document::{TranscriptDocument, TranscriptExport};
+use transcriber_core::transcript::markdown::{self, MarkdownOptions, MarkdownTranscript};
+use transcriber_core::transcript::subtitles::{self, SubtitleFormat, SubtitleOptions};
+
+/// Identifies the metadata file of a session bundle
+const BUNDLE_FORMAT: &str = "transcriber-bundle";
+
+/// Bumped whenever the bundle layout changes
+const BUNDLE_VERSION: u32 = 1;
+
+/// How the recording goes into a bundle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BundleAudio {
+    /// The file as recorded
+    #[default]
+    Original,
+    /// 16 kHz mono 16-bit WAV, a fraction of the size and all transcription
+    /// needs
+    Speech,
+    /// Leave the recording out
+    None,
+}
+
+/// A transcript file a bundle can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BundleTranscriptFormat {
+    Text,
+    Markdown,
+    Srt,
+    Vtt,
+    /// The versioned document `export_json` writes
+    Json,
+}
+
+impl BundleTranscriptFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Markdown => "md",
+            Self::Srt => SubtitleFormat::Srt.extension(),
+            Self::Vtt => SubtitleFormat::Vtt.extension(),
+            Self::Json => "json",
+        }
+    }
+}
+
+/// What goes into a bundle
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BundleOptions {
+    pub audio: BundleAudio,
+    /// Formats without what they need, such as subtitles for a transcript
+    /// without timestamps, are skipped
+    pub transcript_formats: Vec<BundleTranscriptFormat>,
+    pub markdown: MarkdownOptions,
+    pub subtitles: SubtitleOptions,
+}
+
+impl Default for BundleOptions {
+    fn default() -> Self {
+        Self {
+            audio: BundleAudio::default(),
+            transcript_formats: vec![BundleTranscriptFormat::Text, BundleTranscriptFormat::Json],
+            markdown: MarkdownOptions::default(),
+            subtitles: SubtitleOptions::default(),
+        }
+    }
+}
+
+/// `metadata.json` of a bundle: what the session was and which file is which
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleMetadata {
+    pub format: String,
+    pub version: u32,
+    /// RFC 3339
+    pub exported_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Name of the recording in the bundle, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<String>,
+    /// Names of the transcript files in the bundle
+    pub transcripts: Vec<String>,
+}
+
+/// The recording as `audio` asks for it, with its name in the bundle
+fn recording_entry(
+    audio_path: &Path,
+    audio: BundleAudio,
+) -> Result<Option<(String, Vec<u8>)>, String> {
+    match audio {
+        BundleAudio::None => Ok(None),
+        BundleAudio::Original => {
+            let data = std::fs::read(audio_path)
+                .map_err(|e| format!("Failed to read recording: {}", e))?;
+            let extension = audio_path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "wav".to_string());
+            Ok(Some((format!("recording.{}", extension), data)))
+        }
+        BundleAudio::Speech => {
+            let samples = audio::load_speech_samples(audio_path)?;
+            let data = audio::encode_speech_wav(&samples)?;
+            Ok(Some(("recording.wav".to_string(), data)))
+        }
+    }
+}
+
+/// The files of a bundle, `metadata.json` last. `date` is the creation time
+/// as shown in the Markdown heading.
+fn bundle_entries(
+    transcript: TranscriptExport,
+    recording: Option<(String, Vec<u8>)>,
+    options: &BundleOptions,
+    date: Option<String>,
+    exported_at: String,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut entries = Vec::new();
+    let mut metadata = BundleMetadata {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: exported_at.clone(),
+        title: transcript.title.clone(),
+        created_at: transcript.created_at.clone(),
+        language: transcript.language.clone(),
+        recording: None,
+        transcripts: Vec::new(),
+    };
+
+    if let Some((name, data)) = recording {
+        metadata.recording = Some(name.clone());
+        entries.push((name, data));
+    }
+
+    for &format in &options.transcript_formats {
+        let name = format!("transcript.{}", format.extension());
+        if entries.iter().any(|(existing, _)| *existing == name) {
+            continue;
+        }
+        let contents = match format {
+            BundleTranscriptFormat::Text => {
+                let mut text = transcript.text.trim().to_string();
+                text.push('\n');
+                text
+            }
+            BundleTranscriptFormat::Markdown => markdown::render(
+                &MarkdownTranscript {
+                    title: transcript.title.clone(),
+                    date: date.clone(),
+                    summary: None,
+                    segments: transcript.segments.clone(),
+                    text: transcript.text.clone(),
+                },
+                &options.markdown,
+            ),
+            BundleTranscriptFormat::Srt | BundleTranscriptFormat::Vtt => {
+                if transcript.segments.is_empty() {
+                    continue;
+                }
+                let format = if format == BundleTranscriptFormat::Srt {
+                    SubtitleFormat::Srt
+                } else {
+                    SubtitleFormat::Vtt
+                };
+                subtitles::render(&transcript.segments, format, &options.subtitles)
+            }
+            BundleTranscriptFormat::Json => {
+                let document = TranscriptDocument::new(transcript.clone(), exported_at.clone());
+                serde_json::to_string_pretty(&document)
+                    .map_err(|e| format!("Failed to serialize transcript: {}", e))?
+            }
+        };
+        metadata.transcripts.push(name.clone());
+        entries.push((name, contents.into_bytes()));
+    }
+
+    let metadata = serde_json::to_vec_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize bundle metadata: {}", e))?;
+    entries.push(("metadata.json".to_string(), metadata));
+    Ok(entries)
+}
+
+/// Pack `entries` into a zip archive. Text is compressed; audio, which
+/// barely shrinks, is stored as is.
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        let method = if name.starts_with("recording.") {
+            CompressionMethod::Stored
+        } else {
+            CompressionMethod::Deflated
+        };
+        zip.start_file(
+            name.as_str(),
+            SimpleFileOptions::default().compression_method(method),
+        )
+        .and_then(|_| zip.write_all(data).map_err(Into::into))
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    }
+    let cursor = zip
+        .finish()
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Package a session for archiving: the recording at `audio_path` (as
+/// `options.audio` says), the transcript in each of
+/// `options.transcript_formats` and a `metadata.json`, as one zip saved
+/// through the save dialog. Returns the path written.
+#[tauri::command]
+pub async fn export_bundle(
+    app: AppHandle,
+    transcript: TranscriptExport,
+    audio_path: Option<String>,
+    default_filename: Option<String>,
+    options: Option<BundleOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let default_filename = default_filename
+        .or_else(|| transcript.title.clone())
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "session".to_string());
+
+    let bundle = {
+        let app = app.clone();
+        tokio::task::spawn_blocking(move || {
+            let recording = match &audio_path {
+                Some(path) => recording_entry(Path::new(path), options.audio)?,
+                None => None,
+            };
+            let date = transcript.created_at.as_deref().map(|created_at| {
+                timestamps::formatter(&app)
+                    .format(created_at)
+                    .map(|formatted| formatted.display)
+                    .unwrap_or_else(|_| created_at.to_string())
+            });
+
+            let entries = bundle_entries(
+                transcript,
+                recording,
+                &options,
+                date,
+                chrono::Utc::now().to_rfc3339(),
+            )?;
+            write_zip(&entries)
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??
+    };
+
+    let mut filename = std::path::PathBuf::from(default_filename);
+    if filename.extension().is_none() {
+        filename.set_extension("zip");
+    }
+    files::save_with_dialog(
+        &app,
+        &bundle,
+        &filename.to_string_lossy(),
+        &[FileDialogFilter::new("Zip archive", "zip")],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use transcriber_core::transcription::TranscriptSegment;
+
+    #[test]
+    fn test_bundle_zip() {
+        let transcript = TranscriptExport {
+            title: Some("Standup".to_string()),
+            text: "Morning all.".to_string(),
+            segments: vec![TranscriptSegment {
+                start_ms: 0,
+                end_ms: 1500,
+                text: "Morning all.".to_string(),
+                speaker: Some("A".to_string()),
+                confidence: None,
+                words: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let options = BundleOptions {
+            transcript_formats: vec![
+                BundleTranscriptFormat::Text,
+                BundleTranscriptFormat::Srt,
+                BundleTranscriptFormat::Json,
+                BundleTranscriptFormat::Text,
+            ],
+            ..Default::default()
+        };
+        let recording = Some(("recording.wav".to_string(), vec![1, 2, 3]));
+
+        let entries = bundle_entries(
+            transcript.clone(),
+            recording,
+            &options,
+            None,
+            "2026-10-15T09:00:00Z".to_string(),
+        )
+        .unwrap();
+        let mut archive =
+            zip::ZipArchive::new(std::io::Cursor::new(write_zip(&entries).unwrap())).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 5);
+
+        let mut read = |name: &str| {
+            let mut contents = String::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        };
+        assert_eq!(read("transcript.txt"), "Morning all.\n");
+        assert!(read("transcript.srt").starts_with("1\n00:00:00,000 --> "));
+        assert!(TranscriptDocument::parse(&read("transcript.json")).is_ok());
+
+        let metadata: BundleMetadata = serde_json::from_str(&read("metadata.json")).unwrap();
+        assert_eq!(metadata.format, BUNDLE_FORMAT);
+        assert_eq!(metadata.recording.as_deref(), Some("recording.wav"));
+        assert_eq!(
+            metadata.transcripts,
+            ["transcript.txt", "transcript.srt", "transcript.json"]
+        );
+
+        // Subtitles need timestamps
+        let untimed = TranscriptExport {
+            segments: Vec::new(),
+            ..transcript
+        };
+        let options = BundleOptions {
+            transcript_formats: vec![BundleTranscriptFormat::Vtt],
+            ..Default::default()
+        };
+        let entries = bundle_entries(untimed, None, &options, None, String::new()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "metadata.json");
+    }
+}
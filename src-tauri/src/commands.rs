@@ -1,56 +1,539 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use base64::Engine as _;
+use std::collections::HashMap;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 
 // Import necessary traits for Unix permission handling
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
 
-use crate::crypto;
+use crate::biometric;
+use crate::policy;
+use crate::profile;
+use crate::secure_audit::{self, AuditAction};
+use crate::settings;
+use crate::team_config;
+use crate::transcription::provider;
+use crate::vault::{self, EntryState, Vault, VaultEntry};
+use transcriber_core::crypto::{self, ContextKey, DiagnosticCheck, KeyContext, Zeroize, Zeroizing};
 
-/// Get the path to the secure storage file in the app's data directory
-fn get_secure_storage_path(app: &AppHandle, key: &str) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+/// Identifies secure storage backups written by `export_secure_storage`
+const BACKUP_FORMAT: &str = "transcriber-secure-backup";
+
+/// Shortest passphrase accepted for a backup
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Random key in the app data directory, used where no machine ID can be
+/// read (e.g. some containers). Outside the secure directory so that a reset
+/// keeps it.
+const FALLBACK_KEY_FILE_NAME: &str = "fallback.key";
+
+/// Argon2id salt and settings of a passphrase-protected store. Its presence
+/// means the store must be unlocked before use.
+const PASSPHRASE_FILE_NAME: &str = ".passphrase";
+
+/// How often values whose TTL ran out are purged
+const EXPIRY_SWEEP_INTERVAL_SECS: u64 = 60;
+
+/// Name of the file holding the fingerprint of the key the store was written with
+const KEY_CHECK_FILE_NAME: &str = ".keycheck";
+
+/// Written and removed again by `test_secure_storage`. Starts with a dot, so
+/// it is never taken for a value of the old layout.
+const SELF_TEST_FILE_NAME: &str = ".selftest";
+
+/// Encrypted and decrypted by `test_secure_storage`
+const SELF_TEST_PROBE: &[u8] = b"transcriber-secure-storage-self-test";
+
+/// Returned whenever stored data was encrypted with a key that is no longer available
+const KEY_MISMATCH_ERROR: &str = "Secure storage was encrypted with a different key. \
+     Restore it with a recovery key or reset secure storage.";
+
+/// Serializes read-modify-write cycles on the vault file
+static VAULT_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// A decrypted value kept in memory. Wiped when dropped.
+struct CachedValue {
+    value: String,
+    /// Unix seconds, as in `ValueMeta`
+    expires_at: Option<i64>,
+}
+
+impl Drop for CachedValue {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// What secure storage keeps in memory, managed by Tauri
+#[derive(Default)]
+pub struct SecureCache {
+    /// The machine key once read; see `crypto::read_machine_key`
+    machine_key: Mutex<Option<crypto::Key>>,
+    /// Values read while `cache_secure_values` is on, by key. Emptied by
+    /// every change to the store.
+    values: Mutex<HashMap<String, CachedValue>>,
+}
+
+impl SecureCache {
+    /// The machine key, read from the machine ID on first use
+    fn machine_key(&self) -> Result<crypto::Key, String> {
+        let mut machine_key = self.machine_key.lock();
+        if let Some(key) = &*machine_key {
+            return Ok(key.clone());
+        }
+        let key = crypto::read_machine_key()?;
+        *machine_key = Some(key.clone());
+        Ok(key)
+    }
+
+    fn clear_values(&self) {
+        self.values.lock().clear();
+    }
+
+    /// Drop the cached values and machine key
+    pub fn clear(&self) {
+        self.clear_values();
+        *self.machine_key.lock() = None;
+    }
+}
+
+fn clear_value_cache(app: &AppHandle) {
+    app.state::<SecureCache>().clear_values();
+}
+
+/// Get the path to the secure storage directory of the current storage profile
+fn get_secure_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let secure_dir = profile::storage_dir(app)?.join("secure");
 
-    let secure_dir = app_data_dir.join("secure");
-    
     if !secure_dir.exists() {
         fs::create_dir_all(&secure_dir)
             .map_err(|e| format!("Failed to create secure directory: {}", e))?;
     }
 
-    let sanitized_key = key.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|'], "_");
-    Ok(secure_dir.join(sanitized_key))
+    Ok(secure_dir)
+}
+
+/// Write data to a file readable only by the current user
+fn write_secure_file(path: &Path, data: &[u8]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to open secure file: {}", e))?;
+
+    file.write_all(data)
+        .map_err(|e| format!("Failed to write data: {}", e))
+}
+
+/// `write_secure_file` to a temporary file that is then renamed over `path`,
+/// so `path` is either complete or left as it was
+fn replace_secure_file(path: &Path, data: &[u8]) -> Result<(), String> {
+    let temp_path = path.with_extension("tmp");
+    write_secure_file(&temp_path, data)
+        .and_then(|()| {
+            fs::rename(&temp_path, path)
+                .map_err(|e| format!("Failed to replace secure file: {}", e))
+        })
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp_path);
+        })
+}
+
+/// Make sure the store was written with the current key. Stores created before
+/// key checks existed get one as soon as their data decrypts successfully.
+fn verify_storage_key(secure_dir: &Path) -> Result<(), String> {
+    let key_check_path = secure_dir.join(KEY_CHECK_FILE_NAME);
+    let fingerprint = crypto::current_key_fingerprint()?;
+
+    if key_check_path.exists() {
+        let stored = fs::read_to_string(&key_check_path)
+            .map_err(|e| format!("Failed to read key check: {}", e))?;
+
+        if stored.trim() != fingerprint {
+            return Err(KEY_MISMATCH_ERROR.to_string());
+        }
+
+        return Ok(());
+    }
+
+    if !vault::decrypts_with(secure_dir, &ContextKey::current(KeyContext::SecureValues)?) {
+        return Err(KEY_MISMATCH_ERROR.to_string());
+    }
+
+    write_secure_file(&key_check_path, fingerprint.as_bytes())
+}
+
+/// Decrypt the vault, converting a store from before the vault on first use
+fn load_vault(secure_dir: &Path) -> Result<Vault, String> {
+    verify_storage_key(secure_dir)?;
+    vault::load(secure_dir, &ContextKey::current(KeyContext::SecureValues)?)
+}
+
+/// Encrypt and save the vault, dropping cached values
+fn save_vault(app: &AppHandle, secure_dir: &Path, vault: &Vault) -> Result<(), String> {
+    clear_value_cache(app);
+    vault::save(secure_dir, vault, &ContextKey::current(KeyContext::SecureValues)?)
+}
+
+/// Delete the values whose TTL ran out and return their keys
+fn purge_expired(
+    app: &AppHandle,
+    secure_dir: &Path,
+    vault: &mut Vault,
+) -> Result<Vec<String>, String> {
+    let expired = vault.purge_expired(chrono::Utc::now().timestamp());
+    if !expired.is_empty() {
+        save_vault(app, secure_dir, vault)?;
+        secure_audit::record(app, AuditAction::Delete, "expiry", &expired, true);
+    }
+    Ok(expired)
+}
+
+/// Load the vault after purging values whose TTL ran out, so they read as
+/// missing
+fn load_live_vault(app: &AppHandle, secure_dir: &Path) -> Result<Vault, String> {
+    let mut vault = load_vault(secure_dir)?;
+    purge_expired(app, secure_dir, &mut vault)?;
+    Ok(vault)
+}
+
+/// Namespace of a key such as `providers/openai/api_key`: everything before
+/// the last slash
+fn namespace_of(key: &str) -> Option<&str> {
+    key.rsplit_once('/').map(|(namespace, _)| namespace)
+}
+
+/// Whether `key` is in `namespace` or one nested in it
+fn in_namespace(key: &str, namespace: &str) -> bool {
+    key.strip_prefix(namespace.trim_end_matches('/'))
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Make sure a namespace is a path of plain, non-empty segments
+fn check_namespace(namespace: &str) -> Result<(), String> {
+    let valid = namespace.split('/').all(|segment| {
+        !segment.is_empty()
+            && !segment.starts_with('.')
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    });
+
+    if !valid {
+        return Err(format!("Invalid secure storage namespace: {}", namespace));
+    }
+    Ok(())
+}
+
+/// Make sure a value can be stored under `key`: a namespaced key needs a
+/// valid namespace
+fn check_key(key: &str) -> Result<(), String> {
+    namespace_of(key).map_or(Ok(()), check_namespace)
 }
 
+/// Store a secure value. With `ttl_secs`, e.g. for an OAuth access token,
+/// the value reads as missing once that many seconds have passed and is
+/// then purged; storing it again without a TTL keeps it indefinitely.
 #[tauri::command]
 pub async fn set_secure_value(
     app: AppHandle,
     key: String,
     value: String,
+    ttl_secs: Option<u64>,
 ) -> Result<(), String> {
+    if policy::is_cloud_credential(&key) {
+        policy::ensure_cloud_allowed()?;
+        team_config::ensure_online_allowed()?;
+    }
+
     tokio::task::spawn_blocking(move || {
-        let file_path = get_secure_storage_path(&app, &key)?;
+        let result = write_secure_value(&app, &key, &value, ttl_secs);
+        secure_audit::record(&app, AuditAction::Write, "set_secure_value", [&key], result.is_ok());
+        result
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
 
-        // This now calls the NEW crypto::encrypt (Machine ID based)
-        let encrypted_value = crypto::encrypt(value.as_bytes())?;
-        
-        let mut options = OpenOptions::new();
-        options.write(true).create(true).truncate(true);
+/// Encrypt and store a secure value. Blocking, like `read_secure_value`.
+fn write_secure_value(
+    app: &AppHandle,
+    key: &str,
+    value: &str,
+    ttl_secs: Option<u64>,
+) -> Result<(), String> {
+    check_key(key)?;
+    let secure_dir = get_secure_dir(app)?;
 
-        #[cfg(unix)]
-        options.mode(0o600);
+    let _guard = VAULT_LOCK.lock();
+    let mut vault = load_vault(&secure_dir)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = ttl_secs.map(|ttl| now.saturating_add(i64::try_from(ttl).unwrap_or(i64::MAX)));
+    vault.set(key, value, now, expires_at);
+    save_vault(app, &secure_dir, &vault)
+}
+
+/// Why a secure value could not be read, so the frontend can tell a value
+/// that was never set from one that has to be migrated or entered again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SecureValueError {
+    /// No value is stored for the key
+    Missing,
+    /// The value was stored in plain text by an old version; run
+    /// `migrate_plaintext_secrets` to accept it
+    Legacy,
+    /// The value was damaged or encrypted with another key when the store
+    /// moved into the vault
+    Corrupted { message: String },
+    /// Anything else, such as a locked store or an unreadable file
+    Failed { message: String },
+}
+
+impl std::fmt::Display for SecureValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "No secure value is stored for this key"),
+            Self::Legacy => write!(f, "Secure value is stored unencrypted and must be migrated"),
+            Self::Corrupted { message } => write!(f, "Secure value is corrupted: {}", message),
+            Self::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for SecureValueError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+/// The value of a vault entry, if it can be handed out
+fn entry_value(entry: Option<&VaultEntry>) -> Result<String, SecureValueError> {
+    let entry = entry.ok_or(SecureValueError::Missing)?;
+    match entry.state {
+        EntryState::Ok => Ok(entry.value.clone()),
+        EntryState::Legacy => Err(SecureValueError::Legacy),
+        EntryState::Corrupted => Err(SecureValueError::Corrupted {
+            message: "It did not decrypt when secure storage moved into the vault".to_string(),
+        }),
+    }
+}
+
+/// Read and decrypt a secure value; empty when it is not set. `caller`
+/// names who read it in the audit log. Blocking, for backend code that
+/// needs a credential.
+pub fn read_secure_value(app: &AppHandle, key: &str, caller: &str) -> Result<String, String> {
+    let result = load_secure_value(app, key);
+    secure_audit::record(app, AuditAction::Read, caller, [key], result.is_ok());
+    match result {
+        Ok(value) => Ok(value),
+        Err(SecureValueError::Missing) => Ok(String::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn load_secure_value(app: &AppHandle, key: &str) -> Result<String, SecureValueError> {
+    let cache = settings::load_settings(app).is_ok_and(|settings| settings.cache_secure_values);
+    let now = chrono::Utc::now().timestamp();
+    if cache {
+        if let Some(cached) = app.state::<SecureCache>().values.lock().get(key) {
+            if cached.expires_at.is_none_or(|expires_at| expires_at > now) {
+                return Ok(cached.value.clone());
+            }
+        }
+    }
+
+    let secure_dir = get_secure_dir(app)?;
+
+    // Held until the value is cached, so a concurrent write cannot be undone
+    let _guard = VAULT_LOCK.lock();
+    let vault = load_live_vault(app, &secure_dir)?;
+    let entry = vault.entries.get(key);
+    let value = entry_value(entry)?;
+    if cache {
+        app.state::<SecureCache>().values.lock().insert(
+            key.to_string(),
+            CachedValue {
+                value: value.clone(),
+                expires_at: entry.and_then(|entry| entry.meta.expires_at),
+            },
+        );
+    }
+    Ok(value)
+}
+
+/// The value of `key`. Unlike most commands this fails with a
+/// `SecureValueError`, so the frontend can tell a missing value from a
+/// legacy or corrupted one.
+#[tauri::command]
+pub async fn get_secure_value(app: AppHandle, key: String) -> Result<String, SecureValueError> {
+    // Without its credentials the frontend cannot reach a blocked provider
+    if policy::is_cloud_credential(&key) {
+        policy::ensure_cloud_allowed()?;
+        team_config::ensure_online_allowed()?;
+    }
+    biometric::ensure_verified(&app).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let result = load_secure_value(&app, &key);
+        secure_audit::record(&app, AuditAction::Read, "get_secure_value", [&key], result.is_ok());
+        result
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Accept the values that an old version stored in plain text, which
+/// `get_secure_value` reports as `Legacy` until then. They are encrypted
+/// along with the rest of the vault. Returns the number of migrated values.
+#[tauri::command]
+pub async fn migrate_plaintext_secrets(app: AppHandle) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_vault(&secure_dir)?;
+        let migrated = vault.accept_legacy();
+        if !migrated.is_empty() {
+            save_vault(&app, &secure_dir, &vault)?;
+            secure_audit::record(
+                &app,
+                AuditAction::Write,
+                "migrate_plaintext_secrets",
+                &migrated,
+                true,
+            );
+        }
+        Ok(migrated.len())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Whether a value is stored for `key`, even an empty one. Only the key list
+/// is read: no value is decrypted, and one whose TTL ran out reads as missing
+/// without being purged.
+#[tauri::command]
+pub async fn has_secure_value(app: AppHandle, key: String) -> Result<bool, String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+        let now = chrono::Utc::now().timestamp();
+
+        let _guard = VAULT_LOCK.lock();
+        verify_storage_key(&secure_dir)?;
+        let values_key = ContextKey::current(KeyContext::SecureValues)?;
+        match vault::contains(&secure_dir, &values_key, &key, now)? {
+            Some(found) => Ok(found),
+            // A store that has to be converted is loaded in full this once
+            None => Ok(vault::load(&secure_dir, &values_key)?
+                .entries
+                .get(&key)
+                .is_some_and(|entry| !entry.meta.is_expired(now))),
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Unix seconds as RFC 3339
+fn format_timestamp(secs: Option<i64>) -> Option<String> {
+    secs.and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|time| time.to_rfc3339())
+}
+
+/// What is known about a stored value, without the value itself
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureValueInfo {
+    pub key: String,
+    pub label: Option<String>,
+    /// RFC 3339; `None` for values stored before this was tracked
+    pub created_at: Option<String>,
+    /// RFC 3339
+    pub modified_at: Option<String>,
+    /// RFC 3339; see `set_secure_value`
+    pub expires_at: Option<String>,
+}
 
-        let mut file = options
-            .open(&file_path)
-            .map_err(|e| format!("Failed to open secure file: {}", e))?;
+/// Label and timestamps of a stored value, e.g. for "OpenAI key, last
+/// updated 3 months ago". `None` when nothing is stored for `key`.
+#[tauri::command]
+pub async fn get_secure_value_info(
+    app: AppHandle,
+    key: String,
+) -> Result<Option<SecureValueInfo>, String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let vault = load_live_vault(&app, &secure_dir)?;
+        let Some(entry) = vault.entries.get(&key) else {
+            return Ok(None);
+        };
+
+        let value_meta = entry.meta.clone();
+        Ok(Some(SecureValueInfo {
+            key,
+            label: value_meta.label,
+            created_at: format_timestamp(value_meta.created_at),
+            modified_at: format_timestamp(value_meta.modified_at),
+            expires_at: format_timestamp(value_meta.expires_at),
+        }))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Set or, with `None`, remove the label shown for a stored value
+#[tauri::command]
+pub async fn set_secure_value_label(
+    app: AppHandle,
+    key: String,
+    label: Option<String>,
+) -> Result<(), String> {
+    let label = label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty());
+
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_live_vault(&app, &secure_dir)?;
+        let Some(entry) = vault.entries.get_mut(&key) else {
+            return Err(format!("No secure value is stored for {}", key));
+        };
+
+        entry.meta.label = label;
+        save_vault(&app, &secure_dir, &vault)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn delete_secure_value(app: AppHandle, key: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
 
-        file.write_all(encrypted_value.as_bytes())
-            .map_err(|e| format!("Failed to write data: {}", e))?;
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_vault(&secure_dir)?;
+        if vault.remove(&key) {
+            save_vault(&app, &secure_dir, &vault)?;
+            secure_audit::record(&app, AuditAction::Delete, "delete_secure_value", [&key], true);
+        }
 
         Ok(())
     })
@@ -58,46 +541,1256 @@ pub async fn set_secure_value(
     .map_err(|e| format!("Task failed: {}", e))?
 }
 
+/// Names of the stored keys, sorted, only those in `namespace` if one is
+/// given
+fn stored_keys(vault: &Vault, namespace: Option<&str>) -> Vec<String> {
+    vault
+        .entries
+        .keys()
+        .filter(|key| namespace.is_none_or(|namespace| in_namespace(key, namespace)))
+        .cloned()
+        .collect()
+}
+
+/// List the names of the stored secure values, never the values themselves,
+/// optionally only those in a namespace such as `providers/openai` and the
+/// namespaces nested in it
+#[tauri::command]
+pub async fn list_secure_keys(
+    app: AppHandle,
+    namespace: Option<String>,
+) -> Result<Vec<String>, String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let vault = load_live_vault(&app, &secure_dir)?;
+        Ok(stored_keys(&vault, namespace.as_deref()))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Delete every secure value in a namespace and the namespaces nested in it,
+/// e.g. all tokens of one provider. Returns the number of deleted values.
 #[tauri::command]
-pub async fn get_secure_value(app: AppHandle, key: String) -> Result<String, String> {
+pub async fn clear_secure_namespace(app: AppHandle, namespace: String) -> Result<usize, String> {
+    let namespace = namespace.trim_end_matches('/').to_string();
+    check_namespace(&namespace)?;
+
     tokio::task::spawn_blocking(move || {
-        let file_path = get_secure_storage_path(&app, &key)?;
+        let secure_dir = get_secure_dir(&app)?;
 
-        if !file_path.exists() {
-            return Ok(String::new());
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_vault(&secure_dir)?;
+        let keys = stored_keys(&vault, Some(&namespace));
+        for key in &keys {
+            vault.remove(key);
         }
 
-        let file_content = fs::read(&file_path)
-            .map_err(|e| format!("Failed to read secure value: {}", e))?;
+        if !keys.is_empty() {
+            save_vault(&app, &secure_dir, &vault)?;
+            secure_audit::record(&app, AuditAction::Delete, "clear_secure_namespace", &keys, true);
+        }
+        Ok(keys.len())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Payload of `secure-values-expired`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExpiredValuesEvent {
+    keys: Vec<String>,
+}
 
-        let encrypted_string = String::from_utf8(file_content)
-            .map_err(|e| format!("Invalid UTF-8 in secure storage: {}", e))?;
+/// Purge values whose TTL ran out every minute, telling the frontend which
+/// ones are gone so it can e.g. refresh an access token
+pub fn start_expiry_sweep(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(EXPIRY_SWEEP_INTERVAL_SECS)).await;
 
-        // This now calls the NEW crypto::decrypt (Machine ID based)
-        match crypto::decrypt(&encrypted_string) {
-            Ok(decrypted_bytes) => {
-                String::from_utf8(decrypted_bytes)
-                    .map_err(|e| format!("Decrypted data is not valid UTF-8: {}", e))
-            },
-            Err(e) => Err(format!("Failed to decrypt secure value: {}", e))
+            let sweep_app = app.clone();
+            let expired = tokio::task::spawn_blocking(move || {
+                let secure_dir = get_secure_dir(&sweep_app)?;
+                if crypto::is_store_locked() || !secure_dir.join(vault::VAULT_FILE_NAME).exists() {
+                    return Ok(Vec::new());
+                }
+
+                let _guard = VAULT_LOCK.lock();
+                let mut vault = load_vault(&secure_dir)?;
+                purge_expired(&sweep_app, &secure_dir, &mut vault)
+            })
+            .await
+            .map_err(|e| format!("Task failed: {}", e))
+            .and_then(|result| result);
+
+            match expired {
+                Ok(keys) if !keys.is_empty() => {
+                    let _ = app.emit("secure-values-expired", ExpiredValuesEvent { keys });
+                }
+                Ok(_) => {}
+                Err(e) if e == KEY_MISMATCH_ERROR => {}
+                Err(e) => eprintln!("Failed to purge expired secure values: {}", e),
+            }
+        }
+    });
+}
+
+/// Fail if any of `keys` is a cloud credential and cloud providers are blocked,
+/// by the managed policy or the team config
+fn ensure_keys_allowed<'a>(mut keys: impl Iterator<Item = &'a String>) -> Result<(), String> {
+    if keys.any(|key| policy::is_cloud_credential(key)) {
+        policy::ensure_cloud_allowed()?;
+        team_config::ensure_online_allowed()?;
+    }
+    Ok(())
+}
+
+/// Read several secure values at once, e.g. the credentials needed at
+/// startup, decrypting the vault only once. Keys without a value are left
+/// out of the result.
+#[tauri::command]
+pub async fn get_secure_values(
+    app: AppHandle,
+    keys: Vec<String>,
+) -> Result<HashMap<String, String>, String> {
+    ensure_keys_allowed(keys.iter())?;
+    biometric::ensure_verified(&app).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let vault = load_live_vault(&app, &secure_dir)?;
+        let mut values = HashMap::with_capacity(keys.len());
+        let mut result = Ok(());
+        for key in &keys {
+            match entry_value(vault.entries.get(key)) {
+                Ok(value) => {
+                    values.insert(key.clone(), value);
+                }
+                Err(SecureValueError::Missing) => {}
+                Err(e) => {
+                    result = Err(e.to_string());
+                    break;
+                }
+            }
         }
+        secure_audit::record(&app, AuditAction::Read, "get_secure_values", &keys, result.is_ok());
+        result.map(|()| values)
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
 }
 
+/// Store several secure values at once, saving the vault only once
 #[tauri::command]
-pub async fn delete_secure_value(app: AppHandle, key: String) -> Result<(), String> {
+pub async fn set_secure_values(
+    app: AppHandle,
+    values: HashMap<String, String>,
+) -> Result<(), String> {
+    ensure_keys_allowed(values.keys())?;
+    values.keys().try_for_each(|key| check_key(key))?;
+
     tokio::task::spawn_blocking(move || {
-        let file_path = get_secure_storage_path(&app, &key)?;
+        let secure_dir = get_secure_dir(&app)?;
 
-        if file_path.exists() {
-            fs::remove_file(&file_path)
-                .map_err(|e| format!("Failed to delete secure value: {}", e))?;
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_vault(&secure_dir)?;
+        let now = chrono::Utc::now().timestamp();
+        for (key, value) in &values {
+            vault.set(key, value, now, None);
         }
+        let result = save_vault(&app, &secure_dir, &vault);
+        secure_audit::record(
+            &app,
+            AuditAction::Write,
+            "set_secure_values",
+            values.keys(),
+            result.is_ok(),
+        );
+        result
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Delete several secure values at once, saving the vault only once
+#[tauri::command]
+pub async fn delete_secure_values(app: AppHandle, keys: Vec<String>) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
 
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_vault(&secure_dir)?;
+        let removed: Vec<&String> = keys.iter().filter(|key| vault.remove(key)).collect();
+
+        if !removed.is_empty() {
+            save_vault(&app, &secure_dir, &vault)?;
+            secure_audit::record(&app, AuditAction::Delete, "delete_secure_values", removed, true);
+        }
         Ok(())
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
-}
\ No newline at end of file
+}
+
+/// Credentials for a transcription provider, stored as one value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ProviderCredentials {
+    /// Provider id, as in `list_transcription_providers`
+    pub provider: String,
+    pub api_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+/// Structured values kept in secure storage, tagged with their schema, e.g.
+/// `{ "schema": "providerCredentials", "provider": "openai", "apiKey": "..." }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "schema", rename_all = "camelCase")]
+pub enum SecureJson {
+    ProviderCredentials(ProviderCredentials),
+}
+
+impl SecureJson {
+    /// Check what the types alone cannot
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            SecureJson::ProviderCredentials(credentials) => {
+                provider::get(&credentials.provider)?;
+                if credentials.api_key.trim().is_empty() {
+                    return Err("API key must not be empty".to_string());
+                }
+                if let Some(endpoint) = &credentials.endpoint {
+                    if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+                        return Err(format!("Invalid endpoint: {}", endpoint));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Parse and validate a stored value
+    fn parse(value: &str) -> Result<Self, String> {
+        let parsed: Self = serde_json::from_str(value)
+            .map_err(|e| format!("Stored value does not match its schema: {}", e))?;
+        parsed
+            .validate()
+            .map_err(|e| format!("Stored value is invalid: {}", e))?;
+        Ok(parsed)
+    }
+}
+
+/// Validate and store a structured value
+#[tauri::command]
+pub async fn set_secure_json(app: AppHandle, key: String, value: SecureJson) -> Result<(), String> {
+    if policy::is_cloud_credential(&key) {
+        policy::ensure_cloud_allowed()?;
+        team_config::ensure_online_allowed()?;
+    }
+    value.validate()?;
+    let json = serde_json::to_string(&value)
+        .map_err(|e| format!("Failed to serialize secure value: {}", e))?;
+
+    tokio::task::spawn_blocking(move || {
+        let result = write_secure_value(&app, &key, &json, None);
+        secure_audit::record(&app, AuditAction::Write, "set_secure_json", [&key], result.is_ok());
+        result
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Read a structured value, `None` when it is not set. A value that no
+/// longer matches its schema is an error rather than being passed on.
+#[tauri::command]
+pub async fn get_secure_json(app: AppHandle, key: String) -> Result<Option<SecureJson>, String> {
+    if policy::is_cloud_credential(&key) {
+        policy::ensure_cloud_allowed()?;
+        team_config::ensure_online_allowed()?;
+    }
+    biometric::ensure_verified(&app).await?;
+
+    let value =
+        tokio::task::spawn_blocking(move || read_secure_value(&app, &key, "get_secure_json"))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+    if value.is_empty() {
+        return Ok(None);
+    }
+    SecureJson::parse(&value).map(Some)
+}
+
+/// Key contexts the frontend may encrypt its own data with. Secure values and
+/// the audit log are left out, so frontend data never shares their key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataContext {
+    /// Draft transcripts, history entries and exports
+    History,
+    /// Payloads exchanged with sync backends
+    Sync,
+}
+
+impl From<DataContext> for KeyContext {
+    fn from(context: DataContext) -> Self {
+        match context {
+            DataContext::History => KeyContext::History,
+            DataContext::Sync => KeyContext::Sync,
+        }
+    }
+}
+
+/// Encrypt text for the frontend with the key of `context` on this machine.
+/// Returns base64 of the nonce and ciphertext, for `decrypt_data`.
+#[tauri::command]
+pub async fn encrypt_data(data: String, context: DataContext) -> Result<String, String> {
+    let data = Zeroizing::new(data);
+    tokio::task::spawn_blocking(move || crypto::encrypt(context.into(), data.as_bytes()))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Decrypt text `encrypt_data` encrypted with the same `context`
+#[tauri::command]
+pub async fn decrypt_data(data: String, context: DataContext) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let decrypted = crypto::decrypt(context.into(), &data)
+            .map_err(|e| format!("Failed to decrypt data: {}", e))?;
+        std::str::from_utf8(&decrypted)
+            .map(str::to_string)
+            .map_err(|e| format!("Decrypted data is not text: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Structured diagnostics for the key infrastructure
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyringHealth {
+    healthy: bool,
+    checks: Vec<DiagnosticCheck>,
+}
+
+/// Verify that the encryption key is available and usable, and that data
+/// already in secure storage can still be decrypted with it.
+///
+/// The key is derived from the machine ID rather than kept in the OS keyring,
+/// so "reachable" means the machine ID can be read.
+#[tauri::command]
+pub async fn check_keyring_health(app: AppHandle) -> Result<KeyringHealth, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut checks = crypto::run_key_diagnostics();
+
+        if checks.iter().all(|check| check.ok) {
+            let secure_dir = get_secure_dir(&app)?;
+
+            let _guard = VAULT_LOCK.lock();
+            checks.push(DiagnosticCheck::new(
+                "existingData",
+                load_vault(&secure_dir).map(|vault| {
+                    if vault.entries.is_empty() {
+                        "No secure values stored yet".to_string()
+                    } else {
+                        format!("Secure vault with {} values decrypts", vault.entries.len())
+                    }
+                }),
+            ));
+        }
+
+        Ok(KeyringHealth {
+            healthy: checks.iter().all(|check| check.ok),
+            checks,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Result of `test_secure_storage`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureStorageSelfTest {
+    healthy: bool,
+    backend: crypto::KeyBackend,
+    locked: bool,
+    checks: Vec<DiagnosticCheck>,
+}
+
+/// Encrypt a probe the way a secure value is stored, with a new data key
+/// wrapped by the current key, and decrypt it again
+fn envelope_roundtrip() -> Result<String, String> {
+    let key = ContextKey::current(KeyContext::SecureValues)?;
+    let data_key = crypto::DataKey::generate();
+    let encrypted = data_key.encrypt(SELF_TEST_PROBE)?;
+    let wrapped = key.wrap_key(&data_key)?;
+
+    let decrypted = key.unwrap_key(&wrapped)?.decrypt(&encrypted)?;
+    if *decrypted != SELF_TEST_PROBE {
+        return Err("Decrypted probe does not match the original".to_string());
+    }
+    Ok("Encrypted and decrypted a probe with a wrapped data key".to_string())
+}
+
+/// Write, read back and remove a probe file in the secure directory
+fn write_access_check(secure_dir: &Path) -> Result<String, String> {
+    let path = secure_dir.join(SELF_TEST_FILE_NAME);
+    write_secure_file(&path, SELF_TEST_PROBE)?;
+    let read = fs::read(&path).map_err(|e| format!("Failed to read probe file: {}", e));
+    fs::remove_file(&path).map_err(|e| format!("Failed to remove probe file: {}", e))?;
+
+    if read? != SELF_TEST_PROBE {
+        return Err("Probe file read back differently".to_string());
+    }
+    Ok(format!("{} is writable", secure_dir.display()))
+}
+
+/// Fail if any of `paths` that exist can be read by other users
+#[cfg(unix)]
+fn file_permissions_check(paths: &[PathBuf]) -> Result<String, String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut loose = Vec::new();
+    for path in paths {
+        let Ok(metadata) = fs::metadata(path) else {
+            continue;
+        };
+        if metadata.is_file() && metadata.permissions().mode() & 0o077 != 0 {
+            loose.push(path.display().to_string());
+        }
+    }
+
+    if loose.is_empty() {
+        Ok("Key and secure files are only accessible to the current user".to_string())
+    } else {
+        Err(format!("Accessible to other users: {}", loose.join(", ")))
+    }
+}
+
+#[cfg(not(unix))]
+fn file_permissions_check(_paths: &[PathBuf]) -> Result<String, String> {
+    Ok("Access to the app data folder is managed by the operating system".to_string())
+}
+
+/// Exercise every layer of secure storage and report each one: the key
+/// source, the store key and its fingerprint, encryption with a wrapped data
+/// key, the vault, writing to the secure directory, and the permissions of
+/// the files in it. Meant for support, to tell a key problem from an app bug;
+/// no stored value is changed.
+#[tauri::command]
+pub async fn test_secure_storage(app: AppHandle) -> Result<SecureStorageSelfTest, String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+        let locked = crypto::is_store_locked();
+        let mut checks = crypto::run_key_diagnostics();
+
+        let _guard = VAULT_LOCK.lock();
+        let store_key = if locked {
+            Err(crypto::STORE_LOCKED_ERROR.to_string())
+        } else {
+            verify_storage_key(&secure_dir)
+                .map(|()| "Secure storage was written with the current key".to_string())
+        };
+        let store_key_ok = store_key.is_ok();
+        checks.push(DiagnosticCheck::new("storeKey", store_key));
+
+        if !locked {
+            checks.push(DiagnosticCheck::new("envelopeRoundtrip", envelope_roundtrip()));
+        }
+        if store_key_ok {
+            let vault = ContextKey::current(KeyContext::SecureValues)
+                .and_then(|key| vault::load(&secure_dir, &key))
+                .map(|vault| format!("Secure vault with {} values decrypts", vault.entries.len()));
+            checks.push(DiagnosticCheck::new("vault", vault));
+        }
+        checks.push(DiagnosticCheck::new("writeAccess", write_access_check(&secure_dir)));
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(&secure_dir)
+            .map_err(|e| format!("Failed to read secure directory: {}", e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        paths.push(profile::storage_dir(&app)?.join(FALLBACK_KEY_FILE_NAME));
+        checks.push(DiagnosticCheck::new("filePermissions", file_permissions_check(&paths)));
+
+        Ok(SecureStorageSelfTest {
+            healthy: checks.iter().all(|check| check.ok),
+            backend: crypto::key_backend(),
+            locked,
+            checks,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Whether the secure store can be read with the current key
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SecureStorageState {
+    Ok,
+    KeyMismatch,
+    /// Protected by a passphrase that was not entered yet
+    Locked,
+}
+
+/// Payload of the `secure-storage-key-mismatch` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyMismatchEvent {
+    message: String,
+}
+
+/// Report whether secure storage is readable with the current key
+#[tauri::command]
+pub async fn get_secure_storage_status(app: AppHandle) -> Result<SecureStorageState, String> {
+    if crypto::is_store_locked() {
+        return Ok(SecureStorageState::Locked);
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        match verify_storage_key(&secure_dir) {
+            Ok(()) => Ok(SecureStorageState::Ok),
+            Err(e) if e == KEY_MISMATCH_ERROR => Ok(SecureStorageState::KeyMismatch),
+            Err(e) => Err(e),
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Check the storage key at launch and tell the frontend if secrets became
+/// unreadable, instead of letting every later command fail on its own. A
+/// store from before the vault is converted here. A passphrase-protected
+/// store starts locked until `unlock_secure_storage`.
+pub fn check_storage_key_on_startup(app: &AppHandle) {
+    let Ok(secure_dir) = get_secure_dir(app) else {
+        return;
+    };
+
+    let _guard = VAULT_LOCK.lock();
+    let passphrase_path = secure_dir.join(PASSPHRASE_FILE_NAME);
+    if passphrase_path.exists() && passphrase_never_applied(&secure_dir) {
+        if let Err(e) = fs::remove_file(&passphrase_path) {
+            eprintln!("Failed to remove unused passphrase settings: {}", e);
+        }
+    }
+    if passphrase_path.exists() {
+        crypto::lock_store();
+        let _ = app.emit("secure-storage-locked", ());
+        return;
+    }
+
+    match load_vault(&secure_dir) {
+        Ok(_) => {}
+        Err(e) if e == KEY_MISMATCH_ERROR => {
+            let _ = app.emit("secure-storage-key-mismatch", KeyMismatchEvent { message: e });
+        }
+        Err(e) => eprintln!("Failed to open secure storage: {}", e),
+    }
+}
+
+/// Check that the encryption key works and secure storage decrypts with it
+pub fn check_secure_storage(app: &AppHandle) -> Result<String, String> {
+    if let Some(check) = crypto::run_key_diagnostics()
+        .into_iter()
+        .find(|check| !check.ok)
+    {
+        return Err(check.detail);
+    }
+
+    let secure_dir = get_secure_dir(app)?;
+    let _guard = VAULT_LOCK.lock();
+    verify_storage_key(&secure_dir)?;
+
+    Ok("Secure storage is readable".to_string())
+}
+
+/// Read the Argon2id settings of a passphrase-protected store, if it is one
+fn load_passphrase_params(
+    secure_dir: &Path,
+) -> Result<Option<crypto::PassphraseKeyParams>, String> {
+    match fs::read_to_string(secure_dir.join(PASSPHRASE_FILE_NAME)) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Invalid passphrase settings: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read passphrase settings: {}", e)),
+    }
+}
+
+/// Wrap the data keys of the vault with `new_master` and record its
+/// fingerprint. The values themselves are not encrypted again. The vault is
+/// replaced in one step, so a failure leaves the store as it was.
+/// The caller switches the store over to the new key afterwards.
+fn rekey_store(secure_dir: &Path, new_master: &[u8; 32]) -> Result<usize, String> {
+    let vault = load_vault(secure_dir)?;
+    let new_key = ContextKey::from_master(new_master, KeyContext::SecureValues);
+
+    vault::save(secure_dir, &vault, &new_key)?;
+    write_secure_file(
+        &secure_dir.join(KEY_CHECK_FILE_NAME),
+        crypto::key_fingerprint(new_master).as_bytes(),
+    )?;
+
+    Ok(vault.entries.len())
+}
+
+/// Record `params` and switch the store over to `key`, derived from them.
+/// The params are written first: a store encrypted under a key whose salt
+/// was lost could never be opened again. If switching fails, the previous
+/// params, if any, are put back.
+fn apply_passphrase(
+    secure_dir: &Path,
+    params: &crypto::PassphraseKeyParams,
+    key: &[u8; 32],
+) -> Result<(), String> {
+    let path = secure_dir.join(PASSPHRASE_FILE_NAME);
+    let previous = fs::read(&path).ok();
+    let contents = serde_json::to_string_pretty(params)
+        .map_err(|e| format!("Failed to serialize passphrase settings: {}", e))?;
+    replace_secure_file(&path, contents.as_bytes())?;
+
+    if let Err(e) = rekey_store(secure_dir, key) {
+        let _ = match previous {
+            Some(previous) => replace_secure_file(&path, &previous),
+            None => fs::remove_file(&path).map_err(|e| e.to_string()),
+        };
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Whether the store is still encrypted with the machine key although it
+/// has passphrase settings, i.e. the app quit while a passphrase was being
+/// set up
+fn passphrase_never_applied(secure_dir: &Path) -> bool {
+    let stored = fs::read_to_string(secure_dir.join(KEY_CHECK_FILE_NAME));
+    let machine_key = crypto::machine_key();
+    match (stored, machine_key) {
+        (Ok(stored), Ok(machine_key)) => stored.trim() == crypto::key_fingerprint(&machine_key),
+        _ => false,
+    }
+}
+
+/// Protect secure storage with a passphrase. The key is derived with
+/// Argon2id and only kept in memory, so after a restart the store stays
+/// locked until `unlock_secure_storage`. With `bind_to_machine` the machine
+/// key is needed as well, so a copied store cannot be opened elsewhere.
+#[tauri::command]
+pub async fn enable_secure_passphrase(
+    app: AppHandle,
+    passphrase: String,
+    bind_to_machine: bool,
+) -> Result<(), String> {
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+        let params = crypto::PassphraseKeyParams::new(bind_to_machine);
+        let key = crypto::derive_store_key(&passphrase, &params)?;
+
+        let _guard = VAULT_LOCK.lock();
+        clear_value_cache(&app);
+        apply_passphrase(&secure_dir, &params, &key)?;
+        crypto::unlock_store(key);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Remove the passphrase and go back to the machine key. The store must be
+/// unlocked.
+#[tauri::command]
+pub async fn disable_secure_passphrase(app: AppHandle) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+        let machine_key = crypto::machine_key()?;
+
+        let _guard = VAULT_LOCK.lock();
+        if load_passphrase_params(&secure_dir)?.is_none() {
+            return Err("Secure storage has no passphrase".to_string());
+        }
+        clear_value_cache(&app);
+        rekey_store(&secure_dir, &machine_key)?;
+
+        fs::remove_file(secure_dir.join(PASSPHRASE_FILE_NAME))
+            .map_err(|e| format!("Failed to remove passphrase settings: {}", e))?;
+        crypto::use_machine_key();
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Derive the key from the passphrase and keep it in memory until the app
+/// quits or `lock_secure_storage` is called
+#[tauri::command]
+pub async fn unlock_secure_storage(app: AppHandle, passphrase: String) -> Result<(), String> {
+    let passphrase = Zeroizing::new(passphrase);
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+        let params = load_passphrase_params(&secure_dir)?
+            .ok_or_else(|| "Secure storage has no passphrase".to_string())?;
+        let key = crypto::derive_store_key(&passphrase, &params)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let stored = fs::read_to_string(secure_dir.join(KEY_CHECK_FILE_NAME))
+            .map_err(|e| format!("Failed to read key check: {}", e))?;
+        if stored.trim() != crypto::key_fingerprint(&key) {
+            return Err("Wrong passphrase".to_string());
+        }
+
+        crypto::unlock_store(key);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Drop cached secure values and the cached machine key from memory
+#[tauri::command]
+pub fn clear_secure_cache(cache: tauri::State<SecureCache>) {
+    cache.clear();
+}
+
+/// Drop everything secure storage keeps in memory. A passphrase-protected
+/// store also forgets its key and stays unavailable until the passphrase is
+/// entered again.
+#[tauri::command]
+pub async fn lock_secure_storage(app: AppHandle) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        app.state::<SecureCache>().clear();
+        if load_passphrase_params(&secure_dir)?.is_some() {
+            crypto::lock_store();
+            let _ = app.emit("secure-storage-locked", ());
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// The fallback key in `path`, created there if no machine ID is available.
+/// `None` when the machine key works and no fallback key was ever needed.
+/// Once created, the fallback key is kept even if a machine ID shows up
+/// later, since the store is encrypted with it.
+fn load_fallback_key(
+    path: &Path,
+    machine_id_available: bool,
+) -> Result<Option<crypto::Key>, String> {
+    if path.exists() {
+        let encoded = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read fallback key: {}", e))?;
+        return crypto::decode_recovery_key(&encoded)
+            .map(Some)
+            .map_err(|e| format!("Invalid fallback key: {}", e));
+    }
+    if machine_id_available {
+        return Ok(None);
+    }
+
+    let key = crypto::generate_key();
+    let encoded = Zeroizing::new(base64::engine::general_purpose::STANDARD.encode(key.as_slice()));
+    write_secure_file(path, encoded.as_bytes())?;
+    Ok(Some(key))
+}
+
+/// Pick the key backend at launch, before anything is decrypted: the
+/// machine key, kept in `SecureCache`, or the fallback key file where there
+/// is no machine ID
+pub fn init_key_backend(app: &AppHandle) {
+    let handle = app.clone();
+    crypto::set_machine_key_source(move || handle.state::<SecureCache>().machine_key());
+
+    let storage_dir = match profile::storage_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Failed to set up the fallback key: {}", e);
+            return;
+        }
+    };
+    let path = storage_dir.join(FALLBACK_KEY_FILE_NAME);
+    if !path.exists() && crypto::machine_id_available() {
+        return;
+    }
+
+    let result = load_fallback_key(&path, false);
+    match result {
+        Ok(Some(key)) => crypto::use_fallback_key(key),
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to set up the fallback key: {}", e),
+    }
+}
+
+/// Which key protects secure storage
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureStorageBackend {
+    pub backend: crypto::KeyBackend,
+    /// The key can be read by anyone with access to the app data directory
+    pub reduced_security: bool,
+    pub description: String,
+}
+
+/// Report which key backend is active, so the settings screen can warn
+/// about reduced security
+#[tauri::command]
+pub fn get_secure_storage_backend() -> SecureStorageBackend {
+    let backend = crypto::key_backend();
+    let description = match backend {
+        crypto::KeyBackend::MachineId => "Encrypted with a key derived from this machine's ID",
+        crypto::KeyBackend::KeyFile => {
+            "Reduced security: this machine has no machine ID, so the key is stored in the \
+             app data folder. Anyone who can read that folder can decrypt your secrets. Set \
+             a passphrase to protect them."
+        }
+        crypto::KeyBackend::Passphrase => "Encrypted with a key derived from your passphrase",
+    };
+
+    SecureStorageBackend {
+        backend,
+        reduced_security: backend == crypto::KeyBackend::KeyFile,
+        description: description.to_string(),
+    }
+}
+
+/// Export the current encryption key so the user can keep it somewhere safe
+/// and later recover secure storage with `restore_secure_storage`. The key
+/// opens every secure value, so it is gated like reading them.
+#[tauri::command]
+pub async fn export_recovery_key(app: AppHandle) -> Result<String, String> {
+    biometric::ensure_verified(&app).await?;
+
+    tokio::task::spawn_blocking(crypto::export_recovery_key)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// A passphrase-encrypted copy of all secure values
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SecureBackup {
+    format: String,
+    version: u32,
+    /// Encrypts the values as a JSON object of key to `BackupValue`
+    #[serde(flatten)]
+    sealed: crypto::PassphraseSealed,
+}
+
+/// A value in a backup, with its label, expiry and timestamps. Wiped from
+/// memory when dropped.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupValue {
+    value: String,
+    #[serde(flatten)]
+    meta: vault::ValueMeta,
+}
+
+impl Drop for BackupValue {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Write all secure values to `path`, encrypted with a passphrase instead of
+/// this machine's key so `import_secure_storage` can read them anywhere.
+/// Returns the number of exported values.
+#[tauri::command]
+pub async fn export_secure_storage(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<usize, String> {
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+    biometric::ensure_verified(&app).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let values = {
+            let _guard = VAULT_LOCK.lock();
+            let vault = load_live_vault(&app, &secure_dir)?;
+            let mut values = HashMap::with_capacity(vault.entries.len());
+            for (key, entry) in &vault.entries {
+                let value = entry_value(Some(entry)).map_err(|e| format!("{}: {}", key, e))?;
+                let meta = entry.meta.clone();
+                values.insert(key.clone(), BackupValue { value, meta });
+            }
+            values
+        };
+        secure_audit::record(&app, AuditAction::Read, "export_secure_storage", values.keys(), true);
+
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&values)
+                .map_err(|e| format!("Failed to serialize secure values: {}", e))?,
+        );
+        let backup = SecureBackup {
+            format: BACKUP_FORMAT.to_string(),
+            version: 1,
+            sealed: crypto::seal_with_passphrase(&passphrase, &plaintext)?,
+        };
+        let contents = serde_json::to_string_pretty(&backup)
+            .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+
+        write_secure_file(Path::new(&path), contents.as_bytes())?;
+        Ok(values.len())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Read a backup written by `export_secure_storage` and store its values
+/// with this machine's key. Values already stored under the same keys are
+/// replaced, others are kept. Returns the number of imported values.
+#[tauri::command]
+pub async fn import_secure_storage(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<usize, String> {
+    let passphrase = Zeroizing::new(passphrase);
+    tokio::task::spawn_blocking(move || {
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read backup: {}", e))?;
+        let backup: SecureBackup = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse backup: {}", e))?;
+        if backup.format != BACKUP_FORMAT || backup.version != 1 {
+            return Err("Not a supported secure storage backup".to_string());
+        }
+
+        let plaintext = crypto::open_with_passphrase(&backup.sealed, &passphrase)?;
+        let values: HashMap<String, BackupValue> = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Invalid backup contents: {}", e))?;
+
+        values.keys().try_for_each(|key| check_key(key))?;
+        ensure_keys_allowed(values.keys())?;
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        let mut vault = load_vault(&secure_dir)?;
+        let now = chrono::Utc::now().timestamp();
+        for (key, backup_value) in &values {
+            vault.restore(key, &backup_value.value, backup_value.meta.clone(), now);
+        }
+        save_vault(&app, &secure_dir, &vault)?;
+        secure_audit::record(
+            &app,
+            AuditAction::Write,
+            "import_secure_storage",
+            values.keys(),
+            true,
+        );
+
+        Ok(values.len())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Discard all secure values that can no longer be decrypted and start over
+/// with the current key
+#[tauri::command]
+pub async fn reset_secure_storage(app: AppHandle) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        fs::remove_dir_all(&secure_dir)
+            .map_err(|e| format!("Failed to reset secure storage: {}", e))?;
+        fs::create_dir_all(&secure_dir)
+            .map_err(|e| format!("Failed to create secure directory: {}", e))?;
+
+        // The passphrase went with the store
+        clear_value_cache(&app);
+        crypto::use_machine_key();
+        let no_keys: [&str; 0] = [];
+        secure_audit::record(&app, AuditAction::Delete, "reset_secure_storage", no_keys, true);
+        verify_storage_key(&secure_dir)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Re-encrypt all secure values written under a previous key (given as a
+/// recovery key) with the current key. Returns the number of restored values.
+#[tauri::command]
+pub async fn restore_secure_storage(
+    app: AppHandle,
+    recovery_key: String,
+) -> Result<usize, String> {
+    let recovery_key = Zeroizing::new(recovery_key);
+    tokio::task::spawn_blocking(move || {
+        let old_key = crypto::decode_recovery_key(&recovery_key)?;
+        let secure_dir = get_secure_dir(&app)?;
+
+        let _guard = VAULT_LOCK.lock();
+        clear_value_cache(&app);
+
+        let key_check_path = secure_dir.join(KEY_CHECK_FILE_NAME);
+        if key_check_path.exists() {
+            let stored = fs::read_to_string(&key_check_path)
+                .map_err(|e| format!("Failed to read key check: {}", e))?;
+
+            if stored.trim() != crypto::key_fingerprint(&old_key) {
+                return Err("Recovery key does not match the stored data".to_string());
+            }
+        }
+
+        // A store from before the vault is converted with the old key first
+        let vault = vault::load(
+            &secure_dir,
+            &ContextKey::from_master(&old_key, KeyContext::SecureValues),
+        )?;
+        save_vault(&app, &secure_dir, &vault)?;
+        write_secure_file(&key_check_path, crypto::current_key_fingerprint()?.as_bytes())?;
+        secure_audit::record(
+            &app,
+            AuditAction::Write,
+            "restore_secure_storage",
+            vault.entries.keys(),
+            true,
+        );
+
+        Ok(vault.entries.len())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test helper: create an empty secure directory under the system temp dir
+    fn temp_secure_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("secure-test-{}-{}", name, rand::random::<u64>()));
+        fs::create_dir_all(&dir).expect("Failed to create temp dir");
+        dir
+    }
+
+    #[test]
+    fn test_entry_values() {
+        let mut vault = Vault::default();
+        vault.set("openai_api_key", "sk-test", 500, None);
+        let states = [("plain_key", EntryState::Legacy), ("broken_key", EntryState::Corrupted)];
+        for (key, state) in states {
+            vault.set(key, "data", 500, None);
+            vault.entries.get_mut(key).unwrap().state = state;
+        }
+
+        assert_eq!(entry_value(vault.entries.get("openai_api_key")).unwrap(), "sk-test");
+        assert_eq!(entry_value(vault.entries.get("unknown")), Err(SecureValueError::Missing));
+        assert_eq!(entry_value(vault.entries.get("plain_key")), Err(SecureValueError::Legacy));
+        assert!(matches!(
+            entry_value(vault.entries.get("broken_key")),
+            Err(SecureValueError::Corrupted { .. })
+        ));
+
+        assert_eq!(vault.accept_legacy(), vec!["plain_key"]);
+        assert_eq!(entry_value(vault.entries.get("plain_key")).unwrap(), "data");
+        assert!(vault.accept_legacy().is_empty());
+    }
+
+    #[test]
+    fn test_stored_keys_by_namespace() {
+        let mut vault = Vault::default();
+        for key in ["providers/openai/api_key", "providers/azure/key", "providersx/key", "top"] {
+            check_key(key).unwrap();
+            vault.set(key, "data", 500, None);
+        }
+
+        assert_eq!(stored_keys(&vault, None).len(), 4);
+        assert_eq!(
+            stored_keys(&vault, Some("providers")),
+            vec!["providers/azure/key", "providers/openai/api_key"]
+        );
+        assert_eq!(
+            stored_keys(&vault, Some("providers/openai/")),
+            vec!["providers/openai/api_key"]
+        );
+        assert!(check_key("../escape/key").is_err());
+        assert!(check_key("a//b").is_err());
+    }
+
+    #[test]
+    fn test_secure_json_is_validated() {
+        let stored = r#"{"schema":"providerCredentials","provider":"openai","apiKey":"sk-test"}"#;
+        let SecureJson::ProviderCredentials(credentials) = SecureJson::parse(stored).unwrap();
+        assert_eq!(credentials.api_key, "sk-test");
+        assert_eq!(credentials.region, None);
+        assert_eq!(
+            serde_json::to_string(&SecureJson::ProviderCredentials(credentials)).unwrap(),
+            stored
+        );
+
+        for invalid in [
+            "sk-plain-string",
+            r#"{"schema":"unknown","apiKey":"sk-test"}"#,
+            r#"{"schema":"providerCredentials","provider":"openai"}"#,
+            r#"{"schema":"providerCredentials","provider":"openai","apiKey":"x","extra":1}"#,
+            r#"{"schema":"providerCredentials","provider":"nope","apiKey":"sk-test"}"#,
+            r#"{"schema":"providerCredentials","provider":"openai","apiKey":" "}"#,
+        ] {
+            assert!(SecureJson::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_data_contexts() {
+        let parse = |json: &str| serde_json::from_str::<DataContext>(json);
+        assert_eq!(KeyContext::from(parse(r#""history""#).unwrap()), KeyContext::History);
+        assert_eq!(KeyContext::from(parse(r#""sync""#).unwrap()), KeyContext::Sync);
+        assert!(parse(r#""secureValues""#).is_err());
+        assert!(parse(r#""auditLog""#).is_err());
+    }
+
+    #[test]
+    fn test_secure_cache() {
+        let cache = SecureCache::default();
+        let key = cache.machine_key().expect("Machine ID should be readable");
+        assert_eq!(*cache.machine_key.lock(), Some(key.clone()));
+        cache.values.lock().insert(
+            "api_key".to_string(),
+            CachedValue {
+                value: "sk-test".to_string(),
+                expires_at: None,
+            },
+        );
+
+        cache.clear();
+        assert!(cache.machine_key.lock().is_none());
+        assert!(cache.values.lock().is_empty());
+        assert_eq!(cache.machine_key().unwrap(), key);
+    }
+
+    #[test]
+    fn test_rekey_store() {
+        let secure_dir = temp_secure_dir("rekey");
+        let mut vault = Vault::default();
+        vault.set("openai_api_key", "sk-test", 500, None);
+        let key = ContextKey::current(KeyContext::SecureValues).unwrap();
+        vault::save(&secure_dir, &vault, &key).unwrap();
+
+        let new_master = [7u8; 32];
+        assert_eq!(rekey_store(&secure_dir, &new_master).unwrap(), 1);
+
+        let new_key = ContextKey::from_master(&new_master, KeyContext::SecureValues);
+        let rekeyed = vault::load(&secure_dir, &new_key).unwrap();
+        assert_eq!(rekeyed.entries["openai_api_key"].value, "sk-test");
+        assert_eq!(
+            fs::read_to_string(secure_dir.join(KEY_CHECK_FILE_NAME)).unwrap(),
+            crypto::key_fingerprint(&new_master)
+        );
+        assert_eq!(verify_storage_key(&secure_dir), Err(KEY_MISMATCH_ERROR.to_string()));
+
+        fs::remove_dir_all(&secure_dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_passphrase() {
+        let secure_dir = temp_secure_dir("passphrase");
+        let mut vault = Vault::default();
+        vault.set("openai_api_key", "sk-test", 500, None);
+        let key = ContextKey::current(KeyContext::SecureValues).unwrap();
+        vault::save(&secure_dir, &vault, &key).unwrap();
+        let params = crypto::PassphraseKeyParams::new(false);
+        let passphrase_key = [9u8; 32];
+
+        // A failed switch leaves no settings for a key the store does not use
+        let key_check = secure_dir.join(KEY_CHECK_FILE_NAME);
+        fs::write(&key_check, "other").unwrap();
+        assert!(apply_passphrase(&secure_dir, &params, &passphrase_key).is_err());
+        assert!(!secure_dir.join(PASSPHRASE_FILE_NAME).exists());
+        assert!(vault::load(&secure_dir, &key).is_ok());
+
+        fs::remove_file(&key_check).unwrap();
+        assert!(!passphrase_never_applied(&secure_dir));
+        apply_passphrase(&secure_dir, &params, &passphrase_key).unwrap();
+        let salt = |dir: &Path| load_passphrase_params(dir).unwrap().map(|params| params.salt);
+        assert_eq!(salt(&secure_dir), Some(params.salt.clone()));
+        assert_eq!(
+            fs::read_to_string(&key_check).unwrap(),
+            crypto::key_fingerprint(&passphrase_key)
+        );
+        assert!(!passphrase_never_applied(&secure_dir));
+
+        // A failed change of passphrase keeps the settings of the current one
+        fs::write(&key_check, "other").unwrap();
+        let new_params = crypto::PassphraseKeyParams::new(false);
+        assert!(apply_passphrase(&secure_dir, &new_params, &[3u8; 32]).is_err());
+        assert_eq!(salt(&secure_dir), Some(params.salt));
+
+        fs::remove_dir_all(&secure_dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_access_check() {
+        let secure_dir = temp_secure_dir("self-test");
+        assert!(write_access_check(&secure_dir).is_ok());
+        assert!(!secure_dir.join(SELF_TEST_FILE_NAME).exists());
+        assert!(write_access_check(&secure_dir.join("missing")).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let private = secure_dir.join(KEY_CHECK_FILE_NAME);
+            write_secure_file(&private, b"fingerprint").unwrap();
+            let missing = secure_dir.join(FALLBACK_KEY_FILE_NAME);
+            assert!(file_permissions_check(&[private.clone(), missing]).is_ok());
+
+            let shared = secure_dir.join(vault::VAULT_FILE_NAME);
+            fs::write(&shared, "{}").unwrap();
+            fs::set_permissions(&shared, fs::Permissions::from_mode(0o644)).unwrap();
+            let error = file_permissions_check(&[private, shared]).unwrap_err();
+            assert!(error.contains(vault::VAULT_FILE_NAME) && !error.contains(KEY_CHECK_FILE_NAME));
+        }
+
+        fs::remove_dir_all(&secure_dir).unwrap();
+    }
+
+    #[test]
+    fn test_fallback_key_file() {
+        let dir = temp_secure_dir("fallback");
+        let path = dir.join(FALLBACK_KEY_FILE_NAME);
+
+        assert_eq!(load_fallback_key(&path, true).unwrap(), None);
+        assert!(!path.exists());
+
+        let key = load_fallback_key(&path, false).unwrap().unwrap();
+        assert_eq!(load_fallback_key(&path, true).unwrap(), Some(key));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,512 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::audio::{self, AudioRecorder, RecordingConfig, VadConfig, SPEECH_SAMPLE_RATE};
+use crate::auto_transcribe::OutputAction;
+use crate::transcription::{self, jobs, provider, provider::ProviderRequest, Source};
+use crate::{settings, transcript};
+
+/// A step of a dictation profile. Stages run in the order listed, which must
+/// follow capture → VAD → transcribe → format → output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum StageConfig {
+    /// Record with these processing options until `stop_dictation`
+    Capture {
+        #[serde(default)]
+        config: RecordingConfig,
+    },
+    /// Drop pauses from the recording before it is transcribed
+    Vad {
+        #[serde(default)]
+        vad: VadConfig,
+    },
+    /// Transcribe with any engine that transcribes files
+    Transcribe { provider: ProviderRequest },
+    /// Write out spoken URLs, emails and code sections; see `format_transcript`
+    Format,
+    /// Hand the transcript on; a profile may have several outputs
+    Output { action: OutputAction },
+}
+
+impl StageConfig {
+    fn name(&self) -> &'static str {
+        match self {
+            StageConfig::Capture { .. } => "capture",
+            StageConfig::Vad { .. } => "vad",
+            StageConfig::Transcribe { .. } => "transcribe",
+            StageConfig::Format => "format",
+            StageConfig::Output { .. } => "output",
+        }
+    }
+
+    /// Position in the graph; only outputs may repeat
+    fn rank(&self) -> u8 {
+        match self {
+            StageConfig::Capture { .. } => 0,
+            StageConfig::Vad { .. } => 1,
+            StageConfig::Transcribe { .. } => 2,
+            StageConfig::Format => 3,
+            StageConfig::Output { .. } => 4,
+        }
+    }
+}
+
+/// The stages one dictation runs through
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PipelineProfile {
+    pub stages: Vec<StageConfig>,
+}
+
+impl PipelineProfile {
+    /// Check the stages form a pipeline that can run
+    fn validate(&self) -> Result<(), String> {
+        match self.stages.first() {
+            Some(StageConfig::Capture { .. }) => {}
+            _ => return Err("A profile must start with a capture stage".to_string()),
+        }
+        for pair in self.stages.windows(2) {
+            let (before, after) = (&pair[0], &pair[1]);
+            if after.rank() < before.rank() || (after.rank() == before.rank() && after.rank() < 4) {
+                return Err(format!(
+                    "The {} stage cannot follow the {} stage",
+                    after.name(),
+                    before.name()
+                ));
+            }
+        }
+
+        let transcribes = self
+            .stages
+            .iter()
+            .any(|stage| matches!(stage, StageConfig::Transcribe { .. }));
+        if let Some(stage) = self.stages.iter().find(|stage| stage.rank() > 2) {
+            if !transcribes {
+                return Err(format!(
+                    "The {} stage needs a transcribe stage",
+                    stage.name()
+                ));
+            }
+        }
+        for stage in &self.stages {
+            if let StageConfig::Transcribe { provider } = stage {
+                if !provider::get(&provider.provider)?.capabilities().file {
+                    return Err(format!(
+                        "{} cannot transcribe recordings",
+                        provider.provider
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StageState {
+    Started,
+    Completed,
+    Failed,
+}
+
+/// Payload of `dictation-stage`, emitted as each stage starts and ends
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StageEvent {
+    run_id: String,
+    /// Position of the stage in the profile
+    index: usize,
+    stage: &'static str,
+    state: StageState,
+    /// Set once the stage ended
+    duration_ms: Option<u64>,
+    /// e.g. the transcription job id or how much silence was removed
+    detail: Option<String>,
+    error: Option<String>,
+}
+
+/// How long a stage took
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageMetric {
+    pub stage: &'static str,
+    pub duration_ms: u64,
+}
+
+/// Payload of `dictation-completed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DictationCompleted {
+    run_id: String,
+    profile: String,
+    /// Transcript after the last text stage, if the profile transcribes
+    text: Option<String>,
+    metrics: Vec<StageMetric>,
+    total_ms: u64,
+}
+
+/// Payload of `dictation-failed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DictationFailed {
+    run_id: String,
+    profile: String,
+    stage: &'static str,
+    error: String,
+    /// Stages that completed before the failure
+    metrics: Vec<StageMetric>,
+}
+
+/// A dictation between `start_dictation` and the end of its last stage
+struct Run {
+    run_id: String,
+    profile_name: String,
+    profile: PipelineProfile,
+    started: Instant,
+}
+
+/// The dictation being recorded; processing continues in the background
+/// after `stop_dictation` takes it out
+static RECORDING: Lazy<Mutex<Option<Run>>> = Lazy::new(|| Mutex::new(None));
+
+fn emit_stage(
+    app: &AppHandle,
+    run_id: &str,
+    index: usize,
+    stage: &StageConfig,
+    state: StageState,
+    duration_ms: Option<u64>,
+    outcome: Option<&Result<Option<String>, String>>,
+) {
+    let (detail, error) = match outcome {
+        Some(Ok(detail)) => (detail.clone(), None),
+        Some(Err(error)) => (None, Some(error.clone())),
+        None => (None, None),
+    };
+    let _ = app.emit(
+        "dictation-stage",
+        StageEvent {
+            run_id: run_id.to_string(),
+            index,
+            stage: stage.name(),
+            state,
+            duration_ms,
+            detail,
+            error,
+        },
+    );
+}
+
+/// What the stages after capture work on
+struct Dictation {
+    /// 16 kHz mono
+    samples: Vec<f32>,
+    text: Option<String>,
+}
+
+/// Transcribe as a queued job and wait for it
+async fn transcribe(
+    app: &AppHandle,
+    samples: &[f32],
+    provider: ProviderRequest,
+) -> Result<(String, String), String> {
+    let wav = audio::encode_speech_wav(samples)?;
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let recorder = app.state::<AudioRecorder>();
+    let job_id = transcription::submit_with(
+        app.clone(),
+        &recorder,
+        Source::Wav(wav),
+        provider,
+        move |_, _, outcome| {
+            let _ = sender.send(outcome.clone());
+        },
+    )
+    .await?;
+
+    // The job drops the sender without an outcome when it is cancelled
+    let result = receiver.await.map_err(|_| jobs::CANCELLED.to_string())??;
+    Ok((job_id, result.text))
+}
+
+/// Run one stage after capture, returning a detail for its events
+async fn run_stage(
+    app: &AppHandle,
+    stage: &StageConfig,
+    dictation: &mut Dictation,
+) -> Result<Option<String>, String> {
+    match stage {
+        StageConfig::Capture { .. } => Err("Only the first stage may capture".to_string()),
+        StageConfig::Vad { vad } => {
+            let samples = std::mem::take(&mut dictation.samples);
+            let before = samples.len();
+            let vad = vad.clone();
+            dictation.samples =
+                tokio::task::spawn_blocking(move || audio::remove_silence(&samples, &vad))
+                    .await
+                    .map_err(|e| format!("Task failed: {}", e))?;
+
+            let removed = before.saturating_sub(dictation.samples.len());
+            Ok(Some(format!(
+                "Removed {} ms of silence",
+                removed as u64 * 1000 / SPEECH_SAMPLE_RATE as u64
+            )))
+        }
+        StageConfig::Transcribe { provider } => {
+            let (job_id, text) = transcribe(app, &dictation.samples, provider.clone()).await?;
+            dictation.text = Some(text);
+            Ok(Some(format!("Job {}", job_id)))
+        }
+        StageConfig::Format => {
+            let text = dictation.text.as_deref().ok_or("Nothing was transcribed")?;
+            dictation.text = Some(transcript::format_text(text));
+            Ok(None)
+        }
+        StageConfig::Output { action } => {
+            let text = dictation.text.clone().ok_or("Nothing was transcribed")?;
+            if *action == OutputAction::Copy {
+                transcript::copy_transcript(text).await?;
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Run the stages after capture, emitting `dictation-stage` for each and
+/// `dictation-completed` or `dictation-failed` at the end
+async fn process(app: AppHandle, run: Run, capture_ms: u64, samples: Vec<f32>) {
+    let mut metrics = vec![StageMetric {
+        stage: "capture",
+        duration_ms: capture_ms,
+    }];
+    let mut dictation = Dictation {
+        samples,
+        text: None,
+    };
+
+    for (index, stage) in run.profile.stages.iter().enumerate().skip(1) {
+        emit_stage(
+            &app,
+            &run.run_id,
+            index,
+            stage,
+            StageState::Started,
+            None,
+            None,
+        );
+        let started = Instant::now();
+        let outcome = run_stage(&app, stage, &mut dictation).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let state = match outcome {
+            Ok(_) => StageState::Completed,
+            Err(_) => StageState::Failed,
+        };
+        emit_stage(
+            &app,
+            &run.run_id,
+            index,
+            stage,
+            state,
+            Some(duration_ms),
+            Some(&outcome),
+        );
+
+        if let Err(error) = outcome {
+            let _ = app.emit(
+                "dictation-failed",
+                DictationFailed {
+                    run_id: run.run_id,
+                    profile: run.profile_name,
+                    stage: stage.name(),
+                    error,
+                    metrics,
+                },
+            );
+            return;
+        }
+        metrics.push(StageMetric {
+            stage: stage.name(),
+            duration_ms,
+        });
+    }
+
+    let _ = app.emit(
+        "dictation-completed",
+        DictationCompleted {
+            run_id: run.run_id,
+            profile: run.profile_name,
+            text: dictation.text,
+            metrics,
+            total_ms: run.started.elapsed().as_millis() as u64,
+        },
+    );
+}
+
+fn load_profile(app: &AppHandle, name: &str) -> Result<PipelineProfile, String> {
+    settings::load_settings(app)?
+        .pipeline_profiles
+        .remove(name)
+        .ok_or_else(|| format!("Unknown pipeline profile: {}", name))
+}
+
+/// Dictation profiles by name
+#[tauri::command]
+pub fn list_pipeline_profiles(app: AppHandle) -> Result<BTreeMap<String, PipelineProfile>, String> {
+    Ok(settings::load_settings(&app)?.pipeline_profiles)
+}
+
+/// Save a dictation profile, or delete it with `None`
+#[tauri::command]
+pub fn set_pipeline_profile(
+    app: AppHandle,
+    name: String,
+    profile: Option<PipelineProfile>,
+) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        match profile {
+            Some(profile) => {
+                profile.validate()?;
+                current.pipeline_profiles.insert(name, profile);
+            }
+            None => {
+                current.pipeline_profiles.remove(&name);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Start a dictation with a profile: its capture stage starts recording at
+/// once, the other stages run after `stop_dictation`. Progress arrives as
+/// `dictation-stage` events with each stage's duration, the result as
+/// `dictation-completed` or `dictation-failed`. Returns the run id.
+#[tauri::command]
+pub fn start_dictation(
+    app: AppHandle,
+    recorder: tauri::State<AudioRecorder>,
+    profile: String,
+) -> Result<String, String> {
+    let mut recording = RECORDING.lock();
+    if recording.is_some() {
+        return Err("A dictation is already being recorded".to_string());
+    }
+
+    let profile_name = profile;
+    let profile = load_profile(&app, &profile_name)?;
+    profile.validate()?;
+    let Some(capture @ StageConfig::Capture { config }) = profile.stages.first() else {
+        return Err("A profile must start with a capture stage".to_string());
+    };
+
+    let run_id = transcription::generate_job_id();
+    emit_stage(&app, &run_id, 0, capture, StageState::Started, None, None);
+    if let Err(e) = audio::start_recording(app.clone(), recorder, Some(config.clone()), None, None)
+    {
+        let outcome = Err(e.clone());
+        emit_stage(
+            &app,
+            &run_id,
+            0,
+            capture,
+            StageState::Failed,
+            Some(0),
+            Some(&outcome),
+        );
+        return Err(e);
+    }
+
+    *recording = Some(Run {
+        run_id: run_id.clone(),
+        profile_name,
+        profile,
+        started: Instant::now(),
+    });
+    Ok(run_id)
+}
+
+/// Stop recording the current dictation and run the rest of its profile in
+/// the background; see `start_dictation`
+#[tauri::command]
+pub fn stop_dictation(
+    app: AppHandle,
+    recorder: tauri::State<AudioRecorder>,
+) -> Result<String, String> {
+    let run = RECORDING
+        .lock()
+        .take()
+        .ok_or("No dictation is being recorded")?;
+    let capture = &run.profile.stages[0];
+    let capture_ms = run.started.elapsed().as_millis() as u64;
+
+    let stopped = audio::finish_recording(&recorder, None, None)
+        .and_then(|_| audio::last_take_speech_samples(&recorder));
+    let outcome = stopped.as_ref().map(|_| None).map_err(String::clone);
+    let state = match outcome {
+        Ok(_) => StageState::Completed,
+        Err(_) => StageState::Failed,
+    };
+    emit_stage(
+        &app,
+        &run.run_id,
+        0,
+        capture,
+        state,
+        Some(capture_ms),
+        Some(&outcome),
+    );
+    let samples = stopped?;
+
+    let run_id = run.run_id.clone();
+    tauri::async_runtime::spawn(process(app, run, capture_ms, samples));
+    Ok(run_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(json: &str) -> PipelineProfile {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_profile_order() {
+        let valid = profile(
+            r#"{ "stages": [
+                { "type": "capture", "config": { "targetSampleRate": 16000 } },
+                { "type": "vad" },
+                { "type": "transcribe", "provider": { "provider": "openai" } },
+                { "type": "format" },
+                { "type": "output", "action": "copy" },
+                { "type": "output", "action": "event" }
+            ] }"#,
+        );
+        assert!(valid.validate().is_ok());
+
+        let no_capture = profile(r#"{ "stages": [{ "type": "format" }] }"#);
+        assert!(no_capture.validate().is_err());
+
+        let reversed = profile(
+            r#"{ "stages": [
+                { "type": "capture" },
+                { "type": "transcribe", "provider": { "provider": "openai" } },
+                { "type": "vad" }
+            ] }"#,
+        );
+        assert_eq!(
+            reversed.validate().unwrap_err(),
+            "The vad stage cannot follow the transcribe stage"
+        );
+
+        let untranscribed =
+            profile(r#"{ "stages": [{ "type": "capture" }, { "type": "format" }] }"#);
+        assert!(untranscribed.validate().is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::files;
+use crate::profile;
+use crate::secure_delete;
+use crate::settings;
+use transcriber_core::crypto::{self, KeyContext, Zeroizing};
+use transcriber_core::history;
+
+/// Folder in the profile's storage directory with one encrypted file per draft
+const DRAFTS_DIR: &str = "drafts";
+
+const DRAFT_EXTENSION: &str = "draft";
+
+/// Longest accepted draft id
+const MAX_DRAFT_ID_LEN: usize = 64;
+
+/// Serializes writes and deletions of drafts
+static DRAFTS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// The transcript being worked on, as last pushed by the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    /// Chosen by the frontend, e.g. the id of the recording
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub text: String,
+    /// RFC 3339 of the first save
+    pub created_at: String,
+    /// RFC 3339 of the last save
+    pub updated_at: String,
+}
+
+pub fn drafts_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = profile::storage_dir(app)?.join(DRAFTS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create drafts directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Draft ids end up in a file name, so only a safe set of characters is
+/// accepted
+fn draft_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_DRAFT_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid draft id: {}", id));
+    }
+    Ok(dir.join(format!("{}.{}", id, DRAFT_EXTENSION)))
+}
+
+fn read_draft(path: &Path) -> Result<Draft, String> {
+    let encrypted = fs::read_to_string(path).map_err(|e| format!("Failed to read draft: {}", e))?;
+    let json = crypto::decrypt(KeyContext::History, &encrypted)
+        .map_err(|e| format!("Failed to decrypt draft: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid draft: {}", e))
+}
+
+/// Encrypt `text` into the draft `id` in `dir`, keeping its creation time
+fn write_draft(
+    dir: &Path,
+    id: &str,
+    title: Option<String>,
+    text: String,
+    now: &str,
+) -> Result<(), String> {
+    let path = draft_path(dir, id)?;
+    let created_at = read_draft(&path)
+        .map(|draft| draft.created_at)
+        .unwrap_or_else(|_| now.to_string());
+    let draft = Draft {
+        id: id.to_string(),
+        title,
+        text,
+        created_at,
+        updated_at: now.to_string(),
+    };
+
+    let json = Zeroizing::new(
+        serde_json::to_vec(&draft).map_err(|e| format!("Failed to serialize draft: {}", e))?,
+    );
+    let encrypted = crypto::encrypt(KeyContext::History, &json)?;
+    files::write_atomic(&path, encrypted.as_bytes())
+        .map_err(|e| format!("Failed to write draft: {}", e))
+}
+
+/// All drafts in `dir` that decrypt, most recently saved first
+fn list_drafts(dir: &Path) -> Result<Vec<Draft>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read drafts: {}", e))?;
+    let mut drafts = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(DRAFT_EXTENSION) {
+            continue;
+        }
+        match read_draft(&path) {
+            Ok(draft) => drafts.push(draft),
+            Err(e) => eprintln!("Skipping draft {}: {}", path.display(), e),
+        }
+    }
+    drafts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(drafts)
+}
+
+/// Delete the drafts in `dir` created more than `max_days` before `now`,
+/// returning how many were removed
+pub fn prune_expired(
+    dir: &Path,
+    max_days: u32,
+    now: chrono::DateTime<chrono::Utc>,
+    secure: bool,
+) -> Result<usize, String> {
+    let _lock = DRAFTS_LOCK.lock();
+    let mut removed = 0;
+    for draft in list_drafts(dir)? {
+        if history::is_expired(&draft.created_at, max_days, now) {
+            secure_delete::delete_path(&draft_path(dir, &draft.id)?, secure)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Store the transcript being worked on, encrypted, so it survives a crash.
+/// The frontend calls this periodically while the user edits and
+/// `discard_draft` once the transcript is saved for good.
+#[tauri::command]
+pub async fn autosave_draft(
+    app: AppHandle,
+    id: String,
+    title: Option<String>,
+    text: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let dir = drafts_dir(&app)?;
+        let _lock = DRAFTS_LOCK.lock();
+        write_draft(&dir, &id, title, text, &chrono::Utc::now().to_rfc3339())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Drafts left behind, e.g. by a crash, most recently saved first, with
+/// their text to restore
+#[tauri::command]
+pub async fn recover_drafts(app: AppHandle) -> Result<Vec<Draft>, String> {
+    tokio::task::spawn_blocking(move || list_drafts(&drafts_dir(&app)?))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Delete a draft that was saved or is no longer wanted, overwriting it
+/// first when secure deletion is on
+#[tauri::command]
+pub async fn discard_draft(app: AppHandle, id: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let path = draft_path(&drafts_dir(&app)?, &id)?;
+        let secure = settings::load_effective_settings(&app)?.secure_delete;
+
+        let _lock = DRAFTS_LOCK.lock();
+        if path.exists() {
+            secure_delete::delete_path(&path, secure)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drafts_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("drafts-test-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let first = "2026-10-15T09:00:00+00:00";
+        write_draft(&dir, "rec-1", None, "Hello".to_string(), first).unwrap();
+        write_draft(
+            &dir,
+            "rec-2",
+            None,
+            "Other".to_string(),
+            "2026-10-15T09:01:00+00:00",
+        )
+        .unwrap();
+        let last = "2026-10-15T09:02:00+00:00";
+        write_draft(
+            &dir,
+            "rec-1",
+            Some("Call".to_string()),
+            "Hello world".to_string(),
+            last,
+        )
+        .unwrap();
+        fs::write(dir.join("broken.draft"), "not encrypted").unwrap();
+
+        let contents = fs::read_to_string(dir.join("rec-1.draft")).unwrap();
+        assert!(!contents.contains("Hello"));
+
+        let drafts = list_drafts(&dir).unwrap();
+        assert_eq!(drafts.len(), 2);
+        assert_eq!(drafts[0].id, "rec-1");
+        assert_eq!(drafts[0].text, "Hello world");
+        assert_eq!(drafts[0].title.as_deref(), Some("Call"));
+        assert_eq!(drafts[0].created_at, first);
+        assert_eq!(drafts[0].updated_at, last);
+
+        assert!(write_draft(&dir, "../escape", None, String::new(), last).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_expired() {
+        let dir = std::env::temp_dir().join(format!("drafts-prune-test-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+
+        write_draft(
+            &dir,
+            "old",
+            None,
+            "Old".to_string(),
+            "2026-10-01T09:00:00+00:00",
+        )
+        .unwrap();
+        write_draft(
+            &dir,
+            "new",
+            None,
+            "New".to_string(),
+            "2026-10-14T09:00:00+00:00",
+        )
+        .unwrap();
+
+        let now = chrono::DateTime::parse_from_rfc3339("2026-10-15T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(prune_expired(&dir, 7, now, false), Ok(1));
+
+        let drafts = list_drafts(&dir).unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].id, "new");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+use tauri::{AppHandle, Emitter};
+use transcriber_core::{EventSink, Events};
+
+/// Forwards the engine's events to the frontend
+struct AppEvents(AppHandle);
+
+impl EventSink for AppEvents {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.0.emit(event, payload);
+    }
+}
+
+/// Events the engine reports, emitted to the frontend as they are
+pub fn app_events(app: &AppHandle) -> Events {
+    Events::new(AppEvents(app.clone()))
+}
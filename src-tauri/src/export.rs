@@ -0,0 +1,277 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tera::{Context, Tera};
+
+use crate::analytics::{self, SpeakerTalkTime};
+use crate::policy;
+use crate::timestamps::{self, Formatter};
+use crate::transcript::Segment;
+use crate::transcription::TranscriptSegment;
+use transcriber_core::history;
+
+/// A user template in the templates directory, e.g. `meeting-notes.md`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTemplate {
+    /// File name, passed back to `render_export_template`
+    name: String,
+    /// Extension of the exported file, taken from the template's own
+    extension: Option<String>,
+}
+
+/// Result of `list_export_templates`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTemplates {
+    /// Where users drop their template files
+    dir: String,
+    templates: Vec<ExportTemplate>,
+}
+
+/// Values a template can use
+#[derive(Debug, Serialize)]
+struct TemplateContext<'a> {
+    /// The entry as stored by the frontend (title, summary, tags, data, ...)
+    entry: &'a serde_json::Value,
+    /// Prose and code parts of the transcript
+    segments: Vec<Segment>,
+    speakers: &'a serde_json::Value,
+    /// Per-speaker talk time, when the entry has diarized segments
+    talk_time: Vec<SpeakerTalkTime>,
+    highlights: &'a serde_json::Value,
+    exported_at: String,
+}
+
+/// Template names come from the frontend; only plain file names inside the
+/// templates directory are accepted
+fn template_name(name: &str) -> Result<&str, String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '));
+
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!("Invalid template name: {}", name))
+    }
+}
+
+/// Get (and create) the directory holding user templates
+fn get_templates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("templates");
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create templates directory: {}", e))?;
+
+    Ok(dir)
+}
+
+/// File names of all templates in `dir`, sorted
+fn template_files(dir: &Path) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read templates directory: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| template_name(name).is_ok())
+        .collect();
+
+    names.sort();
+    Ok(names)
+}
+
+/// Tera's top-level error only says which template failed; the cause is in the chain
+fn describe(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Render `name` with all templates in `dir` loaded, so templates can
+/// `{% include %}` or `{% extends %}` one another
+fn render(
+    dir: &Path,
+    name: &str,
+    entry: &serde_json::Value,
+    formatter: &Formatter,
+) -> Result<String, String> {
+    let name = template_name(name)?;
+
+    let mut templates = Vec::new();
+    for file in template_files(dir)? {
+        let content = fs::read_to_string(dir.join(&file))
+            .map_err(|e| format!("Failed to read template {}: {}", file, e))?;
+        templates.push((file, content));
+    }
+    if !templates.iter().any(|(file, _)| file == name) {
+        return Err(format!("Template not found: {}", name));
+    }
+
+    let mut tera = Tera::default();
+    tera.add_raw_templates(templates)
+        .map_err(|e| format!("Failed to parse templates: {}", describe(&e)))?;
+    // `{{ entry.createdAt | local_time }}` in the user's time zone and locale
+    let local_time = formatter.clone();
+    tera.register_filter(
+        "local_time",
+        move |value: &tera::Value, _: &HashMap<String, tera::Value>| {
+            let timestamp = tera::try_get_value!("local_time", "value", String, value);
+            local_time
+                .format(&timestamp)
+                .map(|formatted| tera::Value::String(formatted.display))
+                .map_err(tera::Error::msg)
+        },
+    );
+
+    let transcript = entry
+        .get("originalTranscript")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default();
+    let empty = serde_json::Value::Array(Vec::new());
+    let timed_segments: Vec<TranscriptSegment> = entry
+        .get("segments")
+        .and_then(|segments| serde_json::from_value(segments.clone()).ok())
+        .unwrap_or_default();
+
+    let context = Context::from_serialize(TemplateContext {
+        entry,
+        segments: crate::transcript::segments(transcript),
+        speakers: entry.get("speakers").unwrap_or(&empty),
+        talk_time: analytics::talk_time(&timed_segments),
+        highlights: entry.get("highlights").unwrap_or(&empty),
+        exported_at: formatter.now(),
+    })
+    .map_err(|e| format!("Failed to build template context: {}", describe(&e)))?;
+
+    tera.render(name, &context)
+        .map_err(|e| format!("Failed to render template: {}", describe(&e)))
+}
+
+/// List the user's export templates
+#[tauri::command]
+pub async fn list_export_templates(app: AppHandle) -> Result<ExportTemplates, String> {
+    tokio::task::spawn_blocking(move || {
+        let dir = get_templates_dir(&app)?;
+        let templates = template_files(&dir)?
+            .into_iter()
+            .map(|name| ExportTemplate {
+                extension: Path::new(&name)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_string()),
+                name,
+            })
+            .collect();
+
+        Ok(ExportTemplates {
+            dir: dir.to_string_lossy().to_string(),
+            templates,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Render an entry with a user template. Templates are read on every call, so
+/// edits show up without restarting the app.
+#[tauri::command]
+pub async fn render_export_template(
+    app: AppHandle,
+    template: String,
+    entry: serde_json::Value,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let formatter = timestamps::formatter(&app);
+        render(&get_templates_dir(&app)?, &template, &entry, &formatter)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Export history entries (all, or the user's selection) to a standalone SQLite
+/// database at `path`; the schema is documented in `core/src/history.rs`.
+/// Entries older than the managed `MaxHistoryDays` are left out.
+#[tauri::command]
+pub async fn export_sqlite(
+    app: AppHandle,
+    path: String,
+    mut entries: Vec<history::HistoryEntry>,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(max_days) = policy::current().max_history_days {
+            history::retain_recent(&mut entries, max_days, chrono::Utc::now());
+        }
+        history::export(Path::new(&path), &entries, &timestamps::formatter(&app))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_templates_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("export-templates-test-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_render_with_entry_and_segments() {
+        let dir = temp_templates_dir("render");
+        fs::write(dir.join("header.md"), "# {{ entry.title }}").unwrap();
+        fs::write(
+            dir.join("notes.md"),
+            concat!(
+                "{% include \"header.md\" %}\n",
+                "{% for tag in entry.tags %}#{{ tag }} {% endfor %}\n",
+                "{% for segment in segments %}",
+                "[{{ segment.kind }}] {{ segment.text }}\n",
+                "{% endfor %}",
+                "{{ speakers | length }}\n",
+                "{% for s in talk_time %}{{ s.speaker }}={{ s.talkTimeMs }} {% endfor %}\n",
+                "{{ entry.createdAt | local_time }}"
+            ),
+        )
+        .unwrap();
+
+        let entry = serde_json::json!({
+            "title": "Standup",
+            "createdAt": "2024-05-01T22:30:00.000Z",
+            "tags": ["team", "daily"],
+            "originalTranscript": "Run this. Begin code. ls dash la. End code.",
+            "segments": [
+                { "startMs": 0, "endMs": 1000, "text": "Run this.", "speaker": "A" },
+                { "startMs": 1000, "endMs": 4000, "text": "ls dash la", "speaker": "B" },
+            ],
+        });
+
+        let formatter = Formatter::new(Some("Europe/Berlin"), Some("de-DE")).unwrap();
+        assert_eq!(
+            render(&dir, "notes.md", &entry, &formatter).unwrap(),
+            concat!(
+                "# Standup\n#team #daily \n[prose] Run this.\n[code] ls dash la\n0\n",
+                "B=3000 A=1000 \n02.05.2024 00:30"
+            )
+        );
+        assert!(render(&dir, "missing.md", &entry, &formatter).is_err());
+        assert!(render(&dir, "../notes.md", &entry, &formatter).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
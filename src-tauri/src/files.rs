@@ -0,0 +1,428 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::recent_files;
+use crate::settings;
+use crate::timestamps;
+
+/// A file type offered in the save and open dialogs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDialogFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+impl FileDialogFilter {
+    pub fn new(name: &str, extension: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            extensions: vec![extension.to_string()],
+        }
+    }
+}
+
+/// What happens to an existing file before a save overwrites it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SaveBackup {
+    /// Overwrite it
+    #[default]
+    Off,
+    /// Copy it to `<name>.bak`, replacing the previous backup
+    Bak,
+    /// Copy it to `<name>.<YYYYMMDD-HHMMSS>.bak`, keeping every backup
+    Timestamped,
+}
+
+/// Where `mode` backs up the file at `path`, given the local time `now`
+fn backup_path(path: &Path, mode: SaveBackup, now: &chrono::NaiveDateTime) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy();
+    let backup = match mode {
+        SaveBackup::Off => return None,
+        SaveBackup::Bak => format!("{}.bak", name),
+        SaveBackup::Timestamped => format!("{}.{}.bak", name, now.format("%Y%m%d-%H%M%S")),
+    };
+    Some(path.with_file_name(backup))
+}
+
+/// Copy the file at `path` to its backup before it is overwritten; nothing
+/// to do for a new file
+fn back_up(path: &Path, mode: SaveBackup) -> std::io::Result<()> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    match backup_path(path, mode, &chrono::Local::now().naive_local()) {
+        Some(backup) => fs::copy(path, backup).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Back up files before a save overwrites them, so an accidental overwrite
+/// can be undone
+#[tauri::command]
+pub fn set_save_backup(app: AppHandle, mode: SaveBackup) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        current.save_backup = mode;
+        Ok(())
+    })
+}
+
+/// Show a native save dialog and write `data` to the selected path
+pub fn save_with_dialog(
+    app: &AppHandle,
+    data: &[u8],
+    default_filename: &str,
+    filters: &[FileDialogFilter],
+) -> Result<String, String> {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+    // Build the file dialog with filters
+    let mut dialog = app.dialog().file();
+
+    // Set default filename, in the user's export folder if they chose one
+    dialog = dialog.set_file_name(default_filename);
+    if let Some(folder) = default_export_folder(app) {
+        dialog = dialog.set_directory(folder);
+    }
+
+    // Add file filters - convert Vec<String> to Vec<&str>
+    for filter in filters.iter() {
+        let ext_refs: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
+        dialog = dialog.add_filter(&filter.name, &ext_refs);
+    }
+
+    // Show save dialog and get the selected path
+    let file_path = match dialog.blocking_save_file() {
+        Some(path) => path,
+        None => return Err("User cancelled save dialog".to_string()),
+    };
+
+    // Get the path string
+    let path_string = file_path.to_string();
+    let path = std::path::Path::new(&path_string);
+
+    // Back up an existing file, then write content to the file, replacing an
+    // existing one only once it is complete
+    let mode = settings::load_settings(app)
+        .map(|settings| settings.save_backup)
+        .unwrap_or_default();
+    match back_up(path, mode).and_then(|_| write_atomic(path, data)) {
+        Ok(_) => {
+            recent_files::record(app, &path_string);
+            Ok(path_string)
+        }
+        Err(e) => {
+            // Show error dialog to user
+            app.dialog()
+                .message(format!("Failed to save file: {}", e))
+                .kind(MessageDialogKind::Error)
+                .blocking_show();
+            Err(format!("Failed to write file: {}", e))
+        }
+    }
+}
+
+/// `folder` if it is an existing directory. A folder that was removed or
+/// unmounted since it was chosen is ignored rather than failing the dialog.
+fn existing_dir(folder: Option<&str>) -> Option<PathBuf> {
+    folder
+        .map(PathBuf::from)
+        .filter(|folder| folder.is_dir())
+}
+
+/// The folder save dialogs should open in, if the user chose one and it
+/// still exists
+pub fn default_export_folder(app: &AppHandle) -> Option<PathBuf> {
+    let settings = settings::load_settings(app).ok()?;
+    existing_dir(settings.default_export_folder.as_deref())
+}
+
+/// Get the persisted default export folder, if any
+#[tauri::command]
+pub fn get_default_export_folder(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::load_settings(&app)?.default_export_folder)
+}
+
+/// Persist the folder save dialogs open in (`None` leaves it to the OS)
+#[tauri::command]
+pub fn set_default_export_folder(app: AppHandle, folder: Option<String>) -> Result<(), String> {
+    let folder = folder
+        .map(|folder| folder.trim().to_string())
+        .filter(|folder| !folder.is_empty());
+    if let Some(folder) = &folder {
+        if existing_dir(Some(folder)).is_none() {
+            return Err(format!("Not a folder: {}", folder));
+        }
+    }
+
+    settings::update_settings(&app, |current| {
+        current.default_export_folder = folder;
+        Ok(())
+    })
+}
+
+/// Replace the file at `path` with `data` in one step: write a temporary
+/// file next to it, flush it to disk and rename it over the target. A crash
+/// or full disk mid-write leaves the previous file intact. An existing
+/// file keeps its permissions.
+pub fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Not a file path"))?;
+    let temp_path = dir.join(format!(
+        ".{}.{:016x}.tmp",
+        name.to_string_lossy(),
+        rand::random::<u64>()
+    ));
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    // Make the rename itself survive a crash
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// The text `append_entry` adds to a file: the separator (only when the file
+/// already has content), an optional timestamp line, then `text`, always
+/// starting and ending on a line of its own
+fn format_entry(
+    last_byte: Option<u8>,
+    text: &str,
+    separator: Option<&str>,
+    timestamp: Option<&str>,
+) -> String {
+    let mut entry = String::new();
+    if let Some(last_byte) = last_byte {
+        if last_byte != b'\n' {
+            entry.push('\n');
+        }
+        if let Some(separator) = separator {
+            entry.push_str(separator);
+            if !separator.ends_with('\n') {
+                entry.push('\n');
+            }
+        }
+    }
+    if let Some(timestamp) = timestamp {
+        entry.push_str(timestamp);
+        entry.push('\n');
+    }
+    entry.push_str(text);
+    if !text.ends_with('\n') {
+        entry.push('\n');
+    }
+    entry
+}
+
+/// The last byte of the file at `path`; `None` if it is empty or missing
+fn last_byte(path: &Path) -> Result<Option<u8>, String> {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to open file: {}", e)),
+    };
+    if file
+        .seek(SeekFrom::End(0))
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        == 0
+    {
+        return Ok(None);
+    }
+
+    let mut byte = [0u8; 1];
+    file.seek(SeekFrom::End(-1))
+        .and_then(|_| file.read_exact(&mut byte))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(Some(byte[0]))
+}
+
+/// Append `text` to the file at `path`, creating it if needed
+fn append_entry(
+    path: &Path,
+    text: &str,
+    separator: Option<&str>,
+    timestamp: Option<&str>,
+) -> Result<(), String> {
+    let entry = format_entry(last_byte(path)?, text, separator, timestamp);
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    file.write_all(entry.as_bytes())
+        .map_err(|e| format!("Failed to append to file: {}", e))
+}
+
+/// Append a dictation to a file such as a daily note instead of overwriting
+/// it, optionally after `separator` and a line with the current time in the
+/// user's time zone and locale
+#[tauri::command]
+pub async fn append_to_file(
+    app: AppHandle,
+    file_path: String,
+    text: String,
+    separator: Option<String>,
+    timestamp: Option<bool>,
+) -> Result<String, String> {
+    if file_path.trim().is_empty() {
+        return Err("No file path given".to_string());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let timestamp = if timestamp.unwrap_or(false) {
+            let formatter = timestamps::formatter(&app);
+            Some(formatter.format(&formatter.now())?.display)
+        } else {
+            None
+        };
+
+        append_entry(
+            Path::new(&file_path),
+            &text,
+            separator.as_deref(),
+            timestamp.as_deref(),
+        )?;
+        recent_files::record(&app, &file_path);
+        Ok(file_path)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_back_up() {
+        let dir = std::env::temp_dir().join(format!("backup-test-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transcript.md");
+
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 10, 15)
+            .unwrap()
+            .and_hms_opt(9, 5, 30)
+            .unwrap();
+        assert_eq!(backup_path(&path, SaveBackup::Off, &now), None);
+        assert_eq!(
+            backup_path(&path, SaveBackup::Bak, &now),
+            Some(dir.join("transcript.md.bak"))
+        );
+        assert_eq!(
+            backup_path(&path, SaveBackup::Timestamped, &now),
+            Some(dir.join("transcript.md.20261015-090530.bak"))
+        );
+
+        back_up(&path, SaveBackup::Bak).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::write(&path, "last week").unwrap();
+        back_up(&path, SaveBackup::Bak).unwrap();
+        write_atomic(&path, b"this week").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("transcript.md.bak")).unwrap(),
+            "last week"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "this week");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_existing_dir() {
+        let dir = std::env::temp_dir();
+        let file = dir.join(format!("export-test-{}.txt", rand::random::<u64>()));
+        fs::write(&file, "").unwrap();
+
+        assert_eq!(existing_dir(dir.to_str()), Some(dir.clone()));
+        assert_eq!(existing_dir(file.to_str()), None);
+        assert_eq!(existing_dir(dir.join("missing-folder").to_str()), None);
+        assert_eq!(existing_dir(None), None);
+
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join(format!("atomic-test-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transcript.txt");
+
+        write_atomic(&path, b"first").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert!(write_atomic(&dir.join("missing/transcript.txt"), b"data").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_entry() {
+        let path = std::env::temp_dir().join(format!("append-test-{}.md", rand::random::<u64>()));
+
+        append_entry(
+            &path,
+            "First note",
+            Some("---"),
+            Some("Oct 15, 2026, 9:00 AM"),
+        )
+        .unwrap();
+        append_entry(&path, "Second note\n", Some("---"), None).unwrap();
+        append_entry(&path, "Third note", None, None).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Oct 15, 2026, 9:00 AM\nFirst note\n---\nSecond note\nThird note\n"
+        );
+
+        fs::write(&path, "No trailing newline").unwrap();
+        append_entry(&path, "Appended", Some("\n"), None).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "No trailing newline\n\nAppended\n"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{audio, commands};
+
+/// Free space below which recordings and model downloads may fail
+const LOW_DISK_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+const CRITICAL_DISK_SPACE_BYTES: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Outcome of one startup check
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthItem {
+    /// "microphone", "secureStorage", "models", "diskSpace" or "shortcut"
+    pub id: String,
+    pub status: HealthStatus,
+    pub message: String,
+    /// What the user can do about a warning or error
+    pub action: Option<String>,
+}
+
+impl HealthItem {
+    pub(crate) fn new(
+        id: &str,
+        result: Result<String, String>,
+        failed: HealthStatus,
+        action: &str,
+    ) -> Self {
+        match result {
+            Ok(message) => Self {
+                id: id.to_string(),
+                status: HealthStatus::Ok,
+                message,
+                action: None,
+            },
+            Err(message) => Self {
+                id: id.to_string(),
+                status: failed,
+                message,
+                action: Some(action.to_string()),
+            },
+        }
+    }
+}
+
+/// Payload of the `health-report` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// Worst status of all items
+    pub status: HealthStatus,
+    pub items: Vec<HealthItem>,
+}
+
+/// Outcome of registering the global shortcut, which only happens at startup
+static SHORTCUT: OnceCell<Result<String, String>> = OnceCell::new();
+
+/// Free bytes on the filesystem holding `path`
+#[cfg(unix)]
+fn available_space(path: &Path) -> Result<u64, String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| "Invalid path".to_string())?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is only read on success
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        stat.assume_init()
+    };
+
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn available_space(path: &Path) -> Result<u64, String> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_to_caller: *mut u64,
+            total: *mut u64,
+            total_free: *mut u64,
+        ) -> i32;
+    }
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut free = 0u64;
+    // SAFETY: `path` is NUL-terminated; the other outputs may be null
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            path.as_ptr(),
+            &mut free,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+
+    Ok(free)
+}
+
+/// Free bytes where recordings and models are saved
+pub(crate) fn app_data_space(app: &AppHandle) -> Result<u64, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    // The directory may not exist before the first save
+    let existing = dir.ancestors().find(|dir| dir.exists()).unwrap_or(&dir);
+    available_space(existing)
+}
+
+fn disk_space_item(app: &AppHandle) -> HealthItem {
+    let action = "Free up disk space so recordings and models can be saved";
+    match app_data_space(app) {
+        Ok(bytes) => {
+            let status = disk_space_status(bytes);
+            HealthItem {
+                id: "diskSpace".to_string(),
+                status,
+                message: format!("{:.1} GB free", bytes as f64 / 1e9),
+                action: (status != HealthStatus::Ok).then(|| action.to_string()),
+            }
+        }
+        Err(e) => HealthItem::new(
+            "diskSpace",
+            Err(format!("Failed to check free disk space: {}", e)),
+            HealthStatus::Warning,
+            action,
+        ),
+    }
+}
+
+pub(crate) fn disk_space_status(available_bytes: u64) -> HealthStatus {
+    if available_bytes < CRITICAL_DISK_SPACE_BYTES {
+        HealthStatus::Error
+    } else if available_bytes < LOW_DISK_SPACE_BYTES {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Ok
+    }
+}
+
+fn models_item(app: &AppHandle) -> Option<HealthItem> {
+    if !cfg!(feature = "local-whisper") {
+        return None;
+    }
+
+    let result = crate::transcription::models::installed_models(app).and_then(|models| {
+        if models.is_empty() {
+            Err("No Whisper model is installed".to_string())
+        } else {
+            Ok(format!("Installed models: {}", models.join(", ")))
+        }
+    });
+    Some(HealthItem::new(
+        "models",
+        result,
+        HealthStatus::Warning,
+        "Download a Whisper model to transcribe offline",
+    ))
+}
+
+/// Run all checks
+fn run_checks(app: &AppHandle) -> HealthReport {
+    let mut items = vec![
+        HealthItem::new(
+            "microphone",
+            audio::check_input_device(),
+            HealthStatus::Error,
+            "Connect a microphone and allow the app to use it in the system settings",
+        ),
+        HealthItem::new(
+            "secureStorage",
+            commands::check_secure_storage(app),
+            HealthStatus::Error,
+            "Restore secure storage with a recovery key or reset it",
+        ),
+    ];
+    items.extend(models_item(app));
+    items.push(disk_space_item(app));
+    if let Some(shortcut) = SHORTCUT.get() {
+        items.push(HealthItem::new(
+            "shortcut",
+            shortcut.clone(),
+            HealthStatus::Warning,
+            "Close the app that uses the shortcut, then restart",
+        ));
+    }
+
+    HealthReport {
+        status: items
+            .iter()
+            .map(|item| item.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok),
+        items,
+    }
+}
+
+/// Record how registering the global shortcut went, then run the checks in
+/// the background and emit the `health-report` event
+pub fn check_on_startup(app: &AppHandle, shortcut: Result<String, String>) {
+    let _ = SHORTCUT.set(shortcut);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let _ = app.emit("health-report", run_checks(&app));
+    });
+}
+
+/// Run the startup checks again, e.g. after the user fixed a problem or when
+/// the window loaded too late for the `health-report` event
+#[tauri::command]
+pub async fn get_health_report(app: AppHandle) -> Result<HealthReport, String> {
+    tokio::task::spawn_blocking(move || run_checks(&app))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_space_status() {
+        assert_eq!(disk_space_status(50 * 1024 * 1024), HealthStatus::Error);
+        assert_eq!(disk_space_status(500 * 1024 * 1024), HealthStatus::Warning);
+        assert_eq!(disk_space_status(20 * 1024 * 1024 * 1024), HealthStatus::Ok);
+        assert!(HealthStatus::Error > HealthStatus::Warning);
+    }
+}
@@ -1,10 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
-struct FileDialogFilter {
-    name: String,
-    extensions: Vec<String>,
-}
+use files::{save_with_dialog, FileDialogFilter};
 
 /// Save file command that opens a native save dialog and writes content to disk
 #[tauri::command]
@@ -14,41 +10,136 @@ async fn save_file(
     default_filename: String,
     filters: Vec<FileDialogFilter>,
 ) -> Result<String, String> {
+    save_with_dialog(&app, content.as_bytes(), &default_filename, &filters)
+}
+
+/// Binary variant of `save_file` for audio, DOCX and other non-text exports.
+/// The content arrives as base64 and is written to disk as raw bytes.
+#[tauri::command]
+async fn save_binary_file(
+    app: tauri::AppHandle,
+    base64_data: String,
+    default_filename: String,
+    filters: Vec<FileDialogFilter>,
+) -> Result<String, String> {
+    use base64::prelude::*;
+
+    // Decode base64 data
+    let binary_data = BASE64_STANDARD
+        .decode(&base64_data)
+        .map_err(|e| format!("Failed to decode base64 data: {}", e))?;
+
+    save_with_dialog(&app, &binary_data, &default_filename, &filters)
+}
+
+/// How `open_file` returns the contents of the selected files
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum FileContentEncoding {
+    /// As text, for transcripts
+    Utf8,
+    /// As base64, for audio and other binary files
+    Base64,
+}
+
+/// A file selected in the open dialog
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenedFile {
+    path: String,
+    /// Only when contents were asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+/// Open file command that shows a native open dialog and returns the selected
+/// path(s), with their contents if `read_contents` says how to encode them
+#[tauri::command]
+async fn open_file(
+    app: tauri::AppHandle,
+    filters: Vec<FileDialogFilter>,
+    multiple: Option<bool>,
+    read_contents: Option<FileContentEncoding>,
+) -> Result<Vec<OpenedFile>, String> {
     use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+    use base64::prelude::*;
 
     // Build the file dialog with filters
     let mut dialog = app.dialog().file();
 
-    // Set default filename
-    dialog = dialog.set_file_name(&default_filename);
-
-    // Add file filters - convert Vec<String> to Vec<&str>
     for filter in filters.iter() {
         let ext_refs: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
         dialog = dialog.add_filter(&filter.name, &ext_refs);
     }
 
-    // Show save dialog and get the selected path
-    let file_path = match dialog.blocking_save_file() {
-        Some(path) => path,
-        None => return Err("User cancelled save dialog".to_string()),
+    // Show open dialog and get the selected path(s)
+    let file_paths = if multiple.unwrap_or(false) {
+        dialog.blocking_pick_files()
+    } else {
+        dialog.blocking_pick_file().map(|path| vec![path])
+    };
+    let file_paths = match file_paths {
+        Some(paths) if !paths.is_empty() => paths,
+        _ => return Err("User cancelled open dialog".to_string()),
     };
 
-    // Get the path string
-    let path_string = file_path.to_string();
-    let path = std::path::Path::new(&path_string);
+    let mut files = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths {
+        let path_string = file_path.to_string();
+        let path = std::path::Path::new(&path_string);
 
-    // Write content to the file
-    match std::fs::write(path, content.as_bytes()) {
-        Ok(_) => Ok(path_string),
-        Err(e) => {
-            // Show error dialog to user
-            app.dialog()
-                .message(format!("Failed to save file: {}", e))
-                .kind(MessageDialogKind::Error)
-                .blocking_show();
-            Err(format!("Failed to write file: {}", e))
-        }
+        // Read content from the file
+        let content = match read_contents {
+            None => None,
+            Some(FileContentEncoding::Utf8) => Some(std::fs::read_to_string(path)),
+            Some(FileContentEncoding::Base64) => {
+                Some(std::fs::read(path).map(|data| BASE64_STANDARD.encode(data)))
+            }
+        };
+        let content = match content.transpose() {
+            Ok(content) => content,
+            Err(e) => {
+                // Show error dialog to user
+                app.dialog()
+                    .message(format!("Failed to open file: {}", e))
+                    .kind(MessageDialogKind::Error)
+                    .blocking_show();
+                return Err(format!("Failed to read file: {}", e));
+            }
+        };
+
+        recent_files::record(&app, &path_string);
+        files.push(OpenedFile {
+            path: path_string,
+            content,
+        });
+    }
+
+    Ok(files)
+}
+
+/// Show a native folder picker and return the selected folder. It opens in
+/// `default_path` if given, otherwise in the default export folder.
+#[tauri::command]
+async fn pick_directory(
+    app: tauri::AppHandle,
+    default_path: Option<String>,
+) -> Result<String, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let mut dialog = app.dialog().file();
+
+    let start = default_path
+        .map(std::path::PathBuf::from)
+        .filter(|path| path.is_dir())
+        .or_else(|| files::default_export_folder(&app));
+    if let Some(start) = start {
+        dialog = dialog.set_directory(start);
+    }
+
+    match dialog.blocking_pick_folder() {
+        Some(path) => Ok(path.to_string()),
+        None => Err("User cancelled folder dialog".to_string()),
     }
 }
 
@@ -72,7 +163,7 @@ async fn save_audio_file(
 
     // Write binary data to file
     let path = std::path::Path::new(&file_path);
-    match std::fs::write(path, binary_data) {
+    match files::write_atomic(path, &binary_data) {
         Ok(_) => Ok(file_path),
         Err(e) => {
             // Show error dialog to user
@@ -98,35 +189,222 @@ async fn toggle_window_visibility(window: tauri::Window) -> Result<(), String> {
     Ok(())
 }
 
+mod analytics;
+mod auto_transcribe;
+mod biometric;
+mod bundle;
 mod commands;
-mod crypto;
 mod audio;
+mod dictation;
+mod drafts;
+mod events;
+mod export;
+mod files;
+mod health;
+mod maintenance;
+mod network;
+mod screenshot;
+mod secure_audit;
+mod secure_delete;
+mod policy;
+mod profile;
+mod recent_files;
+mod settings;
+mod team_config;
+mod timestamps;
+mod transcript;
+mod transcription;
+mod usage;
+mod vault;
+mod wipe;
+mod window_context;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // The app binary doubles as the audio capture helper process
+    if let Some(code) = audio::run_capture_helper_if_requested() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .manage(audio::AudioRecorder::default())
+        .manage(commands::SecureCache::default())
         .invoke_handler(tauri::generate_handler![
             save_file,
+            save_binary_file,
+            open_file,
+            pick_directory,
             save_audio_file,
+            files::append_to_file,
+            files::get_default_export_folder,
+            files::set_default_export_folder,
+            files::set_save_backup,
+            recent_files::get_recent_files,
+            recent_files::set_recent_file_pinned,
+            recent_files::clear_recent_files,
+            drafts::autosave_draft,
+            drafts::recover_drafts,
+            drafts::discard_draft,
             toggle_window_visibility,
             commands::get_secure_value,
+            commands::has_secure_value,
             commands::set_secure_value,
             commands::delete_secure_value,
+            commands::migrate_plaintext_secrets,
+            commands::list_secure_keys,
+            commands::clear_secure_namespace,
+            commands::get_secure_value_info,
+            commands::set_secure_value_label,
+            commands::set_secure_json,
+            commands::get_secure_json,
+            commands::export_secure_storage,
+            commands::import_secure_storage,
+            commands::enable_secure_passphrase,
+            commands::disable_secure_passphrase,
+            commands::unlock_secure_storage,
+            commands::lock_secure_storage,
+            commands::clear_secure_cache,
+            commands::get_secure_storage_backend,
+            biometric::get_biometric_status,
+            biometric::set_biometric_unlock,
+            commands::get_secure_values,
+            commands::set_secure_values,
+            commands::delete_secure_values,
+            commands::check_keyring_health,
+            commands::test_secure_storage,
+            commands::encrypt_data,
+            commands::decrypt_data,
+            profile::get_storage_profile,
+            commands::get_secure_storage_status,
+            commands::export_recovery_key,
+            commands::reset_secure_storage,
+            commands::restore_secure_storage,
+            secure_audit::get_secure_audit_log,
+            secure_audit::clear_secure_audit_log,
             audio::start_recording,
             audio::stop_recording,
+            audio::list_input_devices,
+            audio::get_preferred_input_device,
+            audio::set_preferred_input_device,
+            audio::set_input_device_alias,
+            audio::test_input_device,
+            audio::preflight::preflight_recording,
+            audio::set_monitoring,
+            audio::get_recording_context,
+            wipe::wipe_all_data,
+            secure_delete::delete_file,
+            secure_delete::set_secure_delete,
+            team_config::get_team_config,
+            team_config::refresh_team_config,
+            team_config::set_team_config_source,
+            policy::get_managed_policy,
+            screenshot::attach_screenshot,
+            transcript::format_transcript,
+            transcript::copy_transcript,
+            transcript::chunk_transcript,
+            transcript::count_tokens,
+            transcript::render_subtitles,
+            transcript::save_subtitles,
+            transcript::export_srt,
+            transcript::export_vtt,
+            transcript::render_markdown,
+            transcript::export_markdown,
+            transcript::export_json,
+            bundle::export_bundle,
+            transcript::get_profanity_filter,
+            transcript::set_profanity_filter,
+            transcript::get_punctuation_restoration,
+            transcript::set_punctuation_restoration,
+            transcript::list_corrections,
+            transcript::add_correction,
+            transcript::remove_correction,
+            transcript::get_post_processing,
+            transcript::set_post_processing,
+            transcription::transcribe_recording,
+            transcription::transcribe_file,
+            transcription::detect_language,
+            transcription::submit_transcription,
+            transcription::transcribe_files,
+            transcription::transcribe_in_cloud,
+            transcription::transcribe_with_openai,
+            transcription::start_live_transcription,
+            transcription::start_realtime_transcription,
+            transcription::stop_live_transcription,
+            transcription::provider::list_transcription_providers,
+            auto_transcribe::get_auto_transcribe_settings,
+            auto_transcribe::set_auto_transcribe_settings,
+            dictation::list_pipeline_profiles,
+            dictation::set_pipeline_profile,
+            dictation::start_dictation,
+            dictation::stop_dictation,
+            analytics::get_pace_stats,
+            analytics::get_speaking_analytics,
+            timestamps::get_timestamp_format,
+            timestamps::set_timestamp_format,
+            timestamps::format_timestamps,
+            analytics::get_talk_time,
+            transcription::models::list_whisper_models,
+            transcription::models::download_whisper_model,
+            transcription::models::delete_whisper_model,
+            transcription::models::recommend_model,
+            transcription::models::get_model_variants,
+            transcription::models::download_core_ml_encoder,
+            transcription::models::delete_core_ml_encoder,
+            transcription::acceleration::get_whisper_acceleration,
+            transcription::vosk::get_vosk_status,
+            transcription::acceleration::set_whisper_backend,
+            transcription::resources::get_job_resource_usage,
+            transcription::resources::set_job_memory_budget,
+            transcription::jobs::get_transcription_job,
+            transcription::jobs::list_transcription_jobs,
+            transcription::jobs::cancel_transcription_job,
+            transcription::retry_queue::list_queued_transcriptions,
+            transcription::retry_queue::retry_queued_transcriptions,
+            transcription::retry_queue::remove_queued_transcription,
+            network::get_network_status,
+            usage::get_usage_stats,
+            export::list_export_templates,
+            export::render_export_template,
+            export::export_sqlite,
+            health::get_health_report,
+            maintenance::get_maintenance_status,
+            maintenance::set_maintenance_settings,
         ])
         .setup(|app| {
             use tauri::Manager;
             use tauri::tray::{TrayIconBuilder, TrayIconEvent, MouseButton};
-            use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+            use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutEvent, ShortcutState};
 
             let window = app.get_webview_window("main").unwrap();
 
+            // Load the managed policy before anything consults it
+            policy::load_on_startup(app.handle());
+            transcription::register_local_provider(app.handle());
+
+            // Keep the secrets of dev builds and other profiles apart
+            profile::load_on_startup(app.handle());
+
+            // Fall back to a key file on machines without a machine ID
+            commands::init_key_backend(app.handle());
+
+            // Warn the frontend early if stored secrets can no longer be decrypted
+            commands::check_storage_key_on_startup(app.handle());
+            commands::start_expiry_sweep(app.handle());
+
+            // Fetch the administrator-provided team config, if one is set up
+            team_config::load_on_startup(app.handle());
+
+            // Pick up a recording that outlived the previous session
+            audio::reattach_on_startup(app.handle());
+            transcription::retry_queue::start_on_startup(app.handle());
+
+            // Run heavy background tasks when the machine is not in use
+            maintenance::start_on_startup(app.handle());
+
             #[cfg(debug_assertions)]
             {
                 window.open_devtools();
@@ -164,7 +442,7 @@ pub fn run() {
             // Clone window for the closure
             let window_clone = window.clone();
 
-            app.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, event| {
+            let on_shortcut = move |_app: &tauri::AppHandle, _shortcut: &_, event: ShortcutEvent| {
                 if event.state == ShortcutState::Pressed {
                     let window = &window_clone;
 
@@ -177,7 +455,16 @@ pub fn run() {
                         let _ = window.unminimize();
                     }
                 }
-            }).expect("Failed to register global shortcut");
+            };
+            let registered = app.global_shortcut().on_shortcut(shortcut, on_shortcut);
+
+            // Report problems found at startup to the frontend in one go
+            health::check_on_startup(
+                app.handle(),
+                registered
+                    .map(|_| format!("{} is registered", shortcut))
+                    .map_err(|e| format!("Failed to register {}: {}", shortcut, e)),
+            );
 
             Ok(())
         })
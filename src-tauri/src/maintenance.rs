@@ -0,0 +1,455 @@
+use chrono::Timelike;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::audio::{self, AudioRecorder};
+use crate::{drafts, policy, secure_delete, settings};
+
+/// How often the scheduler checks whether it may run
+const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Leftover partial files younger than this may still be in use
+const STALE_PART_FILE_SECS: u64 = 24 * 60 * 60;
+
+/// When heavy background work may run. Tasks run while on AC power (if
+/// required) and either inside the window or once the app has been idle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    /// Local hour the window opens, 0-23
+    pub window_start_hour: u8,
+    /// Local hour the window closes; before the start hour, the window
+    /// spans midnight
+    pub window_end_hour: u8,
+    /// Also run outside the window once nothing has been recorded for
+    /// `idle_minutes`
+    pub run_when_idle: bool,
+    pub idle_minutes: u32,
+    /// Wait while running on battery
+    pub require_ac_power: bool,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_start_hour: 2,
+            window_end_hour: 5,
+            run_when_idle: true,
+            idle_minutes: 15,
+            require_ac_power: true,
+        }
+    }
+}
+
+/// A background task and how often it runs
+struct MaintenanceTask {
+    name: &'static str,
+    interval: Duration,
+    /// Returns a short summary of the work done
+    run: fn(&AppHandle) -> Result<String, String>,
+}
+
+const TASKS: &[MaintenanceTask] = &[
+    MaintenanceTask {
+        name: "cache-cleanup",
+        interval: Duration::from_secs(24 * 60 * 60),
+        run: clean_cache,
+    },
+    MaintenanceTask {
+        name: "history-retention",
+        interval: Duration::from_secs(60 * 60),
+        run: enforce_history_retention,
+    },
+];
+
+/// Outcome of a task's last run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub name: String,
+    /// When the task last ran, RFC 3339
+    pub last_run: Option<String>,
+    pub last_result: Option<String>,
+    pub last_error: Option<String>,
+    /// Whether the task runs at the next opportunity
+    pub due: bool,
+}
+
+/// Scheduler state as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    pub settings: MaintenanceSettings,
+    /// `None` when the power source cannot be determined, e.g. on desktops
+    pub on_ac_power: Option<bool>,
+    pub in_window: bool,
+    pub idle: bool,
+    /// Why due tasks cannot run right now
+    pub paused_reason: Option<String>,
+    /// Task currently running
+    pub running: Option<String>,
+    pub tasks: Vec<TaskStatus>,
+}
+
+#[derive(Default)]
+struct TaskState {
+    last_run: Option<Instant>,
+    last_run_at: Option<String>,
+    last_result: Option<String>,
+    last_error: Option<String>,
+}
+
+struct SchedulerState {
+    /// Last time a recording was seen in progress
+    last_activity: Instant,
+    running: Option<&'static str>,
+    tasks: Vec<TaskState>,
+}
+
+static STATE: Lazy<Mutex<SchedulerState>> = Lazy::new(|| {
+    Mutex::new(SchedulerState {
+        last_activity: Instant::now(),
+        running: None,
+        tasks: TASKS.iter().map(|_| TaskState::default()).collect(),
+    })
+});
+
+/// Whether `hour` falls into the window from `start` (inclusive) to `end`
+/// (exclusive), which may span midnight
+fn in_window(hour: u8, start: u8, end: u8) -> bool {
+    if start <= end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// Why maintenance may not run now, or `None` if it may
+fn paused_reason(
+    settings: &MaintenanceSettings,
+    in_window: bool,
+    idle: bool,
+    on_ac_power: Option<bool>,
+) -> Option<String> {
+    if !settings.enabled {
+        return Some("Maintenance is disabled".to_string());
+    }
+    if settings.require_ac_power && on_ac_power == Some(false) {
+        return Some("Waiting for AC power".to_string());
+    }
+    if in_window || (settings.run_when_idle && idle) {
+        return None;
+    }
+
+    let window = format!(
+        "Waiting for the maintenance window ({:02}:00-{:02}:00)",
+        settings.window_start_hour, settings.window_end_hour
+    );
+    Some(if settings.run_when_idle {
+        format!("{} or {} idle minutes", window, settings.idle_minutes)
+    } else {
+        window
+    })
+}
+
+/// Whether the machine runs on AC power; `None` if unknown
+#[cfg(target_os = "linux")]
+fn on_ac_power() -> Option<bool> {
+    let mut has_battery = false;
+    let mut discharging = false;
+
+    for supply in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |name: &str| {
+            fs::read_to_string(supply.path().join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            "Mains" | "USB" if read("online") == "1" => return Some(true),
+            "Battery" => {
+                has_battery = true;
+                discharging |= read("status") == "Discharging";
+            }
+            _ => {}
+        }
+    }
+
+    has_battery.then_some(!discharging)
+}
+
+#[cfg(target_os = "macos")]
+fn on_ac_power() -> Option<bool> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+
+    if output.contains("'AC Power'") {
+        Some(true)
+    } else if output.contains("'Battery Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+fn on_ac_power() -> Option<bool> {
+    #[repr(C)]
+    #[allow(non_snake_case, dead_code)]
+    struct SystemPowerStatus {
+        ACLineStatus: u8,
+        BatteryFlag: u8,
+        BatteryLifePercent: u8,
+        SystemStatusFlag: u8,
+        BatteryLifeTime: u32,
+        BatteryFullLifeTime: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    let mut status = std::mem::MaybeUninit::<SystemPowerStatus>::uninit();
+    // SAFETY: the struct matches SYSTEM_POWER_STATUS and is only read on success
+    let status = unsafe {
+        if GetSystemPowerStatus(status.as_mut_ptr()) == 0 {
+            return None;
+        }
+        status.assume_init()
+    };
+
+    match status.ACLineStatus {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn on_ac_power() -> Option<bool> {
+    None
+}
+
+fn current_status(app: &AppHandle, settings: MaintenanceSettings) -> MaintenanceStatus {
+    let recording = audio::is_recording(&app.state::<AudioRecorder>());
+    let in_window = in_window(
+        chrono::Local::now().hour() as u8,
+        settings.window_start_hour,
+        settings.window_end_hour,
+    );
+    let on_ac_power = on_ac_power();
+
+    let mut state = STATE.lock();
+    if recording {
+        state.last_activity = Instant::now();
+    }
+    let idle =
+        state.last_activity.elapsed() >= Duration::from_secs(settings.idle_minutes as u64 * 60);
+
+    let tasks = TASKS
+        .iter()
+        .zip(&state.tasks)
+        .map(|(task, task_state)| TaskStatus {
+            name: task.name.to_string(),
+            last_run: task_state.last_run_at.clone(),
+            last_result: task_state.last_result.clone(),
+            last_error: task_state.last_error.clone(),
+            due: task_state
+                .last_run
+                .is_none_or(|last_run| last_run.elapsed() >= task.interval),
+        })
+        .collect();
+
+    MaintenanceStatus {
+        paused_reason: paused_reason(&settings, in_window, idle, on_ac_power),
+        settings,
+        on_ac_power,
+        in_window,
+        idle,
+        running: state.running.map(str::to_string),
+        tasks,
+    }
+}
+
+/// Run the due tasks if conditions allow, re-checking them between tasks
+fn run_due_tasks(app: &AppHandle) -> Result<(), String> {
+    for (index, task) in TASKS.iter().enumerate() {
+        let settings = settings::load_settings(app)?.maintenance;
+        let status = current_status(app, settings);
+        if status.paused_reason.is_some() {
+            return Ok(());
+        }
+        if !status.tasks[index].due {
+            continue;
+        }
+
+        STATE.lock().running = Some(task.name);
+        let _ = app.emit("maintenance-status", current_status(app, status.settings));
+
+        let result = (task.run)(app);
+
+        let mut state = STATE.lock();
+        state.running = None;
+        let task_state = &mut state.tasks[index];
+        task_state.last_run = Some(Instant::now());
+        task_state.last_run_at = Some(chrono::Utc::now().to_rfc3339());
+        match result {
+            Ok(summary) => {
+                task_state.last_result = Some(summary);
+                task_state.last_error = None;
+            }
+            Err(e) => task_state.last_error = Some(e),
+        }
+        drop(state);
+
+        let settings = settings::load_settings(app)?.maintenance;
+        let _ = app.emit("maintenance-status", current_status(app, settings));
+    }
+
+    Ok(())
+}
+
+/// Start the scheduler thread
+pub fn start_on_startup(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECS));
+        if let Err(e) = run_due_tasks(&app) {
+            eprintln!("Maintenance failed: {}", e);
+        }
+    });
+}
+
+/// Collect `.part` files, such as those left behind by interrupted model downloads
+fn collect_part_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_part_files(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "part")
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Remove stale partial files from the app's data and cache directories
+fn clean_cache(app: &AppHandle) -> Result<String, String> {
+    let secure = settings::load_effective_settings(app)?.secure_delete;
+    let dirs = [app.path().app_data_dir(), app.path().app_cache_dir()];
+
+    let mut files = Vec::new();
+    for dir in dirs.into_iter().flatten() {
+        collect_part_files(&dir, &mut files);
+    }
+
+    let mut removed = 0;
+    for file in files {
+        let stale = fs::metadata(&file)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age.as_secs() >= STALE_PART_FILE_SECS);
+        if stale && secure_delete::delete_path(&file, secure).is_ok() {
+            removed += 1;
+        }
+    }
+
+    Ok(format!("Removed {} leftover partial files", removed))
+}
+
+/// Delete drafts older than the managed `MaxHistoryDays`
+fn enforce_history_retention(app: &AppHandle) -> Result<String, String> {
+    let Some(max_days) = policy::current().max_history_days else {
+        return Ok("No history retention limit".to_string());
+    };
+
+    let secure = settings::load_effective_settings(app)?.secure_delete;
+    let removed = drafts::prune_expired(
+        &drafts::drafts_dir(app)?,
+        max_days,
+        chrono::Utc::now(),
+        secure,
+    )?;
+
+    Ok(format!(
+        "Removed {} drafts older than {} days",
+        removed, max_days
+    ))
+}
+
+/// Get the maintenance settings, whether tasks may run now and how each
+/// task last went
+#[tauri::command]
+pub fn get_maintenance_status(app: AppHandle) -> Result<MaintenanceStatus, String> {
+    let settings = settings::load_settings(&app)?.maintenance;
+    Ok(current_status(&app, settings))
+}
+
+/// Change when maintenance tasks may run
+#[tauri::command]
+pub fn set_maintenance_settings(
+    app: AppHandle,
+    maintenance: MaintenanceSettings,
+) -> Result<MaintenanceStatus, String> {
+    if maintenance.window_start_hour > 23 || maintenance.window_end_hour > 23 {
+        return Err("Window hours must be between 0 and 23".to_string());
+    }
+
+    settings::update_settings(&app, |current| {
+        current.maintenance = maintenance.clone();
+        Ok(())
+    })?;
+
+    Ok(current_status(&app, maintenance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_can_span_midnight() {
+        assert!(in_window(3, 2, 5));
+        assert!(!in_window(5, 2, 5));
+        assert!(in_window(23, 22, 6));
+        assert!(in_window(0, 22, 6));
+        assert!(!in_window(12, 22, 6));
+    }
+
+    #[test]
+    fn test_waiting_for() {
+        let settings = MaintenanceSettings::default();
+        assert_eq!(paused_reason(&settings, true, false, Some(true)), None);
+        assert_eq!(paused_reason(&settings, false, true, None), None);
+        assert_eq!(
+            paused_reason(&settings, true, true, Some(false)).as_deref(),
+            Some("Waiting for AC power")
+        );
+        assert!(paused_reason(&settings, false, false, Some(true))
+            .unwrap()
+            .contains("02:00-05:00"));
+
+        let settings = MaintenanceSettings {
+            run_when_idle: false,
+            ..MaintenanceSettings::default()
+        };
+        assert!(paused_reason(&settings, false, true, Some(true)).is_some());
+    }
+}
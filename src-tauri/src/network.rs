@@ -0,0 +1,65 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const PROBE_HOSTS: [&str; 2] = ["cloudflare.com:443", "www.google.com:443"];
+const PROBE_TIMEOUT_SECS: u64 = 3;
+
+/// Payload of `network-status-changed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub online: bool,
+    /// RFC 3339
+    pub checked_at: String,
+}
+
+/// Result of the last check; `None` before the first
+static STATUS: Lazy<Mutex<Option<NetworkStatus>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether any well-known host accepts a connection
+async fn probe() -> bool {
+    tokio::task::spawn_blocking(|| {
+        PROBE_HOSTS.iter().any(|host| {
+            host.to_socket_addrs()
+                .ok()
+                .and_then(|mut addresses| addresses.next())
+                .is_some_and(|address| {
+                    TcpStream::connect_timeout(&address, Duration::from_secs(PROBE_TIMEOUT_SECS))
+                        .is_ok()
+                })
+        })
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Check the network now, emitting `network-status-changed` when it went
+/// online or offline since the last check
+pub async fn check(app: &AppHandle) -> bool {
+    let online = probe().await;
+    let status = NetworkStatus {
+        online,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let previous = STATUS.lock().replace(status.clone());
+    if previous.is_none_or(|previous| previous.online != online) {
+        let _ = app.emit("network-status-changed", status);
+    }
+    online
+}
+
+/// Check whether the network is reachable. Queued transcriptions start as
+/// soon as it is; see `list_queued_transcriptions`.
+#[tauri::command]
+pub async fn get_network_status(app: AppHandle) -> NetworkStatus {
+    check(&app).await;
+    STATUS
+        .lock()
+        .clone()
+        .expect("status was set by the check above")
+}
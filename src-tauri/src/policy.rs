@@ -0,0 +1,198 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// Returned by commands that a managed policy blocks
+pub const CLOUD_DISABLED_ERROR: &str = "Cloud providers are disabled by your organization's policy";
+
+/// Policies an administrator can enforce through managed preferences (macOS),
+/// the registry (Windows) or `/etc/voice-assistant/policy.json` (Linux).
+/// Sources use the PascalCase names, e.g. `DisableCloudProviders`.
+/// Cloud access and history retention are enforced here in the backend;
+/// telemetry lives in the frontend, which reads the policy through
+/// `get_managed_policy` and also prunes the history it keeps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    rename_all(serialize = "camelCase", deserialize = "PascalCase"),
+    default
+)]
+pub struct ManagedPolicy {
+    /// Block every provider that sends audio or text off this machine
+    pub disable_cloud_providers: bool,
+    /// Send no usage data anywhere
+    pub disable_telemetry: bool,
+    /// Delete history entries older than this many days
+    pub max_history_days: Option<u32>,
+}
+
+impl ManagedPolicy {
+    /// Combine two sources, keeping the stricter value of each policy
+    fn merge(self, other: ManagedPolicy) -> ManagedPolicy {
+        ManagedPolicy {
+            disable_cloud_providers: self.disable_cloud_providers || other.disable_cloud_providers,
+            disable_telemetry: self.disable_telemetry || other.disable_telemetry,
+            max_history_days: match (self.max_history_days, other.max_history_days) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+/// Effective policy and where it was read from
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagedPolicyStatus {
+    pub policy: ManagedPolicy,
+    /// Locations that contributed to the policy; empty when the app is unmanaged
+    pub sources: Vec<String>,
+}
+
+static POLICY: OnceCell<ManagedPolicyStatus> = OnceCell::new();
+
+/// Effective managed policy (unrestricted until loaded)
+pub fn current() -> ManagedPolicy {
+    POLICY.get().map(|status| status.policy).unwrap_or_default()
+}
+
+/// Fail with `CLOUD_DISABLED_ERROR` if cloud providers are disabled
+pub fn ensure_cloud_allowed() -> Result<(), String> {
+    if current().disable_cloud_providers {
+        Err(CLOUD_DISABLED_ERROR.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether a secure storage key holds credentials for a cloud provider
+pub fn is_cloud_credential(key: &str) -> bool {
+    key.ends_with("_api_key")
+}
+
+#[cfg(target_os = "macos")]
+fn read_sources(identifier: &str) -> Vec<(String, ManagedPolicy)> {
+    // Computer-level profiles first, then the ones scoped to the current user
+    let mut paths = vec![format!("/Library/Managed Preferences/{}.plist", identifier)];
+    if let Ok(user) = std::env::var("USER") {
+        paths.push(format!(
+            "/Library/Managed Preferences/{}/{}.plist",
+            user, identifier
+        ));
+    }
+
+    paths
+        .into_iter()
+        .filter(|path| std::path::Path::new(path).exists())
+        .filter_map(|path| match plist::from_file::<_, ManagedPolicy>(&path) {
+            Ok(policy) => Some((path, policy)),
+            Err(e) => {
+                eprintln!("Failed to read managed preferences {}: {}", path, e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn read_sources(_identifier: &str) -> Vec<(String, ManagedPolicy)> {
+    use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+    use winreg::RegKey;
+
+    const POLICY_KEY: &str = "SOFTWARE\\Policies\\VoiceAssistant";
+
+    [("HKLM", HKEY_LOCAL_MACHINE), ("HKCU", HKEY_CURRENT_USER)]
+        .into_iter()
+        .filter_map(|(name, hive)| {
+            let key = RegKey::predef(hive).open_subkey(POLICY_KEY).ok()?;
+            let flag = |value: &str| {
+                key.get_value::<u32, _>(value)
+                    .map(|v| v != 0)
+                    .unwrap_or(false)
+            };
+            let policy = ManagedPolicy {
+                disable_cloud_providers: flag("DisableCloudProviders"),
+                disable_telemetry: flag("DisableTelemetry"),
+                max_history_days: key.get_value::<u32, _>("MaxHistoryDays").ok(),
+            };
+            Some((format!("{}\\{}", name, POLICY_KEY), policy))
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn read_sources(_identifier: &str) -> Vec<(String, ManagedPolicy)> {
+    const POLICY_FILE: &str = "/etc/voice-assistant/policy.json";
+
+    let Ok(content) = std::fs::read_to_string(POLICY_FILE) else {
+        return Vec::new();
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(policy) => vec![(POLICY_FILE.to_string(), policy)],
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", POLICY_FILE, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Read the managed policy once at startup; it stays fixed until the app restarts
+pub fn load_on_startup(app: &AppHandle) {
+    let sources = read_sources(&app.config().identifier);
+
+    let status = ManagedPolicyStatus {
+        policy: sources
+            .iter()
+            .fold(ManagedPolicy::default(), |policy, (_, source)| {
+                policy.merge(*source)
+            }),
+        sources: sources.into_iter().map(|(location, _)| location).collect(),
+    };
+
+    let _ = POLICY.set(status);
+}
+
+/// Get the managed policy so the frontend can hide or lock restricted options
+#[tauri::command]
+pub fn get_managed_policy() -> ManagedPolicyStatus {
+    POLICY.get().cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_stricter_values() {
+        let machine = ManagedPolicy {
+            disable_cloud_providers: true,
+            disable_telemetry: false,
+            max_history_days: Some(90),
+        };
+        let user = ManagedPolicy {
+            disable_cloud_providers: false,
+            disable_telemetry: true,
+            max_history_days: Some(30),
+        };
+
+        let merged = machine.merge(user);
+
+        assert!(merged.disable_cloud_providers);
+        assert!(merged.disable_telemetry);
+        assert_eq!(merged.max_history_days, Some(30));
+        assert_eq!(
+            ManagedPolicy::default().merge(machine).max_history_days,
+            Some(90)
+        );
+    }
+
+    #[test]
+    fn test_policy_file_uses_pascal_case() {
+        let policy: ManagedPolicy =
+            serde_json::from_str(r#"{"DisableCloudProviders":true,"MaxHistoryDays":7}"#).unwrap();
+
+        assert!(policy.disable_cloud_providers);
+        assert!(!policy.disable_telemetry);
+        assert_eq!(policy.max_history_days, Some(7));
+    }
+}
@@ -0,0 +1,149 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use transcriber_core::crypto;
+
+/// Selects an isolated storage profile, e.g. `TRANSCRIBER_PROFILE=work`
+const PROFILE_ENV_VAR: &str = "TRANSCRIBER_PROFILE";
+
+/// Profile of debug builds, so a dev build never touches the secrets of an
+/// installed release
+const DEV_PROFILE: &str = "dev";
+
+/// Identifier of the release build. Its default profile keeps the key and
+/// folder secure storage had before profiles existed.
+const RELEASE_IDENTIFIER: &str = "com.voiceassistant.app";
+
+/// Longest accepted profile name
+const MAX_PROFILE_LEN: usize = 32;
+
+/// The storage profile this process runs with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageProfile {
+    /// `None` for the default profile
+    pub name: Option<String>,
+    /// Mixed into the key of secure values; `None` for the default profile
+    /// of the release build
+    pub service_name: Option<String>,
+}
+
+static PROFILE: OnceCell<StorageProfile> = OnceCell::new();
+
+/// Profile names end up in a folder name, so only a safe set of characters
+/// is accepted
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROFILE_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid storage profile \"{}\": use up to {} letters, digits, '-' or '_'",
+            name, MAX_PROFILE_LEN
+        ))
+    }
+}
+
+/// The profile of an app with `identifier`, given the profile requested in
+/// the environment and whether this is a debug build
+fn resolve(
+    identifier: &str,
+    requested: Option<&str>,
+    debug: bool,
+) -> Result<StorageProfile, String> {
+    let name = match requested.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => {
+            validate_name(name)?;
+            Some(name.to_string())
+        }
+        None => debug.then(|| DEV_PROFILE.to_string()),
+    };
+
+    let service_name = match &name {
+        None if identifier == RELEASE_IDENTIFIER => None,
+        None => Some(identifier.to_string()),
+        Some(name) => Some(format!("{}/{}", identifier, name)),
+    };
+
+    Ok(StorageProfile { name, service_name })
+}
+
+/// Pick the storage profile at launch, before anything is decrypted. An
+/// invalid profile name is ignored rather than risk using another profile's
+/// data under it.
+pub fn load_on_startup(app: &AppHandle) {
+    let identifier = &app.config().identifier;
+    let requested = std::env::var(PROFILE_ENV_VAR).ok();
+    let debug = cfg!(debug_assertions);
+
+    let profile = resolve(identifier, requested.as_deref(), debug).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        resolve(identifier, None, debug).unwrap_or_default()
+    });
+
+    crypto::set_service_name(profile.service_name.clone());
+    let _ = PROFILE.set(profile);
+}
+
+/// Current storage profile (the default one until loaded)
+pub fn current() -> StorageProfile {
+    PROFILE.get().cloned().unwrap_or_default()
+}
+
+/// Folder in the app data directory that holds the secure storage, its key
+/// file and audit log: the app data directory itself for the default
+/// profile, `profiles/<name>` in it for any other
+pub fn storage_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let dir = match current().name {
+        Some(name) => app_data_dir.join("profiles").join(name),
+        None => app_data_dir,
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create storage directory: {}", e))?;
+    Ok(dir)
+}
+
+#[tauri::command]
+pub fn get_storage_profile() -> StorageProfile {
+    current()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_profile() {
+        let release = resolve(RELEASE_IDENTIFIER, None, false).unwrap();
+        assert_eq!(release, StorageProfile::default());
+
+        let dev = resolve(RELEASE_IDENTIFIER, None, true).unwrap();
+        assert_eq!(dev.name.as_deref(), Some(DEV_PROFILE));
+        assert_eq!(
+            dev.service_name.as_deref(),
+            Some("com.voiceassistant.app/dev")
+        );
+
+        let work = resolve(RELEASE_IDENTIFIER, Some(" work "), true).unwrap();
+        assert_eq!(work.name.as_deref(), Some("work"));
+        assert_ne!(work.service_name, dev.service_name);
+
+        let fork = resolve("com.example.fork", Some(""), false).unwrap();
+        assert_eq!(fork.name, None);
+        assert_eq!(fork.service_name.as_deref(), Some("com.example.fork"));
+
+        for invalid in ["../secure", "a b", &"x".repeat(MAX_PROFILE_LEN + 1)] {
+            assert!(resolve(RELEASE_IDENTIFIER, Some(invalid), false).is_err());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::files;
+use crate::profile;
+
+const RECENT_FILES_FILE: &str = "recent-files.json";
+
+/// Unpinned files kept in the list; pinned ones are never dropped
+const MAX_RECENT_FILES: usize = 20;
+
+/// Serializes updates of the recent files list
+static RECENT_FILES_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// A file the user saved or opened through a dialog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFile {
+    pub path: String,
+    /// The file name, for menus
+    pub title: String,
+    /// RFC 3339 of the last save or open
+    pub timestamp: String,
+    pub pinned: bool,
+}
+
+fn recent_files_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(profile::storage_dir(app)?.join(RECENT_FILES_FILE))
+}
+
+fn load(path: &Path) -> Result<Vec<RecentFile>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read recent files: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse recent files: {}", e))
+}
+
+fn save(path: &Path, recent: &[RecentFile]) -> Result<(), String> {
+    let content = serde_json::to_vec_pretty(recent)
+        .map_err(|e| format!("Failed to serialize recent files: {}", e))?;
+    files::write_atomic(path, &content).map_err(|e| format!("Failed to write recent files: {}", e))
+}
+
+/// Move `file_path` to the front of `recent`, keeping whether it was pinned,
+/// and drop the oldest unpinned files beyond the limit
+fn touch(recent: &mut Vec<RecentFile>, file_path: &str, now: &str) {
+    let pinned = recent
+        .iter()
+        .find(|file| file.path == file_path)
+        .is_some_and(|file| file.pinned);
+    recent.retain(|file| file.path != file_path);
+
+    let title = Path::new(file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file_path.to_string());
+    recent.insert(
+        0,
+        RecentFile {
+            path: file_path.to_string(),
+            title,
+            timestamp: now.to_string(),
+            pinned,
+        },
+    );
+
+    let mut unpinned = 0;
+    recent.retain(|file| {
+        if file.pinned {
+            return true;
+        }
+        unpinned += 1;
+        unpinned <= MAX_RECENT_FILES
+    });
+}
+
+/// Pinned files first, then the rest, each most recent first
+fn sorted(mut recent: Vec<RecentFile>) -> Vec<RecentFile> {
+    recent.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then_with(|| b.timestamp.cmp(&a.timestamp))
+    });
+    recent
+}
+
+/// Put a file that was just saved or opened at the top of the list. A
+/// failure is only logged, so it never fails the save itself.
+pub fn record(app: &AppHandle, file_path: &str) {
+    let result = recent_files_path(app).and_then(|path| {
+        let _lock = RECENT_FILES_LOCK.lock();
+        let mut recent = load(&path).unwrap_or_default();
+        touch(&mut recent, file_path, &chrono::Utc::now().to_rfc3339());
+        save(&path, &recent)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to update recent files: {}", e);
+    }
+}
+
+/// Paths of all files in the list
+pub fn paths(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let path = recent_files_path(app)?;
+    let _lock = RECENT_FILES_LOCK.lock();
+    Ok(load(&path)?
+        .into_iter()
+        .map(|file| PathBuf::from(file.path))
+        .collect())
+}
+
+/// Files recently saved or opened, pinned ones first, for a "recent
+/// exports" menu
+#[tauri::command]
+pub fn get_recent_files(app: AppHandle) -> Result<Vec<RecentFile>, String> {
+    let path = recent_files_path(&app)?;
+    let _lock = RECENT_FILES_LOCK.lock();
+    Ok(sorted(load(&path)?))
+}
+
+/// Pin a file so it stays in the list, or unpin it
+#[tauri::command]
+pub fn set_recent_file_pinned(
+    app: AppHandle,
+    file_path: String,
+    pinned: bool,
+) -> Result<Vec<RecentFile>, String> {
+    let path = recent_files_path(&app)?;
+    let _lock = RECENT_FILES_LOCK.lock();
+    let mut recent = load(&path)?;
+    let file = recent
+        .iter_mut()
+        .find(|file| file.path == file_path)
+        .ok_or_else(|| format!("Not a recent file: {}", file_path))?;
+    file.pinned = pinned;
+    save(&path, &recent)?;
+    Ok(sorted(recent))
+}
+
+/// Empty the list; pinned files stay unless `include_pinned` is set
+#[tauri::command]
+pub fn clear_recent_files(
+    app: AppHandle,
+    include_pinned: Option<bool>,
+) -> Result<Vec<RecentFile>, String> {
+    let path = recent_files_path(&app)?;
+    let _lock = RECENT_FILES_LOCK.lock();
+    let mut recent = load(&path)?;
+    if include_pinned.unwrap_or(false) {
+        recent.clear();
+    } else {
+        recent.retain(|file| file.pinned);
+    }
+    save(&path, &recent)?;
+    Ok(sorted(recent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(i: usize) -> String {
+        format!("2026-10-15T09:{:02}:00+00:00", i)
+    }
+
+    #[test]
+    fn test_touch() {
+        let mut recent = Vec::new();
+        touch(&mut recent, "/notes/a.md", &timestamp(0));
+        recent[0].pinned = true;
+        for i in 1..=MAX_RECENT_FILES + 5 {
+            touch(&mut recent, &format!("/notes/{}.txt", i), &timestamp(i));
+        }
+        assert_eq!(recent.len(), MAX_RECENT_FILES + 1);
+        assert!(recent.iter().any(|file| file.path == "/notes/a.md"));
+        assert!(!recent.iter().any(|file| file.path == "/notes/1.txt"));
+
+        touch(&mut recent, "/notes/a.md", &timestamp(59));
+        assert_eq!(recent.len(), MAX_RECENT_FILES + 1);
+        assert_eq!(recent[0].title, "a.md");
+        assert!(recent[0].pinned);
+
+        let sorted = sorted(recent);
+        assert_eq!(sorted[0].path, "/notes/a.md");
+        assert_eq!(
+            sorted[1].path,
+            format!("/notes/{}.txt", MAX_RECENT_FILES + 5)
+        );
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let path = std::env::temp_dir().join(format!("recent-test-{}.json", rand::random::<u64>()));
+        assert!(load(&path).unwrap().is_empty());
+
+        let mut recent = Vec::new();
+        touch(&mut recent, "/notes/a.md", &timestamp(0));
+        save(&path, &recent).unwrap();
+        assert_eq!(load(&path).unwrap(), recent);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// What `attach_screenshot` captures
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScreenshotTarget {
+    /// The primary display
+    Screen,
+    /// The focused window of another app, falling back to the topmost one
+    #[default]
+    ActiveWindow,
+}
+
+/// Screenshot stored with an entry
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotAttachment {
+    path: String,
+    width: u32,
+    height: u32,
+    captured_at: String,
+    target: ScreenshotTarget,
+}
+
+/// Directory name for an entry's attachments. Entry ids come from the
+/// frontend, so anything that could escape the attachments directory is rejected.
+fn entry_dir_name(entry_id: &str) -> Result<&str, String> {
+    let valid = !entry_id.is_empty()
+        && entry_id.len() <= 128
+        && entry_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(entry_id)
+    } else {
+        Err(format!("Invalid entry id: {}", entry_id))
+    }
+}
+
+/// Get (and create) the directory holding an entry's attachments
+fn get_entry_dir(app: &AppHandle, entry_id: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("attachments")
+        .join(entry_dir_name(entry_id)?);
+
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create attachments directory: {}", e))?;
+
+    Ok(dir)
+}
+
+/// Capture the target and save it as PNG, returning its dimensions
+#[cfg(feature = "screenshots")]
+fn capture_png(target: ScreenshotTarget, path: &Path) -> Result<(u32, u32), String> {
+    use xcap::{Monitor, Window};
+
+    let primary_monitor = || -> Result<Monitor, String> {
+        let monitors = Monitor::all().map_err(|e| format!("Failed to list displays: {}", e))?;
+        let primary = monitors
+            .iter()
+            .position(|monitor| monitor.is_primary().unwrap_or(false))
+            .unwrap_or(0);
+        monitors
+            .into_iter()
+            .nth(primary)
+            .ok_or_else(|| "No display found".to_string())
+    };
+
+    let image = match target {
+        ScreenshotTarget::Screen => primary_monitor()?.capture_image(),
+        ScreenshotTarget::ActiveWindow => {
+            // Skip this app's own windows: the marker is usually set from here
+            let own_pid = std::process::id();
+            let windows: Vec<Window> = Window::all()
+                .map_err(|e| format!("Failed to list windows: {}", e))?
+                .into_iter()
+                .filter(|window| {
+                    window.pid().map(|pid| pid != own_pid).unwrap_or(false)
+                        && !window.is_minimized().unwrap_or(true)
+                })
+                .collect();
+
+            let focused = windows
+                .iter()
+                .position(|window| window.is_focused().unwrap_or(false))
+                .unwrap_or(0);
+
+            match windows.get(focused) {
+                Some(window) => window.capture_image(),
+                None => primary_monitor()?.capture_image(),
+            }
+        }
+    }
+    .map_err(|e| format!("Failed to capture screenshot: {}", e))?;
+
+    image
+        .save(path)
+        .map_err(|e| format!("Failed to write screenshot: {}", e))?;
+
+    Ok((image.width(), image.height()))
+}
+
+#[cfg(not(feature = "screenshots"))]
+fn capture_png(_target: ScreenshotTarget, _path: &Path) -> Result<(u32, u32), String> {
+    Err("Screenshots are not supported in this build".to_string())
+}
+
+/// Capture the screen or the active window and store it with an entry.
+/// On macOS this needs the Screen Recording permission.
+#[tauri::command]
+pub async fn attach_screenshot(
+    app: AppHandle,
+    entry_id: String,
+    target: Option<ScreenshotTarget>,
+) -> Result<ScreenshotAttachment, String> {
+    tokio::task::spawn_blocking(move || {
+        let target = target.unwrap_or_default();
+        let captured_at = chrono::Utc::now();
+        let path = get_entry_dir(&app, &entry_id)?.join(format!(
+            "screenshot-{}.png",
+            captured_at.format("%Y%m%dT%H%M%S%3fZ")
+        ));
+
+        let (width, height) = capture_png(target, &path)?;
+
+        Ok(ScreenshotAttachment {
+            path: path.to_string_lossy().to_string(),
+            width,
+            height,
+            captured_at: captured_at.to_rfc3339(),
+            target,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_dir_name_rejects_path_components() {
+        assert_eq!(entry_dir_name("entry_42-a").unwrap(), "entry_42-a");
+        assert!(entry_dir_name("").is_err());
+        assert!(entry_dir_name("..").is_err());
+        assert!(entry_dir_name("a/b").is_err());
+        assert!(entry_dir_name("a\\b").is_err());
+    }
+}
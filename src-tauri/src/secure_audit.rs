@@ -0,0 +1,212 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::profile;
+use transcriber_core::crypto::{ContextKey, KeyContext};
+
+/// In the storage directory of the profile rather than the secure directory,
+/// so that a reset of secure storage keeps the record of what happened
+/// before it
+const AUDIT_FILE: &str = "secure-audit.log";
+
+/// Serializes appends to and clearing of the audit log
+static AUDIT_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditAction {
+    Read,
+    Write,
+    Delete,
+    /// The log itself was cleared; always its first entry afterwards
+    Clear,
+}
+
+/// One access to secure storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// RFC 3339
+    pub timestamp: String,
+    pub action: AuditAction,
+    /// Names of the values, never the values themselves
+    pub keys: Vec<String>,
+    /// The command that accessed them, or the backend feature for reads the
+    /// frontend did not ask for
+    pub command: String,
+    pub success: bool,
+}
+
+fn audit_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(profile::storage_dir(app)?.join(AUDIT_FILE))
+}
+
+/// Append `entry` as one encrypted line. Entries are encrypted with the
+/// machine key, so the log stays readable while the store is locked and
+/// the names of the keys do not show in the file.
+fn append(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    let json =
+        serde_json::to_vec(entry).map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+    let line = ContextKey::current(KeyContext::AuditLog)?.encrypt(&json)?;
+
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))
+}
+
+/// The entries in `path`, oldest first. Lines written with another machine's
+/// key are skipped.
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read audit log: {}", e)),
+    };
+
+    let key = ContextKey::current(KeyContext::AuditLog)?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| key.decrypt(line).ok())
+        .filter_map(|json| serde_json::from_slice(&json).ok())
+        .collect())
+}
+
+fn new_entry<K: AsRef<str>>(
+    action: AuditAction,
+    command: &str,
+    keys: impl IntoIterator<Item = K>,
+    success: bool,
+) -> AuditEntry {
+    AuditEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        action,
+        keys: keys
+            .into_iter()
+            .map(|key| key.as_ref().to_string())
+            .collect(),
+        command: command.to_string(),
+        success,
+    }
+}
+
+/// Log an access to the values of `keys` by `command`, logging instead of
+/// failing: auditing never fails the access itself
+pub fn record<K: AsRef<str>>(
+    app: &AppHandle,
+    action: AuditAction,
+    command: &str,
+    keys: impl IntoIterator<Item = K>,
+    success: bool,
+) {
+    let entry = new_entry(action, command, keys, success);
+    let result = audit_path(app).and_then(|path| {
+        let _lock = AUDIT_LOCK.lock();
+        append(&path, &entry)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record secure storage access: {}", e);
+    }
+}
+
+/// Reads, writes and deletions of secure values, newest first, at most
+/// `limit` of them
+#[tauri::command]
+pub async fn get_secure_audit_log(
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    tokio::task::spawn_blocking(move || {
+        let path = audit_path(&app)?;
+        let mut entries = {
+            let _lock = AUDIT_LOCK.lock();
+            read_entries(&path)?
+        };
+        entries.reverse();
+        entries.truncate(limit.unwrap_or(usize::MAX));
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Empty the audit log. The clearing itself is logged, so the log never
+/// silently starts over.
+#[tauri::command]
+pub async fn clear_secure_audit_log(app: AppHandle) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let path = audit_path(&app)?;
+
+        let _lock = AUDIT_LOCK.lock();
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to clear audit log: {}", e)),
+        }
+        let no_keys: [&str; 0] = [];
+        append(
+            &path,
+            &new_entry(AuditAction::Clear, "clear_secure_audit_log", no_keys, true),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_roundtrip() {
+        let path = std::env::temp_dir().join(format!("audit-test-{}.log", rand::random::<u64>()));
+
+        append(
+            &path,
+            &new_entry(
+                AuditAction::Write,
+                "set_secure_value",
+                ["openai_api_key"],
+                true,
+            ),
+        )
+        .unwrap();
+        append(
+            &path,
+            &new_entry(AuditAction::Read, "get_secure_values", ["a", "b"], false),
+        )
+        .unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not an entry\n")
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("openai_api_key"));
+
+        let entries = read_entries(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::Write);
+        assert_eq!(entries[0].keys, vec!["openai_api_key"]);
+        assert_eq!(entries[1].command, "get_secure_values");
+        assert!(!entries[1].success);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,291 @@
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::drafts;
+use crate::recent_files;
+use crate::settings;
+
+/// Size of the zero buffer used when overwriting files
+const OVERWRITE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Outcome of deleting a single file
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteReport {
+    path: String,
+    overwritten: bool,
+    removed: bool,
+    /// Whether the overwrite can be relied upon to destroy the old contents
+    guaranteed: bool,
+    /// Explanation when the platform cannot guarantee a secure delete
+    warning: Option<String>,
+}
+
+/// Best-effort overwrite of a file's contents with zeros, flushed to disk
+pub fn overwrite_file(path: &Path, size: u64) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = vec![0u8; OVERWRITE_CHUNK_SIZE];
+    let mut remaining = size;
+
+    while remaining > 0 {
+        let len = remaining.min(OVERWRITE_CHUNK_SIZE as u64) as usize;
+        file.write_all(&zeros[..len])?;
+        remaining -= len as u64;
+    }
+
+    file.sync_all()
+}
+
+/// Check whether the file lives on a rotational disk (Linux only).
+/// Returns `None` if it cannot be determined.
+#[cfg(target_os = "linux")]
+fn is_rotational(path: &Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = fs::metadata(path).ok()?.dev();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+
+    // Partitions don't have a queue directory; their parent disk does
+    let sys_dev = fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor)).ok()?;
+    let rotational = [sys_dev.join("queue"), sys_dev.join("../queue")]
+        .iter()
+        .find_map(|queue| fs::read_to_string(queue.join("rotational")).ok())?;
+
+    Some(rotational.trim() == "1")
+}
+
+/// `statfs` magic numbers of filesystems that write changed blocks elsewhere
+/// (copy-on-write or log-structured), so an overwrite leaves the old blocks
+/// behind even on a rotational disk
+#[cfg(target_os = "linux")]
+const COPY_ON_WRITE_FILESYSTEMS: &[(u32, &str)] = &[
+    (0x9123_683e, "Btrfs"),
+    (0x2fc1_2fc1, "ZFS"),
+    (0xca45_1a4e, "bcachefs"),
+    (0xf2f5_2010, "F2FS"),
+    (0x3434, "NILFS"),
+];
+
+/// Name of the filesystem with `statfs` type `fs_type` if it is copy-on-write
+#[cfg(target_os = "linux")]
+fn copy_on_write_name(fs_type: u32) -> Option<&'static str> {
+    COPY_ON_WRITE_FILESYSTEMS
+        .iter()
+        .find(|(magic, _)| *magic == fs_type)
+        .map(|(_, name)| *name)
+}
+
+/// The `statfs` type of the filesystem holding `path`, or `None` if it
+/// cannot be read
+#[cfg(target_os = "linux")]
+fn filesystem_type(path: &Path) -> Option<u32> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid out pointer
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // Magic numbers are 32 bits; `f_type` is wider on some targets
+    Some(stat.f_type as u32)
+}
+
+/// Explain why overwriting `path` in place may not destroy its old contents,
+/// or `None` if the platform allows a reliable overwrite
+fn secure_delete_caveat(path: &Path) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = path;
+        Some(
+            "APFS is copy-on-write and Macs use SSDs, so overwritten data may survive \
+             in unreferenced blocks. Use FileVault to protect deleted data."
+                .to_string(),
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        match filesystem_type(path).map(copy_on_write_name) {
+            Some(Some(name)) => {
+                return Some(format!(
+                    "{} does not overwrite data in place, so the old contents may survive \
+                     in unreferenced blocks. Use full-disk encryption to protect deleted data.",
+                    name
+                ))
+            }
+            Some(None) => {}
+            None => {
+                return Some(
+                    "Could not determine the filesystem; copy-on-write filesystems may keep \
+                     copies of the overwritten data."
+                        .to_string(),
+                )
+            }
+        }
+
+        match is_rotational(path) {
+            Some(true) => None,
+            Some(false) => Some(
+                "The file is stored on an SSD; wear levelling may keep copies of the \
+                 overwritten data. Use full-disk encryption to protect deleted data."
+                    .to_string(),
+            ),
+            None => Some(
+                "Could not determine the storage type; copy-on-write filesystems and SSDs \
+                 may keep copies of the overwritten data."
+                    .to_string(),
+            ),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = path;
+        Some(
+            "Secure deletion cannot be verified on this platform; SSD wear levelling may \
+             keep copies of the overwritten data. Use BitLocker to protect deleted data."
+                .to_string(),
+        )
+    }
+}
+
+/// Delete a file, overwriting its contents first when `secure` is set
+pub fn delete_path(path: &Path, secure: bool) -> Result<DeleteReport, String> {
+    let metadata =
+        fs::symlink_metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+
+    let mut report = DeleteReport {
+        path: path.display().to_string(),
+        overwritten: false,
+        removed: false,
+        guaranteed: false,
+        warning: None,
+    };
+
+    if secure && metadata.is_file() {
+        report.warning = secure_delete_caveat(path);
+        report.guaranteed = report.warning.is_none();
+
+        overwrite_file(path, metadata.len())
+            .map_err(|e| format!("Failed to overwrite file: {}", e))?;
+        report.overwritten = true;
+    }
+
+    fs::remove_file(path).map_err(|e| format!("Failed to delete file: {}", e))?;
+    report.removed = true;
+
+    Ok(report)
+}
+
+/// `path` with its directory resolved, so `..` and symlinked folders cannot
+/// lead out of an allowed directory. The file itself is not resolved: a
+/// symlink is deleted, not its target.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())?;
+    Some(fs::canonicalize(parent).ok()?.join(name))
+}
+
+/// Whether `path` is inside one of `dirs` or is one of `files`
+fn in_scope(path: &Path, dirs: &[PathBuf], files: &[PathBuf]) -> bool {
+    let Some(path) = resolve(path) else {
+        return false;
+    };
+    dirs.iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .any(|dir| path.starts_with(dir))
+        || files
+            .iter()
+            .filter_map(|file| resolve(file))
+            .any(|file| file == path)
+}
+
+/// Delete an audio or transcript file: a draft, or a file saved or opened
+/// through the app's dialogs, as listed in the recent files. Other paths are
+/// rejected. When `secure` is not given, the secure-delete setting, or the
+/// team config override, decides whether to overwrite first.
+#[tauri::command]
+pub async fn delete_file(
+    app: AppHandle,
+    path: String,
+    secure: Option<bool>,
+) -> Result<DeleteReport, String> {
+    tokio::task::spawn_blocking(move || {
+        let dirs = [drafts::drafts_dir(&app)?];
+        if !in_scope(Path::new(&path), &dirs, &recent_files::paths(&app)?) {
+            return Err(format!("Not a file the app saved or opened: {}", path));
+        }
+
+        let secure = match secure {
+            Some(secure) => secure,
+            None => settings::load_effective_settings(&app)?.secure_delete,
+        };
+
+        delete_path(Path::new(&path), secure)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Enable or disable secure deletion for file removal
+#[tauri::command]
+pub fn set_secure_delete(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        current.secure_delete = enabled;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_scope() {
+        let root =
+            std::env::temp_dir().join(format!("delete-scope-test-{}", rand::random::<u64>()));
+        let drafts = root.join("drafts");
+        let other = root.join("other");
+        fs::create_dir_all(&drafts).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        let saved = other.join("saved.txt");
+        let dirs = [drafts.clone()];
+        let files = [saved.clone()];
+
+        assert!(in_scope(&drafts.join("a.draft"), &dirs, &files));
+        assert!(in_scope(&saved, &dirs, &files));
+        assert!(in_scope(&drafts.join("../other/saved.txt"), &dirs, &files));
+        assert!(!in_scope(&other.join("secret.txt"), &dirs, &files));
+        assert!(!in_scope(
+            &drafts.join("../other/secret.txt"),
+            &dirs,
+            &files
+        ));
+        assert!(!in_scope(&drafts.join(".."), &dirs, &files));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&other, drafts.join("link")).unwrap();
+            assert!(!in_scope(&drafts.join("link/secret.txt"), &dirs, &files));
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_copy_on_write_name() {
+        assert_eq!(copy_on_write_name(0x9123_683e), Some("Btrfs"));
+        assert_eq!(copy_on_write_name(0x2fc1_2fc1), Some("ZFS"));
+        // ext4
+        assert_eq!(copy_on_write_name(0xef53), None);
+        assert!(filesystem_type(&std::env::temp_dir()).is_some());
+    }
+}
@@ -0,0 +1,138 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::auto_transcribe::AutoTranscribeSettings;
+use crate::biometric::BiometricUnlock;
+use crate::dictation::PipelineProfile;
+use crate::files::{self, SaveBackup};
+use crate::maintenance::MaintenanceSettings;
+use crate::team_config;
+use crate::transcript::{Correction, PostProcessing, ProfanityFilter};
+use crate::transcription::acceleration::WhisperBackend;
+
+/// Backend settings persisted as JSON in the app data directory.
+/// Only non-sensitive preferences belong here; credentials go through secure storage.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackendSettings {
+    /// Stable id of the input device `start_recording` should prefer
+    pub preferred_input_device: Option<String>,
+    /// Names the user gave input devices, by stable device id
+    pub input_device_aliases: BTreeMap<String, String>,
+    /// Overwrite audio and transcript files before deleting them
+    pub secure_delete: bool,
+    /// URL of the signed team config bundle published by an administrator
+    pub team_config_url: Option<String>,
+    /// Base64 ed25519 key the team config bundle must be signed with
+    pub team_config_public_key: Option<String>,
+    /// When background maintenance tasks may run
+    pub maintenance: MaintenanceSettings,
+    /// Capture audio in a separate helper process, which keeps recording if
+    /// the app crashes. Off by default; the app captures in process then.
+    pub capture_helper: bool,
+    /// Memory transcription jobs should stay within; nearing it emits a warning
+    pub job_memory_budget_mb: Option<u64>,
+    /// IANA time zone timestamps are shown in; `None` follows the system
+    pub display_time_zone: Option<String>,
+    /// Locale such as "de-DE" timestamps are formatted for; `None` follows the system
+    pub display_locale: Option<String>,
+    /// What happens when a recording stops
+    pub auto_transcribe: AutoTranscribeSettings,
+    /// Dictation profiles by name; see `start_dictation`
+    pub pipeline_profiles: BTreeMap<String, PipelineProfile>,
+    /// Masks or removes profanity in finished transcripts
+    pub profanity_filter: ProfanityFilter,
+    /// Adds punctuation and capitals to transcripts from engines that leave
+    /// them out
+    pub restore_punctuation: bool,
+    /// Misrecognized words and what they should be; see `add_correction`
+    pub corrections: Vec<Correction>,
+    /// Steps finished transcripts run through; see `set_post_processing`
+    pub post_processing: PostProcessing,
+    /// Where local Whisper models run
+    pub whisper_backend: WhisperBackend,
+    /// GPU local models run on, for machines with several
+    pub whisper_gpu_device: u32,
+    /// Ask for Touch ID or Windows Hello before secure values reach the
+    /// frontend; see `set_biometric_unlock`
+    pub biometric_unlock: BiometricUnlock,
+    /// Keep secure values in memory after their first read, until
+    /// `clear_secure_cache` or `lock_secure_storage`
+    pub cache_secure_values: bool,
+    /// Folder save dialogs open in; `None` leaves it to the OS
+    pub default_export_folder: Option<String>,
+    /// Copy a file the save dialog is about to overwrite to a backup first
+    pub save_backup: SaveBackup,
+}
+
+/// Serializes changes to the settings file, so two commands changing
+/// different settings do not undo each other
+static SETTINGS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Get the path to the backend settings file in the app's data directory
+fn get_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+
+    Ok(app_data_dir.join("settings.json"))
+}
+
+/// Load backend settings, falling back to defaults if none have been saved yet
+pub fn load_settings(app: &AppHandle) -> Result<BackendSettings, String> {
+    let path = get_settings_path(app)?;
+
+    if !path.exists() {
+        return Ok(BackendSettings::default());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read settings: {}", e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+/// Load backend settings with the active team config applied on top.
+/// Use this for behaviour; use `load_settings` when editing and saving.
+pub fn load_effective_settings(app: &AppHandle) -> Result<BackendSettings, String> {
+    let mut settings = load_settings(app)?;
+    if let Some(team) = team_config::current() {
+        team.apply(&mut settings);
+    }
+    Ok(settings)
+}
+
+/// Persist backend settings
+fn save_settings(app: &AppHandle, settings: &BackendSettings) -> Result<(), String> {
+    let path = get_settings_path(app)?;
+
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    files::write_atomic(&path, content.as_bytes())
+        .map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Load the backend settings, apply `change` and save them, with no other
+/// change in between. Nothing is saved when `change` fails.
+pub fn update_settings<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut BackendSettings) -> Result<T, String>,
+) -> Result<T, String> {
+    let _lock = SETTINGS_LOCK.lock();
+    let mut settings = load_settings(app)?;
+    let result = change(&mut settings)?;
+    save_settings(app, &settings)?;
+    Ok(result)
+}
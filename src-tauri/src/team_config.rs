@@ -0,0 +1,370 @@
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::{self, BackendSettings};
+
+const CACHE_FILE_NAME: &str = "team-config.json";
+const FETCH_TIMEOUT_SECS: u64 = 10;
+
+/// Returned for cloud providers and their credentials under `force_offline`
+pub const FORCE_OFFLINE_ERROR: &str =
+    "Your organization's team config keeps all processing on this machine";
+
+/// Organisation-wide configuration published by an administrator.
+/// Unset fields leave the local settings in charge.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TeamConfig {
+    /// Transcription providers users may pick; `None` allows all
+    pub allowed_providers: Option<Vec<String>>,
+    /// Keep all processing on this machine
+    pub force_offline: bool,
+    /// Default prompts by name, used where the user has not set their own
+    pub default_prompts: HashMap<String, String>,
+    /// Overrides the local secure delete preference
+    pub secure_delete: Option<bool>,
+}
+
+impl TeamConfig {
+    /// Apply the fields the backend enforces on top of local settings
+    pub fn apply(&self, settings: &mut BackendSettings) {
+        if let Some(secure_delete) = self.secure_delete {
+            settings.secure_delete = secure_delete;
+        }
+    }
+
+    /// Fail if this config rules out the provider `id`; `offline` if it keeps
+    /// all processing on this machine
+    pub fn check_provider(&self, id: &str, offline: bool) -> Result<(), String> {
+        if self.force_offline && !offline {
+            return Err(FORCE_OFFLINE_ERROR.to_string());
+        }
+        match &self.allowed_providers {
+            Some(allowed) if !allowed.iter().any(|allowed| allowed == id) => Err(format!(
+                "The provider {} is not allowed by your organization's team config",
+                id
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Fail if the active team config rules out the provider `id`; see
+/// `TeamConfig::check_provider`
+pub fn ensure_provider_allowed(id: &str, offline: bool) -> Result<(), String> {
+    current().map_or(Ok(()), |config| config.check_provider(id, offline))
+}
+
+/// Fail with `FORCE_OFFLINE_ERROR` if the active team config keeps all
+/// processing on this machine
+pub fn ensure_online_allowed() -> Result<(), String> {
+    if current().is_some_and(|config| config.force_offline) {
+        Err(FORCE_OFFLINE_ERROR.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Bundle as served by the configuration host: `payload` is the base64 of the
+/// `TeamConfig` JSON and `signature` the base64 ed25519 signature over those bytes
+#[derive(Debug, Deserialize)]
+struct SignedBundle {
+    payload: String,
+    signature: String,
+}
+
+/// Where the active team config came from
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TeamConfigOrigin {
+    Remote,
+    /// Last verified bundle, used while the host is unreachable
+    Cache,
+}
+
+/// Team config state as reported to the frontend
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamConfigStatus {
+    /// A config URL and public key are set
+    pub configured: bool,
+    pub origin: Option<TeamConfigOrigin>,
+    pub fetched_at: Option<String>,
+    pub config: Option<TeamConfig>,
+    /// Why the latest fetch or verification failed
+    pub error: Option<String>,
+}
+
+static STATUS: Lazy<Mutex<TeamConfigStatus>> =
+    Lazy::new(|| Mutex::new(TeamConfigStatus::default()));
+
+/// Currently active, verified team config
+pub fn current() -> Option<TeamConfig> {
+    STATUS.lock().config.clone()
+}
+
+/// Decode a base64 ed25519 public key
+fn parse_public_key(public_key: &str) -> Result<VerifyingKey, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(public_key.trim())
+        .map_err(|e| format!("Failed to decode public key: {}", e))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "Public key must be 32 bytes".to_string())?;
+
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+/// Verify a signed bundle and parse its payload
+fn verify_bundle(bundle: &[u8], public_key: &str) -> Result<TeamConfig, String> {
+    let key = parse_public_key(public_key)?;
+    let bundle: SignedBundle =
+        serde_json::from_slice(bundle).map_err(|e| format!("Failed to parse bundle: {}", e))?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let payload = engine
+        .decode(&bundle.payload)
+        .map_err(|e| format!("Failed to decode payload: {}", e))?;
+    let signature = engine
+        .decode(&bundle.signature)
+        .map_err(|e| format!("Failed to decode signature: {}", e))?;
+    let signature =
+        Signature::from_slice(&signature).map_err(|e| format!("Invalid signature: {}", e))?;
+
+    key.verify(&payload, &signature)
+        .map_err(|_| "Signature verification failed".to_string())?;
+
+    serde_json::from_slice(&payload).map_err(|e| format!("Failed to parse team config: {}", e))
+}
+
+/// Get the path of the last verified bundle in the app's data directory
+fn get_cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+
+    Ok(app_data_dir.join(CACHE_FILE_NAME))
+}
+
+async fn fetch_bundle(url: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch team config: {}", e))?;
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read team config: {}", e))?;
+
+    Ok(body.to_vec())
+}
+
+/// Fetch and verify the team config, falling back to the cached bundle (which
+/// is verified again, so a tampered cache is rejected) when the host is unreachable
+async fn load_team_config(app: &AppHandle, settings: &BackendSettings) -> TeamConfigStatus {
+    let (Some(url), Some(public_key)) = (
+        settings.team_config_url.as_deref(),
+        settings.team_config_public_key.as_deref(),
+    ) else {
+        return TeamConfigStatus::default();
+    };
+
+    let mut status = TeamConfigStatus {
+        configured: true,
+        ..Default::default()
+    };
+    let cache_path = get_cache_path(app);
+
+    match fetch_bundle(url).await {
+        Ok(bundle) => match verify_bundle(&bundle, public_key) {
+            Ok(config) => {
+                if let Ok(path) = &cache_path {
+                    if let Err(e) = fs::write(path, &bundle) {
+                        eprintln!("Failed to cache team config: {}", e);
+                    }
+                }
+                status.origin = Some(TeamConfigOrigin::Remote);
+                status.fetched_at = Some(chrono::Utc::now().to_rfc3339());
+                status.config = Some(config);
+                return status;
+            }
+            Err(e) => status.error = Some(e),
+        },
+        Err(e) => status.error = Some(e),
+    }
+
+    let cached = cache_path.ok().and_then(|path| fs::read(path).ok());
+    if let Some(Ok(config)) = cached.map(|bundle| verify_bundle(&bundle, public_key)) {
+        status.origin = Some(TeamConfigOrigin::Cache);
+        status.config = Some(config);
+    }
+
+    status
+}
+
+/// Reload the team config and notify the frontend
+async fn refresh(app: &AppHandle) -> Result<TeamConfigStatus, String> {
+    let settings = settings::load_settings(app)?;
+    let status = load_team_config(app, &settings).await;
+
+    *STATUS.lock() = status.clone();
+    let _ = app.emit("team-config-updated", status.clone());
+
+    Ok(status)
+}
+
+/// Fetch the team config in the background during startup
+pub fn load_on_startup(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = refresh(&app).await {
+            eprintln!("Failed to load team config: {}", e);
+        }
+    });
+}
+
+/// Get the active team config and where it came from
+#[tauri::command]
+pub fn get_team_config() -> TeamConfigStatus {
+    STATUS.lock().clone()
+}
+
+/// Fetch the team config again
+#[tauri::command]
+pub async fn refresh_team_config(app: AppHandle) -> Result<TeamConfigStatus, String> {
+    refresh(&app).await
+}
+
+/// Point the app at a team config host, or pass `None` to stop using one
+#[tauri::command]
+pub async fn set_team_config_source(
+    app: AppHandle,
+    url: Option<String>,
+    public_key: Option<String>,
+) -> Result<TeamConfigStatus, String> {
+    if let Some(public_key) = &public_key {
+        parse_public_key(public_key)?;
+    }
+
+    settings::update_settings(&app, |current| {
+        current.team_config_url = url;
+        current.team_config_public_key = public_key;
+        Ok(())
+    })?;
+
+    if let Ok(path) = get_cache_path(&app) {
+        let _ = fs::remove_file(path);
+    }
+
+    refresh(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signed_bundle(key: &SigningKey, payload: &[u8]) -> Vec<u8> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        serde_json::json!({
+            "payload": engine.encode(payload),
+            "signature": engine.encode(key.sign(payload).to_bytes()),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        base64::engine::general_purpose::STANDARD.encode(key.verifying_key().to_bytes())
+    }
+
+    #[test]
+    fn test_check_provider() {
+        let unrestricted = TeamConfig::default();
+        assert!(unrestricted.check_provider("openai", false).is_ok());
+
+        let allowed = TeamConfig {
+            allowed_providers: Some(vec!["local".to_string(), "deepgram".to_string()]),
+            ..Default::default()
+        };
+        assert!(allowed.check_provider("deepgram", false).is_ok());
+        assert!(allowed.check_provider("openai", false).is_err());
+
+        let offline = TeamConfig {
+            force_offline: true,
+            ..allowed
+        };
+        assert!(offline.check_provider("local", true).is_ok());
+        assert_eq!(
+            offline.check_provider("deepgram", false),
+            Err(FORCE_OFFLINE_ERROR.to_string())
+        );
+    }
+
+    #[test]
+    fn test_verify_accepts_valid_signature() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let payload = br#"{"allowedProviders":["local"],"forceOffline":true}"#;
+
+        let config = verify_bundle(&signed_bundle(&key, payload), &public_key(&key)).unwrap();
+
+        assert_eq!(config.allowed_providers, Some(vec!["local".to_string()]));
+        assert!(config.force_offline);
+        assert!(config.secure_delete.is_none());
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_payload_and_wrong_key() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let bundle = signed_bundle(&key, br#"{"forceOffline":true}"#);
+
+        let tampered = String::from_utf8(bundle.clone()).unwrap().replace(
+            &base64::engine::general_purpose::STANDARD.encode(br#"{"forceOffline":true}"#),
+            &base64::engine::general_purpose::STANDARD.encode(br#"{"forceOffline":false}"#),
+        );
+
+        assert!(verify_bundle(tampered.as_bytes(), &public_key(&key)).is_err());
+        assert!(verify_bundle(&bundle, &public_key(&other)).is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides_only_set_fields() {
+        let mut local = BackendSettings {
+            secure_delete: true,
+            ..Default::default()
+        };
+
+        TeamConfig::default().apply(&mut local);
+        assert!(local.secure_delete);
+
+        let team = TeamConfig {
+            secure_delete: Some(false),
+            ..Default::default()
+        };
+        team.apply(&mut local);
+        assert!(!local.secure_delete);
+    }
+}
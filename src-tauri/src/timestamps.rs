@@ -0,0 +1,82 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::settings::{self, BackendSettings};
+pub use transcriber_core::timestamps::{FormattedTimestamp, Formatter};
+
+/// Formatter for the settings' overrides; an unknown time zone falls back to
+/// the system's
+fn formatter_for(settings: &BackendSettings) -> Formatter {
+    Formatter::with_fallback(
+        settings.display_time_zone.as_deref(),
+        settings.display_locale.as_deref(),
+    )
+}
+
+/// Formatter for the user's time zone and locale
+pub fn formatter(app: &AppHandle) -> Formatter {
+    formatter_for(&settings::load_effective_settings(app).unwrap_or_default())
+}
+
+/// Time zone and locale timestamps are shown in
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimestampFormat {
+    /// IANA name, or the UTC offset when the system's zone has no known name
+    time_zone: String,
+    locale: String,
+    /// Whether the settings override the system's time zone
+    time_zone_overridden: bool,
+    /// Whether the settings override the system's locale
+    locale_overridden: bool,
+}
+
+fn timestamp_format(app: &AppHandle) -> TimestampFormat {
+    let settings = settings::load_effective_settings(app).unwrap_or_default();
+    let formatter = formatter_for(&settings);
+    TimestampFormat {
+        time_zone: formatter.time_zone(),
+        locale: formatter.locale().to_string(),
+        time_zone_overridden: settings.display_time_zone.is_some(),
+        locale_overridden: settings.display_locale.is_some(),
+    }
+}
+
+/// The time zone and locale used for history listings and exports
+#[tauri::command]
+pub fn get_timestamp_format(app: AppHandle) -> TimestampFormat {
+    timestamp_format(&app)
+}
+
+/// Override the time zone (IANA name such as "Europe/Berlin") and locale
+/// (such as "de-DE") timestamps are shown in; `None` follows the system
+#[tauri::command]
+pub fn set_timestamp_format(
+    app: AppHandle,
+    time_zone: Option<String>,
+    locale: Option<String>,
+) -> Result<TimestampFormat, String> {
+    Formatter::new(time_zone.as_deref(), None)?;
+    let locale = locale.filter(|locale| !locale.trim().is_empty());
+
+    settings::update_settings(&app, |current| {
+        current.display_time_zone = time_zone;
+        current.display_locale = locale.map(|locale| locale.trim().replace('_', "-"));
+        Ok(())
+    })?;
+
+    Ok(timestamp_format(&app))
+}
+
+/// Format stored timestamps for a history listing, in the order given
+#[tauri::command]
+pub fn format_timestamps(
+    app: AppHandle,
+    timestamps: Vec<String>,
+) -> Result<Vec<FormattedTimestamp>, String> {
+    let formatter = formatter(&app);
+    timestamps
+        .iter()
+        .map(|timestamp| formatter.format(timestamp))
+        .collect()
+}
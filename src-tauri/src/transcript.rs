@@ -0,0 +1,343 @@
+use clipboard_rs::{Clipboard, ClipboardContent, ClipboardContext};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tauri::AppHandle;
+
+use crate::files::{self, FileDialogFilter};
+use crate::settings;
+use crate::timestamps;
+use transcriber_core::transcript::document::{TranscriptDocument, TranscriptExport};
+use transcriber_core::transcript::markdown::{self, MarkdownOptions, MarkdownTranscript};
+use transcriber_core::transcript::subtitles::{self, SubtitleFormat, SubtitleOptions};
+use transcriber_core::transcript::{self, chunking, rich_text};
+pub use transcriber_core::transcript::{
+    format_text, segments, ChunkOptions, Correction, FormattedTranscript, PostProcessing,
+    ProfanityFilter, Segment, TokenCount, TranscriptChunk,
+};
+use transcriber_core::transcription::TranscriptSegment;
+
+/// Write out spoken URLs and emails and turn "begin code" ... "end code"
+/// sections into code blocks with their symbols intact
+#[tauri::command]
+pub fn format_transcript(text: String) -> FormattedTranscript {
+    transcript::format(&text)
+}
+
+/// Kept alive for the app's lifetime: on X11 the clipboard contents are served
+/// by their owner and vanish when the context is dropped
+static CLIPBOARD: Lazy<Mutex<Option<ClipboardContext>>> = Lazy::new(|| Mutex::new(None));
+
+/// Copy a formatted transcript as plain text, HTML and RTF at once, so word
+/// processors keep headings, lists and code blocks while terminals get clean text
+#[tauri::command]
+pub async fn copy_transcript(text: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let contents = vec![
+            ClipboardContent::Text(rich_text::to_plain(&text)),
+            ClipboardContent::Html(rich_text::to_html(&text)),
+            ClipboardContent::Rtf(rich_text::to_rtf(&text)),
+        ];
+
+        let mut clipboard = CLIPBOARD.lock();
+        if clipboard.is_none() {
+            let context =
+                ClipboardContext::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+            *clipboard = Some(context);
+        }
+
+        clipboard
+            .as_ref()
+            .expect("clipboard context initialized above")
+            .set(contents)
+            .map_err(|e| format!("Failed to copy to clipboard: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Split a long transcript into token-budgeted, overlapping chunks for
+/// feeding into an LLM
+#[tauri::command]
+pub async fn chunk_transcript(
+    text: String,
+    options: Option<ChunkOptions>,
+) -> Result<Vec<TranscriptChunk>, String> {
+    tokio::task::spawn_blocking(move || chunking::chunk(&text, &options.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Count the tokens of `text` for an OpenAI model or encoding ("o200k_base",
+/// "cl100k_base"), with the room left in the model's context window
+#[tauri::command]
+pub async fn count_tokens(text: String, model: String) -> Result<TokenCount, String> {
+    tokio::task::spawn_blocking(move || chunking::count_tokens(&text, &model))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Render the segments of a transcription result as SRT or WebVTT subtitles.
+/// Long segments are split over several cues, keeping lines and cue
+/// durations within `options`.
+#[tauri::command]
+pub fn render_subtitles(
+    segments: Vec<TranscriptSegment>,
+    format: SubtitleFormat,
+    options: Option<SubtitleOptions>,
+) -> Result<String, String> {
+    if segments.is_empty() {
+        return Err("The transcript has no timestamps to make subtitles from".to_string());
+    }
+    Ok(subtitles::render(
+        &segments,
+        format,
+        &options.unwrap_or_default(),
+    ))
+}
+
+/// Write subtitles as with `render_subtitles` to `path`, adding the format's
+/// extension if it has none. Returns the path written.
+#[tauri::command]
+pub async fn save_subtitles(
+    segments: Vec<TranscriptSegment>,
+    format: SubtitleFormat,
+    path: String,
+    options: Option<SubtitleOptions>,
+) -> Result<String, String> {
+    let contents = render_subtitles(segments, format, options)?;
+    let mut path = std::path::PathBuf::from(path);
+    if path.extension().is_none() {
+        path.set_extension(format.extension());
+    }
+
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| format!("Failed to write subtitles: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// `default_filename` with `extension` added if it has none, or
+/// "transcript" if none was given
+fn export_filename(default_filename: Option<String>, extension: &str) -> String {
+    let mut path = std::path::PathBuf::from(
+        default_filename
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| "transcript".to_string()),
+    );
+    if path.extension().is_none() {
+        path.set_extension(extension);
+    }
+    path.to_string_lossy().to_string()
+}
+
+/// Render the segments as SRT subtitles (numbered cues, `HH:MM:SS,mmm`
+/// timecodes, wrapped lines) and save them through the save dialog.
+/// Returns the path written.
+#[tauri::command]
+pub async fn export_srt(
+    app: AppHandle,
+    segments: Vec<TranscriptSegment>,
+    default_filename: Option<String>,
+    options: Option<SubtitleOptions>,
+) -> Result<String, String> {
+    let contents = render_subtitles(segments, SubtitleFormat::Srt, options)?;
+    files::save_with_dialog(
+        &app,
+        contents.as_bytes(),
+        &export_filename(default_filename, SubtitleFormat::Srt.extension()),
+        &[FileDialogFilter::new("SubRip subtitles", "srt")],
+    )
+}
+
+/// Render the segments as WebVTT captions for the web (`WEBVTT` header,
+/// `HH:MM:SS.mmm` timecodes, speakers as voice tags unless turned off in
+/// `options`) and save them through the save dialog. Returns the path written.
+#[tauri::command]
+pub async fn export_vtt(
+    app: AppHandle,
+    segments: Vec<TranscriptSegment>,
+    default_filename: Option<String>,
+    options: Option<SubtitleOptions>,
+) -> Result<String, String> {
+    let contents = render_subtitles(segments, SubtitleFormat::Vtt, options)?;
+    files::save_with_dialog(
+        &app,
+        contents.as_bytes(),
+        &export_filename(default_filename, SubtitleFormat::Vtt.extension()),
+        &[FileDialogFilter::new("WebVTT captions", "vtt")],
+    )
+}
+
+/// Render a transcript as Markdown: title and date heading, optional summary
+/// section, and one paragraph per segment with its timestamp and speaker as
+/// `options` say. A date given as a stored timestamp is shown in the user's
+/// time zone and locale.
+#[tauri::command]
+pub fn render_markdown(
+    app: AppHandle,
+    mut transcript: MarkdownTranscript,
+    options: Option<MarkdownOptions>,
+) -> String {
+    if let Some(date) = transcript.date.take() {
+        let formatted = timestamps::formatter(&app).format(&date);
+        transcript.date = Some(formatted.map(|formatted| formatted.display).unwrap_or(date));
+    }
+    markdown::render(&transcript, &options.unwrap_or_default())
+}
+
+/// Render a transcript as with `render_markdown` and save it through the
+/// save dialog. Returns the path written.
+#[tauri::command]
+pub async fn export_markdown(
+    app: AppHandle,
+    transcript: MarkdownTranscript,
+    default_filename: Option<String>,
+    options: Option<MarkdownOptions>,
+) -> Result<String, String> {
+    let default_filename = default_filename.or_else(|| transcript.title.clone());
+    let contents = render_markdown(app.clone(), transcript, options);
+    files::save_with_dialog(
+        &app,
+        contents.as_bytes(),
+        &export_filename(default_filename, "md"),
+        &[FileDialogFilter::new("Markdown", "md")],
+    )
+}
+
+/// Save a transcript with its segments, word timestamps, speakers, audio
+/// metadata and provider as a versioned JSON document through the save
+/// dialog. Returns the path written.
+#[tauri::command]
+pub async fn export_json(
+    app: AppHandle,
+    transcript: TranscriptExport,
+    default_filename: Option<String>,
+) -> Result<String, String> {
+    let default_filename = default_filename.or_else(|| transcript.title.clone());
+    let document = TranscriptDocument::new(transcript, chrono::Utc::now().to_rfc3339());
+    let contents = serde_json::to_string_pretty(&document)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+    files::save_with_dialog(
+        &app,
+        contents.as_bytes(),
+        &export_filename(default_filename, "json"),
+        &[FileDialogFilter::new("Transcript JSON", "json")],
+    )
+}
+
+/// Get how finished transcripts are filtered for profanity
+#[tauri::command]
+pub fn get_profanity_filter(app: AppHandle) -> Result<ProfanityFilter, String> {
+    Ok(settings::load_settings(&app)?.profanity_filter)
+}
+
+/// Mask or remove profanity in finished transcripts from now on, or stop
+/// with mode `off`. Live results are not filtered.
+#[tauri::command]
+pub fn set_profanity_filter(app: AppHandle, filter: ProfanityFilter) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        current.profanity_filter = filter;
+        Ok(())
+    })
+}
+
+/// Whether finished transcripts without punctuation get it restored
+#[tauri::command]
+pub fn get_punctuation_restoration(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load_settings(&app)?.restore_punctuation)
+}
+
+/// Add punctuation and capitals to finished transcripts from engines that
+/// emit neither, such as lowercase streaming models. Spoken punctuation
+/// ("comma", "period") is written out, and pauses end sentences. Transcripts
+/// that already have punctuation are left alone.
+#[tauri::command]
+pub fn set_punctuation_restoration(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        current.restore_punctuation = enabled;
+        Ok(())
+    })
+}
+
+/// The user's corrections, in the order they were added
+#[tauri::command]
+pub fn list_corrections(app: AppHandle) -> Result<Vec<Correction>, String> {
+    Ok(settings::load_settings(&app)?.corrections)
+}
+
+/// Replace `wrong` with `right` in every finished transcript from now on,
+/// e.g. a name the engine keeps misspelling. `wrong` matches whole words in
+/// any case; adding it again changes its replacement. `right` is also given
+/// to engines that take a vocabulary prompt.
+#[tauri::command]
+pub fn add_correction(app: AppHandle, wrong: String, right: String) -> Result<(), String> {
+    let wrong = wrong.split_whitespace().collect::<Vec<_>>().join(" ");
+    if wrong.is_empty() {
+        return Err("The text to correct is empty".to_string());
+    }
+
+    settings::update_settings(&app, |current| {
+        let right = right.trim().to_string();
+        match current
+            .corrections
+            .iter_mut()
+            .find(|correction| correction.wrong.to_lowercase() == wrong.to_lowercase())
+        {
+            Some(correction) => correction.right = right,
+            None => current.corrections.push(Correction { wrong, right }),
+        }
+        Ok(())
+    })
+}
+
+/// Stop correcting `wrong`
+#[tauri::command]
+pub fn remove_correction(app: AppHandle, wrong: String) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        let count = current.corrections.len();
+        current
+            .corrections
+            .retain(|correction| correction.wrong.to_lowercase() != wrong.trim().to_lowercase());
+        if current.corrections.len() == count {
+            return Err(format!("No correction for \"{}\"", wrong));
+        }
+        Ok(())
+    })
+}
+
+/// The post-processing steps finished transcripts run through, in order
+#[tauri::command]
+pub fn get_post_processing(app: AppHandle) -> Result<PostProcessing, String> {
+    Ok(settings::load_settings(&app)?.post_processing)
+}
+
+/// Replace the post-processing steps: filler trimming, casing, corrections
+/// and custom regex rules, each enabled or not, run in the given order
+#[tauri::command]
+pub fn set_post_processing(app: AppHandle, post_processing: PostProcessing) -> Result<(), String> {
+    post_processing.compile(&[])?;
+
+    settings::update_settings(&app, |current| {
+        current.post_processing = post_processing;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_filename() {
+        assert_eq!(export_filename(None, "srt"), "transcript.srt");
+        assert_eq!(
+            export_filename(Some("Meeting 10-15".to_string()), "md"),
+            "Meeting 10-15.md"
+        );
+        assert_eq!(
+            export_filename(Some("captions.txt".to_string()), "srt"),
+            "captions.txt"
+        );
+        assert_eq!(export_filename(Some(" ".to_string()), "vtt"), "transcript.vtt");
+    }
+}
@@ -0,0 +1,921 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::analytics::{PaceMetrics, PaceTracker};
+use crate::audio::{self, AudioRecorder};
+use crate::events::app_events;
+use crate::{commands, policy, settings, team_config, usage};
+use provider::{AudioFile, ProviderCapabilities, ProviderRequest, TranscriptionProvider};
+use transcriber_core::transcript::corrections;
+use transcriber_core::transcription::{chunking, restore_punctuation};
+pub use transcriber_core::transcription::{
+    LanguageDetection, TranscriptSegment, TranscriptionResult,
+};
+use transcriber_core::Events;
+
+pub mod acceleration;
+mod hardware;
+pub mod jobs;
+pub mod models;
+pub mod provider;
+pub mod resources;
+pub mod retry_queue;
+pub mod vosk;
+
+#[cfg(feature = "local-whisper")]
+mod whisper;
+
+/// Stand-in for builds without whisper.cpp
+#[cfg(not(feature = "local-whisper"))]
+mod whisper {
+    use super::{LanguageDetection, TranscribeOptions, TranscriptSegment, TranscriptionResult};
+
+    pub fn transcribe(
+        _model_path: &std::path::Path,
+        _acceleration: super::acceleration::Acceleration,
+        _samples: &[f32],
+        _options: &TranscribeOptions,
+        _on_segment: impl FnMut(&TranscriptSegment) + 'static,
+        _control: super::jobs::JobControl,
+    ) -> Result<TranscriptionResult, String> {
+        Err("Local transcription is not supported in this build".to_string())
+    }
+
+    pub fn detect_language(
+        _model_path: &std::path::Path,
+        _acceleration: super::acceleration::Acceleration,
+        _samples: &[f32],
+    ) -> Result<LanguageDetection, String> {
+        Err("Local transcription is not supported in this build".to_string())
+    }
+}
+
+/// Options shared by the transcription commands
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranscribeOptions {
+    /// Whisper model name, e.g. "base", "small.en" or the quantized "base-q5_1"
+    /// (file `ggml-<model>.bin`)
+    pub model: String,
+    /// ISO 639-1 code; `None` or "auto" detects the language
+    pub language: Option<String>,
+    /// Translate the speech to English instead of transcribing it
+    pub translate: bool,
+    /// Label segments with speakers; locally this needs a tinydiarize model
+    pub diarize: bool,
+    /// Id to follow the job with `get_job_resource_usage` while it runs
+    pub job_id: Option<String>,
+    /// CPU threads for local transcription; `None` uses up to 8
+    pub threads: Option<usize>,
+    /// Vocabulary or context that guides the transcription
+    pub prompt: Option<String>,
+}
+
+impl Default for TranscribeOptions {
+    fn default() -> Self {
+        Self {
+            model: "base".to_string(),
+            language: None,
+            translate: false,
+            diarize: false,
+            job_id: None,
+            threads: None,
+            prompt: None,
+        }
+    }
+}
+
+/// Normalize the requested language: "auto" and empty mean detection, and
+/// English-only models cannot be asked for anything but English
+fn resolve_language(options: &mut TranscribeOptions) -> Result<(), String> {
+    let language = options
+        .language
+        .take()
+        .map(|language| language.trim().to_lowercase())
+        .filter(|language| !language.is_empty() && language != "auto");
+
+    if let Some(language) = &language {
+        let valid =
+            (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase());
+        if !valid {
+            return Err(format!("Invalid language code: {}", language));
+        }
+    }
+
+    if models::english_only(&options.model) {
+        match language.as_deref() {
+            None | Some("en") => {
+                options.language = Some("en".to_string());
+                return Ok(());
+            }
+            Some(other) => {
+                return Err(format!(
+                    "Model \"{}\" only supports English; use a multilingual model for \"{}\"",
+                    options.model, other
+                ))
+            }
+        }
+    }
+
+    options.language = language;
+    Ok(())
+}
+
+/// Whisper only diarizes with tinydiarize models, which mark speaker turns
+fn check_diarization(options: &TranscribeOptions) -> Result<(), String> {
+    if options.diarize && !options.model.ends_with("-tdrz") {
+        return Err(format!(
+            "Model \"{}\" cannot tell speakers apart; \
+             use a tinydiarize model such as small.en-tdrz",
+            options.model
+        ));
+    }
+    Ok(())
+}
+
+/// Get (and create) the directory local models are stored in
+pub fn get_models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("models");
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create models directory: {}", e))?;
+    }
+
+    Ok(dir)
+}
+
+/// Path of an installed Whisper model
+fn model_path(app: &AppHandle, model: &str) -> Result<PathBuf, String> {
+    let path = get_models_dir(app)?.join(models::model_file_name(model)?);
+    if !path.exists() {
+        return Err(format!(
+            "Model \"{}\" is not installed (expected at {})",
+            model,
+            path.display()
+        ));
+    }
+
+    Ok(path)
+}
+
+/// `options` with the user's corrections added to the prompt; jobs get them
+/// through `provider_work` instead
+fn with_vocabulary(
+    app: &AppHandle,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscribeOptions, String> {
+    let mut options = options.unwrap_or_default();
+    let corrections = settings::load_settings(app)?.corrections;
+    options.prompt = corrections::vocabulary_prompt(&corrections, options.prompt.as_deref());
+    Ok(options)
+}
+
+async fn transcribe_samples(
+    app: AppHandle,
+    samples: Vec<f32>,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    let mut options = options.unwrap_or_default();
+    resolve_language(&mut options)?;
+    check_diarization(&options)?;
+    let model_path = model_path(&app, &options.model)?;
+    let acceleration = acceleration::current(&app)?;
+
+    let job_id = options.job_id.clone().unwrap_or_else(generate_job_id);
+    let monitor = resources::JobMonitor::start(&app, &job_id);
+    let control = jobs::JobControl::for_job(&app, &job_id);
+
+    // Running word count and pace, as segments are decoded
+    let mut pace = PaceTracker::default();
+    let on_segment = move |segment: &TranscriptSegment| {
+        let _ = app.emit("transcription-pace", pace.push(segment));
+    };
+
+    let mut result = tokio::task::spawn_blocking(move || {
+        let _monitor = monitor;
+        whisper::transcribe(
+            &model_path,
+            acceleration,
+            &samples,
+            &options,
+            on_segment,
+            control,
+        )
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+
+    result.pace = PaceMetrics::from_segments(&result.segments, result.duration_ms);
+    Ok(result)
+}
+
+/// Transcribe the last stopped recording locally with Whisper
+#[tauri::command]
+pub async fn transcribe_recording(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    let samples = audio::last_take_speech_samples(&recorder)?;
+    let options = with_vocabulary(&app, options)?;
+    let result = transcribe_samples(app.clone(), samples, Some(options)).await?;
+    clean_up(&app, result)
+}
+
+async fn load_file_samples(path: String) -> Result<Vec<f32>, String> {
+    tokio::task::spawn_blocking(move || audio::load_speech_samples(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Transcribe a WAV file locally with Whisper
+#[tauri::command]
+pub async fn transcribe_file(
+    app: AppHandle,
+    path: String,
+    options: Option<TranscribeOptions>,
+) -> Result<TranscriptionResult, String> {
+    let samples = load_file_samples(path).await?;
+    let options = with_vocabulary(&app, options)?;
+    let result = transcribe_samples(app.clone(), samples, Some(options)).await?;
+    clean_up(&app, result)
+}
+
+/// Local transcription with whisper.cpp, using the app's models and job
+/// tracking
+pub struct LocalWhisper(AppHandle);
+
+/// Offer local transcription; registered at startup, as it needs the app
+pub fn register_local_provider(app: &AppHandle) {
+    provider::register(std::sync::Arc::new(LocalWhisper(app.clone())));
+    provider::register(std::sync::Arc::new(vosk::Vosk(app.clone())));
+}
+
+impl TranscriptionProvider for LocalWhisper {
+    fn id(&self) -> &'static str {
+        "whisper"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            file: cfg!(feature = "local-whisper"),
+            diarization: true,
+            language_detection: true,
+            offline: true,
+            prompt: true,
+            ..Default::default()
+        }
+    }
+
+    fn transcribe_file(
+        &self,
+        _events: Events,
+        _credentials: provider::Credentials,
+        audio: AudioFile,
+        options: serde_json::Value,
+    ) -> Result<provider::ProviderFuture<TranscriptionResult>, String> {
+        let options: TranscribeOptions = provider::parse_options(options)?;
+        let app = self.0.clone();
+        Ok(Box::pin(async move {
+            let samples =
+                tokio::task::spawn_blocking(move || audio::decode_speech_samples(&audio.bytes))
+                    .await
+                    .map_err(|e| format!("Task failed: {}", e))??;
+            transcribe_samples(app, samples, Some(options)).await
+        }))
+    }
+}
+
+/// Detect the spoken language of a WAV file, or of the last stopped recording
+/// when no path is given. Only the first 30 seconds are considered.
+#[tauri::command]
+pub async fn detect_language(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    path: Option<String>,
+    model: Option<String>,
+) -> Result<LanguageDetection, String> {
+    let model = model.unwrap_or_else(|| TranscribeOptions::default().model);
+    if models::english_only(&model) {
+        return Err(format!(
+            "Model \"{}\" only supports English and cannot detect languages",
+            model
+        ));
+    }
+    let model_path = model_path(&app, &model)?;
+    let acceleration = acceleration::current(&app)?;
+
+    let samples = match path {
+        Some(path) => load_file_samples(path).await?,
+        None => audio::last_take_speech_samples(&recorder)?,
+    };
+
+    tokio::task::spawn_blocking(move || {
+        whisper::detect_language(&model_path, acceleration, &samples)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Payload of `transcription-completed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionCompleted {
+    job_id: String,
+    result: TranscriptionResult,
+}
+
+/// Payload of `transcription-failed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionFailed {
+    job_id: String,
+    error: String,
+}
+
+/// Read a secret such as an API key from secure storage, failing if it is not set
+async fn read_secret(app: &AppHandle, key: &'static str, name: &str) -> Result<String, String> {
+    let app = app.clone();
+    let value =
+        tokio::task::spawn_blocking(move || commands::read_secure_value(&app, key, "transcription"))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+    if value.is_empty() {
+        return Err(format!("No {} is set", name));
+    }
+
+    Ok(value)
+}
+
+pub fn generate_job_id() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Emit `transcription-completed` or `transcription-failed` for a job
+fn emit_outcome(app: &AppHandle, job_id: String, outcome: Result<TranscriptionResult, String>) {
+    let _ = match outcome {
+        Ok(result) => app.emit(
+            "transcription-completed",
+            TranscriptionCompleted { job_id, result },
+        ),
+        Err(error) => app.emit(
+            "transcription-failed",
+            TranscriptionFailed { job_id, error },
+        ),
+    };
+}
+
+/// Check the provider, policy and credentials, then hand the audio over; the
+/// audio is counted in the usage stats and the transcript cleaned up once it
+/// is done
+async fn prepare_file_job(
+    app: &AppHandle,
+    request: &ProviderRequest,
+    audio: AudioFile,
+) -> Result<provider::ProviderFuture<TranscriptionResult>, String> {
+    let work = provider_work(app, request, audio).await?;
+    let app = app.clone();
+    let provider_id = request.provider.clone();
+    Ok(Box::pin(async move {
+        let result = work.await?;
+        usage::track(&app, &provider_id, result.duration_ms);
+        clean_up(&app, result)
+    }))
+}
+
+/// Restore punctuation, run the post-processing pipeline and apply the
+/// profanity filter, as the user chose, to a finished transcript
+fn clean_up(
+    app: &AppHandle,
+    mut result: TranscriptionResult,
+) -> Result<TranscriptionResult, String> {
+    let current = settings::load_settings(app)?;
+    if current.restore_punctuation {
+        restore_punctuation(&mut result);
+    }
+
+    let pipeline = current.post_processing.compile(&current.corrections)?;
+    let filter = current.profanity_filter;
+    let clean = |text: &str| filter.apply(&pipeline.apply(text));
+    result.text = clean(&result.text);
+    for segment in &mut result.segments {
+        segment.text = clean(&segment.text);
+    }
+    Ok(result)
+}
+
+/// The provider's work for a file, split into chunks when it is larger than
+/// the provider accepts
+async fn provider_work(
+    app: &AppHandle,
+    request: &ProviderRequest,
+    audio: AudioFile,
+) -> Result<provider::ProviderFuture<TranscriptionResult>, String> {
+    let provider = provider::get(&request.provider)?;
+    if !provider.capabilities().offline {
+        policy::ensure_cloud_allowed()?;
+    }
+    team_config::ensure_provider_allowed(provider.id(), provider.capabilities().offline)?;
+
+    let credentials = provider::read_credentials(app, provider.as_ref()).await?;
+    let mut options = request.options.clone();
+    if provider.capabilities().prompt {
+        let corrections = settings::load_settings(app)?.corrections;
+        let prompt = options.get("prompt").and_then(|prompt| prompt.as_str());
+        if let Some(prompt) = corrections::vocabulary_prompt(&corrections, prompt) {
+            options.insert("prompt".to_string(), prompt.into());
+        }
+    }
+    let options = serde_json::Value::Object(options);
+    let chunks = match provider.max_file_bytes() {
+        Some(max_bytes) => chunking::split(&audio, max_bytes)?,
+        None => None,
+    };
+    let Some(chunks) = chunks else {
+        return provider.transcribe_file(app_events(app), credentials, audio, options);
+    };
+
+    // Every chunk's work is created up front so bad options fail here
+    let parts = chunks
+        .into_iter()
+        .map(|chunk| {
+            let work = provider.transcribe_file(
+                app_events(app),
+                credentials.clone(),
+                chunk.audio,
+                options.clone(),
+            )?;
+            Ok((chunk.offset_ms, work))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let control = request
+        .options
+        .get("jobId")
+        .and_then(|id| id.as_str())
+        .map(|job_id| jobs::JobControl::for_job(app, job_id));
+
+    Ok(Box::pin(async move {
+        let count = parts.len();
+        let mut results = Vec::with_capacity(count);
+        for (offset_ms, work) in parts {
+            results.push((offset_ms, work.await?));
+            if let Some(control) = &control {
+                control.report_progress((results.len() * 100 / count) as u8);
+            }
+        }
+        Ok(chunking::stitch(results))
+    }))
+}
+
+/// Submit an audio file, or the last stopped recording when no path is given,
+/// to any provider that transcribes files; see `list_transcription_providers`.
+/// Credentials are read from secure storage here, so they never pass through
+/// the webview. Returns a job id at once; follow the job with
+/// `get_transcription_job` or `transcription-job-updated` events, stop it with
+/// `cancel_transcription_job`. The outcome also arrives as a
+/// `transcription-completed` or `transcription-failed` event carrying the id.
+/// Jobs that cannot reach their service are queued instead and retried when
+/// the network returns; see `list_queued_transcriptions`.
+#[tauri::command]
+pub async fn submit_transcription(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    path: Option<String>,
+    provider: ProviderRequest,
+) -> Result<String, String> {
+    let source = match path {
+        Some(path) => Source::File(path),
+        None => Source::LastTake,
+    };
+    submit_with(app, &recorder, source, provider, |_, _, _| {}).await
+}
+
+/// Audio for `submit_with`
+pub enum Source {
+    File(String),
+    /// The last stopped recording
+    LastTake,
+    /// WAV bytes, e.g. of a processed recording
+    Wav(Vec<u8>),
+}
+
+/// `submit_transcription`, calling `then` with the job id and outcome once
+/// the job ends. A job that could not reach its service ends with its error
+/// even though it is queued for retry; retries and cancelled jobs skip `then`.
+pub async fn submit_with(
+    app: AppHandle,
+    recorder: &AudioRecorder,
+    source: Source,
+    mut provider: ProviderRequest,
+    then: impl FnOnce(&AppHandle, &str, &Result<TranscriptionResult, String>) + Send + 'static,
+) -> Result<String, String> {
+    let (bytes, file_name, pending) = match source {
+        Source::File(path) => {
+            let file_name = Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "audio".to_string());
+            let audio = tokio::fs::read(&path)
+                .await
+                .map_err(|e| format!("Failed to read audio file: {}", e))?;
+            (
+                audio,
+                file_name,
+                retry_queue::PendingAudio::File(path.into()),
+            )
+        }
+        Source::LastTake => {
+            let audio = audio::last_take_speech_wav(recorder)?;
+            let pending = retry_queue::PendingAudio::Recording(audio.clone());
+            (audio, "recording.wav".to_string(), pending)
+        }
+        Source::Wav(audio) => {
+            let pending = retry_queue::PendingAudio::Recording(audio.clone());
+            (audio, "recording.wav".to_string(), pending)
+        }
+    };
+
+    // Lets local transcription report progress and notice cancellation
+    let job_id = generate_job_id();
+    provider
+        .options
+        .insert("jobId".to_string(), job_id.clone().into());
+
+    let audio = AudioFile {
+        bytes,
+        file_name: file_name.clone(),
+    };
+    let work = prepare_file_job(&app, &provider, audio).await?;
+    let offline = provider::get(&provider.provider)?.capabilities().offline;
+    let provider_id = provider.provider.clone();
+
+    let event_app = app.clone();
+    let event_job_id = job_id.clone();
+    jobs::submit(&app, &job_id, &provider_id, offline, work, move |outcome| {
+        let (app, job_id) = (event_app, event_job_id);
+        match outcome {
+            Err(error) if retry_queue::is_network_error(&error) => {
+                provider.options.remove("jobId");
+                let queued = retry_queue::enqueue(
+                    &app,
+                    &job_id,
+                    &provider,
+                    &file_name,
+                    pending,
+                    error.clone(),
+                );
+                if let Err(e) = queued {
+                    eprintln!("Failed to queue transcription for retry: {}", e);
+                    emit_outcome(&app, job_id.clone(), Err(error.clone()));
+                }
+                then(&app, &job_id, &Err(error));
+            }
+            outcome => {
+                emit_outcome(&app, job_id.clone(), outcome.clone());
+                then(&app, &job_id, &outcome);
+            }
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// Audio files `transcribe_files` picks up from folders
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "wav", "mp3", "m4a", "aac", "flac", "ogg", "opus", "webm", "mp4",
+];
+
+/// A file `transcribe_files` could not submit
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedFile {
+    path: String,
+    error: String,
+}
+
+/// Jobs started by `transcribe_files`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionBatch {
+    batch_id: String,
+    job_ids: Vec<String>,
+    rejected: Vec<RejectedFile>,
+}
+
+/// The files among `paths`, with folders replaced by the audio files directly
+/// inside them, sorted by name
+fn expand_paths(paths: &[String]) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        if !path.is_dir() {
+            files.push(path.to_string_lossy().to_string());
+            continue;
+        }
+
+        let mut found: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(|e| format!("Failed to read folder {}: {}", path.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.is_file()
+                    && file.extension().is_some_and(|extension| {
+                        let extension = extension.to_string_lossy().to_lowercase();
+                        AUDIO_EXTENSIONS.contains(&extension.as_str())
+                    })
+            })
+            .collect();
+        found.sort();
+        files.extend(found.iter().map(|file| file.to_string_lossy().to_string()));
+    }
+
+    Ok(files)
+}
+
+/// Transcribe many files with one provider, e.g. a backlog of voice memos.
+/// `paths` may name files and folders; a folder adds the audio files directly
+/// inside it. Each file becomes a job as with `submit_transcription`, so local
+/// jobs run one at a time and every job reports as usual. The batch as a
+/// whole is reported by `transcription-batch-progress` events, the last one
+/// with `finished` set. Files that cannot be submitted count as failed.
+#[tauri::command]
+pub async fn transcribe_files(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    paths: Vec<String>,
+    provider: ProviderRequest,
+) -> Result<TranscriptionBatch, String> {
+    let files = tokio::task::spawn_blocking(move || expand_paths(&paths))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+    if files.is_empty() {
+        return Err("No audio files to transcribe".to_string());
+    }
+
+    let mut batch = TranscriptionBatch {
+        batch_id: generate_job_id(),
+        job_ids: Vec::with_capacity(files.len()),
+        rejected: Vec::new(),
+    };
+    jobs::start_batch(&batch.batch_id, files.len());
+
+    for file in files {
+        let source = Source::File(file.clone());
+        match submit_with(
+            app.clone(),
+            &recorder,
+            source,
+            provider.clone(),
+            |_, _, _| {},
+        )
+        .await
+        {
+            Ok(job_id) => {
+                jobs::add_to_batch(&app, &batch.batch_id, &job_id);
+                batch.job_ids.push(job_id);
+            }
+            Err(error) => {
+                jobs::reject_in_batch(&app, &batch.batch_id, &file);
+                batch.rejected.push(RejectedFile { path: file, error });
+            }
+        }
+    }
+
+    Ok(batch)
+}
+
+/// Transcribe with a cloud provider; see `submit_transcription`
+#[tauri::command]
+pub async fn transcribe_in_cloud(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    path: Option<String>,
+    provider: ProviderRequest,
+) -> Result<String, String> {
+    submit_transcription(app, recorder, path, provider).await
+}
+
+/// Transcribe with the OpenAI API; see `submit_transcription`
+#[tauri::command]
+pub async fn transcribe_with_openai(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    path: Option<String>,
+    options: Option<serde_json::Map<String, serde_json::Value>>,
+) -> Result<String, String> {
+    let provider = ProviderRequest {
+        provider: "openai".to_string(),
+        options: options.unwrap_or_default(),
+    };
+    submit_transcription(app, recorder, path, provider).await
+}
+
+/// Payload of `transcription-stream-ended`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionStreamEnded {
+    session_id: String,
+    error: Option<String>,
+}
+
+/// Transcribe the current recording live with a provider that supports
+/// streaming, e.g. `{ "provider": "deepgram", "model": "nova-3" }`. Returns a
+/// session id; results arrive as `transcription-interim` events, which later
+/// results replace, and `transcription-final` events. After
+/// `stop_live_transcription` or `stop_recording`, the remaining results follow
+/// and the session ends with a `transcription-stream-ended` event, which
+/// carries an error if it failed.
+#[tauri::command]
+pub async fn start_live_transcription(
+    app: AppHandle,
+    recorder: tauri::State<'_, AudioRecorder>,
+    provider: ProviderRequest,
+) -> Result<String, String> {
+    let stream = LiveStream::prepare(&app, provider).await?;
+    stream.start(&app, &recorder)
+}
+
+/// Start recording and transcribe it live in one step: the recorder's audio
+/// is resampled for speech and sent to the provider without passing through
+/// the webview. `config` is as for `start_recording`, `provider` as for
+/// `start_live_transcription`, whose events follow. The session ends with
+/// the recording; returns its id.
+#[tauri::command]
+pub async fn start_realtime_transcription(
+    app: AppHandle,
+    config: Option<audio::RecordingConfig>,
+    provider: ProviderRequest,
+) -> Result<String, String> {
+    // Credentials are read first, so a missing key does not leave a recording running
+    let stream = LiveStream::prepare(&app, provider).await?;
+
+    // The recorder's stream may only be created and dropped on the main thread
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let recorder = handle.state::<AudioRecorder>();
+        let result = audio::start_recording(handle.clone(), handle.state(), config, None, None)
+            .and_then(|()| {
+                stream.start(&handle, &recorder).inspect_err(|_| {
+                    audio::discard_recording(&recorder);
+                })
+            });
+        let _ = sender.send(result);
+    })
+    .map_err(|e| format!("Failed to start recording: {}", e))?;
+
+    receiver
+        .await
+        .map_err(|_| "Recording was not started".to_string())?
+}
+
+/// A streaming provider, checked and with its credentials read, ready for
+/// the current recording's audio
+struct LiveStream {
+    provider: std::sync::Arc<dyn TranscriptionProvider>,
+    credentials: provider::Credentials,
+    options: serde_json::Value,
+}
+
+impl LiveStream {
+    async fn prepare(app: &AppHandle, request: ProviderRequest) -> Result<Self, String> {
+        let provider = provider::get(&request.provider)?;
+        if !provider.capabilities().offline {
+            policy::ensure_cloud_allowed()?;
+        }
+        team_config::ensure_provider_allowed(provider.id(), provider.capabilities().offline)?;
+        let credentials = provider::read_credentials(app, provider.as_ref()).await?;
+
+        Ok(Self {
+            provider,
+            credentials,
+            options: serde_json::Value::Object(request.options),
+        })
+    }
+
+    /// Feed the recording, converted to 16 kHz mono, to the provider and
+    /// emit `transcription-stream-ended` once it is done. Returns the
+    /// session id.
+    fn start(self, app: &AppHandle, recorder: &AudioRecorder) -> Result<String, String> {
+        let live_audio = audio::open_live_audio(recorder)?.into_speech();
+
+        let session_id = generate_job_id();
+        let stream = match self.provider.transcribe_stream(
+            app_events(app),
+            self.credentials,
+            session_id.clone(),
+            live_audio,
+            self.options,
+        ) {
+            Ok(stream) => stream,
+            Err(e) => {
+                audio::close_live_audio(recorder);
+                return Err(e);
+            }
+        };
+
+        let app = app.clone();
+        let event_session_id = session_id.clone();
+        let provider_id = self.provider.id();
+        tauri::async_runtime::spawn(async move {
+            // Live audio arrives in real time, so the session lasts as long
+            // as the audio sent
+            let started = std::time::Instant::now();
+            let error = stream.await.err();
+            usage::track(&app, provider_id, started.elapsed().as_millis() as u64);
+            let _ = app.emit(
+                "transcription-stream-ended",
+                TranscriptionStreamEnded {
+                    session_id: event_session_id,
+                    error,
+                },
+            );
+        });
+
+        Ok(session_id)
+    }
+}
+
+/// Stop sending audio to the live transcription; see `start_live_transcription`
+#[tauri::command]
+pub fn stop_live_transcription(recorder: tauri::State<'_, AudioRecorder>) -> Result<(), String> {
+    audio::close_live_audio(&recorder);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(model: &str, language: Option<&str>) -> Result<Option<String>, String> {
+        let mut options = TranscribeOptions {
+            model: model.to_string(),
+            language: language.map(str::to_string),
+            translate: false,
+            diarize: false,
+            job_id: None,
+            threads: None,
+            prompt: None,
+        };
+        resolve_language(&mut options).map(|_| options.language)
+    }
+
+    #[test]
+    fn test_resolve_language() {
+        assert_eq!(resolved("base", None).unwrap(), None);
+        assert_eq!(resolved("base", Some("auto")).unwrap(), None);
+        assert_eq!(
+            resolved("base", Some(" DE ")).unwrap().as_deref(),
+            Some("de")
+        );
+        assert!(resolved("base", Some("german!")).is_err());
+
+        assert_eq!(resolved("base.en", None).unwrap().as_deref(), Some("en"));
+        assert_eq!(
+            resolved("base.en-q5_1", None).unwrap().as_deref(),
+            Some("en")
+        );
+        assert!(resolved("small.en", Some("fr")).is_err());
+    }
+
+    #[test]
+    fn test_expand_paths() {
+        let dir = std::env::temp_dir().join(format!("transcribe-files-{}", generate_job_id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["b.M4A", "a.wav", "notes.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let folder = dir.to_string_lossy().to_string();
+        let files = expand_paths(&[folder, "/memos/c.mp3".to_string()]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.len(), 3);
+        assert!(files[0].ends_with("a.wav"));
+        assert!(files[1].ends_with("b.M4A"));
+        assert_eq!(files[2], "/memos/c.mp3");
+    }
+
+    #[test]
+    fn test_diarization_needs_tinydiarize_model() {
+        let mut options = TranscribeOptions {
+            diarize: true,
+            ..TranscribeOptions::default()
+        };
+        assert!(check_diarization(&options).is_err());
+
+        options.model = "small.en-tdrz".to_string();
+        assert!(check_diarization(&options).is_ok());
+        assert_eq!(resolved("small.en-tdrz", None).unwrap(), None);
+    }
+}
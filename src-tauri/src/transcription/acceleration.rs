@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::hardware;
+use crate::settings;
+
+/// Where local Whisper models run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhisperBackend {
+    /// The best backend this build and machine support
+    #[default]
+    Auto,
+    Cpu,
+    /// Apple GPUs, macOS only
+    Metal,
+    /// NVIDIA GPUs
+    Cuda,
+    /// Any GPU with a Vulkan driver
+    Vulkan,
+}
+
+/// Backends compiled into this build, none without local transcription
+pub fn compiled_backends() -> Vec<WhisperBackend> {
+    let mut backends = Vec::new();
+    if cfg!(all(target_os = "macos", feature = "local-whisper")) {
+        backends.push(WhisperBackend::Metal);
+    }
+    if cfg!(feature = "whisper-cuda") {
+        backends.push(WhisperBackend::Cuda);
+    }
+    if cfg!(feature = "whisper-vulkan") {
+        backends.push(WhisperBackend::Vulkan);
+    }
+    if cfg!(feature = "local-whisper") {
+        backends.push(WhisperBackend::Cpu);
+    }
+    backends
+}
+
+/// Whether `backend` can drive a GPU from vendor `gpu`
+fn supports_gpu(backend: WhisperBackend, gpu: &str) -> bool {
+    match backend {
+        WhisperBackend::Metal => gpu == "Apple",
+        WhisperBackend::Cuda => gpu == "NVIDIA",
+        WhisperBackend::Vulkan => true,
+        WhisperBackend::Auto | WhisperBackend::Cpu => false,
+    }
+}
+
+/// The backend to run on: `Auto` takes the first compiled GPU backend that
+/// fits the detected GPU and falls back to the CPU. An explicit choice must
+/// be compiled in.
+fn resolve(
+    requested: WhisperBackend,
+    compiled: &[WhisperBackend],
+    gpu: Option<&str>,
+) -> Result<WhisperBackend, String> {
+    if requested == WhisperBackend::Auto {
+        let fitting = gpu.and_then(|gpu| {
+            compiled
+                .iter()
+                .copied()
+                .find(|&backend| supports_gpu(backend, gpu))
+        });
+        return Ok(fitting.unwrap_or(WhisperBackend::Cpu));
+    }
+
+    if !compiled.contains(&requested) {
+        return Err(format!(
+            "This build does not support the {:?} backend",
+            requested
+        ));
+    }
+    Ok(requested)
+}
+
+/// How whisper.cpp should load models
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Acceleration {
+    pub backend: WhisperBackend,
+    /// Index of the GPU to use when the backend is not the CPU
+    pub gpu_device: i32,
+}
+
+/// The acceleration the settings ask for, on this build and machine
+pub fn current(app: &AppHandle) -> Result<Acceleration, String> {
+    let settings = settings::load_settings(app)?;
+    let gpu = hardware::detect().gpu;
+    Ok(Acceleration {
+        backend: resolve(
+            settings.whisper_backend,
+            &compiled_backends(),
+            gpu.as_deref(),
+        )?,
+        gpu_device: settings.whisper_gpu_device as i32,
+    })
+}
+
+/// What local transcription can run on and what it will use
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccelerationInfo {
+    /// Backends this build supports
+    compiled: Vec<WhisperBackend>,
+    /// Detected GPU vendor, e.g. "NVIDIA" or "Apple"
+    gpu: Option<String>,
+    /// The backend setting
+    selected: WhisperBackend,
+    gpu_device: u32,
+    /// The backend transcription runs on; `None` when the selected one is
+    /// not in this build
+    active: Option<WhisperBackend>,
+}
+
+/// Report the compiled GPU backends, the detected GPU and the backend local
+/// transcription will run on
+#[tauri::command]
+pub async fn get_whisper_acceleration(app: AppHandle) -> Result<AccelerationInfo, String> {
+    let settings = settings::load_settings(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let compiled = compiled_backends();
+        let gpu = hardware::detect().gpu;
+        AccelerationInfo {
+            active: resolve(settings.whisper_backend, &compiled, gpu.as_deref()).ok(),
+            compiled,
+            gpu,
+            selected: settings.whisper_backend,
+            gpu_device: settings.whisper_gpu_device,
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Choose where local models run and, on machines with several GPUs, which
+/// one. Takes effect with the next transcription.
+#[tauri::command]
+pub fn set_whisper_backend(
+    app: AppHandle,
+    backend: WhisperBackend,
+    gpu_device: Option<u32>,
+) -> Result<(), String> {
+    resolve(backend, &compiled_backends(), None)?;
+
+    settings::update_settings(&app, |current| {
+        current.whisper_backend = backend;
+        current.whisper_gpu_device = gpu_device.unwrap_or(0);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        use WhisperBackend::*;
+        let compiled = [Cuda, Vulkan, Cpu];
+
+        assert_eq!(resolve(Auto, &compiled, Some("NVIDIA")), Ok(Cuda));
+        assert_eq!(resolve(Auto, &compiled, Some("AMD")), Ok(Vulkan));
+        assert_eq!(resolve(Auto, &compiled, None), Ok(Cpu));
+        assert_eq!(resolve(Auto, &[Cpu], Some("NVIDIA")), Ok(Cpu));
+
+        assert_eq!(resolve(Cpu, &compiled, Some("NVIDIA")), Ok(Cpu));
+        assert_eq!(resolve(Vulkan, &compiled, Some("NVIDIA")), Ok(Vulkan));
+        assert!(resolve(Metal, &compiled, Some("Apple")).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// How long each thread runs the benchmark loop
+const BENCHMARK_MS: u64 = 200;
+const BENCHMARK_VECTOR_LEN: usize = 4096;
+
+/// What the machine offers for local transcription
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareInfo {
+    /// Logical cores
+    pub cpu_cores: usize,
+    pub cpu_arch: String,
+    /// SIMD extensions whisper.cpp makes use of, e.g. "avx2" or "neon"
+    pub cpu_features: Vec<String>,
+    pub memory_bytes: Option<u64>,
+    /// Detected GPU vendor, e.g. "NVIDIA" or "Apple"
+    pub gpu: Option<String>,
+}
+
+impl HardwareInfo {
+    /// Vector instructions fast enough for models beyond "base"
+    pub fn has_fast_simd(&self) -> bool {
+        self.cpu_features
+            .iter()
+            .any(|feature| feature == "avx2" || feature == "neon")
+    }
+}
+
+fn cpu_features() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = Vec::new();
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let detected = [
+            ("sse4.1", std::arch::is_x86_feature_detected!("sse4.1")),
+            ("avx", std::arch::is_x86_feature_detected!("avx")),
+            ("avx2", std::arch::is_x86_feature_detected!("avx2")),
+            ("fma", std::arch::is_x86_feature_detected!("fma")),
+            ("f16c", std::arch::is_x86_feature_detected!("f16c")),
+            ("avx512f", std::arch::is_x86_feature_detected!("avx512f")),
+        ];
+        features.extend(detected.iter().filter(|(_, on)| *on).map(|(name, _)| *name));
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        let detected = [
+            ("neon", std::arch::is_aarch64_feature_detected!("neon")),
+            ("fp16", std::arch::is_aarch64_feature_detected!("fp16")),
+            (
+                "dotprod",
+                std::arch::is_aarch64_feature_detected!("dotprod"),
+            ),
+        ];
+        features.extend(detected.iter().filter(|(_, on)| *on).map(|(name, _)| *name));
+    }
+
+    features.into_iter().map(str::to_string).collect()
+}
+
+/// Installed physical memory
+#[cfg(target_os = "linux")]
+fn memory_bytes() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(target_os = "macos")]
+fn memory_bytes() -> Option<u64> {
+    let mut bytes = 0u64;
+    let mut size = std::mem::size_of::<u64>();
+    // SAFETY: the name is NUL-terminated and `bytes` is `size` bytes
+    let result = unsafe {
+        libc::sysctlbyname(
+            c"hw.memsize".as_ptr(),
+            (&mut bytes as *mut u64).cast(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    (result == 0).then_some(bytes)
+}
+
+#[cfg(windows)]
+fn memory_bytes() -> Option<u64> {
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    struct MemoryStatusEx {
+        dwLength: u32,
+        dwMemoryLoad: u32,
+        ullTotalPhys: u64,
+        ullAvailPhys: u64,
+        ullTotalPageFile: u64,
+        ullAvailPageFile: u64,
+        ullTotalVirtual: u64,
+        ullAvailVirtual: u64,
+        ullAvailExtendedVirtual: u64,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GlobalMemoryStatusEx(buffer: *mut MemoryStatusEx) -> i32;
+    }
+
+    // SAFETY: all-zero is a valid MemoryStatusEx
+    let mut status: MemoryStatusEx = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MemoryStatusEx>() as u32;
+    // SAFETY: `status` is valid and its length is set
+    let ok = unsafe { GlobalMemoryStatusEx(&mut status) };
+    (ok != 0).then_some(status.ullTotalPhys)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn memory_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn gpu() -> Option<String> {
+    cfg!(target_arch = "aarch64").then(|| "Apple".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn gpu() -> Option<String> {
+    if std::path::Path::new("/proc/driver/nvidia/version").exists() {
+        return Some("NVIDIA".to_string());
+    }
+
+    // PCI vendor ids of the display devices
+    let cards = std::fs::read_dir("/sys/class/drm").ok()?;
+    cards
+        .filter_map(|card| card.ok())
+        .filter_map(|card| std::fs::read_to_string(card.path().join("device/vendor")).ok())
+        .find_map(|vendor| match vendor.trim() {
+            "0x10de" => Some("NVIDIA".to_string()),
+            "0x1002" => Some("AMD".to_string()),
+            _ => None,
+        })
+}
+
+#[cfg(windows)]
+fn gpu() -> Option<String> {
+    // The CUDA driver is installed with every NVIDIA display driver
+    let system_root = std::env::var_os("SystemRoot")?;
+    std::path::Path::new(&system_root)
+        .join("System32")
+        .join("nvcuda.dll")
+        .exists()
+        .then(|| "NVIDIA".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn gpu() -> Option<String> {
+    None
+}
+
+pub fn detect() -> HardwareInfo {
+    HardwareInfo {
+        cpu_cores: std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        cpu_arch: std::env::consts::ARCH.to_string(),
+        cpu_features: cpu_features(),
+        memory_bytes: memory_bytes(),
+        gpu: gpu(),
+    }
+}
+
+/// Measure floating point throughput in GFLOPS with `threads` threads
+pub fn benchmark_gflops(threads: usize) -> f32 {
+    let duration = Duration::from_millis(BENCHMARK_MS);
+    let started = Instant::now();
+
+    let flops: u64 = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let factors = vec![0.999f32; BENCHMARK_VECTOR_LEN];
+                    let mut values = vec![1.0f32; BENCHMARK_VECTOR_LEN];
+                    let mut rounds = 0u64;
+                    while started.elapsed() < duration {
+                        for _ in 0..64 {
+                            for (value, factor) in values.iter_mut().zip(&factors) {
+                                *value = *value * factor + 0.001;
+                            }
+                        }
+                        rounds += 64;
+                    }
+                    std::hint::black_box(&values);
+                    // One multiply and one add per element
+                    rounds * BENCHMARK_VECTOR_LEN as u64 * 2
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap_or(0))
+            .sum()
+    });
+
+    flops as f32 / started.elapsed().as_secs_f32() / 1e9
+}
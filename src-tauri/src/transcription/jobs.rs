@@ -0,0 +1,412 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Semaphore;
+
+use super::provider::ProviderFuture;
+use super::{emit_outcome, resources, TranscriptionResult};
+
+/// Local jobs running at once; each already uses most of the CPU
+const MAX_LOCAL_JOBS: usize = 1;
+/// Finished jobs kept for `get_transcription_job`
+const MAX_FINISHED_JOBS: usize = 50;
+pub const CANCELLED: &str = "Transcription was cancelled";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    /// Waiting for another local job to finish
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn is_finished(self) -> bool {
+        !matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+/// A submitted transcription, sent as `transcription-job-updated` whenever it
+/// changes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub job_id: String,
+    pub provider: String,
+    pub state: JobState,
+    /// Percent done, for providers that report it
+    pub progress: Option<u8>,
+    /// RFC 3339
+    pub submitted_at: String,
+    pub result: Option<TranscriptionResult>,
+    pub error: Option<String>,
+    /// Set for jobs started together by `transcribe_files`
+    pub batch_id: Option<String>,
+}
+
+struct Job {
+    status: JobStatus,
+    cancelled: Arc<AtomicBool>,
+    task: Option<JoinHandle<()>>,
+}
+
+/// Unfinished jobs and the most recently finished ones, newest first
+static JOBS: Lazy<Mutex<VecDeque<Job>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static LOCAL_SLOTS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(MAX_LOCAL_JOBS));
+
+/// Summed up state of a batch, sent as `transcription-batch-progress`
+/// whenever one of its jobs changes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    pub batch_id: String,
+    /// Files in the batch
+    pub total: usize,
+    pub completed: usize,
+    /// Including files that could not be submitted
+    pub failed: usize,
+    pub cancelled: usize,
+    /// Percent of the whole batch done
+    pub progress: u8,
+    /// Every job has ended
+    pub finished: bool,
+}
+
+/// Jobs started together, with the last state and progress of each. Files
+/// that could not be submitted are kept under their path.
+struct Batch {
+    total: usize,
+    jobs: HashMap<String, (JobState, u8)>,
+}
+
+impl Batch {
+    fn progress(&self, batch_id: &str) -> BatchProgress {
+        let count = |wanted| {
+            self.jobs
+                .values()
+                .filter(|(state, _)| *state == wanted)
+                .count()
+        };
+        let done: usize = self
+            .jobs
+            .values()
+            .map(|&(state, progress)| match state.is_finished() {
+                true => 100,
+                false => progress as usize,
+            })
+            .sum();
+        let (completed, failed, cancelled) = (
+            count(JobState::Completed),
+            count(JobState::Failed),
+            count(JobState::Cancelled),
+        );
+
+        BatchProgress {
+            batch_id: batch_id.to_string(),
+            total: self.total,
+            completed,
+            failed,
+            cancelled,
+            progress: (done / self.total.max(1)) as u8,
+            finished: completed + failed + cancelled >= self.total,
+        }
+    }
+}
+
+/// Unfinished batches by id
+static BATCHES: Lazy<Mutex<HashMap<String, Batch>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Note the state of a job or rejected file in its batch, forgetting the
+/// batch once everything in it has ended. Called with `JOBS` locked, so
+/// changes are recorded in order.
+fn record(batch_id: &str, key: &str, state: JobState, progress: u8) -> Option<BatchProgress> {
+    let mut batches = BATCHES.lock();
+    let batch = batches.get_mut(batch_id)?;
+    batch.jobs.insert(key.to_string(), (state, progress));
+
+    let summary = batch.progress(batch_id);
+    if summary.finished {
+        batches.remove(batch_id);
+    }
+    Some(summary)
+}
+
+fn record_job(status: &JobStatus) -> Option<BatchProgress> {
+    let batch_id = status.batch_id.as_deref()?;
+    let progress = status.progress.unwrap_or(0);
+    record(batch_id, &status.job_id, status.state, progress)
+}
+
+fn emit_batch(app: &AppHandle, progress: Option<BatchProgress>) {
+    if let Some(progress) = progress {
+        let _ = app.emit("transcription-batch-progress", progress);
+    }
+}
+
+/// Change a job and emit `transcription-job-updated`, unless it already
+/// finished
+fn update(app: &AppHandle, job_id: &str, apply: impl FnOnce(&mut JobStatus)) {
+    let (status, batch) = {
+        let mut jobs = JOBS.lock();
+        let Some(job) = jobs
+            .iter_mut()
+            .find(|job| job.status.job_id == job_id && !job.status.state.is_finished())
+        else {
+            return;
+        };
+        apply(&mut job.status);
+        if job.status.state.is_finished() {
+            job.task = None;
+        }
+        (job.status.clone(), record_job(&job.status))
+    };
+    let _ = app.emit("transcription-job-updated", status);
+    emit_batch(app, batch);
+}
+
+/// Track `total` files as one batch; add their jobs with `add_to_batch`
+pub fn start_batch(batch_id: &str, total: usize) {
+    BATCHES.lock().insert(
+        batch_id.to_string(),
+        Batch {
+            total,
+            jobs: HashMap::new(),
+        },
+    );
+}
+
+/// Count a submitted job towards its batch
+pub fn add_to_batch(app: &AppHandle, batch_id: &str, job_id: &str) {
+    let batch = {
+        let mut jobs = JOBS.lock();
+        let Some(job) = jobs.iter_mut().find(|job| job.status.job_id == job_id) else {
+            return;
+        };
+        job.status.batch_id = Some(batch_id.to_string());
+        record_job(&job.status)
+    };
+    emit_batch(app, batch);
+}
+
+/// Count a file of the batch that could not be submitted as failed
+pub fn reject_in_batch(app: &AppHandle, batch_id: &str, path: &str) {
+    let batch = {
+        let _jobs = JOBS.lock();
+        record(batch_id, path, JobState::Failed, 0)
+    };
+    emit_batch(app, batch);
+}
+
+/// Progress reporting and cancellation for the code doing a job's work
+#[derive(Clone)]
+pub struct JobControl {
+    app: AppHandle,
+    job_id: String,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<AtomicU8>,
+}
+
+impl JobControl {
+    /// Control of a submitted job, or a detached one for work outside the
+    /// queue
+    pub fn for_job(app: &AppHandle, job_id: &str) -> Self {
+        let cancelled = JOBS
+            .lock()
+            .iter()
+            .find(|job| job.status.job_id == job_id)
+            .map(|job| Arc::clone(&job.cancelled))
+            .unwrap_or_default();
+        Self {
+            app: app.clone(),
+            job_id: job_id.to_string(),
+            cancelled,
+            progress: Arc::default(),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn report_progress(&self, percent: u8) {
+        let percent = percent.min(100);
+        if self.progress.swap(percent, Ordering::Relaxed) != percent {
+            update(&self.app, &self.job_id, |status| {
+                status.progress = Some(percent);
+            });
+        }
+    }
+}
+
+/// Queue a provider's work under `job_id`. Offline jobs wait for a free local
+/// slot; cloud jobs start at once. `on_finish` gets the outcome unless the
+/// job is cancelled first.
+pub fn submit(
+    app: &AppHandle,
+    job_id: &str,
+    provider: &str,
+    offline: bool,
+    work: ProviderFuture<TranscriptionResult>,
+    on_finish: impl FnOnce(Result<TranscriptionResult, String>) + Send + 'static,
+) {
+    let status = JobStatus {
+        job_id: job_id.to_string(),
+        provider: provider.to_string(),
+        state: JobState::Queued,
+        progress: None,
+        submitted_at: chrono::Utc::now().to_rfc3339(),
+        result: None,
+        error: None,
+        batch_id: None,
+    };
+    {
+        let mut jobs = JOBS.lock();
+        jobs.push_front(Job {
+            status: status.clone(),
+            cancelled: Arc::default(),
+            task: None,
+        });
+        while jobs
+            .iter()
+            .filter(|job| job.status.state.is_finished())
+            .count()
+            > MAX_FINISHED_JOBS
+        {
+            if let Some(oldest) = jobs.iter().rposition(|job| job.status.state.is_finished()) {
+                jobs.remove(oldest);
+            }
+        }
+    }
+    let _ = app.emit("transcription-job-updated", status);
+
+    let task_app = app.clone();
+    let task_job_id = job_id.to_string();
+    let task = tauri::async_runtime::spawn(async move {
+        let (app, job_id) = (task_app, task_job_id);
+        let _slot = match offline {
+            true => Some(LOCAL_SLOTS.acquire().await),
+            false => None,
+        };
+        update(&app, &job_id, |status| status.state = JobState::Running);
+
+        let monitor = resources::JobMonitor::start(&app, &job_id);
+        let outcome = work.await;
+        drop(monitor);
+
+        if JobControl::for_job(&app, &job_id).is_cancelled() {
+            return;
+        }
+        update(&app, &job_id, |status| match &outcome {
+            Ok(result) => {
+                status.state = JobState::Completed;
+                status.progress = Some(100);
+                status.result = Some(result.clone());
+            }
+            Err(error) => {
+                status.state = JobState::Failed;
+                status.error = Some(error.clone());
+            }
+        });
+        on_finish(outcome);
+    });
+
+    if let Some(job) = JOBS
+        .lock()
+        .iter_mut()
+        .find(|job| job.status.job_id == job_id && !job.status.state.is_finished())
+    {
+        job.task = Some(task);
+    }
+}
+
+/// State, progress and outcome of a submitted transcription
+#[tauri::command]
+pub fn get_transcription_job(job_id: String) -> Result<JobStatus, String> {
+    JOBS.lock()
+        .iter()
+        .find(|job| job.status.job_id == job_id)
+        .map(|job| job.status.clone())
+        .ok_or_else(|| format!("Unknown job: {}", job_id))
+}
+
+/// Unfinished and recently finished transcriptions, newest first
+#[tauri::command]
+pub fn list_transcription_jobs() -> Vec<JobStatus> {
+    JOBS.lock().iter().map(|job| job.status.clone()).collect()
+}
+
+/// Stop a queued or running transcription. Its job ends with a
+/// `transcription-failed` event.
+#[tauri::command]
+pub fn cancel_transcription_job(app: AppHandle, job_id: String) -> Result<(), String> {
+    let task = {
+        let mut jobs = JOBS.lock();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.status.job_id == job_id)
+            .ok_or_else(|| format!("Unknown job: {}", job_id))?;
+        if job.status.state.is_finished() {
+            return Err(format!("Job {} has already finished", job_id));
+        }
+        job.cancelled.store(true, Ordering::Relaxed);
+        job.task.take()
+    };
+    update(&app, &job_id, |status| {
+        status.state = JobState::Cancelled;
+        status.error = Some(CANCELLED.to_string());
+    });
+
+    // Local work also checks the flag, since blocking threads cannot be aborted
+    if let Some(task) = task {
+        task.abort();
+    }
+    emit_outcome(&app, job_id, Err(CANCELLED.to_string()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_state() {
+        assert!(!JobState::Queued.is_finished());
+        assert!(!JobState::Running.is_finished());
+        assert!(JobState::Cancelled.is_finished());
+        assert_eq!(
+            serde_json::to_value(JobState::Completed).unwrap(),
+            "completed"
+        );
+    }
+
+    #[test]
+    fn test_batch_progress() {
+        let mut batch = Batch {
+            total: 4,
+            jobs: HashMap::new(),
+        };
+        batch.jobs.insert("a".into(), (JobState::Completed, 100));
+        batch.jobs.insert("b".into(), (JobState::Running, 50));
+        batch.jobs.insert("c.m4a".into(), (JobState::Failed, 0));
+
+        let progress = batch.progress("batch");
+        assert_eq!((progress.completed, progress.failed), (1, 1));
+        // The fourth job has not been added yet
+        assert_eq!(progress.progress, 62);
+        assert!(!progress.finished);
+
+        batch.jobs.insert("b".into(), (JobState::Cancelled, 50));
+        batch.jobs.insert("d".into(), (JobState::Completed, 100));
+        let progress = batch.progress("batch");
+        assert_eq!(progress.progress, 100);
+        assert!(progress.finished);
+    }
+}
@@ -0,0 +1,709 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+
+use super::acceleration::{self, WhisperBackend};
+use super::get_models_dir;
+use super::hardware::{self, HardwareInfo};
+
+/// Where whisper.cpp publishes its GGML models
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// Models offered for download, with their approximate size in MiB
+const AVAILABLE_MODELS: &[(&str, u64)] = &[
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    // Marks speaker turns for diarization
+    ("small.en-tdrz", 465),
+    ("medium", 1500),
+    ("medium.en", 1500),
+    ("large-v3-turbo", 1600),
+    ("large-v3", 3100),
+    // Quantized weights: a fraction of the memory for a small loss in accuracy
+    ("tiny-q5_1", 31),
+    ("tiny.en-q5_1", 31),
+    ("tiny-q8_0", 42),
+    ("base-q5_1", 57),
+    ("base.en-q5_1", 57),
+    ("base-q8_0", 78),
+    ("small-q5_1", 181),
+    ("small.en-q5_1", 181),
+    ("small-q8_0", 252),
+    ("medium-q5_0", 514),
+    ("medium.en-q5_0", 514),
+    ("medium-q8_0", 785),
+    ("large-v3-turbo-q5_0", 547),
+    ("large-v3-turbo-q8_0", 834),
+    ("large-v3-q5_0", 1080),
+];
+
+/// SHA-256 of each file whisper.cpp publishes, by file name: the models in
+/// `AVAILABLE_MODELS` and their zipped Core ML encoders. Pinned here rather
+/// than looked up when downloading, so the host serving a file cannot also
+/// vouch for it. Files without an entry are refused.
+const PUBLISHED_SHA256: &[(&str, &str)] = &[];
+
+/// Quantization suffixes whisper.cpp publishes models with
+const QUANTIZATIONS: &[&str] = &["q5_0", "q5_1", "q8_0"];
+
+/// A step on the ladder `recommend_model` picks from
+struct ModelTier {
+    name: &'static str,
+    /// More accurate variant for English-only use
+    english_only: Option<&'static str>,
+    /// Memory whisper.cpp needs while transcribing, in MiB
+    memory_mb: u64,
+    min_cores: usize,
+    needs_fast_simd: bool,
+    /// Benchmark result below which the model runs slower than real time
+    min_gflops: f32,
+}
+
+/// Best first; the last tier runs anywhere
+const MODEL_TIERS: &[ModelTier] = &[
+    ModelTier {
+        name: "large-v3-turbo",
+        english_only: None,
+        memory_mb: 2000,
+        min_cores: 8,
+        needs_fast_simd: true,
+        min_gflops: 100.0,
+    },
+    ModelTier {
+        name: "small",
+        english_only: Some("small.en"),
+        memory_mb: 852,
+        min_cores: 4,
+        needs_fast_simd: true,
+        min_gflops: 25.0,
+    },
+    ModelTier {
+        name: "base",
+        english_only: Some("base.en"),
+        memory_mb: 388,
+        min_cores: 2,
+        needs_fast_simd: false,
+        min_gflops: 5.0,
+    },
+    ModelTier {
+        name: "tiny",
+        english_only: Some("tiny.en"),
+        memory_mb: 273,
+        min_cores: 1,
+        needs_fast_simd: false,
+        min_gflops: 0.0,
+    },
+];
+
+/// Share of physical memory a model may use, leaving room for the OS and app
+const MODEL_MEMORY_SHARE: u64 = 4;
+
+/// Models currently being downloaded, so a second request does not race the first
+static DOWNLOADS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhisperModel {
+    name: String,
+    approx_size_mb: u64,
+    /// Transcribes English only, but more accurately than the multilingual model
+    english_only: bool,
+    installed: bool,
+    /// Size on disk, when installed
+    size_bytes: Option<u64>,
+    downloading: bool,
+    /// "q5_0", "q5_1" or "q8_0" for quantized models
+    quantization: Option<String>,
+    /// The Core ML encoder for this model is installed
+    core_ml: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress {
+    model: String,
+    downloaded_bytes: u64,
+    total_bytes: Option<u64>,
+}
+
+/// File name of a Whisper model; names come from the frontend and are validated
+pub fn model_file_name(model: &str) -> Result<String, String> {
+    let valid = !model.is_empty()
+        && model
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        && !model.contains("..");
+
+    if valid {
+        Ok(format!("ggml-{}.bin", model))
+    } else {
+        Err(format!("Invalid model name: {}", model))
+    }
+}
+
+/// Quantization of a model, from its name such as "base-q5_1"
+fn quantization(model: &str) -> Option<&'static str> {
+    let (_, suffix) = model.rsplit_once('-')?;
+    QUANTIZATIONS.iter().copied().find(|q| *q == suffix)
+}
+
+/// The full precision model a variant was quantized from
+fn base_model(model: &str) -> &str {
+    match quantization(model) {
+        Some(q) => &model[..model.len() - q.len() - 1],
+        None => model,
+    }
+}
+
+/// Whether a model, quantized or not, only transcribes English
+pub fn english_only(model: &str) -> bool {
+    base_model(model).ends_with(".en")
+}
+
+/// Directory whisper.cpp loads a model's Core ML encoder from. Quantized
+/// variants share the encoder of their base model.
+fn core_ml_encoder_name(model: &str) -> String {
+    format!("ggml-{}-encoder.mlmodelc", base_model(model))
+}
+
+fn model_info(dir: &Path, name: &str, approx_size_mb: u64) -> WhisperModel {
+    let size_bytes = model_file_name(name)
+        .ok()
+        .and_then(|file| fs::metadata(dir.join(file)).ok())
+        .map(|metadata| metadata.len());
+
+    WhisperModel {
+        name: name.to_string(),
+        approx_size_mb,
+        english_only: name.contains(".en"),
+        installed: size_bytes.is_some(),
+        size_bytes,
+        downloading: DOWNLOADS.lock().contains(name),
+        quantization: quantization(name).map(str::to_string),
+        core_ml: dir.join(core_ml_encoder_name(name)).is_dir(),
+    }
+}
+
+fn available_model(name: &str) -> Result<u64, String> {
+    AVAILABLE_MODELS
+        .iter()
+        .find(|(model, _)| *model == name)
+        .map(|(_, size)| *size)
+        .ok_or_else(|| format!("Unknown model: {}", name))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Pinned SHA-256 of a file from `MODEL_BASE_URL`
+fn published_sha256(file_name: &str) -> Result<&'static str, String> {
+    PUBLISHED_SHA256
+        .iter()
+        .find(|(name, _)| *name == file_name)
+        .map(|(_, checksum)| *checksum)
+        .ok_or_else(|| format!("No published checksum for {}", file_name))
+}
+
+/// Download `url` to `path`, returning the SHA-256 of what was written
+async fn download_to(
+    app: &AppHandle,
+    model: &str,
+    url: &str,
+    path: &Path,
+) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download model: {}", e))?;
+
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| format!("Failed to create model file: {}", e))?;
+
+    let mut progress = DownloadProgress {
+        model: model.to_string(),
+        downloaded_bytes: 0,
+        total_bytes: response.content_length(),
+    };
+    let mut hasher = Sha256::new();
+    let mut last_reported_mb = 0;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download model: {}", e))?
+    {
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write model file: {}", e))?;
+
+        // Report once per MiB rather than per network chunk
+        progress.downloaded_bytes += chunk.len() as u64;
+        if progress.downloaded_bytes >> 20 > last_reported_mb {
+            last_reported_mb = progress.downloaded_bytes >> 20;
+            let _ = app.emit("model-download-progress", progress.clone());
+        }
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write model file: {}", e))?;
+    let _ = app.emit("model-download-progress", progress);
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+async fn download(app: &AppHandle, model: &str, path: &Path) -> Result<(), String> {
+    let file_name = model_file_name(model)?;
+    let url = format!("{}/{}", MODEL_BASE_URL, file_name);
+    let expected = published_sha256(&file_name)?;
+
+    // Downloaded next to the model and renamed once verified, so an
+    // interrupted download never looks installed
+    let partial = path.with_extension("bin.part");
+    let result = match download_to(app, model, &url, &partial).await {
+        Ok(actual) if actual == expected => {
+            fs::rename(&partial, path).map_err(|e| format!("Failed to install model: {}", e))
+        }
+        Ok(actual) => Err(format!(
+            "Checksum mismatch for model {} (expected {}, got {})",
+            model, expected, actual
+        )),
+        Err(e) => Err(e),
+    };
+
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Whether the Core ML encoder can run here; whisper.cpp then runs the
+/// encoder on the Neural Engine and the rest with the usual backend
+fn core_ml_support(hardware: &HardwareInfo) -> Result<(), String> {
+    if !cfg!(target_os = "macos") || hardware.cpu_arch != "aarch64" {
+        return Err("The Core ML encoder needs a Mac with Apple Silicon".to_string());
+    }
+    if !cfg!(feature = "whisper-coreml") {
+        return Err("This build does not include Core ML support".to_string());
+    }
+    Ok(())
+}
+
+/// Download a Core ML encoder and unpack it next to the models
+async fn download_core_ml(app: &AppHandle, model: &str, dir: &Path) -> Result<(), String> {
+    let encoder = core_ml_encoder_name(model);
+    let archive_name = format!("{}.zip", encoder);
+    let url = format!("{}/{}", MODEL_BASE_URL, archive_name);
+    let expected = published_sha256(&archive_name)?;
+
+    let archive = dir.join(format!("{}.zip.part", encoder));
+    let result = match download_to(app, &encoder, &url, &archive).await {
+        Ok(actual) if actual == expected => std::process::Command::new("ditto")
+            .arg("-x")
+            .arg("-k")
+            .arg(&archive)
+            .arg(dir)
+            .status()
+            .map_err(|e| format!("Failed to unpack Core ML encoder: {}", e))
+            .and_then(|status| {
+                status
+                    .success()
+                    .then_some(())
+                    .ok_or_else(|| "Failed to unpack Core ML encoder".to_string())
+            }),
+        Ok(actual) => Err(format!(
+            "Checksum mismatch for {} (expected {}, got {})",
+            encoder, expected, actual
+        )),
+        Err(e) => Err(e),
+    };
+
+    let _ = fs::remove_file(&archive);
+    result
+}
+
+/// List downloadable Whisper models and which of them are installed. Models
+/// installed by hand under other names are listed too.
+#[tauri::command]
+pub fn list_whisper_models(app: AppHandle) -> Result<Vec<WhisperModel>, String> {
+    let dir = get_models_dir(&app)?;
+    let mut models: Vec<WhisperModel> = AVAILABLE_MODELS
+        .iter()
+        .map(|(name, size)| model_info(&dir, name, *size))
+        .collect();
+
+    let entries =
+        fs::read_dir(&dir).map_err(|e| format!("Failed to read models directory: {}", e))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file_name
+            .strip_prefix("ggml-")
+            .and_then(|rest| rest.strip_suffix(".bin"))
+        else {
+            continue;
+        };
+
+        if !models.iter().any(|model| model.name == name) {
+            models.push(model_info(&dir, name, 0));
+        }
+    }
+
+    Ok(models)
+}
+
+/// Names of the installed Whisper models
+pub fn installed_models(app: &AppHandle) -> Result<Vec<String>, String> {
+    Ok(list_whisper_models(app.clone())?
+        .into_iter()
+        .filter(|model| model.installed)
+        .map(|model| model.name)
+        .collect())
+}
+
+/// A variant of a model and whether this machine can use it
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVariant {
+    #[serde(flatten)]
+    model: WhisperModel,
+    supported: bool,
+    /// Why the variant is unsupported, or what to expect from it
+    note: Option<String>,
+}
+
+/// The variants of one model, and whether its Core ML encoder can be used
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVariants {
+    /// The full precision model
+    model: String,
+    variants: Vec<ModelVariant>,
+    core_ml_supported: bool,
+    core_ml_installed: bool,
+    /// Why the Core ML encoder cannot be used
+    core_ml_note: Option<String>,
+}
+
+/// Report the full precision and quantized variants of a model and which of
+/// them, and whether the Core ML encoder, this machine supports
+#[tauri::command]
+pub async fn get_model_variants(app: AppHandle, model: String) -> Result<ModelVariants, String> {
+    let dir = get_models_dir(&app)?;
+    let base = base_model(&model).to_string();
+    model_file_name(&base)?;
+
+    tokio::task::spawn_blocking(move || {
+        let hardware = hardware::detect();
+        let variants = AVAILABLE_MODELS
+            .iter()
+            .filter(|(name, _)| base_model(name) == base)
+            .map(|(name, size)| {
+                let model = model_info(&dir, name, *size);
+                let note = (model.quantization.is_some() && !hardware.has_fast_simd()).then(|| {
+                    "Without AVX2 or NEON, quantized models save memory but may run slower"
+                        .to_string()
+                });
+                ModelVariant {
+                    model,
+                    supported: true,
+                    note,
+                }
+            })
+            .collect();
+        let core_ml = core_ml_support(&hardware);
+
+        ModelVariants {
+            core_ml_installed: dir.join(core_ml_encoder_name(&base)).is_dir(),
+            model: base,
+            variants,
+            core_ml_supported: core_ml.is_ok(),
+            core_ml_note: core_ml.err(),
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Suggested local model for this machine and why
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRecommendation {
+    model: String,
+    /// For `TranscribeOptions.threads`
+    threads: usize,
+    installed: bool,
+    hardware: HardwareInfo,
+    /// Multi-threaded floating point throughput, if the benchmark ran
+    benchmark_gflops: Option<f32>,
+    /// Why larger models were ruled out, and notes on the hardware
+    reasons: Vec<String>,
+}
+
+/// Threads for transcription: one core is left to the UI on bigger machines,
+/// and whisper.cpp gains little beyond 8
+fn recommended_threads(cores: usize) -> usize {
+    let threads = if cores > 4 { cores - 1 } else { cores };
+    threads.clamp(1, 8)
+}
+
+/// Walk down the model tiers until one fits the hardware
+fn pick_model(
+    hardware: &HardwareInfo,
+    gflops: Option<f32>,
+    english_only: bool,
+) -> (String, Vec<String>) {
+    let mut reasons = Vec::new();
+
+    for (index, tier) in MODEL_TIERS.iter().enumerate() {
+        let last = index == MODEL_TIERS.len() - 1;
+        let memory_mb = hardware.memory_bytes.map(|bytes| bytes / (1024 * 1024));
+        let ruled_out = if memory_mb.is_some_and(|mb| tier.memory_mb * MODEL_MEMORY_SHARE > mb) {
+            Some(format!(
+                "{} needs about {} MB of memory, too much for {} MB installed",
+                tier.name,
+                tier.memory_mb,
+                memory_mb.unwrap_or(0)
+            ))
+        } else if hardware.cpu_cores < tier.min_cores {
+            Some(format!(
+                "{} needs at least {} CPU cores, found {}",
+                tier.name, tier.min_cores, hardware.cpu_cores
+            ))
+        } else if tier.needs_fast_simd && !hardware.has_fast_simd() {
+            Some(format!(
+                "{} needs a CPU with AVX2 or NEON to run in real time",
+                tier.name
+            ))
+        } else if gflops.is_some_and(|gflops| gflops < tier.min_gflops) {
+            Some(format!(
+                "{} needs about {} GFLOPS, the benchmark measured {:.0}",
+                tier.name,
+                tier.min_gflops,
+                gflops.unwrap_or(0.0)
+            ))
+        } else {
+            None
+        };
+
+        match ruled_out {
+            Some(reason) if !last => reasons.push(reason),
+            _ => {
+                let model = match tier.english_only {
+                    Some(english) if english_only => english,
+                    _ => tier.name,
+                };
+                return (model.to_string(), reasons);
+            }
+        }
+    }
+
+    unreachable!("the last tier is always picked")
+}
+
+/// Recommend a local model and thread count for this machine, based on its
+/// CPU, memory and, with `benchmark`, a short CPU benchmark. Used during
+/// onboarding.
+#[tauri::command]
+pub async fn recommend_model(
+    app: AppHandle,
+    english_only: Option<bool>,
+    benchmark: Option<bool>,
+) -> Result<ModelRecommendation, String> {
+    let dir = get_models_dir(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let hardware = hardware::detect();
+        let threads = recommended_threads(hardware.cpu_cores);
+        let benchmark_gflops = benchmark
+            .unwrap_or(false)
+            .then(|| hardware::benchmark_gflops(threads));
+
+        let (model, mut reasons) =
+            pick_model(&hardware, benchmark_gflops, english_only.unwrap_or(false));
+        if let Some(gpu) = &hardware.gpu {
+            let accelerated = acceleration::compiled_backends()
+                .into_iter()
+                .any(|backend| backend != WhisperBackend::Cpu);
+            reasons.push(if accelerated {
+                format!(
+                    "A {} GPU was found; larger models may run fast enough on it",
+                    gpu
+                )
+            } else {
+                format!(
+                    "A {} GPU was found, but this build runs local transcription on the CPU",
+                    gpu
+                )
+            });
+        }
+
+        ModelRecommendation {
+            installed: model_info(&dir, &model, 0).installed,
+            model,
+            threads,
+            hardware,
+            benchmark_gflops,
+            reasons,
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Download a Whisper model into the models directory, emitting
+/// `model-download-progress` events and verifying its SHA-256 checksum
+#[tauri::command]
+pub async fn download_whisper_model(app: AppHandle, model: String) -> Result<WhisperModel, String> {
+    let approx_size_mb = available_model(&model)?;
+    let dir = get_models_dir(&app)?;
+    let path: PathBuf = dir.join(model_file_name(&model)?);
+
+    if !DOWNLOADS.lock().insert(model.clone()) {
+        return Err(format!("Model {} is already being downloaded", model));
+    }
+    let result = download(&app, &model, &path).await;
+    DOWNLOADS.lock().remove(&model);
+
+    result.map(|_| model_info(&dir, &model, approx_size_mb))
+}
+
+/// Delete an installed Whisper model
+#[tauri::command]
+pub fn delete_whisper_model(app: AppHandle, model: String) -> Result<(), String> {
+    if DOWNLOADS.lock().contains(&model) {
+        return Err(format!("Model {} is being downloaded", model));
+    }
+
+    let path = get_models_dir(&app)?.join(model_file_name(&model)?);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to delete model: {}", e)),
+    }
+}
+
+/// Download the Core ML encoder for a model and its quantized variants,
+/// emitting `model-download-progress` events. whisper.cpp uses it from the
+/// next transcription on.
+#[tauri::command]
+pub async fn download_core_ml_encoder(app: AppHandle, model: String) -> Result<(), String> {
+    let hardware = tokio::task::spawn_blocking(hardware::detect)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?;
+    core_ml_support(&hardware)?;
+    model_file_name(&model)?;
+    let dir = get_models_dir(&app)?;
+
+    let encoder = core_ml_encoder_name(&model);
+    if !DOWNLOADS.lock().insert(encoder.clone()) {
+        return Err(format!("{} is already being downloaded", encoder));
+    }
+    let result = download_core_ml(&app, &model, &dir).await;
+    DOWNLOADS.lock().remove(&encoder);
+    result
+}
+
+/// Delete the Core ML encoder of a model, so it runs entirely on the usual
+/// backend again
+#[tauri::command]
+pub fn delete_core_ml_encoder(app: AppHandle, model: String) -> Result<(), String> {
+    model_file_name(&model)?;
+    let path = get_models_dir(&app)?.join(core_ml_encoder_name(&model));
+    match fs::remove_dir_all(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to delete Core ML encoder: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_file_name_rejects_paths() {
+        assert_eq!(model_file_name("small.en").unwrap(), "ggml-small.en.bin");
+        assert_eq!(model_file_name("large-v3").unwrap(), "ggml-large-v3.bin");
+        assert!(model_file_name("").is_err());
+        assert!(model_file_name("../base").is_err());
+        assert!(model_file_name("a/b").is_err());
+    }
+
+    #[test]
+    fn test_variants() {
+        assert_eq!(quantization("large-v3-turbo-q5_0"), Some("q5_0"));
+        assert_eq!(quantization("large-v3"), None);
+        assert_eq!(base_model("small.en-q5_1"), "small.en");
+        assert_eq!(base_model("small.en-tdrz"), "small.en-tdrz");
+        assert_eq!(
+            core_ml_encoder_name("base-q8_0"),
+            "ggml-base-encoder.mlmodelc"
+        );
+        assert!(AVAILABLE_MODELS
+            .iter()
+            .all(|(name, _)| model_file_name(name).is_ok()));
+    }
+
+    #[test]
+    fn test_published_checksums() {
+        let known = |name: &str| {
+            AVAILABLE_MODELS.iter().any(|(model, _)| {
+                model_file_name(model).is_ok_and(|file| file == name)
+                    || format!("{}.zip", core_ml_encoder_name(model)) == name
+            })
+        };
+        assert!(PUBLISHED_SHA256.iter().all(|(name, checksum)| known(name)
+            && checksum.len() == 64
+            && checksum
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))));
+        assert!(published_sha256("ggml-unknown.bin").is_err());
+    }
+
+    #[test]
+    fn test_pick_model() {
+        let mut hardware = HardwareInfo {
+            cpu_cores: 12,
+            cpu_arch: "x86_64".to_string(),
+            cpu_features: vec!["avx".to_string(), "avx2".to_string()],
+            memory_bytes: Some(32 * 1024 * 1024 * 1024),
+            gpu: None,
+        };
+        assert_eq!(pick_model(&hardware, None, false).0, "large-v3-turbo");
+        assert_eq!(pick_model(&hardware, Some(40.0), true).0, "small.en");
+
+        hardware.memory_bytes = Some(2 * 1024 * 1024 * 1024);
+        let (model, reasons) = pick_model(&hardware, None, false);
+        assert_eq!(model, "base");
+        assert_eq!(reasons.len(), 2);
+
+        hardware.cpu_features.clear();
+        hardware.cpu_cores = 1;
+        assert_eq!(pick_model(&hardware, Some(0.5), true).0, "tiny.en");
+
+        assert_eq!(recommended_threads(1), 1);
+        assert_eq!(recommended_threads(6), 5);
+        assert_eq!(recommended_threads(32), 8);
+    }
+}
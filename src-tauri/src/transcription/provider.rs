@@ -0,0 +1,110 @@
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::AppHandle;
+use transcriber_core::transcription::{assemblyai, azure, deepgram, google, openai};
+
+use super::read_secret;
+pub use transcriber_core::transcription::provider::*;
+
+static PROVIDERS: Lazy<RwLock<Vec<Arc<dyn TranscriptionProvider>>>> = Lazy::new(|| {
+    RwLock::new(vec![
+        Arc::new(openai::OpenAi),
+        Arc::new(assemblyai::AssemblyAi),
+        Arc::new(azure::Azure),
+        Arc::new(google::Google),
+        Arc::new(deepgram::Deepgram),
+    ])
+});
+
+/// Add a provider, replacing one with the same id
+pub fn register(provider: Arc<dyn TranscriptionProvider>) {
+    let mut providers = PROVIDERS.write();
+    providers.retain(|existing| existing.id() != provider.id());
+    providers.push(provider);
+}
+
+pub fn get(id: &str) -> Result<Arc<dyn TranscriptionProvider>, String> {
+    PROVIDERS
+        .read()
+        .iter()
+        .find(|provider| provider.id() == id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown transcription provider: {}", id))
+}
+
+/// Read the secrets a provider needs, failing on the first one that is missing
+pub async fn read_credentials(
+    app: &AppHandle,
+    provider: &dyn TranscriptionProvider,
+) -> Result<Credentials, String> {
+    let mut credentials = Vec::new();
+    for credential in provider.credentials() {
+        let value = read_secret(app, credential.key, credential.name).await?;
+        credentials.push((credential.key, value));
+    }
+    Ok(credentials.into_iter().collect())
+}
+
+/// A registered provider and what it can do
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    pub id: String,
+    pub capabilities: ProviderCapabilities,
+    /// List price per minute of audio; see `get_usage_stats`
+    pub usd_per_minute: Option<f64>,
+}
+
+/// List the transcription providers and their capabilities
+#[tauri::command]
+pub fn list_transcription_providers() -> Vec<ProviderInfo> {
+    PROVIDERS
+        .read()
+        .iter()
+        .map(|provider| ProviderInfo {
+            id: provider.id().to_string(),
+            capabilities: provider.capabilities(),
+            usd_per_minute: provider.usd_per_minute(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl TranscriptionProvider for Echo {
+        fn id(&self) -> &'static str {
+            "echo"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities {
+                offline: true,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_and_request() {
+        assert!(get("openai").is_ok());
+        assert!(get("echo").is_err());
+
+        register(Arc::new(Echo));
+        assert!(get("echo").unwrap().capabilities().offline);
+        assert!(list_transcription_providers()
+            .iter()
+            .any(|info| info.id == "echo" && !info.capabilities.file));
+
+        let request: ProviderRequest =
+            serde_json::from_str(r#"{ "provider": "azure", "language": "de-DE" }"#).unwrap();
+        assert_eq!(request.provider, "azure");
+        assert_eq!(request.options["language"], "de-DE");
+        assert!(!request.options.contains_key("provider"));
+    }
+}
@@ -0,0 +1,327 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::settings;
+
+/// How often a running job's usage is sampled
+const SAMPLE_INTERVAL_MS: u64 = 1000;
+/// Share of the memory budget at which `job-resource-warning` is emitted
+const WARNING_RATIO: f64 = 0.9;
+/// Finished jobs kept for `get_job_resource_usage`
+const MAX_FINISHED_JOBS: usize = 20;
+
+/// Resources used while a transcription job runs. Usage is measured for the
+/// whole app process, so jobs running at the same time share their numbers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub job_id: String,
+    pub running: bool,
+    pub elapsed_ms: u64,
+    /// Over the last sample interval; 100 is one fully busy core
+    pub cpu_percent: f32,
+    pub peak_cpu_percent: f32,
+    /// Resident memory
+    pub memory_bytes: u64,
+    pub peak_memory_bytes: u64,
+    pub memory_budget_bytes: Option<u64>,
+    /// `None` where GPU usage cannot be measured, which currently is everywhere
+    pub gpu_percent: Option<f32>,
+}
+
+/// Payload of `job-resource-warning`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceWarning {
+    job_id: String,
+    memory_bytes: u64,
+    memory_budget_bytes: u64,
+    message: String,
+}
+
+/// Running jobs first, then the most recently finished ones
+static JOBS: Lazy<Mutex<VecDeque<ResourceUsage>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// CPU time the process has used so far
+#[cfg(unix)]
+fn cpu_time() -> Option<Duration> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: `usage` is only read on success
+    let usage = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) != 0 {
+            return None;
+        }
+        usage.assume_init()
+    };
+
+    let time = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    };
+    Some(time(usage.ru_utime) + time(usage.ru_stime))
+}
+
+#[cfg(target_os = "linux")]
+fn memory_bytes() -> Option<u64> {
+    // Second field: resident pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * page_size.max(0) as u64)
+}
+
+#[cfg(target_os = "macos")]
+fn memory_bytes() -> Option<u64> {
+    let mut info = std::mem::MaybeUninit::<libc::proc_taskinfo>::uninit();
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as i32;
+    // SAFETY: `info` is `size` bytes and only read when fully written
+    let info = unsafe {
+        let written = libc::proc_pidinfo(
+            std::process::id() as i32,
+            libc::PROC_PIDTASKINFO,
+            0,
+            info.as_mut_ptr().cast(),
+            size,
+        );
+        if written != size {
+            return None;
+        }
+        info.assume_init()
+    };
+    Some(info.pti_resident_size)
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn memory_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(windows)]
+#[allow(non_snake_case)]
+#[repr(C)]
+#[derive(Default)]
+struct ProcessMemoryCounters {
+    cb: u32,
+    PageFaultCount: u32,
+    PeakWorkingSetSize: usize,
+    WorkingSetSize: usize,
+    QuotaPeakPagedPoolUsage: usize,
+    QuotaPagedPoolUsage: usize,
+    QuotaPeakNonPagedPoolUsage: usize,
+    QuotaNonPagedPoolUsage: usize,
+    PagefileUsage: usize,
+    PeakPagefileUsage: usize,
+}
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetCurrentProcess() -> isize;
+    fn GetProcessTimes(
+        process: isize,
+        creation: *mut u64,
+        exit: *mut u64,
+        kernel: *mut u64,
+        user: *mut u64,
+    ) -> i32;
+    fn K32GetProcessMemoryInfo(
+        process: isize,
+        counters: *mut ProcessMemoryCounters,
+        size: u32,
+    ) -> i32;
+}
+
+#[cfg(windows)]
+fn cpu_time() -> Option<Duration> {
+    let (mut creation, mut exit, mut kernel, mut user) = (0u64, 0u64, 0u64, 0u64);
+    // SAFETY: all outputs are valid; the pseudo handle needs no closing
+    let ok = unsafe {
+        GetProcessTimes(
+            GetCurrentProcess(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+    };
+    // Times are in 100 ns units
+    (ok != 0).then(|| Duration::from_nanos((kernel + user) * 100))
+}
+
+#[cfg(windows)]
+fn memory_bytes() -> Option<u64> {
+    let mut counters = ProcessMemoryCounters {
+        cb: std::mem::size_of::<ProcessMemoryCounters>() as u32,
+        ..Default::default()
+    };
+    // SAFETY: `counters` is valid and its size is passed along
+    let ok = unsafe { K32GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, counters.cb) };
+    (ok != 0).then_some(counters.WorkingSetSize as u64)
+}
+
+fn memory_budget(app: &AppHandle) -> Option<u64> {
+    settings::load_settings(app)
+        .ok()?
+        .job_memory_budget_mb
+        .map(|mb| mb * 1024 * 1024)
+}
+
+/// Whether usage is close enough to the budget to warn about
+fn near_budget(memory_bytes: u64, budget_bytes: u64) -> bool {
+    memory_bytes as f64 >= budget_bytes as f64 * WARNING_RATIO
+}
+
+fn update(job_id: &str, apply: impl FnOnce(&mut ResourceUsage)) {
+    if let Some(usage) = JOBS.lock().iter_mut().find(|usage| usage.job_id == job_id) {
+        apply(usage);
+    }
+}
+
+/// Samples the app's resource usage while a job runs; dropping it marks the
+/// job finished
+pub struct JobMonitor {
+    job_id: String,
+    started: Instant,
+    finished: Arc<AtomicBool>,
+}
+
+impl JobMonitor {
+    pub fn start(app: &AppHandle, job_id: &str) -> Self {
+        let budget = memory_budget(app);
+        let memory = memory_bytes().unwrap_or(0);
+        {
+            let mut jobs = JOBS.lock();
+            jobs.retain(|usage| usage.job_id != job_id);
+            jobs.push_front(ResourceUsage {
+                job_id: job_id.to_string(),
+                running: true,
+                elapsed_ms: 0,
+                cpu_percent: 0.0,
+                peak_cpu_percent: 0.0,
+                memory_bytes: memory,
+                peak_memory_bytes: memory,
+                memory_budget_bytes: budget,
+                gpu_percent: None,
+            });
+            while jobs.iter().filter(|usage| !usage.running).count() > MAX_FINISHED_JOBS {
+                if let Some(oldest) = jobs.iter().rposition(|usage| !usage.running) {
+                    jobs.remove(oldest);
+                }
+            }
+        }
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let sampler_finished = Arc::clone(&finished);
+        let started = Instant::now();
+        let app = app.clone();
+        let id = job_id.to_string();
+        std::thread::spawn(move || {
+            let mut last = (Instant::now(), cpu_time());
+            let mut warned = false;
+
+            while !sampler_finished.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(SAMPLE_INTERVAL_MS));
+
+                let now = (Instant::now(), cpu_time());
+                let cpu_percent = match (last.1, now.1) {
+                    (Some(before), Some(after)) => {
+                        let wall = now.0.duration_since(last.0).as_secs_f32();
+                        (after.saturating_sub(before).as_secs_f32() / wall.max(1e-3)) * 100.0
+                    }
+                    _ => 0.0,
+                };
+                last = now;
+                let memory = memory_bytes().unwrap_or(0);
+
+                update(&id, |usage| {
+                    usage.elapsed_ms = started.elapsed().as_millis() as u64;
+                    usage.cpu_percent = cpu_percent;
+                    usage.peak_cpu_percent = usage.peak_cpu_percent.max(cpu_percent);
+                    usage.memory_bytes = memory;
+                    usage.peak_memory_bytes = usage.peak_memory_bytes.max(memory);
+                });
+
+                if let Some(budget) =
+                    budget.filter(|&budget| !warned && near_budget(memory, budget))
+                {
+                    warned = true;
+                    let _ = app.emit(
+                        "job-resource-warning",
+                        ResourceWarning {
+                            job_id: id.clone(),
+                            memory_bytes: memory,
+                            memory_budget_bytes: budget,
+                            message: format!(
+                                "Transcription is using {} MB of the {} MB memory budget; \
+                                 a smaller model may be a better fit",
+                                memory / (1024 * 1024),
+                                budget / (1024 * 1024)
+                            ),
+                        },
+                    );
+                }
+            }
+        });
+
+        Self {
+            job_id: job_id.to_string(),
+            started,
+            finished,
+        }
+    }
+}
+
+impl Drop for JobMonitor {
+    fn drop(&mut self) {
+        self.finished.store(true, Ordering::Relaxed);
+        update(&self.job_id, |usage| {
+            usage.running = false;
+            usage.elapsed_ms = self.started.elapsed().as_millis() as u64;
+        });
+    }
+}
+
+/// CPU, memory and GPU usage of a running or recently finished transcription job
+#[tauri::command]
+pub fn get_job_resource_usage(job_id: String) -> Result<ResourceUsage, String> {
+    JOBS.lock()
+        .iter()
+        .find(|usage| usage.job_id == job_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown job: {}", job_id))
+}
+
+/// Set the memory budget transcription jobs are warned about, or `None` to
+/// turn the warning off
+#[tauri::command]
+pub fn set_job_memory_budget(app: AppHandle, budget_mb: Option<u64>) -> Result<(), String> {
+    settings::update_settings(&app, |current| {
+        current.job_memory_budget_mb = budget_mb;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_near_budget() {
+        let budget = 1000 * 1024 * 1024;
+        assert!(!near_budget(500 * 1024 * 1024, budget));
+        assert!(near_budget(950 * 1024 * 1024, budget));
+        assert!(near_budget(2 * budget, budget));
+    }
+
+    #[test]
+    fn test_process_measurements() {
+        assert!(cpu_time().is_some());
+        assert!(memory_bytes().is_some_and(|bytes| bytes > 0));
+    }
+}
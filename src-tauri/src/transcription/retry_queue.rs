@@ -0,0 +1,280 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::provider::{AudioFile, ProviderRequest};
+use super::{emit_outcome, prepare_file_job, resources, TranscriptionResult};
+use crate::{network, secure_delete};
+use transcriber_core::crypto::{self, KeyContext};
+
+const QUEUE_FILE: &str = "retry_queue.json";
+/// Recordings copied into the queue, since the last take does not outlive the app
+const AUDIO_DIR: &str = "retry_audio";
+/// How often the network is checked while jobs are queued
+const CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Serializes reads and writes of the queue file
+static QUEUE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+static RETRYING: AtomicBool = AtomicBool::new(false);
+
+/// A cloud transcription that could not reach its service, kept until the
+/// network returns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedJob {
+    /// Id the job was started with; events on retry carry the same id
+    pub job_id: String,
+    pub provider: String,
+    pub options: serde_json::Map<String, serde_json::Value>,
+    pub audio_path: String,
+    pub file_name: String,
+    /// The audio was copied into the queue, encrypted, and is deleted when
+    /// the job leaves it
+    pub owns_audio: bool,
+    /// RFC 3339
+    pub queued_at: String,
+    /// Retries that failed for lack of network
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+/// Where a failed job's audio can be read again
+pub enum PendingAudio {
+    File(PathBuf),
+    /// WAV bytes of the last take
+    Recording(Vec<u8>),
+}
+
+/// Whether a provider error means the service could not be reached, as
+/// opposed to the service rejecting the request
+pub fn is_network_error(error: &str) -> bool {
+    error.starts_with("Failed to reach ") || error.starts_with("Failed to connect to ")
+}
+
+fn queue_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+fn load_queue(app: &AppHandle) -> Result<Vec<QueuedJob>, String> {
+    let path = queue_dir(app)?.join(QUEUE_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read retry queue: {}", e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse retry queue: {}", e))
+}
+
+fn save_queue(app: &AppHandle, jobs: &[QueuedJob]) -> Result<(), String> {
+    let dir = queue_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let contents = serde_json::to_string_pretty(jobs)
+        .map_err(|e| format!("Failed to serialize retry queue: {}", e))?;
+    fs::write(dir.join(QUEUE_FILE), contents)
+        .map_err(|e| format!("Failed to write retry queue: {}", e))
+}
+
+fn update_queue<T>(
+    app: &AppHandle,
+    apply: impl FnOnce(&mut Vec<QueuedJob>) -> T,
+) -> Result<T, String> {
+    let _lock = QUEUE_LOCK.lock();
+    let mut jobs = load_queue(app)?;
+    let value = apply(&mut jobs);
+    save_queue(app, &jobs)?;
+    Ok(value)
+}
+
+/// Keep a job whose provider could not be reached and emit
+/// `transcription-queued`
+pub fn enqueue(
+    app: &AppHandle,
+    job_id: &str,
+    request: &ProviderRequest,
+    file_name: &str,
+    audio: PendingAudio,
+    error: String,
+) -> Result<(), String> {
+    let (audio_path, owns_audio) = match audio {
+        PendingAudio::File(path) => (path, false),
+        PendingAudio::Recording(bytes) => {
+            let dir = queue_dir(app)?.join(AUDIO_DIR);
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create retry audio directory: {}", e))?;
+            let path = dir.join(format!("{}.wav.enc", job_id));
+            let encrypted = crypto::encrypt(KeyContext::PendingAudio, &bytes)?;
+            fs::write(&path, encrypted)
+                .map_err(|e| format!("Failed to save queued audio: {}", e))?;
+            (path, true)
+        }
+    };
+
+    let job = QueuedJob {
+        job_id: job_id.to_string(),
+        provider: request.provider.clone(),
+        options: request.options.clone(),
+        audio_path: audio_path.display().to_string(),
+        file_name: file_name.to_string(),
+        owns_audio,
+        queued_at: chrono::Utc::now().to_rfc3339(),
+        attempts: 0,
+        last_error: error,
+    };
+    update_queue(app, |jobs| {
+        jobs.retain(|queued| queued.job_id != job.job_id);
+        jobs.push(job.clone());
+    })?;
+
+    let _ = app.emit("transcription-queued", job);
+    Ok(())
+}
+
+/// Take a job out of the queue, deleting audio the queue owns
+fn remove(app: &AppHandle, job_id: &str) -> Result<Option<QueuedJob>, String> {
+    let removed = update_queue(app, |jobs| {
+        let index = jobs.iter().position(|job| job.job_id == job_id)?;
+        Some(jobs.remove(index))
+    })?;
+
+    if let Some(job) = removed.as_ref().filter(|job| job.owns_audio) {
+        let path = Path::new(&job.audio_path);
+        if path.exists() {
+            secure_delete::delete_path(path, false)?;
+        }
+    }
+    Ok(removed)
+}
+
+async fn retry_job(app: &AppHandle, job: &QueuedJob) -> Result<TranscriptionResult, String> {
+    let mut bytes = tokio::fs::read(&job.audio_path)
+        .await
+        .map_err(|e| format!("Failed to read queued audio: {}", e))?;
+    if job.owns_audio {
+        let encrypted = String::from_utf8(bytes)
+            .map_err(|_| "Queued audio is not valid encrypted data".to_string())?;
+        bytes = crypto::decrypt(KeyContext::PendingAudio, &encrypted)
+            .map_err(|e| format!("Failed to decrypt queued audio: {}", e))?
+            .to_vec();
+    }
+    let request = ProviderRequest {
+        provider: job.provider.clone(),
+        options: job.options.clone(),
+    };
+    let audio = AudioFile {
+        bytes,
+        file_name: job.file_name.clone(),
+    };
+
+    let work = prepare_file_job(app, &request, audio).await?;
+    let _monitor = resources::JobMonitor::start(app, &job.job_id);
+    work.await
+}
+
+/// Clears `RETRYING` when a retry pass ends
+struct RetryPass;
+
+impl Drop for RetryPass {
+    fn drop(&mut self) {
+        RETRYING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Retry queued jobs in the order they were queued, stopping at the first
+/// one that still cannot reach its service. Returns how many left the queue.
+async fn retry_all(app: &AppHandle) -> Result<usize, String> {
+    if RETRYING.swap(true, Ordering::SeqCst) {
+        return Ok(0);
+    }
+    let _pass = RetryPass;
+
+    let jobs = {
+        let _lock = QUEUE_LOCK.lock();
+        load_queue(app)?
+    };
+    let mut finished = 0;
+    for job in jobs {
+        match retry_job(app, &job).await {
+            Err(error) if is_network_error(&error) => {
+                update_queue(app, |jobs| {
+                    if let Some(queued) = jobs.iter_mut().find(|queued| queued.job_id == job.job_id)
+                    {
+                        queued.attempts += 1;
+                        queued.last_error = error;
+                    }
+                })?;
+                break;
+            }
+            outcome => {
+                // Skip jobs removed by the user while they were retried
+                if remove(app, &job.job_id)?.is_some() {
+                    emit_outcome(app, job.job_id, outcome);
+                    finished += 1;
+                }
+            }
+        }
+    }
+
+    Ok(finished)
+}
+
+/// Watch the network while jobs are queued, including jobs queued before the
+/// app was last closed, and retry them as soon as it is back. Each outcome
+/// arrives as a `transcription-completed` or `transcription-failed` event.
+pub fn start_on_startup(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let queued = load_queue(&app).map(|jobs| !jobs.is_empty());
+            if queued.unwrap_or(false) && network::check(&app).await {
+                if let Err(e) = retry_all(&app).await {
+                    eprintln!("Failed to retry queued transcriptions: {}", e);
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// List cloud transcriptions waiting for the network to return
+#[tauri::command]
+pub fn list_queued_transcriptions(app: AppHandle) -> Result<Vec<QueuedJob>, String> {
+    let _lock = QUEUE_LOCK.lock();
+    load_queue(&app)
+}
+
+/// Retry queued transcriptions now instead of waiting for the next network
+/// check. Returns how many left the queue; their outcome arrives as the usual
+/// `transcription-completed` or `transcription-failed` events.
+#[tauri::command]
+pub async fn retry_queued_transcriptions(app: AppHandle) -> Result<usize, String> {
+    retry_all(&app).await
+}
+
+/// Drop a queued transcription without running it
+#[tauri::command]
+pub fn remove_queued_transcription(app: AppHandle, job_id: String) -> Result<(), String> {
+    remove(&app, &job_id)?
+        .map(|_| ())
+        .ok_or_else(|| format!("Unknown job: {}", job_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_network_error() {
+        assert!(is_network_error("Failed to reach OpenAI: dns error"));
+        assert!(is_network_error("Failed to connect to Deepgram: timed out"));
+        assert!(!is_network_error("OpenAI error 401: invalid api key"));
+        assert!(!is_network_error("No OpenAI API key is set"));
+    }
+}
@@ -0,0 +1,570 @@
+use libloading::Library;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::AppHandle;
+use transcriber_core::audio::LiveAudio;
+use transcriber_core::transcription::{LiveTranscript, TranscriptWord};
+use transcriber_core::Events;
+
+use super::jobs::{JobControl, CANCELLED};
+use super::provider::{self, AudioFile, ProviderCapabilities, TranscriptionProvider};
+use super::{generate_job_id, get_models_dir, TranscriptSegment, TranscriptionResult};
+use crate::analytics::PaceMetrics;
+use crate::audio::{self, SPEECH_SAMPLE_RATE};
+
+/// Samples fed to the recognizer at a time, 0.2 seconds
+const BLOCK_SAMPLES: usize = SPEECH_SAMPLE_RATE as usize / 5;
+
+type ModelNew = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type Free = unsafe extern "C" fn(*mut c_void);
+type RecognizerNew = unsafe extern "C" fn(*mut c_void, f32) -> *mut c_void;
+type SetWords = unsafe extern "C" fn(*mut c_void, c_int);
+type AcceptWaveform = unsafe extern "C" fn(*mut c_void, *const f32, c_int) -> c_int;
+type ReadResult = unsafe extern "C" fn(*mut c_void) -> *const c_char;
+type SetLogLevel = unsafe extern "C" fn(c_int);
+
+/// The functions of libvosk this provider uses. The library is loaded at
+/// runtime, so builds need no Vosk toolchain and the provider reports a
+/// clear error on machines without it.
+struct Api {
+    model_new: ModelNew,
+    model_free: Free,
+    recognizer_new: RecognizerNew,
+    recognizer_set_words: SetWords,
+    recognizer_accept_waveform: AcceptWaveform,
+    recognizer_result: ReadResult,
+    recognizer_partial_result: ReadResult,
+    recognizer_final_result: ReadResult,
+    recognizer_free: Free,
+    /// Keeps the functions above loaded
+    _library: Library,
+}
+
+impl Api {
+    fn load(path: &Path) -> Result<Self, String> {
+        // SAFETY: libvosk has no initialization routines with side effects,
+        // and each symbol is looked up with the signature from vosk_api.h
+        unsafe {
+            let library = Library::new(path).map_err(|e| e.to_string())?;
+            let symbol_error = |e: libloading::Error| format!("Unsupported libvosk: {}", e);
+            let set_log_level = *library
+                .get::<SetLogLevel>(b"vosk_set_log_level\0")
+                .map_err(symbol_error)?;
+            // Kaldi logs every model load to stderr otherwise
+            set_log_level(-1);
+
+            Ok(Self {
+                model_new: *library.get(b"vosk_model_new\0").map_err(symbol_error)?,
+                model_free: *library.get(b"vosk_model_free\0").map_err(symbol_error)?,
+                recognizer_new: *library
+                    .get(b"vosk_recognizer_new\0")
+                    .map_err(symbol_error)?,
+                recognizer_set_words: *library
+                    .get(b"vosk_recognizer_set_words\0")
+                    .map_err(symbol_error)?,
+                recognizer_accept_waveform: *library
+                    .get(b"vosk_recognizer_accept_waveform_f\0")
+                    .map_err(symbol_error)?,
+                recognizer_result: *library
+                    .get(b"vosk_recognizer_result\0")
+                    .map_err(symbol_error)?,
+                recognizer_partial_result: *library
+                    .get(b"vosk_recognizer_partial_result\0")
+                    .map_err(symbol_error)?,
+                recognizer_final_result: *library
+                    .get(b"vosk_recognizer_final_result\0")
+                    .map_err(symbol_error)?,
+                recognizer_free: *library
+                    .get(b"vosk_recognizer_free\0")
+                    .map_err(symbol_error)?,
+                _library: library,
+            })
+        }
+    }
+}
+
+static API: OnceCell<Arc<Api>> = OnceCell::new();
+
+/// Directory Vosk models and, optionally, libvosk are kept in
+fn vosk_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_models_dir(app)?.join("vosk"))
+}
+
+/// Load libvosk from the Vosk directory, or from the system's library path
+fn api(app: &AppHandle) -> Result<Arc<Api>, String> {
+    API.get_or_try_init(|| {
+        let file_name = libloading::library_filename("vosk");
+        let bundled = vosk_dir(app)?.join(&file_name);
+        let path = if bundled.exists() {
+            bundled
+        } else {
+            PathBuf::from(&file_name)
+        };
+
+        Api::load(&path).map(Arc::new).map_err(|e| {
+            format!(
+                "Failed to load Vosk ({}); place {} in {} or install it system-wide",
+                e,
+                file_name.to_string_lossy(),
+                path.parent().unwrap_or(Path::new("")).display()
+            )
+        })
+    })
+    .cloned()
+}
+
+/// A loaded Vosk model, shared by every recognizer using it
+struct Model {
+    api: Arc<Api>,
+    raw: *mut c_void,
+}
+
+// SAFETY: Vosk models are reference counted internally and only read once
+// loaded, so recognizers on any thread may share one
+unsafe impl Send for Model {}
+unsafe impl Sync for Model {}
+
+impl Drop for Model {
+    fn drop(&mut self) {
+        // SAFETY: `raw` came from vosk_model_new and is freed once
+        unsafe { (self.api.model_free)(self.raw) }
+    }
+}
+
+/// Loaded model, kept between calls because loading takes seconds
+type CachedModel = (PathBuf, Arc<Model>);
+static MODEL: Lazy<Mutex<Option<CachedModel>>> = Lazy::new(|| Mutex::new(None));
+
+fn load_model(api: Arc<Api>, path: &Path) -> Result<Arc<Model>, String> {
+    let mut cached = MODEL.lock();
+    if let Some((loaded, model)) = cached.as_ref() {
+        if loaded == path {
+            return Ok(Arc::clone(model));
+        }
+    }
+
+    let c_path = path
+        .to_str()
+        .and_then(|path| CString::new(path).ok())
+        .ok_or("Model path is not valid UTF-8")?;
+    // SAFETY: `c_path` is a valid C string for the duration of the call
+    let raw = unsafe { (api.model_new)(c_path.as_ptr()) };
+    if raw.is_null() {
+        return Err(format!("Failed to load Vosk model {}", path.display()));
+    }
+
+    let model = Arc::new(Model { api, raw });
+    *cached = Some((path.to_path_buf(), Arc::clone(&model)));
+    Ok(model)
+}
+
+/// Recognizes 16 kHz mono audio, one utterance at a time
+struct Recognizer {
+    model: Arc<Model>,
+    raw: *mut c_void,
+}
+
+impl Recognizer {
+    fn new(model: &Arc<Model>) -> Result<Self, String> {
+        let api = &model.api;
+        // SAFETY: the model outlives the recognizer, which holds a reference
+        let raw = unsafe { (api.recognizer_new)(model.raw, SPEECH_SAMPLE_RATE as f32) };
+        if raw.is_null() {
+            return Err("Failed to create Vosk recognizer".to_string());
+        }
+        // SAFETY: `raw` is a live recognizer
+        unsafe { (api.recognizer_set_words)(raw, 1) };
+
+        Ok(Self {
+            model: Arc::clone(model),
+            raw,
+        })
+    }
+
+    /// Feed samples; true when the utterance ended and `result` is ready
+    fn accept(&self, samples: &[f32]) -> Result<bool, String> {
+        // Vosk expects float samples on the 16-bit scale
+        let scaled: Vec<f32> = samples.iter().map(|sample| sample * 32768.0).collect();
+        // SAFETY: the pointer and length describe `scaled`
+        let status = unsafe {
+            (self.model.api.recognizer_accept_waveform)(
+                self.raw,
+                scaled.as_ptr(),
+                scaled.len() as c_int,
+            )
+        };
+        if status < 0 {
+            return Err("Vosk failed to process audio".to_string());
+        }
+        Ok(status == 1)
+    }
+
+    fn read(&self, function: ReadResult) -> String {
+        // SAFETY: Vosk returns a C string owned by the recognizer, valid
+        // until its next call; it is copied right away
+        unsafe { CStr::from_ptr(function(self.raw)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// The utterance that just ended, as JSON
+    fn result(&self) -> String {
+        self.read(self.model.api.recognizer_result)
+    }
+
+    /// The utterance so far, as JSON
+    fn partial_result(&self) -> String {
+        self.read(self.model.api.recognizer_partial_result)
+    }
+
+    /// Whatever is left once the audio ends, as JSON
+    fn final_result(&self) -> String {
+        self.read(self.model.api.recognizer_final_result)
+    }
+}
+
+impl Drop for Recognizer {
+    fn drop(&mut self) {
+        // SAFETY: `raw` came from vosk_recognizer_new and is freed once
+        unsafe { (self.model.api.recognizer_free)(self.raw) }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VoskWord {
+    word: String,
+    /// Seconds since the recognizer started
+    start: f64,
+    end: f64,
+    conf: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct VoskResult {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    result: Vec<VoskWord>,
+    #[serde(default)]
+    partial: String,
+}
+
+/// A finished utterance as a segment; `None` for silence
+fn parse_segment(json: &str) -> Option<TranscriptSegment> {
+    let result: VoskResult = serde_json::from_str(json).ok()?;
+    if result.text.trim().is_empty() {
+        return None;
+    }
+
+    let words: Vec<TranscriptWord> = result
+        .result
+        .into_iter()
+        .map(|word| TranscriptWord {
+            start_ms: (word.start * 1000.0).round() as u64,
+            end_ms: (word.end * 1000.0).round() as u64,
+            text: word.word,
+            confidence: word.conf,
+        })
+        .collect();
+    let confidence = (!words.is_empty())
+        .then(|| words.iter().map(|word| word.confidence).sum::<f32>() / words.len() as f32);
+
+    Some(TranscriptSegment {
+        start_ms: words.first().map_or(0, |word| word.start_ms),
+        end_ms: words.last().map_or(0, |word| word.end_ms),
+        text: result.text.trim().to_string(),
+        speaker: None,
+        confidence,
+        words,
+    })
+}
+
+/// The utterance so far; `None` while nothing was recognized
+fn parse_partial(json: &str) -> Option<String> {
+    let result: VoskResult = serde_json::from_str(json).ok()?;
+    let partial = result.partial.trim();
+    (!partial.is_empty()).then(|| partial.to_string())
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct VoskOptions {
+    /// Folder name of an installed model; the first one when not given
+    model: Option<String>,
+    job_id: Option<String>,
+}
+
+/// Names of the model folders in the Vosk directory
+fn installed_models(dir: &Path) -> Vec<String> {
+    let mut models: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort();
+    models
+}
+
+/// Name and folder of the model to use
+fn model_path(app: &AppHandle, model: Option<&str>) -> Result<(String, PathBuf), String> {
+    let dir = vosk_dir(app)?;
+    let name = match model {
+        Some(name) => name.to_string(),
+        None => installed_models(&dir).into_iter().next().ok_or_else(|| {
+            format!(
+                "No Vosk model is installed; unpack one into {}",
+                dir.display()
+            )
+        })?,
+    };
+
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        && !name.contains("..");
+    if name.is_empty() || !valid {
+        return Err(format!("Invalid model name: {}", name));
+    }
+
+    let path = dir.join(&name);
+    if !path.is_dir() {
+        return Err(format!(
+            "Vosk model \"{}\" is not installed (expected at {})",
+            name,
+            path.display()
+        ));
+    }
+    Ok((name, path))
+}
+
+/// Transcribe 16 kHz mono samples, one segment per utterance
+fn transcribe(
+    model: &Arc<Model>,
+    samples: &[f32],
+    control: &JobControl,
+) -> Result<Vec<TranscriptSegment>, String> {
+    let recognizer = Recognizer::new(model)?;
+    let blocks = samples.len().div_ceil(BLOCK_SAMPLES).max(1);
+
+    let mut segments = Vec::new();
+    for (index, block) in samples.chunks(BLOCK_SAMPLES).enumerate() {
+        if control.is_cancelled() {
+            return Err(CANCELLED.to_string());
+        }
+        if recognizer.accept(block)? {
+            segments.extend(parse_segment(&recognizer.result()));
+        }
+        control.report_progress(((index + 1) * 100 / blocks) as u8);
+    }
+    segments.extend(parse_segment(&recognizer.final_result()));
+    Ok(segments)
+}
+
+/// Position in 16 kHz audio
+fn samples_to_ms(samples: usize) -> u64 {
+    samples as u64 * 1000 / SPEECH_SAMPLE_RATE as u64
+}
+
+/// Lightweight offline recognition with Vosk, for machines where Whisper
+/// models are too heavy. Models are folders such as
+/// `vosk-model-small-en-us-0.15` unpacked into the Vosk directory.
+pub struct Vosk(pub AppHandle);
+
+impl TranscriptionProvider for Vosk {
+    fn id(&self) -> &'static str {
+        "vosk"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            file: true,
+            streaming: true,
+            offline: true,
+            ..Default::default()
+        }
+    }
+
+    fn transcribe_file(
+        &self,
+        _events: Events,
+        _credentials: provider::Credentials,
+        audio: AudioFile,
+        options: serde_json::Value,
+    ) -> Result<provider::ProviderFuture<TranscriptionResult>, String> {
+        let options: VoskOptions = provider::parse_options(options)?;
+        let (name, path) = model_path(&self.0, options.model.as_deref())?;
+        let api = api(&self.0)?;
+        let job_id = options.job_id.unwrap_or_else(generate_job_id);
+        let control = JobControl::for_job(&self.0, &job_id);
+
+        Ok(Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let samples = audio::decode_speech_samples(&audio.bytes)?;
+                let model = load_model(api, &path)?;
+                let segments = transcribe(&model, &samples, &control)?;
+                let duration_ms = samples_to_ms(samples.len());
+
+                Ok(TranscriptionResult {
+                    text: segments
+                        .iter()
+                        .map(|segment| segment.text.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    language: None,
+                    pace: PaceMetrics::from_segments(&segments, duration_ms),
+                    segments,
+                    duration_ms,
+                    engine: "vosk-local".to_string(),
+                    model: name,
+                })
+            })
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?
+        }))
+    }
+
+    fn transcribe_stream(
+        &self,
+        events: Events,
+        _credentials: provider::Credentials,
+        session_id: String,
+        live_audio: LiveAudio,
+        options: serde_json::Value,
+    ) -> Result<provider::ProviderFuture<()>, String> {
+        let options: VoskOptions = provider::parse_options(options)?;
+        let (_, path) = model_path(&self.0, options.model.as_deref())?;
+        let api = api(&self.0)?;
+
+        Ok(Box::pin(async move {
+            let mut receiver = live_audio.into_speech().receiver;
+            tokio::task::spawn_blocking(move || {
+                let model = load_model(api, &path)?;
+                let recognizer = Recognizer::new(&model)?;
+                let live =
+                    |text: String, start_ms, end_ms, confidence, speech_final| LiveTranscript {
+                        session_id: session_id.clone(),
+                        text,
+                        start_ms,
+                        end_ms,
+                        confidence,
+                        speaker: None,
+                        speech_final,
+                    };
+
+                let mut position = 0;
+                let mut utterance_start_ms = 0;
+                let mut last_partial = String::new();
+                while let Some(samples) = receiver.blocking_recv() {
+                    position += samples.len();
+                    if recognizer.accept(&samples)? {
+                        if let Some(segment) = parse_segment(&recognizer.result()) {
+                            events.emit(
+                                "transcription-final",
+                                live(
+                                    segment.text,
+                                    segment.start_ms,
+                                    segment.end_ms,
+                                    segment.confidence,
+                                    true,
+                                ),
+                            );
+                        }
+                        utterance_start_ms = samples_to_ms(position);
+                        last_partial.clear();
+                    } else if let Some(partial) = parse_partial(&recognizer.partial_result()) {
+                        if partial != last_partial {
+                            let end_ms = samples_to_ms(position);
+                            events.emit(
+                                "transcription-interim",
+                                live(partial.clone(), utterance_start_ms, end_ms, None, false),
+                            );
+                            last_partial = partial;
+                        }
+                    }
+                }
+
+                if let Some(segment) = parse_segment(&recognizer.final_result()) {
+                    events.emit(
+                        "transcription-final",
+                        live(
+                            segment.text,
+                            segment.start_ms,
+                            segment.end_ms,
+                            segment.confidence,
+                            true,
+                        ),
+                    );
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?
+        }))
+    }
+}
+
+/// Whether Vosk can run, and with which models
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoskStatus {
+    /// libvosk could be loaded
+    library: bool,
+    /// Why it could not
+    error: Option<String>,
+    /// Installed model folders
+    models: Vec<String>,
+    /// Where models, and optionally libvosk, go
+    dir: String,
+}
+
+/// Report whether libvosk is available and which Vosk models are installed
+#[tauri::command]
+pub fn get_vosk_status(app: AppHandle) -> Result<VoskStatus, String> {
+    let dir = vosk_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create Vosk directory: {}", e))?;
+    let error = api(&app).err();
+
+    Ok(VoskStatus {
+        library: error.is_none(),
+        error,
+        models: installed_models(&dir),
+        dir: dir.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_results() {
+        let result = r#"{
+            "result": [
+                { "conf": 1.0, "end": 1.02, "start": 0.5, "word": "hello" },
+                { "conf": 0.5, "end": 1.5, "start": 1.02, "word": "world" }
+            ],
+            "text": "hello world"
+        }"#;
+        let segment = parse_segment(result).unwrap();
+        assert_eq!(segment.text, "hello world");
+        assert_eq!((segment.start_ms, segment.end_ms), (500, 1500));
+        assert_eq!(segment.confidence, Some(0.75));
+        assert_eq!(segment.words[1].start_ms, 1020);
+
+        assert!(parse_segment(r#"{ "text": "" }"#).is_none());
+        assert_eq!(
+            parse_partial(r#"{ "partial": "hello wor" }"#).as_deref(),
+            Some("hello wor")
+        );
+        assert_eq!(parse_partial(r#"{ "partial": "" }"#), None);
+    }
+}
@@ -0,0 +1,219 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use transcriber_core::transcription::LanguageCandidate;
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+    WhisperToken,
+};
+
+use super::acceleration::{Acceleration, WhisperBackend};
+use super::jobs::{JobControl, CANCELLED};
+use super::{LanguageDetection, TranscribeOptions, TranscriptSegment, TranscriptionResult};
+use crate::analytics::PaceMetrics;
+use crate::audio::SPEECH_SAMPLE_RATE;
+
+/// Loaded model and how, kept between calls because loading takes seconds
+type CachedContext = (PathBuf, Acceleration, Arc<WhisperContext>);
+static CONTEXT: Lazy<Mutex<Option<CachedContext>>> = Lazy::new(|| Mutex::new(None));
+
+fn load_context(
+    model_path: &Path,
+    acceleration: Acceleration,
+) -> Result<Arc<WhisperContext>, String> {
+    let mut cached = CONTEXT.lock();
+    if let Some((path, loaded_with, context)) = cached.as_ref() {
+        if path == model_path && *loaded_with == acceleration {
+            return Ok(Arc::clone(context));
+        }
+    }
+
+    // whisper.cpp runs on whichever GPU backend it was built with
+    let mut params = WhisperContextParameters::default();
+    params
+        .use_gpu(acceleration.backend != WhisperBackend::Cpu)
+        .gpu_device(acceleration.gpu_device);
+
+    let path = model_path.to_str().ok_or("Model path is not valid UTF-8")?;
+    let context = WhisperContext::new_with_params(path, params)
+        .map(Arc::new)
+        .map_err(|e| format!("Failed to load model: {}", e))?;
+
+    *cached = Some((model_path.to_path_buf(), acceleration, Arc::clone(&context)));
+    Ok(context)
+}
+
+/// Mean probability of a segment's text tokens; timestamps and other special
+/// tokens, numbered from end-of-text up, are left out
+fn segment_confidence(state: &WhisperState, segment: i32, eot: WhisperToken) -> Option<f32> {
+    let tokens = state.full_n_tokens(segment).ok()?;
+    let probabilities: Vec<f32> = (0..tokens)
+        .filter(|&token| {
+            state
+                .full_get_token_id(segment, token)
+                .is_ok_and(|id| id < eot)
+        })
+        .filter_map(|token| state.full_get_token_prob(segment, token).ok())
+        .collect();
+
+    (!probabilities.is_empty())
+        .then(|| probabilities.iter().sum::<f32>() / probabilities.len() as f32)
+}
+
+/// Number of alternative languages reported by `detect_language`
+const LANGUAGE_CANDIDATES: usize = 3;
+
+fn thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().min(8))
+        .unwrap_or(4)
+}
+
+/// Detect the spoken language from the first 30 seconds of 16 kHz mono samples
+pub fn detect_language(
+    model_path: &Path,
+    acceleration: Acceleration,
+    samples: &[f32],
+) -> Result<LanguageDetection, String> {
+    let context = load_context(model_path, acceleration)?;
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+    let threads = thread_count();
+    state
+        .pcm_to_mel(samples, threads)
+        .map_err(|e| format!("Language detection failed: {}", e))?;
+    let (_, probabilities) = state
+        .lang_detect(0, threads)
+        .map_err(|e| format!("Language detection failed: {}", e))?;
+
+    let mut candidates: Vec<LanguageCandidate> = probabilities
+        .iter()
+        .enumerate()
+        .filter_map(|(id, probability)| {
+            whisper_rs::get_lang_str(id as i32).map(|language| LanguageCandidate {
+                language: language.to_string(),
+                probability: *probability,
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+    candidates.truncate(LANGUAGE_CANDIDATES);
+
+    let best = candidates
+        .first()
+        .cloned()
+        .ok_or("Language detection returned no languages")?;
+
+    Ok(LanguageDetection {
+        language: best.language,
+        confidence: best.probability,
+        candidates,
+    })
+}
+
+/// Transcribe 16 kHz mono samples with a local Whisper model
+pub fn transcribe(
+    model_path: &Path,
+    acceleration: Acceleration,
+    samples: &[f32],
+    options: &TranscribeOptions,
+    mut on_segment: impl FnMut(&TranscriptSegment) + 'static,
+    control: JobControl,
+) -> Result<TranscriptionResult, String> {
+    let context = load_context(model_path, acceleration)?;
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(options.threads.unwrap_or_else(thread_count) as i32);
+    params.set_language(Some(options.language.as_deref().unwrap_or("auto")));
+    params.set_translate(options.translate);
+    params.set_tdrz_enable(options.diarize);
+    if let Some(prompt) = &options.prompt {
+        params.set_initial_prompt(prompt);
+    }
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+    params.set_segment_callback_safe(move |data: whisper_rs::SegmentCallbackData| {
+        on_segment(&TranscriptSegment {
+            start_ms: data.start_timestamp.max(0) as u64 * 10,
+            end_ms: data.end_timestamp.max(0) as u64 * 10,
+            text: data.text.trim().to_string(),
+            speaker: None,
+            confidence: None,
+            words: Vec::new(),
+        })
+    });
+    let progress = control.clone();
+    params.set_progress_callback_safe(move |percent: i32| {
+        progress.report_progress(percent.clamp(0, 100) as u8)
+    });
+    let abort = control.clone();
+    params.set_abort_callback_safe(move || abort.is_cancelled());
+
+    let full = state.full(params, samples);
+    if control.is_cancelled() {
+        return Err(CANCELLED.to_string());
+    }
+    full.map_err(|e| format!("Transcription failed: {}", e))?;
+
+    let count = state
+        .full_n_segments()
+        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    let mut segments = Vec::with_capacity(count as usize);
+    // tinydiarize marks where the speaker changes but cannot tell speakers
+    // apart, so turns alternate between two labels as in a conversation
+    let mut speaker = 1;
+    for i in 0..count {
+        let text = state
+            .full_get_segment_text_lossy(i)
+            .map_err(|e| format!("Failed to read transcript: {}", e))?;
+        // Whisper timestamps are in centiseconds
+        let start = state.full_get_segment_t0(i).unwrap_or(0).max(0) as u64 * 10;
+        let end = state.full_get_segment_t1(i).unwrap_or(0).max(0) as u64 * 10;
+        segments.push(TranscriptSegment {
+            start_ms: start,
+            end_ms: end,
+            text: text.trim().to_string(),
+            speaker: options.diarize.then(|| format!("Speaker {}", speaker)),
+            confidence: segment_confidence(&state, i, context.token_eot()),
+            words: Vec::new(),
+        });
+
+        if options.diarize && state.full_get_segment_speaker_turn_next(i) {
+            speaker = 3 - speaker;
+        }
+    }
+
+    let language = match &options.language {
+        Some(language) => Some(language.clone()),
+        None => state
+            .full_lang_id_from_state()
+            .ok()
+            .and_then(whisper_rs::get_lang_str)
+            .map(str::to_string),
+    };
+
+    let duration_ms = samples.len() as u64 * 1000 / SPEECH_SAMPLE_RATE as u64;
+
+    Ok(TranscriptionResult {
+        text: segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        language,
+        // Filled in by the caller, the same for every engine
+        pace: PaceMetrics::default(),
+        segments,
+        duration_ms,
+        engine: "whisper-local".to_string(),
+        model: options.model.clone(),
+    })
+}
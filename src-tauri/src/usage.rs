@@ -0,0 +1,167 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::transcription::provider;
+
+const USAGE_FILE: &str = "usage.json";
+
+/// Serializes read-modify-write cycles of the usage file
+static USAGE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// What was sent to one provider in one month
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Usage {
+    audio_ms: u64,
+    requests: u32,
+    /// At the list price when each request was made
+    cost_usd: f64,
+}
+
+/// Usage by month ("2026-10"), then by provider id
+type UsageStore = BTreeMap<String, BTreeMap<String, Usage>>;
+
+fn usage_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(USAGE_FILE))
+}
+
+fn load(app: &AppHandle) -> Result<UsageStore, String> {
+    let path = usage_path(app)?;
+    if !path.exists() {
+        return Ok(UsageStore::new());
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Failed to read usage: {}", e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Failed to parse usage: {}", e))
+}
+
+fn add(store: &mut UsageStore, month: &str, provider: &str, audio_ms: u64, price: Option<f64>) {
+    let usage = store
+        .entry(month.to_string())
+        .or_default()
+        .entry(provider.to_string())
+        .or_default();
+    usage.audio_ms += audio_ms;
+    usage.requests += 1;
+    usage.cost_usd += price.unwrap_or(0.0) * audio_ms as f64 / 60_000.0;
+}
+
+/// Count `audio_ms` of audio sent to a provider this month
+pub fn record(app: &AppHandle, provider_id: &str, audio_ms: u64) -> Result<(), String> {
+    let price = provider::get(provider_id)
+        .ok()
+        .and_then(|provider| provider.usd_per_minute());
+    let month = chrono::Local::now().format("%Y-%m").to_string();
+
+    let _lock = USAGE_LOCK.lock();
+    let mut store = load(app)?;
+    add(&mut store, &month, provider_id, audio_ms, price);
+    let contents = serde_json::to_string_pretty(&store)
+        .map_err(|e| format!("Failed to serialize usage: {}", e))?;
+    fs::write(usage_path(app)?, contents).map_err(|e| format!("Failed to write usage: {}", e))
+}
+
+/// `record`, logging instead of failing: usage tracking never fails a
+/// transcription
+pub fn track(app: &AppHandle, provider_id: &str, audio_ms: u64) {
+    if let Err(e) = record(app, provider_id, audio_ms) {
+        eprintln!("Failed to record usage: {}", e);
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUsage {
+    provider: String,
+    audio_seconds: f64,
+    requests: u32,
+    estimated_cost_usd: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyUsage {
+    /// "2026-10"
+    month: String,
+    providers: Vec<ProviderUsage>,
+    audio_seconds: f64,
+    estimated_cost_usd: f64,
+}
+
+/// The latest `months`, newest first, each with the most expensive provider
+/// first
+fn monthly(store: UsageStore, months: usize) -> Vec<MonthlyUsage> {
+    store
+        .into_iter()
+        .rev()
+        .take(months)
+        .map(|(month, providers)| {
+            let mut providers: Vec<ProviderUsage> = providers
+                .into_iter()
+                .map(|(provider, usage)| ProviderUsage {
+                    provider,
+                    audio_seconds: usage.audio_ms as f64 / 1000.0,
+                    requests: usage.requests,
+                    estimated_cost_usd: usage.cost_usd,
+                })
+                .collect();
+            providers.sort_by(|a, b| b.estimated_cost_usd.total_cmp(&a.estimated_cost_usd));
+
+            MonthlyUsage {
+                month,
+                audio_seconds: providers.iter().map(|usage| usage.audio_seconds).sum(),
+                estimated_cost_usd: providers.iter().map(|usage| usage.estimated_cost_usd).sum(),
+                providers,
+            }
+        })
+        .collect()
+}
+
+/// Audio sent to each provider and its estimated cost at list prices, by
+/// month, newest first. Covers the last 12 months unless `months` says
+/// otherwise.
+#[tauri::command]
+pub fn get_usage_stats(app: AppHandle, months: Option<usize>) -> Result<Vec<MonthlyUsage>, String> {
+    let store = {
+        let _lock = USAGE_LOCK.lock();
+        load(&app)?
+    };
+    Ok(monthly(store, months.unwrap_or(12)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monthly_usage() {
+        let mut store = UsageStore::new();
+        add(&mut store, "2026-09", "openai", 60_000, Some(0.006));
+        add(&mut store, "2026-10", "openai", 90_000, Some(0.006));
+        add(&mut store, "2026-10", "openai", 30_000, Some(0.006));
+        add(&mut store, "2026-10", "whisper", 600_000, None);
+        add(&mut store, "2026-10", "azure", 60_000, Some(0.0167));
+
+        let usage = monthly(store, 12);
+        assert_eq!(usage.len(), 2);
+        let october = &usage[0];
+        assert_eq!(october.month, "2026-10");
+        assert_eq!(october.providers[0].provider, "azure");
+        assert_eq!(october.providers[1].provider, "openai");
+        assert_eq!(october.providers[1].requests, 2);
+        assert_eq!(october.providers[1].audio_seconds, 120.0);
+        assert!((october.providers[1].estimated_cost_usd - 0.012).abs() < 1e-9);
+        assert_eq!(october.audio_seconds, 780.0);
+        assert!((october.estimated_cost_usd - 0.0287).abs() < 1e-9);
+    }
+}
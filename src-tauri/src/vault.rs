@@ -0,0 +1,684 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::secure_delete;
+use transcriber_core::crypto::{self, ContextKey, DataKey, Zeroize, Zeroizing};
+
+/// Name of the file in the secure directory that holds every secure value
+pub const VAULT_FILE_NAME: &str = "vault";
+
+/// Written in full and then renamed over the vault, so a crash mid-write
+/// leaves the previous vault intact
+const VAULT_TEMP_FILE_NAME: &str = "vault.tmp";
+
+/// Format of the vault file, bumped when its contents change shape
+const VAULT_VERSION: u32 = 1;
+
+/// Encrypted index of key names to random file ids, from before the vault
+const LEGACY_INDEX_FILE_NAME: &str = ".index";
+
+/// Encrypted metadata of all values, from before the vault
+const LEGACY_META_FILE_NAME: &str = ".meta";
+
+/// The vault file: its version in the clear so it can be migrated, the
+/// values and their key names encrypted
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    /// `StoredVault` as JSON
+    data: String,
+}
+
+/// The contents of the vault file
+#[derive(Serialize, Deserialize)]
+struct StoredVault {
+    entries: BTreeMap<String, StoredEntry>,
+}
+
+/// A value as written to the vault file
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredEntry {
+    /// The value encrypted with its data key
+    value: String,
+    /// The data key, wrapped by the key of secure values
+    data_key: String,
+    #[serde(default)]
+    state: EntryState,
+    #[serde(flatten)]
+    meta: ValueMeta,
+}
+
+/// Whether a stored value can be handed out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryState {
+    #[default]
+    Ok,
+    /// Carried over from a file an old version wrote in plain text
+    Legacy,
+    /// Carried over from a file that did not decrypt; the value is that
+    /// file's contents
+    Corrupted,
+}
+
+/// What is known about a stored value besides the value itself
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueMeta {
+    /// Unix seconds from which the value counts as missing and is purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// Unix seconds of the first write; unknown for values stored before
+    /// metadata existed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+    /// Unix seconds of the last write
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<i64>,
+    /// Shown instead of the key, e.g. "OpenAI key"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl ValueMeta {
+    /// Whether the TTL of the value ran out by `now` (Unix seconds)
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// One stored value. Not `Debug`, so it cannot end up in a log.
+#[derive(Clone, Default)]
+pub struct VaultEntry {
+    pub value: String,
+    pub state: EntryState,
+    pub meta: ValueMeta,
+    /// How the value was stored when loaded, until it changes
+    sealed: Option<Sealed>,
+}
+
+/// A value encrypted with its data key
+#[derive(Clone)]
+struct Sealed {
+    data_key: DataKey,
+    value: String,
+}
+
+impl VaultEntry {
+    /// Encrypt the value with its data key, or a new one for a changed
+    /// value, and wrap that with `key`. An unchanged value is written as it
+    /// was read, so a new `key` only wraps the data keys again.
+    fn seal(&self, key: &ContextKey) -> Result<StoredEntry, String> {
+        let new_key;
+        let (data_key, value) = match &self.sealed {
+            Some(sealed) => (&sealed.data_key, sealed.value.clone()),
+            None => {
+                new_key = DataKey::generate();
+                let value = new_key.encrypt(self.value.as_bytes())?;
+                (&new_key, value)
+            }
+        };
+
+        Ok(StoredEntry {
+            value,
+            data_key: key.wrap_key(data_key)?,
+            state: self.state,
+            meta: self.meta.clone(),
+        })
+    }
+
+    /// The entry of `stored`. A value that does not decrypt is kept as it
+    /// was stored, for `get_secure_value` to report.
+    fn open(stored: StoredEntry, key: &ContextKey) -> Self {
+        let opened = key.unwrap_key(&stored.data_key).and_then(|data_key| {
+            let decrypted = data_key.decrypt(&stored.value)?;
+            let value = std::str::from_utf8(&decrypted)
+                .map_err(|e| format!("Invalid secure value: {}", e))?
+                .to_string();
+            Ok((value, data_key))
+        });
+
+        match opened {
+            Ok((value, data_key)) => Self {
+                value,
+                state: stored.state,
+                meta: stored.meta,
+                sealed: Some(Sealed {
+                    data_key,
+                    value: stored.value,
+                }),
+            },
+            Err(_) => Self {
+                value: stored.value,
+                state: EntryState::Corrupted,
+                meta: stored.meta,
+                sealed: None,
+            },
+        }
+    }
+}
+
+/// All secure values by key, decrypted. The values are wiped from memory when
+/// it is dropped.
+#[derive(Default)]
+pub struct Vault {
+    pub entries: BTreeMap<String, VaultEntry>,
+}
+
+impl Drop for Vault {
+    fn drop(&mut self) {
+        for entry in self.entries.values_mut() {
+            entry.value.zeroize();
+        }
+    }
+}
+
+impl Vault {
+    /// Store `value` for `key`, written at `now` (Unix seconds) and expiring
+    /// at `expires_at` or never
+    pub fn set(&mut self, key: &str, value: &str, now: i64, expires_at: Option<i64>) {
+        let entry = self.entries.entry(key.to_string()).or_default();
+        entry.value.zeroize();
+        entry.value = value.to_string();
+        entry.sealed = None;
+        entry.state = EntryState::Ok;
+        entry.meta.created_at.get_or_insert(now);
+        entry.meta.modified_at = Some(now);
+        entry.meta.expires_at = expires_at;
+    }
+
+    /// Store `value` for `key` with the metadata it had elsewhere, e.g. in a
+    /// backup. Timestamps missing from `meta` are set to `now`.
+    pub fn restore(&mut self, key: &str, value: &str, meta: ValueMeta, now: i64) {
+        self.set(key, value, now, meta.expires_at);
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.meta = ValueMeta {
+                created_at: meta.created_at.or(Some(now)),
+                modified_at: meta.modified_at.or(Some(now)),
+                ..meta
+            };
+        }
+    }
+
+    /// Remove and wipe the value of `key`; false if there was none
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.entries.remove(key) {
+            Some(mut entry) => {
+                entry.value.zeroize();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the values whose TTL ran out by `now` (Unix seconds) and return
+    /// their keys
+    pub fn purge_expired(&mut self, now: i64) -> Vec<String> {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.meta.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        expired
+    }
+
+    /// Treat values carried over from plain text files as regular values.
+    /// Returns their keys.
+    pub fn accept_legacy(&mut self) -> Vec<String> {
+        let mut accepted = Vec::new();
+        for (key, entry) in self.entries.iter_mut() {
+            if entry.state == EntryState::Legacy {
+                entry.state = EntryState::Ok;
+                accepted.push(key.clone());
+            }
+        }
+        accepted
+    }
+}
+
+/// Decrypt the vault in `secure_dir` with `key`. A store still in the layout
+/// of one file per value is converted first; without one the vault is empty.
+pub fn load(secure_dir: &Path, key: &ContextKey) -> Result<Vault, String> {
+    let path = secure_dir.join(VAULT_FILE_NAME);
+    if !path.exists() {
+        return migrate(secure_dir, key);
+    }
+    read(&path, key)
+}
+
+/// Decrypt the vault file at `path` with `key`
+fn read(path: &Path, key: &ContextKey) -> Result<Vault, String> {
+    let stored = read_stored(path, key)?;
+    Ok(Vault {
+        entries: stored
+            .entries
+            .into_iter()
+            .map(|(name, entry)| (name, VaultEntry::open(entry, key)))
+            .collect(),
+    })
+}
+
+/// The keys and metadata of the vault file at `path`, with every value
+/// still encrypted with its data key
+fn read_stored(path: &Path, key: &ContextKey) -> Result<StoredVault, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read secure vault: {}", e))?;
+    let file: VaultFile =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid secure vault: {}", e))?;
+    if file.version != VAULT_VERSION {
+        return Err(format!(
+            "Unsupported secure vault version: {}",
+            file.version
+        ));
+    }
+
+    let decrypted = key
+        .decrypt(&file.data)
+        .map_err(|e| format!("Failed to decrypt secure vault: {}", e))?;
+    serde_json::from_slice(&decrypted).map_err(|e| format!("Invalid secure vault: {}", e))
+}
+
+/// Whether the vault in `secure_dir` holds a value for `name` whose TTL has
+/// not run out by `now`. Only the keys and metadata are read: no data key is
+/// unwrapped, no value decrypted and nothing written. `None` when the store
+/// has to be converted first.
+pub fn contains(
+    secure_dir: &Path,
+    key: &ContextKey,
+    name: &str,
+    now: i64,
+) -> Result<Option<bool>, String> {
+    let path = secure_dir.join(VAULT_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let stored = read_stored(&path, key)?;
+    Ok(Some(
+        stored
+            .entries
+            .get(name)
+            .is_some_and(|entry| !entry.meta.is_expired(now)),
+    ))
+}
+
+/// Encrypt every value of `vault` with its own data key, wrap those with
+/// `key`, and replace the vault file in one step
+pub fn save(secure_dir: &Path, vault: &Vault, key: &ContextKey) -> Result<(), String> {
+    let mut stored = StoredVault {
+        entries: BTreeMap::new(),
+    };
+    for (name, entry) in &vault.entries {
+        stored.entries.insert(name.clone(), entry.seal(key)?);
+    }
+    let json = Zeroizing::new(
+        serde_json::to_vec(&stored)
+            .map_err(|e| format!("Failed to serialize secure vault: {}", e))?,
+    );
+    let file = VaultFile {
+        version: VAULT_VERSION,
+        data: key.encrypt(&json)?,
+    };
+    let contents = serde_json::to_vec(&file)
+        .map_err(|e| format!("Failed to serialize secure vault: {}", e))?;
+
+    let temp_path = secure_dir.join(VAULT_TEMP_FILE_NAME);
+    write_synced(&temp_path, &contents)?;
+    fs::rename(&temp_path, secure_dir.join(VAULT_FILE_NAME))
+        .map_err(|e| format!("Failed to replace secure vault: {}", e))
+}
+
+/// Whether the stored data, in either layout, decrypts with `key`. Nothing
+/// is converted.
+pub fn decrypts_with(secure_dir: &Path, key: &ContextKey) -> bool {
+    let path = secure_dir.join(VAULT_FILE_NAME);
+    if path.exists() {
+        return read(&path, key).is_ok();
+    }
+
+    match fs::read_to_string(secure_dir.join(LEGACY_INDEX_FILE_NAME)) {
+        Ok(encrypted) => key.decrypt(&encrypted).is_ok(),
+        Err(_) => true,
+    }
+}
+
+/// Write a file readable only by the current user and flush it to disk
+fn write_synced(path: &Path, data: &[u8]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to open secure vault: {}", e))?;
+    file.write_all(data)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Failed to write secure vault: {}", e))
+}
+
+/// Decrypt and parse a file of the old layout, `None` if it does not exist
+fn read_legacy<T: DeserializeOwned>(
+    path: &Path,
+    key: &ContextKey,
+    what: &str,
+) -> Result<Option<T>, String> {
+    let encrypted = match fs::read_to_string(path) {
+        Ok(encrypted) => encrypted,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read secure {}: {}", what, e)),
+    };
+    let decrypted = key
+        .decrypt_legacy(&encrypted)
+        .map_err(|e| format!("Failed to decrypt secure {}: {}", what, e))?;
+    serde_json::from_slice(&decrypted)
+        .map(Some)
+        .map_err(|e| format!("Invalid secure {}: {}", what, e))
+}
+
+/// The entry for a value file of the old layout. A file that is plain text
+/// or does not decrypt is kept as it is, for `get_secure_value` to report.
+fn read_legacy_value(path: &Path, key: &ContextKey) -> Result<VaultEntry, String> {
+    let contents = fs::read(path).map_err(|e| format!("Failed to read secure value: {}", e))?;
+    let (value, state) = match String::from_utf8(contents) {
+        Ok(text) if !crypto::looks_encrypted(&text) => (text, EntryState::Legacy),
+        Ok(text) => {
+            let decrypted = key
+                .decrypt_legacy(text.trim())
+                .ok()
+                .and_then(|bytes| std::str::from_utf8(&bytes).map(str::to_string).ok());
+            match decrypted {
+                Some(value) => (value, EntryState::Ok),
+                None => (text, EntryState::Corrupted),
+            }
+        }
+        Err(e) => (
+            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            EntryState::Corrupted,
+        ),
+    };
+
+    Ok(VaultEntry {
+        value,
+        state,
+        ..VaultEntry::default()
+    })
+}
+
+/// Gather a store of the old layout into a vault: the encrypted index of key
+/// to random file id, one encrypted file per value (in a subdirectory for
+/// namespaced keys), the encrypted metadata, and files named after their key
+/// from before the index. The vault is saved before the old files are
+/// deleted, so an interrupted conversion starts over on the next launch.
+fn migrate(secure_dir: &Path, key: &ContextKey) -> Result<Vault, String> {
+    let index_path = secure_dir.join(LEGACY_INDEX_FILE_NAME);
+    let meta_path = secure_dir.join(LEGACY_META_FILE_NAME);
+    let index: HashMap<String, String> =
+        read_legacy(&index_path, key, "index")?.unwrap_or_default();
+    let mut meta: HashMap<String, ValueMeta> =
+        read_legacy(&meta_path, key, "metadata")?.unwrap_or_default();
+
+    let mut vault = Vault::default();
+    let mut value_paths: Vec<PathBuf> = Vec::new();
+    for (name, file_id) in &index {
+        let path = secure_dir.join(file_id);
+        if path.is_file() {
+            vault
+                .entries
+                .insert(name.clone(), read_legacy_value(&path, key)?);
+            value_paths.push(path);
+        }
+    }
+
+    let mut namespace_dirs = Vec::new();
+    let dir_entries =
+        fs::read_dir(secure_dir).map_err(|e| format!("Failed to read secure directory: {}", e))?;
+    for dir_entry in dir_entries {
+        let path = dir_entry
+            .map_err(|e| format!("Failed to read secure directory: {}", e))?
+            .path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if name.starts_with('.') || name == VAULT_TEMP_FILE_NAME || value_paths.contains(&path) {
+            continue;
+        }
+
+        if path.is_dir() {
+            namespace_dirs.push(path);
+        } else if !vault.entries.contains_key(name) {
+            vault
+                .entries
+                .insert(name.to_string(), read_legacy_value(&path, key)?);
+            value_paths.push(path);
+        }
+    }
+
+    if !index_path.exists() && !meta_path.exists() && value_paths.is_empty() {
+        return Ok(vault);
+    }
+    for (name, entry) in vault.entries.iter_mut() {
+        if let Some(value_meta) = meta.remove(name) {
+            entry.meta = value_meta;
+        }
+    }
+    save(secure_dir, &vault, key)?;
+
+    // Overwritten first, since some of them may hold plain text
+    for path in &value_paths {
+        secure_delete::delete_path(path, true)?;
+    }
+    for path in [&index_path, &meta_path] {
+        if path.exists() {
+            fs::remove_file(path)
+                .map_err(|e| format!("Failed to delete old secure file: {}", e))?;
+        }
+    }
+    for dir in &namespace_dirs {
+        fs::remove_dir_all(dir)
+            .map_err(|e| format!("Failed to delete old secure namespace: {}", e))?;
+    }
+
+    Ok(vault)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use transcriber_core::crypto::KeyContext;
+
+    fn temp_secure_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("vault-test-{}-{}", name, rand::random::<u64>()));
+        fs::create_dir_all(&dir).expect("Failed to create temp dir");
+        dir
+    }
+
+    fn test_key(byte: u8) -> ContextKey {
+        ContextKey::from_master(&[byte; 32], KeyContext::SecureValues)
+    }
+
+    #[test]
+    fn test_vault_roundtrip() {
+        let secure_dir = temp_secure_dir("roundtrip");
+        let key = test_key(1);
+
+        let mut vault = load(&secure_dir, &key).unwrap();
+        assert!(vault.entries.is_empty());
+        assert!(!secure_dir.join(VAULT_FILE_NAME).exists());
+
+        vault.set("access_token", "at", 500, Some(1_000));
+        vault.set("api_key", "sk-test", 500, None);
+        vault.set("api_key", "sk-new", 600, None);
+        save(&secure_dir, &vault, &key).unwrap();
+
+        let contents = fs::read_to_string(secure_dir.join(VAULT_FILE_NAME)).unwrap();
+        assert!(!contents.contains("api_key") && !contents.contains("sk-new"));
+        assert!(!secure_dir.join(VAULT_TEMP_FILE_NAME).exists());
+        assert!(decrypts_with(&secure_dir, &key));
+        assert!(!decrypts_with(&secure_dir, &test_key(2)));
+        assert_eq!(contains(&secure_dir, &key, "api_key", 999), Ok(Some(true)));
+        assert_eq!(contains(&secure_dir, &key, "access_token", 999), Ok(Some(true)));
+        assert_eq!(contains(&secure_dir, &key, "access_token", 1_000), Ok(Some(false)));
+        assert_eq!(contains(&secure_dir, &key, "other", 999), Ok(Some(false)));
+
+        let mut loaded = load(&secure_dir, &key).unwrap();
+        let entry = &loaded.entries["api_key"];
+        assert_eq!(entry.value, "sk-new");
+        assert_eq!(entry.meta.created_at, Some(500));
+        assert_eq!(entry.meta.modified_at, Some(600));
+
+        assert!(loaded.purge_expired(999).is_empty());
+        assert_eq!(loaded.purge_expired(1_000), vec!["access_token"]);
+        assert_eq!(loaded.entries.keys().collect::<Vec<_>>(), vec!["api_key"]);
+
+        let meta = ValueMeta {
+            expires_at: Some(2_000),
+            created_at: Some(100),
+            label: Some("OpenAI key".to_string()),
+            ..Default::default()
+        };
+        loaded.restore("api_key", "sk-restored", meta, 700);
+        let entry = &loaded.entries["api_key"];
+        assert_eq!(entry.value, "sk-restored");
+        assert_eq!(entry.meta.created_at, Some(100));
+        assert_eq!(entry.meta.modified_at, Some(700));
+        assert_eq!(entry.meta.expires_at, Some(2_000));
+        assert_eq!(entry.meta.label.as_deref(), Some("OpenAI key"));
+
+        fs::remove_dir_all(&secure_dir).unwrap();
+    }
+
+    fn read_stored(secure_dir: &Path, key: &ContextKey) -> (u32, StoredVault) {
+        let contents = fs::read_to_string(secure_dir.join(VAULT_FILE_NAME)).unwrap();
+        let file: VaultFile = serde_json::from_str(&contents).unwrap();
+        let stored = serde_json::from_slice(&key.decrypt(&file.data).unwrap()).unwrap();
+        (file.version, stored)
+    }
+
+    #[test]
+    fn test_values_have_own_data_keys() {
+        let secure_dir = temp_secure_dir("envelope");
+        let key = test_key(1);
+
+        let mut vault = Vault::default();
+        vault.set("api_key", "sk-test", 500, None);
+        vault.set("other_key", "sk-test", 500, None);
+        save(&secure_dir, &vault, &key).unwrap();
+
+        let (version, stored) = read_stored(&secure_dir, &key);
+        assert_eq!(version, VAULT_VERSION);
+        let (first, second) = (&stored.entries["api_key"], &stored.entries["other_key"]);
+        let first_key = key.unwrap_key(&first.data_key).unwrap();
+        assert_eq!(*first_key.decrypt(&first.value).unwrap(), b"sk-test");
+        assert!(first_key.decrypt(&second.value).is_err());
+
+        // A new key wraps the data keys again and leaves the values alone
+        let new_key = test_key(2);
+        let loaded = load(&secure_dir, &key).unwrap();
+        save(&secure_dir, &loaded, &new_key).unwrap();
+        let (_, rewrapped) = read_stored(&secure_dir, &new_key);
+        assert_eq!(rewrapped.entries["api_key"].value, first.value);
+        assert_ne!(rewrapped.entries["api_key"].data_key, first.data_key);
+        assert_eq!(
+            load(&secure_dir, &new_key).unwrap().entries["api_key"].value,
+            "sk-test"
+        );
+
+        // A changed value gets a new data key
+        let mut changed = load(&secure_dir, &new_key).unwrap();
+        changed.set("api_key", "sk-new", 600, None);
+        save(&secure_dir, &changed, &new_key).unwrap();
+        let (_, resealed) = read_stored(&secure_dir, &new_key);
+        let resealed_key = new_key
+            .unwrap_key(&resealed.entries["api_key"].data_key)
+            .unwrap();
+        assert!(resealed_key.decrypt(&first.value).is_err());
+
+        fs::remove_dir_all(&secure_dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_layout_is_migrated() {
+        let secure_dir = temp_secure_dir("migrate");
+        let key = test_key(1);
+        let write_encrypted = |name: &str, data: &[u8]| {
+            fs::write(secure_dir.join(name), key.encrypt(data).unwrap()).unwrap();
+        };
+
+        fs::create_dir_all(secure_dir.join("providers/openai")).unwrap();
+        write_encrypted("providers/openai/0a1b", b"sk-openai");
+        write_encrypted("2c3d", b"sk-azure");
+        fs::write(secure_dir.join("4e5f"), "sk-plain").unwrap();
+        let mut corrupted = key.encrypt(b"sk-broken").unwrap();
+        corrupted.replace_range(..4, "AAAA");
+        fs::write(secure_dir.join("6a7b"), &corrupted).unwrap();
+        write_encrypted("unindexed_key", b"sk-old");
+        fs::write(secure_dir.join(".keycheck"), "fingerprint").unwrap();
+
+        let index = HashMap::from([
+            ("providers/openai/api_key", "providers/openai/0a1b"),
+            ("azure_speech_key", "2c3d"),
+            ("plain_key", "4e5f"),
+            ("broken_key", "6a7b"),
+            ("deleted_key", "8c9d"),
+        ]);
+        write_encrypted(LEGACY_INDEX_FILE_NAME, &serde_json::to_vec(&index).unwrap());
+        let meta = HashMap::from([(
+            "azure_speech_key",
+            ValueMeta {
+                label: Some("Azure".to_string()),
+                ..ValueMeta::default()
+            },
+        )]);
+        write_encrypted(LEGACY_META_FILE_NAME, &serde_json::to_vec(&meta).unwrap());
+
+        assert!(decrypts_with(&secure_dir, &key));
+        assert!(!decrypts_with(&secure_dir, &test_key(2)));
+
+        let vault = load(&secure_dir, &key).unwrap();
+        let state = |name: &str| {
+            (
+                vault.entries[name].value.as_str(),
+                vault.entries[name].state,
+            )
+        };
+        assert_eq!(
+            state("providers/openai/api_key"),
+            ("sk-openai", EntryState::Ok)
+        );
+        assert_eq!(state("azure_speech_key"), ("sk-azure", EntryState::Ok));
+        assert_eq!(state("plain_key"), ("sk-plain", EntryState::Legacy));
+        assert_eq!(state("broken_key").1, EntryState::Corrupted);
+        assert_eq!(state("unindexed_key"), ("sk-old", EntryState::Ok));
+        assert_eq!(vault.entries.len(), 5);
+        assert_eq!(
+            vault.entries["azure_speech_key"].meta.label.as_deref(),
+            Some("Azure")
+        );
+
+        let mut names: Vec<String> = fs::read_dir(&secure_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![".keycheck", VAULT_FILE_NAME]);
+
+        let reloaded = load(&secure_dir, &key).unwrap();
+        assert_eq!(reloaded.entries.len(), 5);
+
+        fs::remove_dir_all(&secure_dir).unwrap();
+    }
+}
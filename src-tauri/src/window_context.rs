@@ -0,0 +1,97 @@
+use serde::Serialize;
+
+/// The application the user was working in when a recording started
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowContext {
+    pub app_name: String,
+    /// Empty titles are dropped; on macOS titles need the Screen Recording permission
+    pub window_title: Option<String>,
+    /// Sentence to add to formatting and LLM prompts
+    pub prompt_hint: String,
+}
+
+impl WindowContext {
+    fn new(app_name: String, window_title: String) -> Option<Self> {
+        let app_name = app_name.trim().to_string();
+        if app_name.is_empty() {
+            return None;
+        }
+
+        let window_title = Some(window_title.trim().to_string()).filter(|t| !t.is_empty());
+        let prompt_hint = match &window_title {
+            Some(title) => format!(
+                "This was dictated into {} (window: \"{}\").",
+                app_name, title
+            ),
+            None => format!("This was dictated into {}.", app_name),
+        };
+
+        Some(Self {
+            app_name,
+            window_title,
+            prompt_hint,
+        })
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn frontmost_window() -> Option<(u32, String, String)> {
+    let window = active_win_pos_rs::get_active_window().ok()?;
+    Some((window.process_id as u32, window.app_name, window.title))
+}
+
+/// X11 only, via `xdotool`; Wayland compositors do not expose the focused window
+#[cfg(target_os = "linux")]
+fn frontmost_window() -> Option<(u32, String, String)> {
+    let output = std::process::Command::new("xdotool")
+        .args(["getactivewindow", "getwindowpid", "getwindowname"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let pid: u32 = lines.next()?.trim().parse().ok()?;
+    let title = lines.next().unwrap_or_default().to_string();
+    let app_name = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+
+    Some((pid, app_name, title))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn frontmost_window() -> Option<(u32, String, String)> {
+    None
+}
+
+/// Capture the frontmost application and window title. Returns `None` when it
+/// cannot be determined or when this app itself is in front.
+pub fn capture() -> Option<WindowContext> {
+    let (pid, app_name, title) = frontmost_window()?;
+    if pid == std::process::id() {
+        return None;
+    }
+
+    WindowContext::new(app_name, title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_hint_includes_title_when_known() {
+        let context = WindowContext::new("Xcode".to_string(), " main.swift ".to_string()).unwrap();
+        assert_eq!(context.window_title.as_deref(), Some("main.swift"));
+        assert_eq!(
+            context.prompt_hint,
+            "This was dictated into Xcode (window: \"main.swift\")."
+        );
+
+        let context = WindowContext::new("Xcode".to_string(), String::new()).unwrap();
+        assert_eq!(context.window_title, None);
+        assert_eq!(context.prompt_hint, "This was dictated into Xcode.");
+
+        assert!(WindowContext::new("  ".to_string(), "Title".to_string()).is_none());
+    }
+}
@@ -0,0 +1,197 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::audio::{self, AudioRecorder};
+use crate::commands::SecureCache;
+use crate::secure_delete::overwrite_file;
+use transcriber_core::crypto;
+
+/// Token the frontend must pass to `wipe_all_data` before anything is deleted
+pub const WIPE_CONFIRM_TOKEN: &str = "WIPE-ALL-DATA";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WipedFile {
+    path: String,
+    size: u64,
+    overwritten: bool,
+    removed: bool,
+}
+
+/// Report describing what was (or, in dry-run mode, would be) deleted
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WipeReport {
+    dry_run: bool,
+    directories: Vec<String>,
+    files: Vec<WipedFile>,
+    total_bytes: u64,
+    errors: Vec<String>,
+}
+
+/// Collect every app-owned directory (data, local data, cache, config, logs)
+fn get_app_directories(app: &AppHandle) -> Vec<PathBuf> {
+    let path = app.path();
+    let candidates = [
+        path.app_data_dir(),
+        path.app_local_data_dir(),
+        path.app_cache_dir(),
+        path.app_config_dir(),
+        path.app_log_dir(),
+    ];
+
+    // Several of these resolve to the same directory on some platforms
+    candidates
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Recursively collect all regular files below `dir`
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>, errors: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push(format!("Failed to read directory {}: {}", dir.display(), e));
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_files(&path, files, errors),
+            Ok(file_type) if file_type.is_file() => files.push(path),
+            // Symlinks are removed but never followed or overwritten
+            Ok(_) => files.push(path),
+            Err(e) => errors.push(format!("Failed to stat {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// Overwrite and delete a single file, recording the outcome
+fn wipe_file(path: &Path, dry_run: bool, errors: &mut Vec<String>) -> WipedFile {
+    let metadata = fs::symlink_metadata(path);
+    let is_regular_file = metadata.as_ref().map(|m| m.is_file()).unwrap_or(false);
+    let size = metadata.map(|m| m.len()).unwrap_or(0);
+
+    let mut wiped = WipedFile {
+        path: path.display().to_string(),
+        size,
+        overwritten: false,
+        removed: false,
+    };
+
+    if dry_run {
+        return wiped;
+    }
+
+    if is_regular_file {
+        match overwrite_file(path, size) {
+            Ok(_) => wiped.overwritten = true,
+            Err(e) => errors.push(format!("Failed to overwrite {}: {}", path.display(), e)),
+        }
+    }
+
+    match fs::remove_file(path) {
+        Ok(_) => wiped.removed = true,
+        Err(e) => errors.push(format!("Failed to delete {}: {}", path.display(), e)),
+    }
+
+    wiped
+}
+
+/// Securely delete all locally stored data: secure values, history, audio,
+/// caches and settings in every app-owned directory.
+///
+/// Files are overwritten with zeros before being unlinked. This is best-effort:
+/// copy-on-write filesystems and SSD wear levelling may keep old blocks around.
+/// The encryption key is derived from the machine ID rather than stored in the
+/// OS keyring, so there is no keyring entry to remove; once the ciphertexts are
+/// gone nothing recoverable remains. History kept in the webview's localStorage
+/// must additionally be cleared by the frontend.
+///
+/// A recording in progress, and the capture helper writing it, is stopped
+/// first. Secure storage stays locked afterwards: the cached key and values
+/// are dropped from memory.
+///
+/// With `dry_run` set, nothing is touched and the report lists what would be
+/// deleted. Otherwise `confirm_token` must equal [`WIPE_CONFIRM_TOKEN`].
+#[tauri::command]
+pub async fn wipe_all_data(
+    app: AppHandle,
+    confirm_token: String,
+    dry_run: bool,
+) -> Result<WipeReport, String> {
+    if !dry_run && confirm_token != WIPE_CONFIRM_TOKEN {
+        return Err("Invalid confirmation token, refusing to wipe data".to_string());
+    }
+
+    let helper_exit = if dry_run {
+        None
+    } else {
+        // The recording's stream belongs to the main thread
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let handle = app.clone();
+        app.run_on_main_thread(move || {
+            let _ = sender.send(audio::stop_capture(&handle.state::<AudioRecorder>()));
+        })
+        .map_err(|e| format!("Failed to stop recording: {}", e))?;
+        receiver
+            .await
+            .map_err(|_| "Recording was not stopped".to_string())?
+    };
+
+    tokio::task::spawn_blocking(move || {
+        if let Some(helper_exit) = helper_exit {
+            let _ = helper_exit.join();
+        }
+        if !dry_run {
+            app.state::<SecureCache>().clear();
+            crypto::lock_store();
+        }
+
+        let directories = get_app_directories(&app);
+        let mut errors = Vec::new();
+        let mut paths = Vec::new();
+
+        for dir in directories.iter().filter(|dir| dir.exists()) {
+            collect_files(dir, &mut paths, &mut errors);
+        }
+
+        let files: Vec<WipedFile> = paths
+            .iter()
+            .map(|path| wipe_file(path, dry_run, &mut errors))
+            .collect();
+
+        if !dry_run {
+            for dir in directories.iter().filter(|dir| dir.exists()) {
+                if let Err(e) = fs::remove_dir_all(dir) {
+                    errors.push(format!(
+                        "Failed to remove directory {}: {}",
+                        dir.display(),
+                        e
+                    ));
+                }
+            }
+        }
+
+        Ok(WipeReport {
+            dry_run,
+            directories: directories
+                .iter()
+                .map(|d| d.display().to_string())
+                .collect(),
+            total_bytes: files.iter().map(|f| f.size).sum(),
+            files,
+            errors,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}